@@ -0,0 +1,669 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::coastline::{COASTLINE_SCALES, box_counting_dimension, coastline_length_km};
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::json::{self, ObjectWriter};
+use crate::landmass::label_land;
+use crate::stats::{percentile_sorted, weighted_mean};
+use crate::terrain::ruggedness_field;
+use crate::vector::cell_area_km2;
+
+/// Bumped whenever a field is renamed, removed, or changes meaning; additions keep it.
+/// 2: `sinuosity_index` and `straight_to_turn_ratio` combine row and column scans.
+pub(crate) const ANALYTICS_SCHEMA_VERSION: u32 = 2;
+
+/// Upper edges (km²) of the island-size histogram bins; a final bin holds larger ones.
+pub(crate) const ISLAND_SIZE_BIN_EDGES_KM2: [f64; 5] = [1e3, 1e4, 1e5, 1e6, 1e7];
+
+/// Length of `Analytics::to_array`.
+pub(crate) const ANALYTICS_ARRAY_LEN: usize =
+    12 + (ISLAND_SIZE_BIN_EDGES_KM2.len() + 1) + COASTLINE_SCALES.len() + 2;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct AnalyticsOptions {
+    /// Land threshold for the land-based metrics (hypsometry, TRI, landmasses, coastline).
+    pub sea_level: f32,
+    /// Elevation below which a cell counts toward `hydro_drainage_pct`.
+    pub drainage_cutoff: f32,
+    /// Change between consecutive deltas along a scan line above which sinuosity counts a turn.
+    pub turn_threshold: f32,
+    /// Landmasses at least this large (km²) count toward `islands_above_threshold`.
+    pub island_threshold_km2: f64,
+}
+
+impl Default for AnalyticsOptions {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            drainage_cutoff: 0.42,
+            turn_threshold: 0.0035,
+            island_threshold_km2: 10_000.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl AnalyticsOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AnalyticsOptions {
+        Self::default()
+    }
+
+    /// Defaults with the drainage cutoff moved by the same amount as the sea level, so the
+    /// drainage metric tracks a raised or lowered ocean.
+    pub fn for_sea_level(sea_level: f32) -> AnalyticsOptions {
+        let defaults = Self::default();
+        Self {
+            sea_level,
+            drainage_cutoff: defaults.drainage_cutoff + (sea_level - defaults.sea_level),
+            ..defaults
+        }
+    }
+}
+
+impl AnalyticsOptions {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !(0.0..=1.0).contains(&self.sea_level) {
+            return Err(JsValue::from_str("sea_level must be within [0.0, 1.0]"));
+        }
+        if !self.drainage_cutoff.is_finite() {
+            return Err(JsValue::from_str("drainage_cutoff must be finite"));
+        }
+        if !self.turn_threshold.is_finite() || self.turn_threshold < 0.0 {
+            return Err(JsValue::from_str("turn_threshold must be >= 0"));
+        }
+        if !self.island_threshold_km2.is_finite() || self.island_threshold_km2 < 0.0 {
+            return Err(JsValue::from_str("island_threshold_km2 must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Heightmap analytics, read through the getters or serialised whole by `to_json` and
+/// `to_object`. Non-finite values serialise as `null` in JSON.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Serialize)]
+pub struct Analytics {
+    pub(crate) schema_version: u32,
+    pub(crate) sinuosity_index: f64,
+    pub(crate) sinuosity_index_rows: f64,
+    pub(crate) sinuosity_index_columns: f64,
+    pub(crate) straight_to_turn_ratio: f64,
+    pub(crate) hydro_drainage_pct: f64,
+    pub(crate) hypsometric_integral: f64,
+    pub(crate) tri_land_mean: f64,
+    pub(crate) tri_land_p90: f64,
+    pub(crate) landmass_count: u32,
+    pub(crate) largest_landmass_pct: f64,
+    pub(crate) islands_above_threshold: u32,
+    pub(crate) island_size_histogram: Vec<u32>,
+    pub(crate) coastline_scales_cells: [usize; COASTLINE_SCALES.len()],
+    pub(crate) coastline_km_by_scale: Vec<f64>,
+    pub(crate) coastline_fractal_dimension: f64,
+    pub(crate) latency_ms: f64,
+}
+
+#[wasm_bindgen]
+impl Analytics {
+    #[wasm_bindgen(getter)]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    /// Sinuosity over row and column scans together.
+    #[wasm_bindgen(getter)]
+    pub fn sinuosity_index(&self) -> f64 {
+        self.sinuosity_index
+    }
+
+    /// Sinuosity from east–west (row) scans only.
+    #[wasm_bindgen(getter)]
+    pub fn sinuosity_index_rows(&self) -> f64 {
+        self.sinuosity_index_rows
+    }
+
+    /// Sinuosity from north–south (column) scans only.
+    #[wasm_bindgen(getter)]
+    pub fn sinuosity_index_columns(&self) -> f64 {
+        self.sinuosity_index_columns
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn straight_to_turn_ratio(&self) -> f64 {
+        self.straight_to_turn_ratio
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hydro_drainage_pct(&self) -> f64 {
+        self.hydro_drainage_pct
+    }
+
+    /// Area-weighted hypsometric integral of land, (mean − min) / (max − min).
+    #[wasm_bindgen(getter)]
+    pub fn hypsometric_integral(&self) -> f64 {
+        self.hypsometric_integral
+    }
+
+    /// Mean Terrain Ruggedness Index over land, elevation units.
+    #[wasm_bindgen(getter)]
+    pub fn tri_land_mean(&self) -> f64 {
+        self.tri_land_mean
+    }
+
+    /// 90th percentile Terrain Ruggedness Index over land, elevation units.
+    #[wasm_bindgen(getter)]
+    pub fn tri_land_p90(&self) -> f64 {
+        self.tri_land_p90
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn landmass_count(&self) -> u32 {
+        self.landmass_count
+    }
+
+    /// Share of all land area in the largest landmass, %.
+    #[wasm_bindgen(getter)]
+    pub fn largest_landmass_pct(&self) -> f64 {
+        self.largest_landmass_pct
+    }
+
+    /// Landmasses of at least `AnalyticsOptions::island_threshold_km2` (10 000 km² by
+    /// default).
+    #[wasm_bindgen(getter)]
+    pub fn islands_above_threshold(&self) -> u32 {
+        self.islands_above_threshold
+    }
+
+    /// Landmass counts by area: < 10³, < 10⁴, < 10⁵, < 10⁶, < 10⁷, and ≥ 10⁷ km².
+    pub fn island_size_histogram(&self) -> Box<[u32]> {
+        self.island_size_histogram.clone().into_boxed_slice()
+    }
+
+    /// Coastline length in km measured on the land mask sampled at 1, 2, 4, 8, 16 and 32
+    /// cells per block; the drop with coarser sampling reflects coastline complexity.
+    pub fn coastline_km_by_scale(&self) -> Box<[f64]> {
+        self.coastline_km_by_scale.clone().into_boxed_slice()
+    }
+
+    /// Box-counting fractal dimension of the coastline (≈1 smooth, toward 2 intricate).
+    #[wasm_bindgen(getter)]
+    pub fn coastline_fractal_dimension(&self) -> f64 {
+        self.coastline_fractal_dimension
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn latency_ms(&self) -> f64 {
+        self.latency_ms
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("analytics always serialise")
+    }
+
+    /// The fields of `to_json` as a plain JS object.
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(JsValue::from)
+    }
+
+    /// The fields of `to_json` as one flat array of `ANALYTICS_ARRAY_LEN` numbers, for
+    /// per-frame readback without building or parsing a string. Field order:
+    ///
+    /// | index | field |
+    /// |-------|-------|
+    /// | 0 | schema_version |
+    /// | 1 | sinuosity_index |
+    /// | 2 | sinuosity_index_rows |
+    /// | 3 | sinuosity_index_columns |
+    /// | 4 | straight_to_turn_ratio |
+    /// | 5 | hydro_drainage_pct |
+    /// | 6 | hypsometric_integral |
+    /// | 7 | tri_land_mean |
+    /// | 8 | tri_land_p90 |
+    /// | 9 | landmass_count |
+    /// | 10 | largest_landmass_pct |
+    /// | 11 | islands_above_threshold |
+    /// | 12–17 | island_size_histogram |
+    /// | 18–23 | coastline_km_by_scale |
+    /// | 24 | coastline_fractal_dimension |
+    /// | 25 | latency_ms |
+    ///
+    /// Values are unrounded. Fields are only ever appended, so a reader checking
+    /// `schema_version` and the length can rely on existing indices.
+    pub fn to_array(&self) -> Box<[f64]> {
+        let mut out = Vec::with_capacity(ANALYTICS_ARRAY_LEN);
+        out.extend([
+            self.schema_version as f64,
+            self.sinuosity_index,
+            self.sinuosity_index_rows,
+            self.sinuosity_index_columns,
+            self.straight_to_turn_ratio,
+            self.hydro_drainage_pct,
+            self.hypsometric_integral,
+            self.tri_land_mean,
+            self.tri_land_p90,
+            self.landmass_count as f64,
+            self.largest_landmass_pct,
+            self.islands_above_threshold as f64,
+        ]);
+        out.extend(self.island_size_histogram.iter().map(|&n| n as f64));
+        out.extend(&self.coastline_km_by_scale);
+        out.push(self.coastline_fractal_dimension);
+        out.push(self.latency_ms);
+        debug_assert_eq!(out.len(), ANALYTICS_ARRAY_LEN);
+        out.into_boxed_slice()
+    }
+}
+
+/// Area-weighted land elevations (at or above `sea_level`), ascending.
+struct Hypsometry {
+    samples: Vec<(f32, f64)>,
+    total_area: f64,
+}
+
+impl Hypsometry {
+    fn of_land(flat: &[f32], sea_level: f32, region: Option<&[bool]>) -> Self {
+        let mut samples: Vec<(f32, f64)> = (0..CELL_COUNT)
+            .filter(|&idx| flat[idx] >= sea_level && region.is_none_or(|r| r[idx]))
+            .map(|idx| (flat[idx], cell_area_km2(idx / WIDTH)))
+            .collect();
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total_area = samples.iter().map(|s| s.1).sum();
+        Self {
+            samples,
+            total_area,
+        }
+    }
+
+    fn range(&self) -> Option<(f32, f32)> {
+        let min = self.samples.first()?.0;
+        let max = self.samples.last()?.0;
+        (max > min).then_some((min, max))
+    }
+
+    fn integral(&self) -> f64 {
+        let Some((min, max)) = self.range() else {
+            return 0.0;
+        };
+        (weighted_mean(&self.samples) - min as f64) / (max - min) as f64
+    }
+
+    /// Relative area (share of land at or above) at `count` evenly spaced relative heights
+    /// from 0 to 1.
+    fn curve(&self, count: usize) -> Vec<f64> {
+        let Some((min, max)) = self.range() else {
+            return vec![0.0; count];
+        };
+        let mut above = self.total_area;
+        let mut next = 0;
+        (0..count)
+            .map(|i| {
+                let level = min + (max - min) * i as f32 / (count - 1).max(1) as f32;
+                while next < self.samples.len() && self.samples[next].0 < level {
+                    above -= self.samples[next].1;
+                    next += 1;
+                }
+                (above / self.total_area).max(0.0)
+            })
+            .collect()
+    }
+}
+
+/// Turn and straight counts along scan lines: a turn is a change between consecutive
+/// elevation deltas larger than `threshold`. `None` entries (cells outside the region)
+/// break the line.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct TurnCounts {
+    turns: u64,
+    straights: u64,
+}
+
+/// Position along one scan line, so a line can be fed a piece at a time.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ScanLine {
+    previous: Option<f32>,
+    previous_delta: Option<f32>,
+}
+
+impl TurnCounts {
+    pub(crate) fn step(&mut self, line: &mut ScanLine, value: Option<f32>, threshold: f32) {
+        let Some(value) = value.map(|v| v.clamp(0.0, 1.0)) else {
+            *line = ScanLine::default();
+            return;
+        };
+        if let Some(p) = line.previous {
+            let delta = value - p;
+            if let Some(pd) = line.previous_delta {
+                if (delta - pd).abs() > threshold {
+                    self.turns += 1;
+                } else {
+                    self.straights += 1;
+                }
+            }
+            line.previous_delta = Some(delta);
+        }
+        line.previous = Some(value);
+    }
+
+    pub(crate) fn scan(&mut self, line: impl Iterator<Item = Option<f32>>, threshold: f32) {
+        let mut state = ScanLine::default();
+        for value in line {
+            self.step(&mut state, value, threshold);
+        }
+    }
+
+    pub(crate) fn combined(self, other: TurnCounts) -> TurnCounts {
+        TurnCounts {
+            turns: self.turns + other.turns,
+            straights: self.straights + other.straights,
+        }
+    }
+
+    pub(crate) fn sinuosity_index(&self) -> f64 {
+        1.0 + (self.turns as f64 / self.straights.max(1) as f64) * 0.1
+    }
+
+    pub(crate) fn straight_to_turn_ratio(&self) -> f64 {
+        self.straights as f64 / self.turns.max(1) as f64
+    }
+}
+
+/// Landmass counts per `ISLAND_SIZE_BIN_EDGES_KM2` bin.
+pub(crate) fn island_size_histogram(areas_km2: impl Iterator<Item = f64>) -> Vec<u32> {
+    let mut histogram = vec![0_u32; ISLAND_SIZE_BIN_EDGES_KM2.len() + 1];
+    for area in areas_km2 {
+        let bin = ISLAND_SIZE_BIN_EDGES_KM2
+            .iter()
+            .position(|&edge| area < edge)
+            .unwrap_or(ISLAND_SIZE_BIN_EDGES_KM2.len());
+        histogram[bin] += 1;
+    }
+    histogram
+}
+
+/// Analytics over the cells where `region` is set (the whole grid for `source_of_truth`).
+pub(crate) fn compute(
+    flat: &[f32],
+    latency_ms: f64,
+    options: &AnalyticsOptions,
+    region: &[bool],
+) -> Analytics {
+    let sea_level = options.sea_level;
+    let sample = |idx: usize| region[idx].then_some(flat[idx]);
+    let region_cells = region.iter().filter(|&&r| r).count();
+    let drainage_cells = (0..CELL_COUNT)
+        .filter(|&idx| region[idx] && flat[idx].clamp(0.0, 1.0) < options.drainage_cutoff)
+        .count();
+
+    let mut rows = TurnCounts::default();
+    for y in 0..HEIGHT {
+        rows.scan(
+            (y * WIDTH..(y + 1) * WIDTH).map(sample),
+            options.turn_threshold,
+        );
+    }
+    let mut columns = TurnCounts::default();
+    for x in 0..WIDTH {
+        columns.scan(
+            (0..HEIGHT).map(|y| sample(y * WIDTH + x)),
+            options.turn_threshold,
+        );
+    }
+    let both = rows.combined(columns);
+
+    let land: Vec<bool> = flat.iter().map(|&h| h >= sea_level).collect();
+    let tri = ruggedness_field(flat);
+    let mut land_tri: Vec<f32> = (0..CELL_COUNT)
+        .filter(|&idx| land[idx] && region[idx])
+        .map(|idx| tri[idx])
+        .collect();
+    land_tri.sort_unstable_by(f32::total_cmp);
+    let tri_land_mean =
+        land_tri.iter().map(|&t| t as f64).sum::<f64>() / land_tri.len().max(1) as f64;
+
+    let region_land: Vec<bool> = land.iter().zip(region).map(|(&l, &r)| l && r).collect();
+    let (_, islands) = label_land(&region_land);
+    let land_area: f64 = islands.iter().map(|i| i.area_km2).sum();
+    let largest_landmass_pct = islands
+        .first()
+        .map_or(0.0, |i| i.area_km2 / land_area * 100.0);
+
+    Analytics {
+        schema_version: ANALYTICS_SCHEMA_VERSION,
+        sinuosity_index: both.sinuosity_index(),
+        sinuosity_index_rows: rows.sinuosity_index(),
+        sinuosity_index_columns: columns.sinuosity_index(),
+        straight_to_turn_ratio: both.straight_to_turn_ratio(),
+        hydro_drainage_pct: (drainage_cells as f64 / region_cells.max(1) as f64) * 100.0,
+        hypsometric_integral: Hypsometry::of_land(flat, sea_level, Some(region)).integral(),
+        tri_land_mean,
+        tri_land_p90: percentile_sorted(&land_tri, 90.0) as f64,
+        landmass_count: islands.len() as u32,
+        largest_landmass_pct,
+        islands_above_threshold: islands
+            .iter()
+            .filter(|i| i.area_km2 >= options.island_threshold_km2)
+            .count() as u32,
+        island_size_histogram: island_size_histogram(islands.iter().map(|i| i.area_km2)),
+        coastline_scales_cells: COASTLINE_SCALES,
+        coastline_km_by_scale: COASTLINE_SCALES
+            .iter()
+            .map(|&scale| coastline_length_km(&land, region, scale))
+            .collect(),
+        coastline_fractal_dimension: box_counting_dimension(&land, region),
+        latency_ms,
+    }
+}
+
+/// Structured analytics for a heightmap; `latency_ms` is passed through from the caller.
+#[wasm_bindgen]
+pub fn source_of_truth(flat: &[f32], latency_ms: f64) -> Result<Analytics, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    Ok(compute(
+        flat,
+        latency_ms,
+        &AnalyticsOptions::default(),
+        &vec![true; CELL_COUNT],
+    ))
+}
+
+/// `source_of_truth` serialised as JSON, including `schema_version`.
+#[wasm_bindgen]
+pub fn source_of_truth_json(flat: &[f32], latency_ms: f64) -> Result<String, JsValue> {
+    Ok(source_of_truth(flat, latency_ms)?.to_json())
+}
+
+/// `source_of_truth` as the flat array of `Analytics::to_array`, for tight readback loops.
+#[wasm_bindgen]
+pub fn source_of_truth_array(flat: &[f32], latency_ms: f64) -> Result<Box<[f64]>, JsValue> {
+    Ok(source_of_truth(flat, latency_ms)?.to_array())
+}
+
+/// `source_of_truth` with explicit thresholds, e.g. `AnalyticsOptions.for_sea_level(0.2)`
+/// after the user moves the coastline.
+#[wasm_bindgen]
+pub fn source_of_truth_with_options(
+    flat: &[f32],
+    latency_ms: f64,
+    options: &AnalyticsOptions,
+) -> Result<Analytics, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    options.validate()?;
+    Ok(compute(flat, latency_ms, options, &vec![true; CELL_COUNT]))
+}
+
+/// Analytics for the cell rectangle `[x, x + width) × [y, y + height)`,
+/// e.g. the area the user is zoomed into. Percentages are of the rectangle; landmasses and
+/// coastline are clipped to it.
+#[wasm_bindgen]
+pub fn region_analytics(
+    flat: &[f32],
+    latency_ms: f64,
+    options: &AnalyticsOptions,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<Analytics, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    options.validate()?;
+    let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+    // Checked so huge offsets cannot wrap past the bounds on 32-bit targets.
+    if width == 0
+        || height == 0
+        || x.checked_add(width).is_none_or(|end| end > WIDTH)
+        || y.checked_add(height).is_none_or(|end| end > HEIGHT)
+    {
+        return Err(JsValue::from_str(
+            "region must be a non-empty rectangle within the grid",
+        ));
+    }
+    let mut region = vec![false; CELL_COUNT];
+    for row in y..y + height {
+        region[row * WIDTH + x..row * WIDTH + x + width].fill(true);
+    }
+    Ok(compute(flat, latency_ms, options, &region))
+}
+
+/// Analytics for the cells where `mask` is non-zero (any shape, e.g. a lasso selection).
+#[wasm_bindgen]
+pub fn masked_analytics(
+    flat: &[f32],
+    latency_ms: f64,
+    options: &AnalyticsOptions,
+    mask: &[u8],
+) -> Result<Analytics, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_grid_len(mask, "region mask")?;
+    options.validate()?;
+    let region: Vec<bool> = mask.iter().map(|&m| m != 0).collect();
+    if !region.contains(&true) {
+        return Err(JsValue::from_str("region mask is empty"));
+    }
+    Ok(compute(flat, latency_ms, options, &region))
+}
+
+/// Elevation distribution: `bins` equal-width counts over [0, 1] (values outside clamp into
+/// the end bins) plus summary statistics and the requested `percentiles` (each in [0, 100]):
+/// `{"bins","bin_width","counts":[...],"min","max","mean","median",
+/// "percentiles":[{"p","value"},...]}`.
+#[wasm_bindgen]
+pub fn elevation_stats_json(
+    flat: &[f32],
+    bins: u32,
+    percentiles: &[f64],
+) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if bins == 0 || bins > 65_536 {
+        return Err(JsValue::from_str("bins must be within [1, 65536]"));
+    }
+    if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        return Err(JsValue::from_str("percentiles must be within [0, 100]"));
+    }
+    let mut counts = vec![0_u64; bins as usize];
+    for &h in flat {
+        let bin = (h.clamp(0.0, 1.0) * bins as f32) as usize;
+        counts[bin.min(bins as usize - 1)] += 1;
+    }
+    let mut sorted = flat.to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let mean = flat.iter().map(|&h| h as f64).sum::<f64>() / flat.len() as f64;
+    let quantiles = json::array(percentiles.iter().map(|&p| {
+        format!(
+            "{{\"p\":{p},\"value\":{:.6}}}",
+            percentile_sorted(&sorted, p)
+        )
+    }));
+    Ok(ObjectWriter::new()
+        .integer("bins", bins as u64)
+        .number("bin_width", 1.0 / bins as f64, 8)
+        .raw("counts", &json::array(counts))
+        .number("min", sorted[0] as f64, 6)
+        .number("max", sorted[sorted.len() - 1] as f64, 6)
+        .number("mean", mean, 6)
+        .number("median", percentile_sorted(&sorted, 50.0) as f64, 6)
+        .raw("percentiles", &quantiles)
+        .finish())
+}
+
+/// Hypsometric curve of land: relative area at or above each of `samples` evenly spaced
+/// relative heights (0 = lowest land, 1 = highest peak), area-weighted by latitude:
+/// `{"relative_height":[...],"relative_area":[...],"integral","min","max"}`. Land is cut
+/// at `options.sea_level`, so `integral` matches `Analytics::hypsometric_integral`.
+#[wasm_bindgen]
+pub fn hypsometric_curve_json(
+    flat: &[f32],
+    samples: u32,
+    options: &AnalyticsOptions,
+) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    options.validate()?;
+    if !(2..=4096).contains(&samples) {
+        return Err(JsValue::from_str("samples must be within [2, 4096]"));
+    }
+    let sea_level = options.sea_level;
+    let hypsometry = Hypsometry::of_land(flat, sea_level, None);
+    let count = samples as usize;
+    let heights = (0..count).map(|i| format!("{:.6}", i as f64 / (count - 1) as f64));
+    let areas = hypsometry
+        .curve(count)
+        .into_iter()
+        .map(|a| format!("{a:.6}"));
+    let (min, max) = hypsometry.range().unwrap_or((sea_level, sea_level));
+    Ok(ObjectWriter::new()
+        .raw("relative_height", &json::array(heights))
+        .raw("relative_area", &json::array(areas))
+        .number("integral", hypsometry.integral(), 6)
+        .number("min", min as f64, 6)
+        .number("max", max as f64, 6)
+        .finish())
+}
+
+/// Land share of each of `bands` equal latitude bands, north to south, area-weighted:
+/// `{"bands","band_deg","rows":[{"north","south","land_pct","land_km2"},...]}`. Rows are
+/// assigned to bands by their centre latitude, and land is cut at `options.sea_level` as in
+/// the other analytics.
+#[wasm_bindgen]
+pub fn land_by_latitude_json(
+    flat: &[f32],
+    bands: u32,
+    options: &AnalyticsOptions,
+) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    options.validate()?;
+    if bands == 0 || bands as usize > HEIGHT {
+        return Err(JsValue::from_str("bands must be within [1, grid height]"));
+    }
+    let bands = bands as usize;
+    let mut land_km2 = vec![0.0; bands];
+    let mut total_km2 = vec![0.0; bands];
+    for y in 0..HEIGHT {
+        let band = ((y as f64 + 0.5) / HEIGHT as f64 * bands as f64) as usize;
+        let area = cell_area_km2(y);
+        let land = flat[y * WIDTH..(y + 1) * WIDTH]
+            .iter()
+            .filter(|&&h| h >= options.sea_level)
+            .count();
+        land_km2[band] += land as f64 * area;
+        total_km2[band] += WIDTH as f64 * area;
+    }
+    let band_deg = 180.0 / bands as f64;
+    let rows = json::array((0..bands).map(|b| {
+        ObjectWriter::new()
+            .number("north", 90.0 - b as f64 * band_deg, 4)
+            .number("south", 90.0 - (b + 1) as f64 * band_deg, 4)
+            .number(
+                "land_pct",
+                land_km2[b] / total_km2[b].max(f64::MIN_POSITIVE) * 100.0,
+                4,
+            )
+            .number("land_km2", land_km2[b], 1)
+            .finish()
+    }));
+    Ok(ObjectWriter::new()
+        .integer("bands", bands as u64)
+        .number("band_deg", band_deg, 6)
+        .raw("rows", &rows)
+        .finish())
+}
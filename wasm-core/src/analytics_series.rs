@@ -0,0 +1,122 @@
+use wasm_bindgen::prelude::*;
+
+use crate::analytics::{ANALYTICS_SCHEMA_VERSION, Analytics};
+use crate::json::{self, Json, ObjectWriter};
+
+struct Snapshot {
+    index: u32,
+    label: String,
+    params: Vec<(String, Json)>,
+    /// `{"key":{"from","to"}}` against the previous snapshot's parameters.
+    changes: String,
+    analytics: String,
+}
+
+/// Parameter keys whose values differ between `previous` and `current`, with both values
+/// (`null` where a key is missing on one side).
+fn parameter_diff(previous: &[(String, Json)], current: &[(String, Json)]) -> String {
+    let lookup = |fields: &[(String, Json)], key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .unwrap_or(Json::Null)
+    };
+    let mut keys: Vec<&str> = current.iter().map(|(k, _)| k.as_str()).collect();
+    for (key, _) in previous {
+        if !keys.contains(&key.as_str()) {
+            keys.push(key);
+        }
+    }
+    let mut out = ObjectWriter::new();
+    for key in keys {
+        let (from, to) = (lookup(previous, key), lookup(current, key));
+        if from != to {
+            out.raw(key, &format!("{{\"from\":{from},\"to\":{to}}}"));
+        }
+    }
+    out.finish()
+}
+
+/// Session history of analytics across regenerations, each tagged with the generation
+/// parameters that produced it, so the UI can chart how tweaks move the metrics.
+#[wasm_bindgen]
+pub struct AnalyticsSeries {
+    capacity: usize,
+    next_index: u32,
+    snapshots: Vec<Snapshot>,
+}
+
+#[wasm_bindgen]
+impl AnalyticsSeries {
+    /// Keeps at most `capacity` snapshots, dropping the oldest; 0 keeps all.
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: u32) -> AnalyticsSeries {
+        Self {
+            capacity: capacity as usize,
+            next_index: 0,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// Records `analytics` with the flat JSON object of parameters that generated it
+    /// (e.g. `{"seed":1337,"coverage":0.4}`) and a free-form `label`. The change list is
+    /// taken against the previous snapshot, so the first lists every parameter.
+    pub fn record(
+        &mut self,
+        analytics: &Analytics,
+        params_json: &str,
+        label: &str,
+    ) -> Result<(), JsValue> {
+        let params = match json::parse(params_json).map_err(|e| JsValue::from_str(&e))? {
+            Json::Object(fields) => fields,
+            _ => return Err(JsValue::from_str("params must be a JSON object")),
+        };
+        let previous = self.snapshots.last().map_or(&[][..], |s| &s.params[..]);
+        let changes = parameter_diff(previous, &params);
+        if self.capacity > 0 && self.snapshots.len() == self.capacity {
+            self.snapshots.remove(0);
+        }
+        self.snapshots.push(Snapshot {
+            index: self.next_index,
+            label: label.to_owned(),
+            params,
+            changes,
+            analytics: analytics.to_json(),
+        });
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// Snapshots currently held.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// `{"analytics_schema_version","snapshots":[{"index","label","params","changed",
+    /// "analytics"},...]}`, oldest first. `index` counts every recording in the session, so
+    /// gaps show where old snapshots were dropped.
+    pub fn to_json(&self) -> String {
+        let snapshots = json::array(self.snapshots.iter().map(|s| {
+            ObjectWriter::new()
+                .integer("index", s.index as u64)
+                .raw("label", &json::quote(&s.label))
+                .raw("params", &Json::Object(s.params.clone()).to_string())
+                .raw("changed", &s.changes)
+                .raw("analytics", &s.analytics)
+                .finish()
+        }));
+        ObjectWriter::new()
+            .integer("analytics_schema_version", ANALYTICS_SCHEMA_VERSION as u64)
+            .raw("snapshots", &snapshots)
+            .finish()
+    }
+}
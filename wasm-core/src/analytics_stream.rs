@@ -0,0 +1,183 @@
+use wasm_bindgen::prelude::*;
+
+use crate::analytics::{
+    ANALYTICS_SCHEMA_VERSION, Analytics, AnalyticsOptions, ScanLine, TurnCounts,
+    island_size_histogram,
+};
+use crate::coastline::{COASTLINE_SCALES, CoastlineStream};
+use crate::grid::{HEIGHT, WIDTH};
+use crate::landmass::LandmassStream;
+use crate::stats::percentile_sorted;
+use crate::terrain::ruggedness_in_rows;
+use crate::vector::cell_area_km2;
+
+/// Whole-grid `source_of_truth_with_options` computed from row bands, for callers that never
+/// hold the full heightmap in one buffer (tiled generation, streamed files). Push chunks of
+/// whole rows from north to south, then call `finish`. Besides a few rows of context it
+/// keeps one value per land cell, for the TRI percentile.
+#[wasm_bindgen]
+pub struct AnalyticsAccumulator {
+    options: AnalyticsOptions,
+    next_row: usize,
+    rows: TurnCounts,
+    columns: TurnCounts,
+    column_lines: Vec<ScanLine>,
+    drainage_cells: u64,
+    land_area: f64,
+    land_weighted_sum: f64,
+    land_min: f32,
+    land_max: f32,
+    /// The last two rows received; TRI for a row is taken once the row south of it arrives.
+    window: Vec<Vec<f32>>,
+    land_tri: Vec<f32>,
+    landmasses: LandmassStream,
+    coastline: CoastlineStream,
+}
+
+#[wasm_bindgen]
+impl AnalyticsAccumulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &AnalyticsOptions) -> Result<AnalyticsAccumulator, JsValue> {
+        options.validate()?;
+        Ok(Self {
+            options: *options,
+            next_row: 0,
+            rows: TurnCounts::default(),
+            columns: TurnCounts::default(),
+            column_lines: vec![ScanLine::default(); WIDTH],
+            drainage_cells: 0,
+            land_area: 0.0,
+            land_weighted_sum: 0.0,
+            land_min: f32::INFINITY,
+            land_max: f32::NEG_INFINITY,
+            window: Vec::with_capacity(3),
+            land_tri: Vec::new(),
+            landmasses: LandmassStream::new(),
+            coastline: CoastlineStream::new(),
+        })
+    }
+
+    /// Rows received so far.
+    #[wasm_bindgen(getter)]
+    pub fn rows_received(&self) -> u32 {
+        self.next_row as u32
+    }
+
+    /// Feeds the next band of whole rows (a multiple of the grid width, row-major).
+    pub fn push_chunk(&mut self, chunk: &[f32]) -> Result<(), JsValue> {
+        if chunk.is_empty() || !chunk.len().is_multiple_of(WIDTH) {
+            return Err(JsValue::from_str(
+                "chunk length must be a non-zero multiple of the grid width",
+            ));
+        }
+        if self.next_row + chunk.len() / WIDTH > HEIGHT {
+            return Err(JsValue::from_str("chunk runs past the last grid row"));
+        }
+        for row in chunk.chunks_exact(WIDTH) {
+            self.push_row(row);
+        }
+        Ok(())
+    }
+
+    /// Analytics once every row has been pushed; matches `source_of_truth_with_options` on
+    /// the assembled heightmap up to floating-point summation order.
+    pub fn finish(mut self, latency_ms: f64) -> Result<Analytics, JsValue> {
+        if self.next_row != HEIGHT {
+            return Err(JsValue::from_str(&format!(
+                "accumulator has {} of {HEIGHT} rows",
+                self.next_row
+            )));
+        }
+        let last = self.window.len() - 1;
+        self.take_ruggedness([last.saturating_sub(1), last, last]);
+
+        let both = self.rows.combined(self.columns);
+        let mut land_tri = std::mem::take(&mut self.land_tri);
+        land_tri.sort_unstable_by(f32::total_cmp);
+        let tri_land_mean =
+            land_tri.iter().map(|&t| t as f64).sum::<f64>() / land_tri.len().max(1) as f64;
+        let hypsometric_integral = if self.land_max > self.land_min {
+            (self.land_weighted_sum / self.land_area - self.land_min as f64)
+                / (self.land_max - self.land_min) as f64
+        } else {
+            0.0
+        };
+        let islands = self.landmasses.finish();
+        let total_island_area: f64 = islands.iter().sum();
+        let (coastline_km_by_scale, coastline_fractal_dimension) = self.coastline.finish();
+
+        Ok(Analytics {
+            schema_version: ANALYTICS_SCHEMA_VERSION,
+            sinuosity_index: both.sinuosity_index(),
+            sinuosity_index_rows: self.rows.sinuosity_index(),
+            sinuosity_index_columns: self.columns.sinuosity_index(),
+            straight_to_turn_ratio: both.straight_to_turn_ratio(),
+            hydro_drainage_pct: self.drainage_cells as f64 / (WIDTH * HEIGHT) as f64 * 100.0,
+            hypsometric_integral,
+            tri_land_mean,
+            tri_land_p90: percentile_sorted(&land_tri, 90.0) as f64,
+            landmass_count: islands.len() as u32,
+            largest_landmass_pct: islands
+                .first()
+                .map_or(0.0, |a| a / total_island_area * 100.0),
+            islands_above_threshold: islands
+                .iter()
+                .filter(|&&a| a >= self.options.island_threshold_km2)
+                .count() as u32,
+            island_size_histogram: island_size_histogram(islands.iter().copied()),
+            coastline_scales_cells: COASTLINE_SCALES,
+            coastline_km_by_scale,
+            coastline_fractal_dimension,
+            latency_ms,
+        })
+    }
+}
+
+impl AnalyticsAccumulator {
+    fn push_row(&mut self, row: &[f32]) {
+        let y = self.next_row;
+        self.next_row += 1;
+        let options = self.options;
+
+        self.rows
+            .scan(row.iter().map(|&h| Some(h)), options.turn_threshold);
+        for (line, &h) in self.column_lines.iter_mut().zip(row) {
+            self.columns.step(line, Some(h), options.turn_threshold);
+        }
+        self.drainage_cells += row
+            .iter()
+            .filter(|&&h| h.clamp(0.0, 1.0) < options.drainage_cutoff)
+            .count() as u64;
+
+        let cell_area = cell_area_km2(y);
+        let land: Vec<bool> = row.iter().map(|&h| h >= options.sea_level).collect();
+        for (&h, _) in row.iter().zip(&land).filter(|(_, l)| **l) {
+            self.land_area += cell_area;
+            self.land_weighted_sum += h as f64 * cell_area;
+            self.land_min = self.land_min.min(h);
+            self.land_max = self.land_max.max(h);
+        }
+        self.landmasses.push_row(&land);
+        self.coastline.push_row(land);
+
+        self.window.push(row.to_vec());
+        match self.window.len() {
+            2 => self.take_ruggedness([0, 0, 1]),
+            3 => {
+                self.take_ruggedness([0, 1, 2]);
+                self.window.remove(0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Records TRI over land for the middle of the `window` rows given north to south.
+    fn take_ruggedness(&mut self, window: [usize; 3]) {
+        let rows = window.map(|i| self.window[i].as_slice());
+        for (x, &h) in rows[1].iter().enumerate() {
+            if h >= self.options.sea_level {
+                self.land_tri.push(ruggedness_in_rows(rows, x));
+            }
+        }
+    }
+}
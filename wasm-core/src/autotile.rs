@@ -0,0 +1,121 @@
+use wasm_bindgen::prelude::*;
+
+/// Neighbour offsets (dx, dy) and their bit in an 8-bit index, north-west first in
+/// reading order; the 4-bit index uses north 1, east 2, south 4, west 8.
+const BLOB_BITS: [(i64, i64, u8); 8] = [
+    (-1, -1, 1),
+    (0, -1, 2),
+    (1, -1, 4),
+    (-1, 0, 8),
+    (1, 0, 16),
+    (-1, 1, 32),
+    (0, 1, 64),
+    (1, 1, 128),
+];
+const EDGE_BITS: [(i64, i64, u8); 4] = [(0, -1, 1), (1, 0, 2), (0, 1, 4), (-1, 0, 8)];
+
+/// Index layout for `autotile_map`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct AutotileParams {
+    /// 4 for edge tilesets (16 tiles), 8 for blob tilesets (47 tiles).
+    pub bits: u32,
+    /// Joins the east and west edges, as on the grid and the tile exports.
+    pub wrap: bool,
+}
+
+impl Default for AutotileParams {
+    fn default() -> Self {
+        Self {
+            bits: 8,
+            wrap: true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl AutotileParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AutotileParams {
+        Self::default()
+    }
+}
+
+impl AutotileParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if self.bits != 4 && self.bits != 8 {
+            return Err(JsValue::from_str("bits must be 4 or 8"));
+        }
+        Ok(())
+    }
+}
+
+/// Autotile index of every cell of a class map (`terrain_class_map`, a biome map or the
+/// `terrain` layer of `export_square_tiles`; `width` × `height`, row-major), so tilemap
+/// engines can draw coastlines and borders straight from a standard tileset. Each bit
+/// records whether a neighbour belongs to the cell's own group:
+/// - 4 bits: north 1, east 2, south 4, west 8 — the 16-tile edge set.
+/// - 8 bits: north-west 1, north 2, north-east 4, west 8, east 16, south-west 32,
+///   south 64, south-east 128, with a corner kept only when both edges beside it are
+///   set, so just the 47 blob-set indices occur.
+///
+/// `groups` maps class ids to group ids so that several classes share one tile (e.g.
+/// `[0, 0, 1, 1, 1, 1, 1]` for terrain classes gives a plain land/water coastline); empty
+/// groups each class on its own. Past the north and south edges, and the east and west
+/// ones without `wrap`, neighbours count as the same group.
+#[wasm_bindgen]
+pub fn autotile_map(
+    classes: &[u8],
+    width: u32,
+    height: u32,
+    groups: &[u8],
+    params: &AutotileParams,
+) -> Result<Box<[u8]>, JsValue> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || classes.len() != w * h {
+        return Err(JsValue::from_str("class map length mismatch"));
+    }
+    if !groups.is_empty() && classes.iter().any(|&c| c as usize >= groups.len()) {
+        return Err(JsValue::from_str("groups must cover every class id"));
+    }
+    params.validate()?;
+    let group = |idx: usize| {
+        let class = classes[idx];
+        if groups.is_empty() {
+            class
+        } else {
+            groups[class as usize]
+        }
+    };
+    let same = |x: usize, y: usize, dx: i64, dy: i64| {
+        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+        if !(0..h as i64).contains(&ny) || !params.wrap && !(0..w as i64).contains(&nx) {
+            return true;
+        }
+        group(ny as usize * w + nx.rem_euclid(w as i64) as usize) == group(y * w + x)
+    };
+    let indices = (0..w * h)
+        .map(|idx| {
+            let (x, y) = (idx % w, idx / w);
+            if params.bits == 4 {
+                return EDGE_BITS
+                    .iter()
+                    .filter(|&&(dx, dy, _)| same(x, y, dx, dy))
+                    .fold(0, |index, &(_, _, bit)| index | bit);
+            }
+            let index = BLOB_BITS
+                .iter()
+                .filter(|&&(dx, dy, _)| same(x, y, dx, dy))
+                .fold(0, |index, &(_, _, bit)| index | bit);
+            // A corner only shows when both edges beside it join the cell's group.
+            let mut pruned = index;
+            for (corner, edges) in [(1, 2 | 8), (4, 2 | 16), (32, 64 | 8), (128, 64 | 16)] {
+                if index & edges != edges {
+                    pruned &= !corner;
+                }
+            }
+            pruned
+        })
+        .collect();
+    Ok(indices)
+}
@@ -0,0 +1,127 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::dryland::{DRYLAND_COLD_DESERT, DRYLAND_HOT_DESERT, dryland_class};
+use crate::grid::{CELL_COUNT, check_grid_len};
+use crate::permafrost::TREELINE_WARMEST_MONTH_C;
+
+pub(crate) const BIOME_WATER: u8 = 0;
+pub(crate) const BIOME_ICE: u8 = 1;
+pub(crate) const BIOME_TUNDRA: u8 = 2;
+pub(crate) const BIOME_TAIGA: u8 = 3;
+pub(crate) const BIOME_GRASSLAND: u8 = 4;
+pub(crate) const BIOME_WOODLAND: u8 = 5;
+pub(crate) const BIOME_TEMPERATE_FOREST: u8 = 6;
+pub(crate) const BIOME_TEMPERATE_RAINFOREST: u8 = 7;
+pub(crate) const BIOME_DESERT: u8 = 8;
+pub(crate) const BIOME_SAVANNA: u8 = 9;
+pub(crate) const BIOME_TROPICAL_RAINFOREST: u8 = 10;
+pub(crate) const BIOME_COLD_DESERT: u8 = 11;
+/// Cell of a biome override layer that keeps the classified biome.
+pub(crate) const NO_BIOME_OVERRIDE: u8 = 255;
+
+/// (name, legend colour) indexed by biome id.
+pub(crate) const BIOMES: [(&str, &str); 12] = [
+    ("Water", "#2e5a88"),
+    ("Ice", "#f2f4f7"),
+    ("Tundra", "#a8b4a0"),
+    ("Taiga", "#4f6f4a"),
+    ("Temperate grassland", "#c2c27a"),
+    ("Woodland and shrubland", "#a8a25a"),
+    ("Temperate forest", "#5b8c3e"),
+    ("Temperate rainforest", "#2f6b3f"),
+    ("Subtropical desert", "#e0c98f"),
+    ("Savanna", "#b8b04a"),
+    ("Tropical rainforest", "#1f5e2a"),
+    ("Cold desert", "#c9bfa4"),
+];
+
+/// Whittaker diagram lookup from mean annual temperature (°C) and precipitation (mm/year).
+/// Deserts come from the moisture deficit against PET rather than fixed rainfall cutoffs;
+/// tundra and ice sit beyond the warmest-month treeline shared with the permafrost zoning.
+pub(crate) fn whittaker(temp_c: f32, warmest_month_c: f32, precip_mm: f32, pet_mm: f32) -> u8 {
+    match dryland_class(temp_c, precip_mm, pet_mm) {
+        DRYLAND_HOT_DESERT => return BIOME_DESERT,
+        DRYLAND_COLD_DESERT => return BIOME_COLD_DESERT,
+        _ => {}
+    }
+    if warmest_month_c < 0.0 {
+        BIOME_ICE
+    } else if warmest_month_c < TREELINE_WARMEST_MONTH_C {
+        BIOME_TUNDRA
+    } else if temp_c < 5.0 {
+        if precip_mm < 300.0 {
+            BIOME_GRASSLAND
+        } else {
+            BIOME_TAIGA
+        }
+    } else if temp_c < 20.0 {
+        let warmth = temp_c - 5.0;
+        if precip_mm < 250.0 + 20.0 * warmth {
+            BIOME_GRASSLAND
+        } else if precip_mm < 600.0 + 30.0 * warmth {
+            BIOME_WOODLAND
+        } else if precip_mm < 2200.0 {
+            BIOME_TEMPERATE_FOREST
+        } else {
+            BIOME_TEMPERATE_RAINFOREST
+        }
+    } else if precip_mm < 2500.0 {
+        BIOME_SAVANNA
+    } else {
+        BIOME_TROPICAL_RAINFOREST
+    }
+}
+
+/// Whittaker biome id per cell (0 = water); see `biome_legend_json` for names and colours.
+/// The zones are defined on annual means, so `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn whittaker_biomes(climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("whittaker_biomes")?;
+    let mut biomes = vec![BIOME_WATER; CELL_COUNT];
+    for (idx, biome) in biomes.iter_mut().enumerate() {
+        if climate.water_distance[idx] > 0.0 {
+            *biome = whittaker(
+                climate.temperature[idx],
+                climate.warmest_month_at(idx),
+                climate.precipitation[idx],
+                climate.pet[idx],
+            );
+        }
+    }
+    Ok(biomes.into_boxed_slice())
+}
+
+/// `biomes` (from `whittaker_biomes` or `classify_biomes_with_rules`) with the hand-painted
+/// biomes in `overrides` laid over it, as `TerrainEditor::biome_overrides` returns them
+/// (255 = no override). Water stays water, so an override on ground later flooded lies
+/// dormant until it dries out again.
+#[wasm_bindgen]
+pub fn apply_biome_overrides(biomes: &[u8], overrides: &[u8]) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(biomes, "biome map")?;
+    check_grid_len(overrides, "biome overrides")?;
+    Ok(biomes
+        .iter()
+        .zip(overrides)
+        .map(|(&biome, &paint)| {
+            if biome == BIOME_WATER || paint == NO_BIOME_OVERRIDE {
+                biome
+            } else {
+                paint
+            }
+        })
+        .collect())
+}
+
+/// Legend for `whittaker_biomes`: `[{"id","name","color"}, ...]`.
+#[wasm_bindgen]
+pub fn biome_legend_json() -> String {
+    let entries: Vec<String> = BIOMES
+        .iter()
+        .enumerate()
+        .map(|(id, (name, color))| {
+            format!("{{\"id\":{id},\"name\":\"{name}\",\"color\":\"{color}\"}}")
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
@@ -0,0 +1,198 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{BIOME_WATER, BIOMES, whittaker};
+use crate::climate::Climate;
+use crate::dryland::aridity_index;
+use crate::grid::{CELL_COUNT, check_grid_len};
+use crate::json::{self, Json};
+
+/// Inclusive `[min, max]` bounds; either end may be open.
+#[derive(Clone, Copy)]
+struct Range {
+    min: f32,
+    max: f32,
+}
+
+impl Range {
+    const ANY: Range = Range {
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    };
+
+    fn contains(&self, v: f32) -> bool {
+        v >= self.min && v <= self.max
+    }
+}
+
+struct BiomeRule {
+    id: u8,
+    name: String,
+    color: String,
+    temperature: Range,
+    precipitation: Range,
+    moisture: Range,
+    elevation: Range,
+}
+
+/// Ordered user classification table. Rules are tried first to last and the first match
+/// wins; land cells no rule matches keep their built-in Whittaker biome.
+#[wasm_bindgen]
+pub struct BiomeRuleTable {
+    rules: Vec<BiomeRule>,
+}
+
+fn parse_range(rule: &Json, key: &str) -> Result<Range, String> {
+    let Some(value) = rule.get(key) else {
+        return Ok(Range::ANY);
+    };
+    let bound = |v: &Json, open: f32| match v {
+        Json::Null => Ok(open),
+        _ => v
+            .as_f64()
+            .map(|n| n as f32)
+            .ok_or_else(|| format!("{key} bounds must be numbers or null")),
+    };
+    match value.as_array() {
+        Some([min, max]) => {
+            let range = Range {
+                min: bound(min, f32::NEG_INFINITY)?,
+                max: bound(max, f32::INFINITY)?,
+            };
+            if range.min > range.max {
+                return Err(format!("{key} min exceeds max"));
+            }
+            Ok(range)
+        }
+        _ => Err(format!("{key} must be a [min, max] pair")),
+    }
+}
+
+fn parse_rule(rule: &Json) -> Result<BiomeRule, String> {
+    let id = rule
+        .get("id")
+        .and_then(Json::as_f64)
+        .filter(|id| id.fract() == 0.0 && (1.0..=254.0).contains(id))
+        .ok_or("rule id must be an integer in [1, 254]")? as u8;
+    let text = |key: &str| {
+        rule.get(key)
+            .and_then(Json::as_str)
+            .map(str::to_owned)
+            .ok_or_else(|| format!("rule {id} needs a string {key}"))
+    };
+    let range = |key: &str| parse_range(rule, key).map_err(|e| format!("rule {id}: {e}"));
+    Ok(BiomeRule {
+        id,
+        name: text("name")?,
+        color: text("color")?,
+        temperature: range("temperature")?,
+        precipitation: range("precipitation")?,
+        moisture: range("moisture")?,
+        elevation: range("elevation")?,
+    })
+}
+
+#[wasm_bindgen]
+impl BiomeRuleTable {
+    /// Parses `{"rules":[{"id","name","color","temperature","precipitation","moisture",
+    /// "elevation"}, ...]}`. Ranges are `[min, max]` with `null` for an open end and may be
+    /// omitted: temperature in °C (annual mean), precipitation in mm/year, moisture as the
+    /// aridity index P/PET, elevation as the normalised heightmap value. Id 0 is water;
+    /// reusing a built-in id (1–11) overrides that biome's name and colour in the legend; id 255
+    /// is reserved for "no override" in painted biomes.
+    #[wasm_bindgen(constructor)]
+    pub fn new(json_text: &str) -> Result<BiomeRuleTable, JsValue> {
+        let parse = || -> Result<BiomeRuleTable, String> {
+            let root = json::parse(json_text)?;
+            let rules = root
+                .get("rules")
+                .and_then(Json::as_array)
+                .ok_or("biome rules need a \"rules\" array")?;
+            let rules = rules.iter().map(parse_rule).collect::<Result<_, _>>()?;
+            Ok(BiomeRuleTable { rules })
+        };
+        parse().map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Number of rules in the table.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Legend covering built-in and custom ids: `[{"id","name","color"}, ...]` sorted by id.
+    /// Where several rules share an id, the first one names it.
+    pub fn legend_json(&self) -> String {
+        let mut entries: Vec<(u8, &str, &str)> = BIOMES
+            .iter()
+            .enumerate()
+            .map(|(id, (name, color))| (id as u8, *name, *color))
+            .collect();
+        let mut named: Vec<u8> = Vec::new();
+        for rule in &self.rules {
+            if named.contains(&rule.id) {
+                continue;
+            }
+            named.push(rule.id);
+            let entry = (rule.id, rule.name.as_str(), rule.color.as_str());
+            match entries.iter_mut().find(|e| e.0 == rule.id) {
+                Some(existing) => *existing = entry,
+                None => entries.push(entry),
+            }
+        }
+        entries.sort_by_key(|e| e.0);
+        let entries: Vec<String> = entries
+            .iter()
+            .map(|(id, name, color)| {
+                format!(
+                    "{{\"id\":{id},\"name\":{},\"color\":{}}}",
+                    json::quote(name),
+                    json::quote(color)
+                )
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+impl BiomeRuleTable {
+    fn classify(&self, temp_c: f32, precip_mm: f32, moisture: f32, elevation: f32) -> Option<u8> {
+        self.rules
+            .iter()
+            .find(|r| {
+                r.temperature.contains(temp_c)
+                    && r.precipitation.contains(precip_mm)
+                    && r.moisture.contains(moisture)
+                    && r.elevation.contains(elevation)
+            })
+            .map(|r| r.id)
+    }
+}
+
+/// Biome id per cell from a user rule table (0 = water), falling back to the built-in
+/// Whittaker biome where no rule matches. See `BiomeRuleTable::legend_json`. Rules are
+/// written against annual means, so `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn classify_biomes_with_rules(
+    flat: &[f32],
+    climate: &Climate,
+    table: &BiomeRuleTable,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    climate.require_annual("classify_biomes_with_rules")?;
+    let mut biomes = vec![BIOME_WATER; CELL_COUNT];
+    for (idx, biome) in biomes.iter_mut().enumerate() {
+        if climate.water_distance[idx] <= 0.0 {
+            continue;
+        }
+        let temp = climate.temperature[idx];
+        let precip = climate.precipitation[idx];
+        let pet = climate.pet[idx];
+        *biome = table
+            .classify(temp, precip, aridity_index(precip, pet), flat[idx])
+            .unwrap_or_else(|| whittaker(temp, climate.warmest_month_at(idx), precip, pet));
+    }
+    Ok(biomes.into_boxed_slice())
+}
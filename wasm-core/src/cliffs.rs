@@ -0,0 +1,58 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, SEA_LEVEL, check_grid_len};
+use crate::terrain::slope_degrees;
+use crate::vector::{cell_path_polylines, polylines_json, skeleton_paths, thin_mask};
+
+/// Cliff cells and their centre lines from `detect_cliffs`.
+#[wasm_bindgen]
+pub struct Cliffs {
+    mask: Vec<u8>,
+    lines: Vec<Vec<(f32, f32)>>,
+}
+
+#[wasm_bindgen]
+impl Cliffs {
+    /// 1 where land is steeper than the threshold, else 0.
+    pub fn mask(&self) -> Box<[u8]> {
+        self.mask.clone().into_boxed_slice()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn line_count(&self) -> u32 {
+        self.lines.len() as u32
+    }
+
+    /// Cliff lines as `[[[x,y],...],...]` in cell-centre grid coordinates, following the
+    /// middle of each cliff band; split at junctions and at the east–west seam.
+    pub fn lines_json(&self) -> String {
+        polylines_json(&self.lines)
+    }
+}
+
+/// Flags land cells whose slope exceeds `min_slope_deg` (heights in metres over cells at
+/// their equatorial width, as in `landform_classes`) and thins each cliff band to a line
+/// for hachures or cliff symbols. `tolerance` simplifies the lines, in cells.
+#[wasm_bindgen]
+pub fn detect_cliffs(flat: &[f32], min_slope_deg: f32, tolerance: f32) -> Result<Cliffs, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(min_slope_deg > 0.0 && min_slope_deg < 90.0) {
+        return Err(JsValue::from_str("min_slope_deg must be within (0, 90)"));
+    }
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(JsValue::from_str("tolerance must be >= 0"));
+    }
+    let cliff: Vec<bool> = (0..CELL_COUNT)
+        .map(|idx| flat[idx] >= SEA_LEVEL && slope_degrees(flat, idx) > min_slope_deg)
+        .collect();
+    let mut skeleton = cliff.clone();
+    thin_mask(&mut skeleton);
+    let lines = skeleton_paths(&skeleton)
+        .iter()
+        .flat_map(|path| cell_path_polylines(path, tolerance))
+        .collect();
+    Ok(Cliffs {
+        mask: cliff.into_iter().map(u8::from).collect(),
+        lines,
+    })
+}
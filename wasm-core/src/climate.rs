@@ -0,0 +1,383 @@
+use wasm_bindgen::prelude::*;
+
+use crate::currents::current_anomaly;
+use crate::grid::{
+    CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, distance_field, latitude_deg,
+    sample_bilinear,
+};
+use crate::monsoon::monsoon_winds;
+
+/// Metres represented by the full [sea_level, 1.0] land elevation span.
+pub(crate) const RELIEF_METRES: f32 = 8000.0;
+const LAPSE_RATE_C_PER_M: f32 = 0.0065;
+/// Converts mean per-iteration rainfall (moisture units) into mm/year.
+const PRECIP_SCALE_MM: f32 = 120_000.0;
+const BASE_RAIN_RATE: f32 = 0.02;
+const SATURATION_RAIN_RATE: f32 = 0.5;
+/// Fraction of carried moisture dropped per 1000 m of windward ascent.
+const OROGRAPHIC_RATE: f32 = 0.6;
+const LAND_EVAPORATION_FACTOR: f32 = 0.15;
+/// Holdridge potential evapotranspiration per °C of biotemperature, mm/year.
+const HOLDRIDGE_PET_MM_PER_C: f32 = 58.93;
+/// Relative humidity added right at the shoreline, decaying inland.
+const COASTAL_HUMIDITY_BOOST: f32 = 0.15;
+const COASTAL_HUMIDITY_REACH_CELLS: f32 = 20.0;
+const CONTINENTALITY_REACH_CELLS: f32 = 150.0;
+const AXIAL_TILT_DEG: f32 = 23.44;
+/// Surface temperatures trail the sun by roughly a month.
+const SEASONAL_LAG_MONTHS: f32 = 1.0;
+/// Fraction of the solar declination by which the circulation belts follow the sun.
+const BELT_SHIFT_FACTOR: f32 = 0.5;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct ClimateParams {
+    /// Advection passes; each pass moves moisture `transport_cells` downwind.
+    pub iterations: u32,
+    /// Fraction of the saturation deficit evaporated from ocean cells per pass.
+    pub evaporation: f32,
+    pub transport_cells: f32,
+    pub sea_level: f32,
+    /// Scale on gyre boundary-current temperature anomalies; 0 disables ocean currents.
+    pub ocean_currents: f32,
+    /// 0 = annual mean; 1–12 = January–December, shifting the solar declination.
+    pub season: u32,
+    /// Scale on seasonal wind reversal over large warm landmasses (monthly runs only).
+    pub monsoon_strength: f32,
+}
+
+impl Default for ClimateParams {
+    fn default() -> Self {
+        Self {
+            iterations: 64,
+            evaporation: 0.12,
+            transport_cells: 6.0,
+            sea_level: SEA_LEVEL,
+            ocean_currents: 1.0,
+            season: 0,
+            monsoon_strength: 1.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ClimateParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ClimateParams {
+        Self::default()
+    }
+}
+
+impl ClimateParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if self.iterations == 0 {
+            return Err(JsValue::from_str("iterations must be > 0"));
+        }
+        if !(0.0..=1.0).contains(&self.evaporation) {
+            return Err(JsValue::from_str("evaporation must be within [0.0, 1.0]"));
+        }
+        if !self.transport_cells.is_finite() || self.transport_cells <= 0.0 {
+            return Err(JsValue::from_str("transport_cells must be > 0"));
+        }
+        if !(0.0..1.0).contains(&self.sea_level) {
+            return Err(JsValue::from_str("sea_level must be within [0.0, 1.0)"));
+        }
+        if !self.ocean_currents.is_finite() || self.ocean_currents < 0.0 {
+            return Err(JsValue::from_str("ocean_currents must be >= 0"));
+        }
+        if !self.monsoon_strength.is_finite() || self.monsoon_strength < 0.0 {
+            return Err(JsValue::from_str("monsoon_strength must be >= 0"));
+        }
+        if self.season > 12 {
+            return Err(JsValue::from_str(
+                "season must be 0 (annual) or a month 1-12",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Per-cell climate layers on the fixed grid.
+#[wasm_bindgen]
+pub struct Climate {
+    pub(crate) temperature: Vec<f32>,
+    pub(crate) precipitation: Vec<f32>,
+    pub(crate) moisture: Vec<f32>,
+    pub(crate) current_anomaly: Vec<f32>,
+    pub(crate) humidity: Vec<f32>,
+    pub(crate) pet: Vec<f32>,
+    pub(crate) evapotranspiration: Vec<f32>,
+    /// Cells to the nearest water cell; 0 over water.
+    pub(crate) water_distance: Vec<f32>,
+    /// Surface wind per cell in grid space (+x east, +y south).
+    pub(crate) winds: Vec<(f32, f32)>,
+    /// `ClimateParams::season` the fields were simulated for.
+    pub(crate) season: u32,
+}
+
+#[wasm_bindgen]
+impl Climate {
+    /// Mean temperature for the simulated season, °C.
+    pub fn temperature(&self) -> Box<[f32]> {
+        self.temperature.clone().into_boxed_slice()
+    }
+
+    /// Precipitation, mm/year. Monthly runs report that month's annualised rate.
+    pub fn precipitation(&self) -> Box<[f32]> {
+        self.precipitation.clone().into_boxed_slice()
+    }
+
+    /// Column moisture left after the final advection pass (saturation ≈ 1.0 at 30 °C).
+    pub fn moisture(&self) -> Box<[f32]> {
+        self.moisture.clone().into_boxed_slice()
+    }
+
+    /// Temperature adjustment from ocean boundary currents, °C (already included in
+    /// `temperature`).
+    pub fn current_anomaly(&self) -> Box<[f32]> {
+        self.current_anomaly.clone().into_boxed_slice()
+    }
+
+    /// Mean relative humidity over the run, [0, 1].
+    pub fn humidity(&self) -> Box<[f32]> {
+        self.humidity.clone().into_boxed_slice()
+    }
+
+    /// Potential evapotranspiration, mm/year.
+    pub fn potential_evapotranspiration(&self) -> Box<[f32]> {
+        self.pet.clone().into_boxed_slice()
+    }
+
+    /// Actual evapotranspiration, mm/year, limited by both precipitation and PET.
+    pub fn evapotranspiration(&self) -> Box<[f32]> {
+        self.evapotranspiration.clone().into_boxed_slice()
+    }
+
+    /// Season simulated: 0 = annual mean, 1–12 = January–December.
+    pub fn season(&self) -> u32 {
+        self.season
+    }
+}
+
+impl Climate {
+    /// Half the annual temperature swing at `idx`, °C.
+    pub(crate) fn amplitude_at(&self, idx: usize) -> f32 {
+        temperature_amplitude(latitude_deg(idx / WIDTH), self.water_distance[idx])
+    }
+
+    /// Mean temperature of the warmest month at `idx`, °C.
+    pub(crate) fn warmest_month_at(&self, idx: usize) -> f32 {
+        self.temperature[idx] + self.amplitude_at(idx)
+    }
+
+    /// Rejects monthly runs for classifiers defined on annual means; `what` names the caller.
+    pub(crate) fn require_annual(&self, what: &str) -> Result<(), JsValue> {
+        if self.season != 0 {
+            return Err(JsValue::from_str(&format!(
+                "{what} needs an annual climate (season 0)"
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn elevation_metres(h: f32, sea_level: f32) -> f32 {
+    ((h - sea_level) / (1.0 - sea_level)).max(0.0) * RELIEF_METRES
+}
+
+fn sea_level_temperature(lat_deg: f32) -> f32 {
+    -22.0 + 50.0 * lat_deg.to_radians().cos()
+}
+
+/// Saturation moisture for air at `temp_c` (Clausius–Clapeyron-shaped, 1.0 at 30 °C).
+pub(crate) fn saturation(temp_c: f32) -> f32 {
+    (0.065 * (temp_c - 30.0)).exp().clamp(0.02, 1.2)
+}
+
+pub(crate) fn temperature_field(flat: &[f32], sea_level: f32) -> Vec<f32> {
+    let mut temperature = vec![0.0; CELL_COUNT];
+    for y in 0..HEIGHT {
+        let base = sea_level_temperature(latitude_deg(y));
+        for x in 0..WIDTH {
+            let idx = y * WIDTH + x;
+            temperature[idx] = base - elevation_metres(flat[idx], sea_level) * LAPSE_RATE_C_PER_M;
+        }
+    }
+    temperature
+}
+
+/// Half the annual temperature range, °C: negligible at the equator, growing with latitude
+/// and with distance from moderating water.
+pub(crate) fn temperature_amplitude(lat_deg: f32, water_distance: f32) -> f32 {
+    lat_deg.to_radians().sin().abs() * (4.0 + 14.0 * continentality(water_distance))
+}
+
+/// 0 at the shore, approaching 1 deep inside large landmasses.
+pub(crate) fn continentality(water_distance: f32) -> f32 {
+    1.0 - (-water_distance / CONTINENTALITY_REACH_CELLS).exp()
+}
+
+/// Holdridge PET from biotemperature (temperature clamped to [0, 30] °C), damped in humid
+/// air where the vapour-pressure deficit is small.
+pub(crate) fn potential_evapotranspiration(temp_c: f32, humidity: f32) -> f32 {
+    HOLDRIDGE_PET_MM_PER_C * temp_c.clamp(0.0, 30.0) * (1.15 - 0.3 * humidity)
+}
+
+/// Fu/Choudhury form of the Budyko curve (n = 2): AET approaches the smaller of
+/// precipitation and PET.
+pub(crate) fn actual_evapotranspiration(precip_mm: f32, pet_mm: f32) -> f32 {
+    if precip_mm <= 0.0 || pet_mm <= 0.0 {
+        return 0.0;
+    }
+    precip_mm * pet_mm / (precip_mm * precip_mm + pet_mm * pet_mm).sqrt()
+}
+
+/// Solar declination (degrees) at the middle of `month` (0 = January, fractional allowed).
+pub(crate) fn solar_declination(month: f32) -> f32 {
+    let day = month * 30.44 + 15.0;
+    -AXIAL_TILT_DEG * (std::f32::consts::TAU * (day + 10.0) / 365.0).cos()
+}
+
+/// Declination driving a season's climate, lagged behind the sun; 0 for the annual mean.
+fn seasonal_declination(season: u32) -> f32 {
+    if season == 0 {
+        return 0.0;
+    }
+    solar_declination(season as f32 - 1.0 - SEASONAL_LAG_MONTHS)
+}
+
+fn ramp(a: f32, edge: f32, below: f32, above: f32) -> f32 {
+    let t = ((a - (edge - 5.0)) / 10.0).clamp(0.0, 1.0);
+    below + (above - below) * t
+}
+
+/// Prevailing surface wind in grid space (+x east, +y south) for a three-cell circulation:
+/// trades below 30°, westerlies to 60°, polar easterlies beyond.
+pub(crate) fn prevailing_wind(lat_deg: f32) -> (f32, f32) {
+    let a = lat_deg.abs();
+    let (zonal, equatorward) = if a < 45.0 {
+        (ramp(a, 30.0, -1.0, 1.0), ramp(a, 30.0, 0.35, -0.25))
+    } else {
+        (ramp(a, 60.0, 1.0, -0.6), ramp(a, 60.0, -0.25, 0.25))
+    };
+    (zonal, equatorward * lat_deg.signum())
+}
+
+pub(crate) fn simulate(flat: &[f32], params: &ClimateParams) -> Climate {
+    let sea_level = params.sea_level;
+    let water: Vec<bool> = flat.iter().map(|&h| h < sea_level).collect();
+    let water_distance = distance_field(&water);
+    let declination = seasonal_declination(params.season);
+    let anomaly = current_anomaly(flat, sea_level, params.ocean_currents);
+    let mut temperature = temperature_field(flat, sea_level);
+    for y in 0..HEIGHT {
+        let lat = latitude_deg(y);
+        let swing = declination / AXIAL_TILT_DEG * lat.signum();
+        for x in 0..WIDTH {
+            let idx = y * WIDTH + x;
+            temperature[idx] +=
+                anomaly[idx] + swing * temperature_amplitude(lat, water_distance[idx]);
+        }
+    }
+    let mut winds = monsoon_winds(
+        &water,
+        declination / AXIAL_TILT_DEG,
+        params.monsoon_strength,
+    );
+    for y in 0..HEIGHT {
+        let (u, v) = prevailing_wind(latitude_deg(y) - declination * BELT_SHIFT_FACTOR);
+        for wind in &mut winds[y * WIDTH..(y + 1) * WIDTH] {
+            wind.0 += u;
+            wind.1 += v;
+        }
+    }
+    let capacity: Vec<f32> = temperature.iter().map(|&t| saturation(t)).collect();
+    let metres: Vec<f32> = flat
+        .iter()
+        .map(|&h| elevation_metres(h, sea_level))
+        .collect();
+    let step = params.transport_cells;
+
+    let mut moisture: Vec<f32> = (0..CELL_COUNT)
+        .map(|i| if flat[i] < sea_level { 0.8 } else { 0.3 } * capacity[i])
+        .collect();
+    let mut advected = vec![0.0; CELL_COUNT];
+    let mut rain_total = vec![0.0_f32; CELL_COUNT];
+    let mut moisture_total = vec![0.0_f32; CELL_COUNT];
+
+    for _ in 0..params.iterations {
+        for (i, m) in moisture.iter_mut().enumerate() {
+            let deficit = (capacity[i] - *m).max(0.0);
+            let rate = if flat[i] < sea_level {
+                params.evaporation
+            } else {
+                params.evaporation * LAND_EVAPORATION_FACTOR
+            };
+            *m += rate * deficit;
+        }
+
+        // Semi-Lagrangian transport: each cell pulls moisture from one step upwind.
+        for (idx, &(u, v)) in winds.iter().enumerate() {
+            let (x, y) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+            advected[idx] = sample_bilinear(&moisture, x - u * step, y - v * step);
+        }
+        std::mem::swap(&mut moisture, &mut advected);
+
+        for (idx, &(u, v)) in winds.iter().enumerate() {
+            let (x, y) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+            let m = moisture[idx];
+            let mut rain = m * BASE_RAIN_RATE;
+            if m > capacity[idx] {
+                rain += (m - capacity[idx]) * SATURATION_RAIN_RATE;
+            }
+            let upwind = sample_bilinear(&metres, x - u * step, y - v * step);
+            let ascent = (metres[idx] - upwind).max(0.0);
+            rain += m * (ascent / 1000.0 * OROGRAPHIC_RATE).min(0.5);
+            let rain = rain.min(m);
+            moisture[idx] = m - rain;
+            rain_total[idx] += rain;
+            moisture_total[idx] += m - rain;
+        }
+    }
+
+    let scale = PRECIP_SCALE_MM / params.iterations as f32;
+    let precipitation: Vec<f32> = rain_total.into_iter().map(|r| r * scale).collect();
+
+    let humidity: Vec<f32> = (0..CELL_COUNT)
+        .map(|i| {
+            let mean = moisture_total[i] / params.iterations as f32;
+            let coastal =
+                COASTAL_HUMIDITY_BOOST * (-water_distance[i] / COASTAL_HUMIDITY_REACH_CELLS).exp();
+            (mean / capacity[i] + coastal).clamp(0.0, 1.0)
+        })
+        .collect();
+    let pet: Vec<f32> = (0..CELL_COUNT)
+        .map(|i| potential_evapotranspiration(temperature[i], humidity[i]))
+        .collect();
+    let evapotranspiration = (0..CELL_COUNT)
+        .map(|i| actual_evapotranspiration(precipitation[i], pet[i]))
+        .collect();
+
+    Climate {
+        temperature,
+        precipitation,
+        moisture,
+        current_anomaly: anomaly,
+        humidity,
+        pet,
+        evapotranspiration,
+        water_distance,
+        winds,
+        season: params.season,
+    }
+}
+
+/// Runs the moisture advection model: ocean evaporation, downwind transport along the
+/// prevailing winds, and rainfall from saturation and windward uplift. Set `params.season`
+/// to a month to simulate January vs July with the sun and circulation belts shifted.
+#[wasm_bindgen]
+pub fn simulate_climate(flat: &[f32], params: &ClimateParams) -> Result<Climate, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    Ok(simulate(flat, params))
+}
@@ -0,0 +1,132 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{
+    BIOME_COLD_DESERT, BIOME_DESERT, BIOME_ICE, BIOME_TAIGA, BIOME_TEMPERATE_FOREST,
+    BIOME_TEMPERATE_RAINFOREST, BIOME_TROPICAL_RAINFOREST, BIOMES, whittaker,
+};
+use crate::climate::Climate;
+use crate::dryland::{ARIDITY_CAP, UNEP_ARIDITY_CLASSES, aridity_index, unep_aridity_class};
+use crate::grid::{CELL_COUNT, WIDTH};
+use crate::stats::{weighted_mean, weighted_quantiles};
+use crate::vector::cell_area_km2;
+
+fn aridity_json(climate: &Climate, land: &[usize]) -> String {
+    let mut samples: Vec<(f32, f64)> = land
+        .iter()
+        .map(|&idx| {
+            let ai = aridity_index(climate.precipitation[idx], climate.pet[idx]).min(ARIDITY_CAP);
+            (ai, cell_area_km2(idx / WIDTH))
+        })
+        .collect();
+    let total: f64 = samples.iter().map(|s| s.1).sum();
+    let mut class_area = [0.0_f64; UNEP_ARIDITY_CLASSES.len()];
+    for &(ai, area) in &samples {
+        class_area[unep_aridity_class(ai)] += area;
+    }
+    let pct = |area: f64| {
+        if total > 0.0 {
+            area / total * 100.0
+        } else {
+            0.0
+        }
+    };
+    let classes: Vec<String> = UNEP_ARIDITY_CLASSES
+        .iter()
+        .zip(class_area)
+        .map(|(name, area)| format!("\"{name}\":{:.3}", pct(area)))
+        .collect();
+    let dryland_area: f64 = class_area[..UNEP_ARIDITY_CLASSES.len() - 1].iter().sum();
+    let mean = weighted_mean(&samples);
+    let q = weighted_quantiles(&mut samples, &[0.1, 0.5, 0.9]);
+    format!(
+        "{{\"mean\":{mean:.4},\"p10\":{:.4},\"p50\":{:.4},\"p90\":{:.4},\"class_pct\":{{{}}},\"dryland_pct\":{:.3}}}",
+        q[0],
+        q[1],
+        q[2],
+        classes.join(","),
+        pct(dryland_area),
+    )
+}
+
+const FOREST_BIOMES: [u8; 4] = [
+    BIOME_TAIGA,
+    BIOME_TEMPERATE_FOREST,
+    BIOME_TEMPERATE_RAINFOREST,
+    BIOME_TROPICAL_RAINFOREST,
+];
+
+fn temperature_json(climate: &Climate, land: &[usize]) -> String {
+    let weighted = |idx: usize| (climate.temperature[idx], cell_area_km2(idx / WIDTH));
+    let all: Vec<(f32, f64)> = (0..CELL_COUNT).map(weighted).collect();
+    let land: Vec<(f32, f64)> = land.iter().map(|&idx| weighted(idx)).collect();
+    format!(
+        "{{\"global_mean_c\":{:.3},\"land_mean_c\":{:.3}}}",
+        weighted_mean(&all),
+        weighted_mean(&land)
+    )
+}
+
+fn precipitation_json(climate: &Climate, land: &[usize]) -> String {
+    let mut samples: Vec<(f32, f64)> = land
+        .iter()
+        .map(|&idx| (climate.precipitation[idx], cell_area_km2(idx / WIDTH)))
+        .collect();
+    let mean = weighted_mean(&samples);
+    let q = weighted_quantiles(&mut samples, &[0.1, 0.25, 0.5, 0.75, 0.9]);
+    format!(
+        "{{\"land_mean_mm\":{mean:.1},\"p10\":{:.1},\"p25\":{:.1},\"p50\":{:.1},\"p75\":{:.1},\"p90\":{:.1}}}",
+        q[0], q[1], q[2], q[3], q[4]
+    )
+}
+
+fn biome_json(climate: &Climate, land: &[usize]) -> String {
+    let mut area = [0.0_f64; BIOMES.len()];
+    for &idx in land {
+        let biome = whittaker(
+            climate.temperature[idx],
+            climate.warmest_month_at(idx),
+            climate.precipitation[idx],
+            climate.pet[idx],
+        );
+        area[biome as usize] += cell_area_km2(idx / WIDTH);
+    }
+    let total: f64 = area.iter().sum();
+    let pct = |a: f64| if total > 0.0 { a / total * 100.0 } else { 0.0 };
+    let share = |ids: &[u8]| pct(ids.iter().map(|&b| area[b as usize]).sum());
+    let per_biome: Vec<String> = BIOMES
+        .iter()
+        .zip(area)
+        .skip(1)
+        .map(|((name, _), a)| format!("\"{name}\":{:.3}", pct(a)))
+        .collect();
+    format!(
+        "{{\"biome_pct\":{{{}}},\"desert_pct\":{:.3},\"forest_pct\":{:.3},\"ice_pct\":{:.3}}}",
+        per_biome.join(","),
+        share(&[BIOME_DESERT, BIOME_COLD_DESERT]),
+        share(&FOREST_BIOMES),
+        share(&[BIOME_ICE]),
+    )
+}
+
+/// Climate summary for regression checks, area-weighted:
+/// `{"land_cells","temperature":{"global_mean_c","land_mean_c"},
+/// "precipitation":{"land_mean_mm","p10","p25","p50","p75","p90"},
+/// "biomes":{"biome_pct":{<name>:pct,...},"desert_pct","forest_pct","ice_pct"},
+/// "aridity":{"mean","p10","p50","p90","class_pct":{...},"dryland_pct"}}`.
+/// Percentages are of land area. `class_pct` gives the share in each UNEP aridity class
+/// (Earth: roughly 41 % dryland, 7.5 % hyper-arid). `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn climate_analytics_json(climate: &Climate) -> Result<String, JsValue> {
+    climate.require_annual("climate_analytics_json")?;
+    let land: Vec<usize> = (0..CELL_COUNT)
+        .filter(|&idx| climate.water_distance[idx] > 0.0)
+        .collect();
+    Ok(format!(
+        "{{\"land_cells\":{},\"temperature\":{},\"precipitation\":{},\"biomes\":{},\"aridity\":{}}}",
+        land.len(),
+        temperature_json(climate, &land),
+        precipitation_json(climate, &land),
+        biome_json(climate, &land),
+        aridity_json(climate, &land)
+    ))
+}
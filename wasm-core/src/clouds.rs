@@ -0,0 +1,139 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, smoothstep};
+use crate::noise::tiled_fbm;
+
+/// Histogram resolution used to hit the requested coverage exactly.
+const COVERAGE_BINS: usize = 1024;
+/// Width of the soft cloud edge above the coverage threshold, in fbm units.
+const EDGE_SOFTNESS: f32 = 0.08;
+/// Domain warp amplitude in first-octave lattice cells.
+const WARP_CELLS: f32 = 0.35;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct CloudParams {
+    pub seed: u32,
+    /// Fraction of the map covered by cloud, [0, 1].
+    pub coverage: f32,
+    /// Peak opacity of thick cloud, [0, 1].
+    pub density: f32,
+    /// First-octave lattice cells across the map width; larger = smaller weather systems.
+    pub scale: u32,
+    pub octaves: u32,
+    /// Amplitude falloff per octave, (0, 1].
+    pub roughness: f32,
+    /// Animation phase. The pattern morphs with period 2π and scrolls east by `drift`
+    /// lattice cells per unit, so integer multiples of 2π loop when `drift * 2π` is a
+    /// whole number of lattice cells.
+    pub time: f32,
+    pub drift: f32,
+}
+
+impl Default for CloudParams {
+    fn default() -> Self {
+        Self {
+            seed: crate::DEFAULT_SEED,
+            coverage: 0.55,
+            density: 0.85,
+            scale: 12,
+            octaves: 6,
+            roughness: 0.55,
+            time: 0.0,
+            drift: 0.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl CloudParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CloudParams {
+        Self::default()
+    }
+}
+
+impl CloudParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(0.0..=1.0).contains(&self.coverage) {
+            return Err(JsValue::from_str("coverage must be within [0.0, 1.0]"));
+        }
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(JsValue::from_str("density must be within [0.0, 1.0]"));
+        }
+        if self.scale == 0 || self.scale as usize > WIDTH {
+            return Err(JsValue::from_str("scale must be within [1, grid width]"));
+        }
+        if self.octaves == 0 || self.octaves > 12 {
+            return Err(JsValue::from_str("octaves must be within [1, 12]"));
+        }
+        if !self.roughness.is_finite() || self.roughness <= 0.0 || self.roughness > 1.0 {
+            return Err(JsValue::from_str("roughness must be within (0.0, 1.0]"));
+        }
+        if !self.time.is_finite() || !self.drift.is_finite() {
+            return Err(JsValue::from_str("time and drift must be finite"));
+        }
+        Ok(())
+    }
+}
+
+/// Value at which a `coverage` fraction of `field` (values in [0, 1]) lies above.
+fn coverage_threshold(field: &[f32], coverage: f32) -> f32 {
+    let mut bins = vec![0_u32; COVERAGE_BINS];
+    for &v in field {
+        bins[((v * COVERAGE_BINS as f32) as usize).min(COVERAGE_BINS - 1)] += 1;
+    }
+    let target = (coverage as f64 * field.len() as f64) as u64;
+    let mut above = 0_u64;
+    for (i, &count) in bins.iter().enumerate().rev() {
+        above += count as u64;
+        if above >= target {
+            return i as f32 / COVERAGE_BINS as f32;
+        }
+    }
+    0.0
+}
+
+/// Cloud opacity per cell in [0, 1], seamless across the east–west seam. The same seed,
+/// scale and time always give the same sky, so renders of one world composite the same
+/// clouds. Cells are sampled on a square lattice so weather systems are not stretched.
+#[wasm_bindgen]
+pub fn cloud_layer(params: &CloudParams) -> Result<Box<[f32]>, JsValue> {
+    params.validate()?;
+    let period = params.scale as i32;
+    let cells_per_lattice = WIDTH as f32 / params.scale as f32;
+    let (sin_t, cos_t) = params.time.sin_cos();
+    let scroll = params.drift * params.time;
+    let warp_seed = params.seed ^ 0x6a09e667;
+
+    let mut field = vec![0.0_f32; CELL_COUNT];
+    for y in 0..HEIGHT {
+        let py = (y as f32 + 0.5) / cells_per_lattice;
+        for x in 0..WIDTH {
+            let px = (x as f32 + 0.5) / cells_per_lattice;
+            let wa = tiled_fbm(px, py, period, 0.5, 3, warp_seed) * 2.0 - 1.0;
+            let wb = tiled_fbm(px, py + 31.7, period, 0.5, 3, warp_seed ^ 0x3c6ef372) * 2.0 - 1.0;
+            // Rotating the warp vector morphs the pattern and returns to it every 2π.
+            let wx = (wa * cos_t - wb * sin_t) * WARP_CELLS;
+            let wy = (wa * sin_t + wb * cos_t) * WARP_CELLS;
+            field[y * WIDTH + x] = tiled_fbm(
+                px + wx - scroll,
+                py + wy,
+                period,
+                params.roughness,
+                params.octaves,
+                params.seed,
+            );
+        }
+    }
+
+    let threshold = coverage_threshold(&field, params.coverage);
+    for v in field.iter_mut() {
+        *v = if params.coverage <= 0.0 {
+            0.0
+        } else {
+            params.density * smoothstep(threshold, threshold + EDGE_SOFTNESS, *v)
+        };
+    }
+    Ok(field.into_boxed_slice())
+}
@@ -0,0 +1,231 @@
+use crate::grid::{HEIGHT, WIDTH};
+use crate::vector::block_size_km;
+
+/// Sampling scales (cells per block side) for coastline length and box counting.
+pub(crate) const COASTLINE_SCALES: [usize; 6] = [1, 2, 4, 8, 16, 32];
+
+/// Mask downsampled by `scale`: a block is set when at least half its cells are.
+fn downsample(land: &[bool], scale: usize) -> (Vec<bool>, usize, usize) {
+    let (w, h) = (WIDTH / scale, HEIGHT / scale);
+    let mut out = vec![false; w * h];
+    for by in 0..h {
+        for bx in 0..w {
+            let mut count = 0;
+            for y in by * scale..(by + 1) * scale {
+                count += land[y * WIDTH + bx * scale..y * WIDTH + (bx + 1) * scale]
+                    .iter()
+                    .filter(|&&l| l)
+                    .count();
+            }
+            out[by * w + bx] = count * 2 >= scale * scale;
+        }
+    }
+    (out, w, h)
+}
+
+/// Total land–water boundary length (km) of `land` sampled at `scale`, wrapping east–west.
+/// Only edges between two blocks inside `region` count.
+pub(crate) fn coastline_length_km(land: &[bool], region: &[bool], scale: usize) -> f64 {
+    let (mask, w, h) = downsample(land, scale);
+    let (inside, _, _) = downsample(region, scale);
+    let mut length = 0.0;
+    for by in 0..h {
+        let (_, block_h) = block_size_km(0.0, scale, scale);
+        // The edge between two rows sits at their shared latitude.
+        let boundary_lat = 90.0 - (by + 1) as f64 * scale as f64 * 180.0 / HEIGHT as f64;
+        let (boundary_w, _) = block_size_km(boundary_lat, scale, scale);
+        for bx in 0..w {
+            let (here, east, south) = (by * w + bx, by * w + (bx + 1) % w, (by + 1) * w + bx);
+            if !inside[here] {
+                continue;
+            }
+            if inside[east] && mask[here] != mask[east] {
+                length += block_h;
+            }
+            if by + 1 < h && inside[south] && mask[here] != mask[south] {
+                length += boundary_w;
+            }
+        }
+    }
+    length
+}
+
+/// Whether column `x` of the middle of three consecutive land rows (north to south) is
+/// coast: land with a 4-neighbour in the water, wrapping east–west. Pass a row twice to
+/// clamp at a pole.
+pub(crate) fn coast_in_rows(rows: [&[bool]; 3], x: usize) -> bool {
+    let row = rows[1];
+    row[x] && (!row[(x + WIDTH - 1) % WIDTH] || !row[(x + 1) % WIDTH] || !rows[0][x] || !rows[2][x])
+}
+
+/// Coast cells of the whole grid.
+fn coast_cells(land: &[bool]) -> Vec<bool> {
+    let row = |y: usize| &land[y.min(HEIGHT - 1) * WIDTH..][..WIDTH];
+    (0..WIDTH * HEIGHT)
+        .map(|idx| {
+            let (x, y) = (idx % WIDTH, idx / WIDTH);
+            coast_in_rows([row(y.saturating_sub(1)), row(y), row(y + 1)], x)
+        })
+        .collect()
+}
+
+/// Box-counting dimension of the coastline in grid space: the least-squares slope of
+/// log(occupied boxes) against log(1 / box size) over `COASTLINE_SCALES`, counting coast
+/// cells inside `region`. Smooth coasts approach 1, highly crenellated ones approach 2;
+/// 0 when there is no coast.
+pub(crate) fn box_counting_dimension(land: &[bool], region: &[bool]) -> f64 {
+    let coast: Vec<bool> = coast_cells(land)
+        .into_iter()
+        .zip(region)
+        .map(|(c, &r)| c && r)
+        .collect();
+    let counts = COASTLINE_SCALES.map(|scale| {
+        let (w, h) = (WIDTH / scale, HEIGHT / scale);
+        let mut boxes = vec![false; w * h];
+        for (idx, _) in coast.iter().enumerate().filter(|(_, c)| **c) {
+            boxes[(idx / WIDTH / scale) * w + (idx % WIDTH) / scale] = true;
+        }
+        boxes.iter().filter(|&&b| b).count()
+    });
+    dimension_from_box_counts(&counts)
+}
+
+/// Regression slope over the non-empty `COASTLINE_SCALES` box counts.
+fn dimension_from_box_counts(counts: &[usize]) -> f64 {
+    let points: Vec<(f64, f64)> = COASTLINE_SCALES
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| **count > 0)
+        .map(|(&scale, &count)| ((1.0 / scale as f64).ln(), (count as f64).ln()))
+        .collect();
+    if points.len() < 2 {
+        return 0.0;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let cov: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let var: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    cov / var
+}
+
+/// Per-scale state of `CoastlineStream`: land counts and coast boxes for the block row
+/// being filled, and the finished block row above it.
+struct ScaleStream {
+    scale: usize,
+    land_counts: Vec<usize>,
+    above: Option<Vec<bool>>,
+    length_km: f64,
+    coast_boxes: Vec<bool>,
+    box_count: usize,
+}
+
+impl ScaleStream {
+    fn push_land_row(&mut self, y: usize, land: &[bool]) {
+        let scale = self.scale;
+        for (count, block) in self.land_counts.iter_mut().zip(land.chunks(scale)) {
+            *count += block.iter().filter(|&&l| l).count();
+        }
+        if !(y + 1).is_multiple_of(scale) {
+            return;
+        }
+        let mask: Vec<bool> = self
+            .land_counts
+            .iter()
+            .map(|&count| count * 2 >= scale * scale)
+            .collect();
+        self.land_counts.fill(0);
+        let w = mask.len();
+        let (_, block_h) = block_size_km(0.0, scale, scale);
+        for bx in 0..w {
+            if mask[bx] != mask[(bx + 1) % w] {
+                self.length_km += block_h;
+            }
+        }
+        if let Some(above) = &self.above {
+            let boundary_lat = 90.0 - (y + 1 - scale) as f64 * 180.0 / HEIGHT as f64;
+            let (boundary_w, _) = block_size_km(boundary_lat, scale, scale);
+            for bx in 0..w {
+                if above[bx] != mask[bx] {
+                    self.length_km += boundary_w;
+                }
+            }
+        }
+        self.above = Some(mask);
+    }
+
+    fn push_coast_row(&mut self, y: usize, coast: &[bool]) {
+        for (x, _) in coast.iter().enumerate().filter(|(_, c)| **c) {
+            self.coast_boxes[x / self.scale] = true;
+        }
+        if (y + 1).is_multiple_of(self.scale) {
+            self.box_count += self.coast_boxes.iter().filter(|&&b| b).count();
+            self.coast_boxes.fill(false);
+        }
+    }
+}
+
+/// Whole-grid `coastline_length_km` at every scale and `box_counting_dimension`, fed one
+/// land row at a time from north to south. Holds two land rows plus one block row per scale.
+pub(crate) struct CoastlineStream {
+    scales: Vec<ScaleStream>,
+    rows: Vec<Vec<bool>>,
+    next_row: usize,
+}
+
+impl CoastlineStream {
+    pub(crate) fn new() -> Self {
+        Self {
+            scales: COASTLINE_SCALES
+                .iter()
+                .map(|&scale| ScaleStream {
+                    scale,
+                    land_counts: vec![0; WIDTH / scale],
+                    above: None,
+                    length_km: 0.0,
+                    coast_boxes: vec![false; WIDTH / scale],
+                    box_count: 0,
+                })
+                .collect(),
+            rows: Vec::with_capacity(3),
+            next_row: 0,
+        }
+    }
+
+    pub(crate) fn push_row(&mut self, land: Vec<bool>) {
+        let y = self.next_row;
+        self.next_row += 1;
+        for scale in &mut self.scales {
+            scale.push_land_row(y, &land);
+        }
+        // Coast cells of the previous row become known once its southern neighbour arrives.
+        self.rows.push(land);
+        match self.rows.len() {
+            2 => self.push_coast_row(y - 1, [0, 0, 1]),
+            3 => {
+                self.push_coast_row(y - 1, [0, 1, 2]);
+                self.rows.remove(0);
+            }
+            _ => {}
+        }
+    }
+
+    fn push_coast_row(&mut self, y: usize, window: [usize; 3]) {
+        let rows = window.map(|i| self.rows[i].as_slice());
+        let coast: Vec<bool> = (0..WIDTH).map(|x| coast_in_rows(rows, x)).collect();
+        for scale in &mut self.scales {
+            scale.push_coast_row(y, &coast);
+        }
+    }
+
+    /// Lengths (km) per `COASTLINE_SCALES` entry and the box-counting dimension.
+    pub(crate) fn finish(mut self) -> (Vec<f64>, f64) {
+        let last = self.rows.len() - 1;
+        self.push_coast_row(self.next_row - 1, [last.saturating_sub(1), last, last]);
+        let counts: Vec<usize> = self.scales.iter().map(|s| s.box_count).collect();
+        (
+            self.scales.iter().map(|s| s.length_km).collect(),
+            dimension_from_box_counts(&counts),
+        )
+    }
+}
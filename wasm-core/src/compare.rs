@@ -0,0 +1,130 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+/// Differences between two heightmaps from `compare_heightmaps`, taken as `b − a`.
+#[wasm_bindgen]
+pub struct HeightmapComparison {
+    rmse: f64,
+    mean_difference: f64,
+    max_difference: f64,
+    max_difference_x: u32,
+    max_difference_y: u32,
+    changed_cells: u32,
+    difference_map: Vec<f32>,
+    map_width: u32,
+    map_height: u32,
+}
+
+#[wasm_bindgen]
+impl HeightmapComparison {
+    /// Root-mean-square difference, elevation units.
+    #[wasm_bindgen(getter)]
+    pub fn rmse(&self) -> f64 {
+        self.rmse
+    }
+
+    /// Mean signed difference (positive where `b` is higher on average).
+    #[wasm_bindgen(getter)]
+    pub fn mean_difference(&self) -> f64 {
+        self.mean_difference
+    }
+
+    /// Largest absolute difference, elevation units.
+    #[wasm_bindgen(getter)]
+    pub fn max_difference(&self) -> f64 {
+        self.max_difference
+    }
+
+    /// Column of the first cell reaching `max_difference`.
+    #[wasm_bindgen(getter)]
+    pub fn max_difference_x(&self) -> u32 {
+        self.max_difference_x
+    }
+
+    /// Row of the first cell reaching `max_difference`.
+    #[wasm_bindgen(getter)]
+    pub fn max_difference_y(&self) -> u32 {
+        self.max_difference_y
+    }
+
+    /// Cells whose absolute difference exceeds the tolerance.
+    #[wasm_bindgen(getter)]
+    pub fn changed_cells(&self) -> u32 {
+        self.changed_cells
+    }
+
+    /// Mean signed difference per `factor × factor` block, row-major,
+    /// `map_width × map_height`.
+    pub fn difference_map(&self) -> Box<[f32]> {
+        self.difference_map.clone().into_boxed_slice()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn map_width(&self) -> u32 {
+        self.map_width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn map_height(&self) -> u32 {
+        self.map_height
+    }
+}
+
+/// Compares heightmap `b` against `a`, e.g. two parameter settings or the GPU and CPU
+/// paths. `tolerance` is the absolute difference a cell must exceed to count as changed;
+/// `factor` (a divisor of both grid dimensions) sets the difference map's block size.
+#[wasm_bindgen]
+pub fn compare_heightmaps(
+    a: &[f32],
+    b: &[f32],
+    tolerance: f32,
+    factor: u32,
+) -> Result<HeightmapComparison, JsValue> {
+    check_grid_len(a, "heightmap a")?;
+    check_grid_len(b, "heightmap b")?;
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(JsValue::from_str("tolerance must be >= 0"));
+    }
+    let factor = factor as usize;
+    if factor == 0 || !WIDTH.is_multiple_of(factor) || !HEIGHT.is_multiple_of(factor) {
+        return Err(JsValue::from_str(
+            "factor must divide the grid width and height",
+        ));
+    }
+    let (map_w, map_h) = (WIDTH / factor, HEIGHT / factor);
+    let mut block_sums = vec![0.0_f64; map_w * map_h];
+    let (mut sum, mut sum_sq) = (0.0_f64, 0.0_f64);
+    let (mut max, mut max_idx) = (0.0_f32, 0);
+    let mut changed_cells = 0;
+    for (idx, (&ha, &hb)) in a.iter().zip(b).enumerate() {
+        let diff = hb - ha;
+        sum += diff as f64;
+        sum_sq += (diff as f64).powi(2);
+        if diff.abs() > max {
+            max = diff.abs();
+            max_idx = idx;
+        }
+        if diff.abs() > tolerance {
+            changed_cells += 1;
+        }
+        let (x, y) = (idx % WIDTH, idx / WIDTH);
+        block_sums[(y / factor) * map_w + x / factor] += diff as f64;
+    }
+    let cells = a.len() as f64;
+    let block_cells = (factor * factor) as f64;
+    Ok(HeightmapComparison {
+        rmse: (sum_sq / cells).sqrt(),
+        mean_difference: sum / cells,
+        max_difference: max as f64,
+        max_difference_x: (max_idx % WIDTH) as u32,
+        max_difference_y: (max_idx / WIDTH) as u32,
+        changed_cells,
+        difference_map: block_sums
+            .into_iter()
+            .map(|s| (s / block_cells) as f32)
+            .collect(),
+        map_width: map_w as u32,
+        map_height: map_h as u32,
+    })
+}
@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::json::{ObjectWriter, array};
+use crate::vector::{simplify_polyline, simplify_ring};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct ContourParams {
+    /// Height of 0 m; heights convert to metres as in `render_hypsometric_rgba`.
+    pub sea_level: f32,
+    /// Metres between contours; lines are drawn at every multiple of it except 0 m,
+    /// which is the coastline.
+    pub interval_metres: f32,
+    /// Every n-th contour (at multiples of `n * interval_metres`) is flagged as an index
+    /// contour, to be drawn heavier and labelled; 0 flags none.
+    pub index_every: u32,
+    /// Also traces depth contours below sea level.
+    pub bathymetry: bool,
+    /// Douglas–Peucker tolerance, cells (0 keeps every crossing).
+    pub tolerance: f32,
+}
+
+impl Default for ContourParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            interval_metres: 200.0,
+            index_every: 5,
+            bathymetry: false,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ContourParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ContourParams {
+        Self::default()
+    }
+}
+
+impl ContourParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        // Keeps the level count, and so the output, bounded.
+        if !(self.interval_metres >= 1.0 && self.interval_metres.is_finite()) {
+            return Err(JsValue::from_str("interval_metres must be >= 1"));
+        }
+        if !self.tolerance.is_finite() || self.tolerance < 0.0 {
+            return Err(JsValue::from_str("tolerance must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// One contour line in cell-centre grid coordinates.
+pub(crate) struct Contour {
+    pub(crate) elevation: f32,
+    pub(crate) index: bool,
+    /// Closed lines repeat no point; the last joins back to the first.
+    pub(crate) closed: bool,
+    pub(crate) points: Vec<(f32, f32)>,
+}
+
+/// Contour lines from `trace_contours`.
+#[wasm_bindgen]
+pub struct Contours {
+    lines: Vec<Contour>,
+}
+
+#[wasm_bindgen]
+impl Contours {
+    #[wasm_bindgen(getter)]
+    pub fn line_count(&self) -> u32 {
+        self.lines.len() as u32
+    }
+
+    /// `[{"elevation","index","closed","points":[[x,y],...]},...]`, lowest level first, in
+    /// cell-centre grid coordinates. Lines run with higher ground on their right.
+    pub fn lines_json(&self) -> String {
+        array(self.lines.iter().map(|line| {
+            let points = array(line.points.iter().map(|(x, y)| format!("[{x:.2},{y:.2}]")));
+            ObjectWriter::new()
+                .number("elevation", line.elevation as f64, 1)
+                .raw("index", if line.index { "true" } else { "false" })
+                .raw("closed", if line.closed { "true" } else { "false" })
+                .raw("points", &points)
+                .finish()
+        }))
+    }
+}
+
+/// Crossing on a grid edge: `2 * idx` for the edge from cell `idx` east to its
+/// neighbour, `2 * idx + 1` for the edge south to its neighbour.
+type EdgeId = u32;
+
+/// Where `level` crosses `edge`, interpolated between its two cell centres.
+fn crossing_point(metres: &[f32], edge: EdgeId, level: f32) -> (f32, f32) {
+    let idx = edge as usize / 2;
+    let east = edge.is_multiple_of(2);
+    let next = if east { idx + 1 } else { idx + WIDTH };
+    let (a, b) = (metres[idx], metres[next]);
+    let t = ((level - a) / (b - a)).clamp(0.0, 1.0);
+    let (x, y) = ((idx % WIDTH) as f32 + 0.5, (idx / WIDTH) as f32 + 0.5);
+    if east { (x + t, y) } else { (x, y + t) }
+}
+
+/// Marching-squares segments of one level, as start edge → end edge with higher ground
+/// on the right of each segment. Saddles are resolved by the mean of the four corners.
+fn level_segments(metres: &[f32], squares: &[usize], level: f32) -> HashMap<EdgeId, EdgeId> {
+    let mut segments = HashMap::new();
+    for &idx in squares {
+        // Corners and edges clockwise from the north-west, in screen space.
+        let corners = [idx, idx + 1, idx + WIDTH + 1, idx + WIDTH];
+        let edges = [2 * idx, 2 * (idx + 1) + 1, 2 * (idx + WIDTH), 2 * idx + 1].map(|e| e as u32);
+        let values = corners.map(|c| metres[c]);
+        let high = values.map(|v| v >= level);
+        let centre_high = values.iter().sum::<f32>() / 4.0 >= level;
+        let crosses: Vec<usize> = (0..4).filter(|&k| high[k] != high[(k + 1) % 4]).collect();
+        for (i, &k) in crosses.iter().enumerate() {
+            // Leaving high ground clockwise: pair with the next crossing clockwise when the
+            // high corners join through the centre, else with the previous one.
+            if !high[k] {
+                continue;
+            }
+            let n = crosses.len();
+            let partner = if centre_high {
+                crosses[(i + 1) % n]
+            } else {
+                crosses[(i + n - 1) % n]
+            };
+            segments.insert(edges[k], edges[partner]);
+        }
+    }
+    segments
+}
+
+/// Chains a level's segments into lines: open lines from their free ends first, then the
+/// closed loops that remain.
+fn chain_segments(mut segments: HashMap<EdgeId, EdgeId>) -> Vec<(bool, Vec<EdgeId>)> {
+    let ends: HashSet<EdgeId> = segments.values().copied().collect();
+    let mut starts: Vec<EdgeId> = segments
+        .keys()
+        .copied()
+        .filter(|s| !ends.contains(s))
+        .collect();
+    starts.sort_unstable();
+    let mut lines = Vec::new();
+    let follow = |start: EdgeId, segments: &mut HashMap<EdgeId, EdgeId>| {
+        let mut line = vec![start];
+        let mut current = start;
+        while let Some(next) = segments.remove(&current) {
+            if next == start {
+                return (true, line);
+            }
+            line.push(next);
+            current = next;
+        }
+        (false, line)
+    };
+    for start in starts {
+        lines.push(follow(start, &mut segments));
+    }
+    while let Some(&start) = segments.keys().min() {
+        lines.push(follow(start, &mut segments));
+    }
+    lines
+}
+
+/// Lines of `level` through `squares`, each flagged closed or open.
+fn trace_level(field: &[f32], squares: &[usize], level: f32) -> Vec<(bool, Vec<(f32, f32)>)> {
+    chain_segments(level_segments(field, squares, level))
+        .into_iter()
+        .map(|(closed, edges)| {
+            let points = edges
+                .iter()
+                .map(|&e| crossing_point(field, e, level))
+                .collect();
+            (closed, points)
+        })
+        .collect()
+}
+
+/// Unsimplified marching-squares lines where `field` crosses `level`, in cell-centre grid
+/// coordinates and flagged closed or open, with values at or above `level` on their right.
+pub(crate) fn isolines(field: &[f32], level: f32) -> Vec<(bool, Vec<(f32, f32)>)> {
+    let squares: Vec<usize> = (0..HEIGHT - 1)
+        .flat_map(|y| (0..WIDTH - 1).map(move |x| y * WIDTH + x))
+        .filter(|&idx| {
+            let high = [idx, idx + 1, idx + WIDTH, idx + WIDTH + 1].map(|c| field[c] >= level);
+            high.contains(&true) && high.contains(&false)
+        })
+        .collect();
+    trace_level(field, &squares, level)
+}
+
+/// `isolines` simplified with `tolerance`, dropping any that collapse to a point.
+pub(crate) fn simplified_isolines(
+    field: &[f32],
+    level: f32,
+    tolerance: f32,
+) -> Vec<(bool, Vec<(f32, f32)>)> {
+    isolines(field, level)
+        .into_iter()
+        .map(|(closed, points)| {
+            let points = if closed {
+                simplify_ring(&points, tolerance)
+            } else {
+                simplify_polyline(&points, tolerance)
+            };
+            (closed, points)
+        })
+        .filter(|(_, points)| points.len() > 1)
+        .collect()
+}
+
+/// Traces contours of `flat` with marching squares between cell centres. Lines end at the
+/// map edges and at the east–west seam.
+pub(crate) fn contour_lines(flat: &[f32], params: &ContourParams) -> Vec<Contour> {
+    let scale = RELIEF_METRES / (1.0 - params.sea_level);
+    let metres: Vec<f32> = flat
+        .iter()
+        .map(|&h| (h - params.sea_level) * scale)
+        .collect();
+    let (lowest, highest) = metres
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &m| {
+            (lo.min(m), hi.max(m))
+        });
+    let step = params.interval_metres;
+    let first = if params.bathymetry {
+        (lowest / step).ceil() as i64
+    } else {
+        1
+    };
+    let last = (highest / step).floor() as i64;
+
+    // Squares indexed by their north-west cell, bucketed by the levels they span.
+    let mut buckets: HashMap<i64, Vec<usize>> = HashMap::new();
+    for y in 0..HEIGHT - 1 {
+        for x in 0..WIDTH - 1 {
+            let idx = y * WIDTH + x;
+            let corners = [idx, idx + 1, idx + WIDTH, idx + WIDTH + 1].map(|c| metres[c]);
+            let lo = corners.iter().copied().fold(f32::INFINITY, f32::min);
+            let hi = corners.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            // Levels strictly above the lowest corner and at or below the highest.
+            let from = ((lo / step).floor() as i64 + 1).max(first);
+            let to = ((hi / step).floor() as i64).min(last);
+            for k in from..=to {
+                if k != 0 {
+                    buckets.entry(k).or_default().push(idx);
+                }
+            }
+        }
+    }
+
+    let mut levels: Vec<i64> = buckets.keys().copied().collect();
+    levels.sort_unstable();
+    let mut contours = Vec::new();
+    for k in levels {
+        let level = k as f32 * step;
+        let index = params.index_every > 0 && k % params.index_every as i64 == 0;
+        for (closed, points) in trace_level(&metres, &buckets[&k], level) {
+            let points = if closed {
+                simplify_ring(&points, params.tolerance)
+            } else {
+                simplify_polyline(&points, params.tolerance)
+            };
+            if points.len() > 1 {
+                contours.push(Contour {
+                    elevation: level,
+                    index,
+                    closed,
+                    points,
+                });
+            }
+        }
+    }
+    contours
+}
+
+/// Contour lines every `interval_metres`, with index contours flagged, for drawing over
+/// the map or exporting as vector linework.
+#[wasm_bindgen]
+pub fn trace_contours(flat: &[f32], params: &ContourParams) -> Result<Contours, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    Ok(Contours {
+        lines: contour_lines(flat, params),
+    })
+}
@@ -0,0 +1,116 @@
+use crate::climate::prevailing_wind;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, latitude_deg};
+
+/// Peak warming from a western boundary current (Gulf Stream, Kuroshio), °C.
+const WARM_CURRENT_C: f32 = 6.0;
+/// Peak cooling from an eastern boundary current (California, Benguela), °C.
+const COLD_CURRENT_C: f32 = 5.0;
+/// e-folding width of a boundary current offshore, cells.
+const CURRENT_WIDTH_CELLS: f32 = 60.0;
+/// e-folding reach of the current's influence inland, cells.
+const INLAND_REACH_CELLS: f32 = 25.0;
+/// Share of the anomaly carried inland against the prevailing wind.
+const DOWNWIND_COAST_WEIGHT: f32 = 0.4;
+
+fn bump(a: f32, lo: f32, hi: f32) -> f32 {
+    if a <= lo || a >= hi {
+        return 0.0;
+    }
+    (std::f32::consts::PI * (a - lo) / (hi - lo)).sin()
+}
+
+/// Gyre orientation by latitude: +1 in the subtropical gyres (warm western, cold eastern
+/// boundaries), negative in the subpolar gyres where the pattern reverses.
+fn gyre_sign(lat_deg: f32) -> f32 {
+    let a = lat_deg.abs();
+    bump(a, 10.0, 48.0) - 0.6 * bump(a, 48.0, 72.0)
+}
+
+/// Distance along the row to the nearest set cell on each side, wrapping east–west.
+/// Rows without any set cell report `f32::INFINITY`.
+fn row_distances(mask: &[bool]) -> (Vec<f32>, Vec<f32>) {
+    let mut west = vec![f32::INFINITY; WIDTH];
+    let mut east = vec![f32::INFINITY; WIDTH];
+    let mut d = f32::INFINITY;
+    for i in 0..2 * WIDTH {
+        let x = i % WIDTH;
+        d = if mask[x] { 0.0 } else { d + 1.0 };
+        if i >= WIDTH {
+            west[x] = d;
+        }
+    }
+    d = f32::INFINITY;
+    for i in (0..2 * WIDTH).rev() {
+        let x = i % WIDTH;
+        d = if mask[x] { 0.0 } else { d + 1.0 };
+        if i < WIDTH {
+            east[x] = d;
+        }
+    }
+    (west, east)
+}
+
+/// Temperature anomaly (°C) from wind-driven gyres: ocean cells carry the boundary current
+/// anomaly, coastal land inherits it with distance decay, weighted toward the upwind coast.
+pub(crate) fn current_anomaly(flat: &[f32], sea_level: f32, strength: f32) -> Vec<f32> {
+    let mut anomaly = vec![0.0; CELL_COUNT];
+    if strength == 0.0 {
+        return anomaly;
+    }
+
+    let mut land = vec![false; WIDTH];
+    let mut ocean_anomaly = vec![0.0; WIDTH];
+    for y in 0..HEIGHT {
+        let lat = latitude_deg(y);
+        let gyre = gyre_sign(lat) * strength;
+        if gyre == 0.0 {
+            continue;
+        }
+        let row = &flat[y * WIDTH..(y + 1) * WIDTH];
+        for (l, &h) in land.iter_mut().zip(row) {
+            *l = h >= sea_level;
+        }
+
+        // Land to the west of an ocean cell means it sits on the basin's western boundary.
+        let (to_land_west, to_land_east) = row_distances(&land);
+        for x in 0..WIDTH {
+            ocean_anomaly[x] = if land[x] {
+                0.0
+            } else {
+                gyre * (WARM_CURRENT_C * (-to_land_west[x] / CURRENT_WIDTH_CELLS).exp()
+                    - COLD_CURRENT_C * (-to_land_east[x] / CURRENT_WIDTH_CELLS).exp())
+            };
+        }
+
+        let (zonal, _) = prevailing_wind(lat);
+        let (west_weight, east_weight) = if zonal >= 0.0 {
+            (1.0, DOWNWIND_COAST_WEIGHT)
+        } else {
+            (DOWNWIND_COAST_WEIGHT, 1.0)
+        };
+        let ocean: Vec<bool> = land.iter().map(|&l| !l).collect();
+        let (to_ocean_west, to_ocean_east) = row_distances(&ocean);
+        for x in 0..WIDTH {
+            let idx = y * WIDTH + x;
+            if !land[x] {
+                anomaly[idx] = ocean_anomaly[x];
+                continue;
+            }
+            let mut total = 0.0;
+            if to_ocean_west[x].is_finite() {
+                let source = (x as i64 - to_ocean_west[x] as i64).rem_euclid(WIDTH as i64);
+                total += ocean_anomaly[source as usize]
+                    * west_weight
+                    * (-to_ocean_west[x] / INLAND_REACH_CELLS).exp();
+            }
+            if to_ocean_east[x].is_finite() {
+                let source = (x + to_ocean_east[x] as usize) % WIDTH;
+                total += ocean_anomaly[source]
+                    * east_weight
+                    * (-to_ocean_east[x] / INLAND_REACH_CELLS).exp();
+            }
+            anomaly[idx] = total;
+        }
+    }
+    anomaly
+}
@@ -0,0 +1,654 @@
+//! zlib (RFC 1950) stream of DEFLATE (RFC 1951) blocks: LZ77 over a 32 KiB window with a
+//! per-block dynamic Huffman code, plus the CRC-32 and Adler-32 checksums the file formats
+//! need, and the matching decoder for imports.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+/// Candidates examined per position; longer chains compress slightly better, much slower.
+const MAX_CHAIN: usize = 64;
+/// LZ77 tokens per block, each block getting its own Huffman code.
+const BLOCK_TOKENS: usize = 1 << 16;
+const END_OF_BLOCK: usize = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code-length code lengths are stored in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0_u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+/// CRC-32 (ISO 3309, as used by PNG and zip) of `bytes`.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |c, &b| {
+        CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1_u32, 0_u32);
+    // 5552 is the longest run before `b` could overflow between reductions.
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Packs bits least-significant first, as DEFLATE stores them.
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, len: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+fn hash3(bytes: &[u8]) -> usize {
+    let v = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+    (v.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Greedy LZ77 parse with hash chains over the last `WINDOW` bytes.
+fn lz77(data: &[u8]) -> Vec<Token> {
+    const NONE: u32 = u32::MAX;
+    let mut head = vec![NONE; 1 << HASH_BITS];
+    let mut prev = vec![NONE; WINDOW];
+    // `prev` links each position to the previous one with the same hash; chains only ever
+    // point backwards and are cut once they leave the window.
+    let insert = |head: &mut [u32], prev: &mut [u32], pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash3(&data[pos..]);
+            prev[pos % WINDOW] = head[h];
+            head[h] = pos as u32;
+        }
+    };
+    let mut tokens = Vec::with_capacity(data.len() / 2);
+    let mut pos = 0;
+    while pos < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash3(&data[pos..])];
+            let mut chain = 0;
+            while candidate != NONE && chain < MAX_CHAIN {
+                let start = candidate as usize;
+                if pos - start > WINDOW {
+                    break;
+                }
+                let len = data[start..]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, pos - start);
+                    if len == max_len {
+                        break;
+                    }
+                }
+                candidate = prev[start % WINDOW];
+                chain += 1;
+            }
+        }
+        if best_len >= MIN_MATCH {
+            tokens.push(Token::Match {
+                length: best_len as u16,
+                distance: best_dist as u16,
+            });
+            for p in pos..pos + best_len {
+                insert(&mut head, &mut prev, p);
+            }
+            pos += best_len;
+        } else {
+            tokens.push(Token::Literal(data[pos]));
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    tokens
+}
+
+/// (code index, extra-bit value) for a match length or distance.
+fn bucket(value: u16, base: &[u16]) -> (usize, u32) {
+    let code = base.partition_point(|&b| b <= value) - 1;
+    (code, (value - base[code]) as u32)
+}
+
+/// Huffman code lengths for `freqs`, none longer than `limit`. Oversized trees are rebuilt
+/// from halved frequencies, which flattens them until they fit.
+fn huffman_lengths(freqs: &[u32], limit: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+    loop {
+        let lengths = unlimited_huffman_lengths(&freqs);
+        if lengths.iter().all(|&l| l <= limit) {
+            return lengths;
+        }
+        for f in freqs.iter_mut().filter(|f| **f > 0) {
+            *f = (*f >> 1).max(1);
+        }
+    }
+}
+
+fn unlimited_huffman_lengths(freqs: &[u32]) -> Vec<u8> {
+    let mut lengths = vec![0_u8; freqs.len()];
+    let used: Vec<usize> = (0..freqs.len()).filter(|&s| freqs[s] > 0).collect();
+    if used.len() < 2 {
+        // A lone symbol still needs a one-bit code.
+        lengths[used.first().copied().unwrap_or(0)] = 1;
+        return lengths;
+    }
+    // Nodes 0..used.len() are leaves; merged nodes follow, each after both children.
+    let mut parent = vec![usize::MAX; used.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = used
+        .iter()
+        .enumerate()
+        .map(|(node, &s)| Reverse((freqs[s] as u64, node)))
+        .collect();
+    while let (Some(Reverse((wa, a))), Some(Reverse((wb, b)))) = (heap.pop(), heap.pop()) {
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        heap.push(Reverse((wa + wb, node)));
+    }
+    let mut depth = vec![0_u8; parent.len()];
+    for node in (0..parent.len() - 1).rev() {
+        depth[node] = depth[parent[node]] + 1;
+    }
+    for (node, &s) in used.iter().enumerate() {
+        lengths[s] = depth[node];
+    }
+    lengths
+}
+
+/// Canonical codes for `lengths`, bit-reversed so `BitWriter` emits them MSB first.
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut count = [0_u32; 16];
+    for &l in lengths.iter().filter(|&&l| l > 0) {
+        count[l as usize] += 1;
+    }
+    let mut next = [0_u32; 16];
+    let mut code = 0;
+    for bits in 1..16 {
+        code = (code + count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&l| {
+            if l == 0 {
+                return 0;
+            }
+            let c = next[l as usize];
+            next[l as usize] += 1;
+            c.reverse_bits() >> (32 - l as u32)
+        })
+        .collect()
+}
+
+/// Run-length codes for a sequence of code lengths: (symbol, extra value, extra bits).
+fn code_length_runs(lengths: &[u8]) -> Vec<(usize, u32, u32)> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let value = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == value).count();
+        if value == 0 && run >= 11 {
+            let n = run.min(138);
+            out.push((18, (n - 11) as u32, 7));
+            i += n;
+        } else if value == 0 && run >= 3 {
+            let n = run.min(10);
+            out.push((17, (n - 3) as u32, 3));
+            i += n;
+        } else if value != 0 && run >= 4 {
+            out.push((value as usize, 0, 0));
+            let n = (run - 1).min(6);
+            out.push((16, (n - 3) as u32, 2));
+            i += 1 + n;
+        } else {
+            out.push((value as usize, 0, 0));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Writes one dynamic-Huffman block holding `tokens`.
+fn write_block(bits: &mut BitWriter, tokens: &[Token], last: bool) {
+    let mut literal_freqs = [0_u32; 286];
+    let mut distance_freqs = [0_u32; 30];
+    for &token in tokens {
+        match token {
+            Token::Literal(b) => literal_freqs[b as usize] += 1,
+            Token::Match { length, distance } => {
+                literal_freqs[257 + bucket(length, &LENGTH_BASE).0] += 1;
+                distance_freqs[bucket(distance, &DISTANCE_BASE).0] += 1;
+            }
+        }
+    }
+    literal_freqs[END_OF_BLOCK] = 1;
+    let literal_lengths = huffman_lengths(&literal_freqs, 15);
+    let distance_lengths = huffman_lengths(&distance_freqs, 15);
+    let literal_codes = canonical_codes(&literal_lengths);
+    let distance_codes = canonical_codes(&distance_lengths);
+    let used = |lengths: &[u8], min: usize| {
+        lengths
+            .iter()
+            .rposition(|&l| l > 0)
+            .map_or(0, |i| i + 1)
+            .max(min)
+    };
+    let hlit = used(&literal_lengths, 257);
+    let hdist = used(&distance_lengths, 1);
+
+    let all_lengths = [&literal_lengths[..hlit], &distance_lengths[..hdist]].concat();
+    let runs = code_length_runs(&all_lengths);
+    let mut cl_freqs = [0_u32; 19];
+    for &(symbol, _, _) in &runs {
+        cl_freqs[symbol] += 1;
+    }
+    let cl_lengths = huffman_lengths(&cl_freqs, 7);
+    let cl_codes = canonical_codes(&cl_lengths);
+    let hclen = CODE_LENGTH_ORDER
+        .iter()
+        .rposition(|&s| cl_lengths[s] > 0)
+        .map_or(0, |i| i + 1)
+        .max(4);
+
+    bits.write(last as u32, 1);
+    bits.write(2, 2);
+    bits.write((hlit - 257) as u32, 5);
+    bits.write((hdist - 1) as u32, 5);
+    bits.write((hclen - 4) as u32, 4);
+    for &s in &CODE_LENGTH_ORDER[..hclen] {
+        bits.write(cl_lengths[s] as u32, 3);
+    }
+    for (symbol, extra, extra_bits) in runs {
+        bits.write(cl_codes[symbol], cl_lengths[symbol] as u32);
+        bits.write(extra, extra_bits);
+    }
+    for &token in tokens {
+        match token {
+            Token::Literal(b) => {
+                bits.write(
+                    literal_codes[b as usize],
+                    literal_lengths[b as usize] as u32,
+                );
+            }
+            Token::Match { length, distance } => {
+                let (code, extra) = bucket(length, &LENGTH_BASE);
+                let symbol = 257 + code;
+                bits.write(literal_codes[symbol], literal_lengths[symbol] as u32);
+                bits.write(extra, LENGTH_EXTRA[code] as u32);
+                let (code, extra) = bucket(distance, &DISTANCE_BASE);
+                bits.write(distance_codes[code], distance_lengths[code] as u32);
+                bits.write(extra, DISTANCE_EXTRA[code] as u32);
+            }
+        }
+    }
+    bits.write(
+        literal_codes[END_OF_BLOCK],
+        literal_lengths[END_OF_BLOCK] as u32,
+    );
+}
+
+/// Raw DEFLATE stream for `data`.
+pub(crate) fn deflate(data: &[u8]) -> Vec<u8> {
+    let tokens = lz77(data);
+    let mut bits = BitWriter::new();
+    let blocks: Vec<&[Token]> = tokens.chunks(BLOCK_TOKENS).collect();
+    if blocks.is_empty() {
+        write_block(&mut bits, &[], true);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        write_block(&mut bits, block, i + 1 == blocks.len());
+    }
+    bits.finish()
+}
+
+/// `data` as a zlib stream (deflate, 32 KiB window), as PNG `IDAT` expects.
+pub(crate) fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x9c];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// Reads bits least-significant first, mirroring `BitWriter`.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or("deflate stream ends early")?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// Canonical Huffman decoding table: symbols sorted by code, with code counts per length.
+struct Decoder {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Decoder {
+    fn new(lengths: &[u8]) -> Result<Decoder, String> {
+        let mut counts = [0_u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1_i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err("over-subscribed Huffman code in deflate stream".to_string());
+            }
+        }
+        let mut offsets = [0_u16; 16];
+        for l in 1..16 {
+            offsets[l] = offsets[l - 1] + counts[l - 1];
+        }
+        let mut symbols = vec![0_u16; lengths.len()];
+        for (symbol, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = symbol as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+        Ok(Decoder { counts, symbols })
+    }
+
+    /// Next symbol, reading the code one bit at a time (codes are stored MSB first).
+    fn decode(&self, bits: &mut BitReader) -> Result<usize, String> {
+        let (mut code, mut first, mut index) = (0_i32, 0_i32, 0_i32);
+        for len in 1..16 {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code in deflate stream".to_string())
+    }
+}
+
+fn fixed_decoders() -> Result<(Decoder, Decoder), String> {
+    let mut lengths = [8_u8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    Ok((Decoder::new(&lengths)?, Decoder::new(&[5; 30])?))
+}
+
+fn dynamic_decoders(bits: &mut BitReader) -> Result<(Decoder, Decoder), String> {
+    let literals = bits.bits(5)? as usize + 257;
+    let distances = bits.bits(5)? as usize + 1;
+    let code_lengths = bits.bits(4)? as usize + 4;
+    let mut lengths = [0_u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = bits.bits(3)? as u8;
+    }
+    let code_length_decoder = Decoder::new(&lengths)?;
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match code_length_decoder.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or("length repeat with no previous length")?;
+                (previous, 3 + bits.bits(2)? as usize)
+            }
+            17 => (0, 3 + bits.bits(3)? as usize),
+            _ => (0, 11 + bits.bits(7)? as usize),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths.len() != literals + distances {
+        return Err("code lengths overrun the dynamic block header".to_string());
+    }
+    Ok((
+        Decoder::new(&lengths[..literals])?,
+        Decoder::new(&lengths[literals..])?,
+    ))
+}
+
+/// Decodes a raw DEFLATE stream, refusing to produce more than `limit` bytes.
+pub(crate) fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut bits = BitReader {
+        bytes: data,
+        pos: 0,
+        bit: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align_to_byte();
+                let header = data
+                    .get(bits.pos..bits.pos + 4)
+                    .ok_or("deflate stream ends early")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err("stored block length check failed".to_string());
+                }
+                let start = bits.pos + 4;
+                let stored = data
+                    .get(start..start + len)
+                    .ok_or("deflate stream ends early")?;
+                out.extend_from_slice(stored);
+                bits.pos = start + len;
+            }
+            kind @ (1 | 2) => {
+                let (literal, distance) = if kind == 1 {
+                    fixed_decoders()?
+                } else {
+                    dynamic_decoders(&mut bits)?
+                };
+                loop {
+                    let symbol = literal.decode(&mut bits)?;
+                    if symbol < END_OF_BLOCK {
+                        out.push(symbol as u8);
+                    } else if symbol == END_OF_BLOCK {
+                        break;
+                    } else {
+                        let code = symbol - 257;
+                        if code >= LENGTH_BASE.len() {
+                            return Err("invalid length code in deflate stream".to_string());
+                        }
+                        let length = LENGTH_BASE[code] as usize
+                            + bits.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                        let code = distance.decode(&mut bits)?;
+                        if code >= DISTANCE_BASE.len() {
+                            return Err("invalid distance code in deflate stream".to_string());
+                        }
+                        let back = DISTANCE_BASE[code] as usize
+                            + bits.bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                        if back > out.len() {
+                            return Err("deflate distance reaches before the output".to_string());
+                        }
+                        let start = out.len() - back;
+                        for i in 0..length {
+                            out.push(out[start + i]);
+                        }
+                    }
+                    if out.len() > limit {
+                        return Err("decompressed data exceeds the size limit".to_string());
+                    }
+                }
+            }
+            _ => return Err("invalid deflate block type".to_string()),
+        }
+        if out.len() > limit {
+            return Err("decompressed data exceeds the size limit".to_string());
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Decodes a zlib stream, checking its header and Adler-32.
+pub(crate) fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("zlib stream too short".to_string());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31) {
+        return Err("not a zlib deflate stream".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+    let out = inflate(&data[2..], limit)?;
+    let stored = u32::from_be_bytes(
+        data[data.len() - 4..]
+            .try_into()
+            .expect("slice is four bytes"),
+    );
+    if adler32(&out) != stored {
+        return Err("zlib checksum mismatch".to_string());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_zlib() {
+        let mut noise = 1_u32;
+        let random: Vec<u8> = (0..5000)
+            .map(|_| {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (noise >> 16) as u8
+            })
+            .collect();
+        let repetitive: Vec<u8> = (0..70_000).map(|i| (i % 251 / 16) as u8).collect();
+        for data in [Vec::new(), b"a".to_vec(), random, repetitive] {
+            let packed = zlib_compress(&data);
+            assert_eq!(
+                zlib_decompress(&packed, data.len()).as_deref(),
+                Ok(&data[..])
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_and_corrupt_streams() {
+        let data = vec![7_u8; 10_000];
+        let packed = zlib_compress(&data);
+        assert!(zlib_decompress(&packed, data.len() - 1).is_err());
+        let mut bad_adler = packed.clone();
+        *bad_adler.last_mut().unwrap() ^= 1;
+        assert!(zlib_decompress(&bad_adler, data.len()).is_err());
+        for len in 0..packed.len() {
+            assert!(
+                zlib_decompress(&packed[..len], data.len()).is_err(),
+                "length {len}"
+            );
+        }
+    }
+}
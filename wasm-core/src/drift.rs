@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, box_blur, check_grid_len, clamp_y, wrap_x};
+
+/// Floats per plate in the plate buffer, as uploaded for pass 1.
+const PLATE_STRIDE: usize = 8;
+/// Most plates pass 1 supports.
+const MAX_PLATES: usize = 24;
+/// Most steps a time-lapse may run.
+const MAX_DRIFT_STEPS: u32 = 1000;
+/// Cell not yet claimed by any plate.
+const NO_PLATE: u32 = u32::MAX;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct DriftParams {
+    /// Steps to simulate; the time-lapse has one more frame, the starting heightmap.
+    pub steps: u32,
+    /// Cells a plate moves per step per unit of plate velocity.
+    pub step_scale: f32,
+    /// Height added along a collision front per step for each cell the plates close by,
+    /// heightmap units; fronts between two continental plates rise twice as fast.
+    pub uplift: f32,
+    /// Half-width of the belt raised along a collision front, cells.
+    pub uplift_width: u32,
+    /// Fraction of the way each cell relaxes towards the mean of its 4 neighbours per
+    /// step, [0, 1].
+    pub erosion: f32,
+    /// Height of the new ocean floor that fills rifts as plates pull apart.
+    pub ridge_height: f32,
+}
+
+impl Default for DriftParams {
+    fn default() -> Self {
+        Self {
+            steps: 60,
+            step_scale: 0.25,
+            uplift: 0.002,
+            uplift_width: 12,
+            erosion: 0.1,
+            ridge_height: 0.1,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DriftParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DriftParams {
+        Self::default()
+    }
+}
+
+impl DriftParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(1..=MAX_DRIFT_STEPS).contains(&self.steps) {
+            return Err(JsValue::from_str(&format!(
+                "steps must be within [1, {MAX_DRIFT_STEPS}]"
+            )));
+        }
+        if !(self.step_scale.is_finite() && self.step_scale >= 0.0) {
+            return Err(JsValue::from_str("step_scale must be finite and >= 0"));
+        }
+        if !(0.0..=1.0).contains(&self.uplift) || !(0.0..=1.0).contains(&self.ridge_height) {
+            return Err(JsValue::from_str(
+                "uplift and ridge_height must be within [0, 1]",
+            ));
+        }
+        if self.uplift_width as usize > WIDTH / 4 {
+            return Err(JsValue::from_str(
+                "uplift_width must be at most a quarter of the grid width",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.erosion) {
+            return Err(JsValue::from_str("erosion must be within [0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+/// One rigid plate: its velocity in cells per step, whether it is oceanic, and its mean
+/// starting height, which decides which of two plates of one kind rides over the other.
+#[derive(Clone, Copy, Debug)]
+struct Plate {
+    velocity: (f32, f32),
+    oceanic: bool,
+    buoyancy: f32,
+}
+
+impl Plate {
+    /// Whole-cell shift of the plate over step `step` (counting from 1), so rounding never
+    /// accumulates however slowly it moves.
+    /// Whether this plate stays on top where it meets `other`: oceanic crust dives under
+    /// continental, and between plates of one kind the lower-lying one goes under. Decided
+    /// per plate rather than per cell, so fronts stay clean instead of interleaving.
+    fn overrides(self, other: Plate) -> bool {
+        match (self.oceanic, other.oceanic) {
+            (false, true) => true,
+            (true, false) => false,
+            _ => self.buoyancy > other.buoyancy,
+        }
+    }
+
+    fn shift(self, step: u32) -> (i64, i64) {
+        let at = |n: u32| {
+            let (vx, vy) = self.velocity;
+            (
+                (vx * n as f32).round() as i64,
+                (vy * n as f32).round() as i64,
+            )
+        };
+        let (before, after) = (at(step - 1), at(step));
+        (after.0 - before.0, after.1 - before.1)
+    }
+}
+
+/// Frames of plates drifting over a heightmap, simulated one step at a time by
+/// `drift_time_lapse`.
+#[wasm_bindgen]
+pub struct DriftTimeLapse {
+    heights: Vec<f32>,
+    plate_ids: Vec<u32>,
+    plates: Vec<Plate>,
+    params: DriftParams,
+    /// Frames handed out so far; frame 0 is the starting heightmap.
+    next: u32,
+}
+
+#[wasm_bindgen]
+impl DriftTimeLapse {
+    #[wasm_bindgen(getter)]
+    pub fn frame_count(&self) -> u32 {
+        self.params.steps + 1
+    }
+
+    /// The next frame's heightmap, or `undefined` after the last.
+    pub fn next_frame(&mut self) -> Option<Box<[f32]>> {
+        if self.next > self.params.steps {
+            return None;
+        }
+        if self.next > 0 {
+            self.step(self.next);
+        }
+        self.next += 1;
+        Some(self.heights.clone().into_boxed_slice())
+    }
+
+    /// Plate owning each cell as of the last frame returned.
+    pub fn plate_map(&self) -> Box<[u32]> {
+        self.plate_ids.clone().into_boxed_slice()
+    }
+}
+
+impl DriftTimeLapse {
+    fn step(&mut self, step: u32) {
+        let shifts: Vec<(i64, i64)> = self.plates.iter().map(|p| p.shift(step)).collect();
+        let mut heights = vec![0.0; CELL_COUNT];
+        let mut ids = vec![NO_PLATE; CELL_COUNT];
+        let mut pressure = vec![0.0_f32; CELL_COUNT];
+        for (idx, &id) in self.plate_ids.iter().enumerate() {
+            let (dx, dy) = shifts[id as usize];
+            let x = wrap_x((idx % WIDTH) as i64 + dx);
+            let y = clamp_y((idx / WIDTH) as i64 + dy);
+            let target = y * WIDTH + x;
+            let h = self.heights[idx];
+            let other = ids[target];
+            if other == NO_PLATE || (other == id && h > heights[target]) {
+                heights[target] = h;
+                ids[target] = id;
+            } else if other != id {
+                let (mine, theirs) = (self.plates[id as usize], self.plates[other as usize]);
+                if mine.overrides(theirs) {
+                    heights[target] = h;
+                    ids[target] = id;
+                }
+                pressure[target] = if mine.oceanic || theirs.oceanic {
+                    1.0
+                } else {
+                    2.0
+                };
+            }
+        }
+        fill_rifts(&mut heights, &mut ids, self.params.ridge_height);
+        self.uplift(&mut heights, &pressure);
+        self.heights = erode(&heights, self.params.erosion);
+        self.plate_ids = ids;
+    }
+
+    /// Raises a belt along the collision fronts marked in `pressure`, peaking on the front.
+    fn uplift(&self, heights: &mut [f32], pressure: &[f32]) {
+        let width = self.params.uplift_width as usize;
+        if self.params.uplift == 0.0 || width == 0 || pressure.iter().all(|&p| p == 0.0) {
+            return;
+        }
+        // Two box passes give a tent profile; its peak over a front one cell wide is
+        // 1 / (2 × outer + 1), so scale back up to `uplift` per cell of overlap.
+        let (inner, outer) = (width / 2, width - width / 2);
+        let belt = box_blur(&box_blur(pressure, inner.max(1)), outer);
+        let gain = self.params.uplift * (2 * outer + 1) as f32;
+        for (h, &b) in heights.iter_mut().zip(&belt) {
+            // Running sums leave rounding residue far from any front; ignore it.
+            if b > 1e-4 {
+                *h = (*h + b * gain).min(1.0);
+            }
+        }
+    }
+}
+
+/// Fills the cells no plate moved onto, where plates pulled apart, with fresh ocean floor
+/// at `ridge_height` belonging to the nearest plate.
+fn fill_rifts(heights: &mut [f32], ids: &mut [u32], ridge_height: f32) {
+    let mut queue: VecDeque<usize> = (0..CELL_COUNT).filter(|&i| ids[i] != NO_PLATE).collect();
+    while let Some(idx) = queue.pop_front() {
+        let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+        for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if !(0..HEIGHT as i64).contains(&ny) {
+                continue;
+            }
+            let n = ny as usize * WIDTH + wrap_x(nx);
+            if ids[n] == NO_PLATE {
+                ids[n] = ids[idx];
+                heights[n] = ridge_height;
+                queue.push_back(n);
+            }
+        }
+    }
+}
+
+/// One step of diffusion: each cell moves `rate` of the way to its 4-neighbour mean.
+fn erode(heights: &[f32], rate: f32) -> Vec<f32> {
+    (0..CELL_COUNT)
+        .map(|idx| {
+            let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+            let at = |nx: i64, ny: i64| heights[clamp_y(ny) * WIDTH + wrap_x(nx)];
+            let mean = (at(x + 1, y) + at(x - 1, y) + at(x, y + 1) + at(x, y - 1)) * 0.25;
+            let h = heights[idx];
+            (h + (mean - h) * rate).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// Time-lapse of the world's plates drifting apart and together, for a tectonic history
+/// animation. `plates` is the plate buffer pass 1 generates from (8 floats per plate:
+/// position, weight, type, velocity, padding) and `plate_ids` its per-cell plate index
+/// read back from the GPU. Each step moves every plate rigidly along its velocity, sinks
+/// oceanic crust under continental crust where plates collide and raises mountains along
+/// the front, fills rifts with new ocean floor, then erodes everything a little.
+#[wasm_bindgen]
+pub fn drift_time_lapse(
+    heightmap: &[f32],
+    plates: &[f32],
+    plate_ids: &[u32],
+    params: &DriftParams,
+) -> Result<DriftTimeLapse, JsValue> {
+    check_grid_len(heightmap, "heightmap")?;
+    check_grid_len(plate_ids, "plate ids")?;
+    params.validate()?;
+    if plates.is_empty()
+        || !plates.len().is_multiple_of(PLATE_STRIDE)
+        || plates.len() / PLATE_STRIDE > MAX_PLATES
+    {
+        return Err(JsValue::from_str(&format!(
+            "plates must hold 1 to {MAX_PLATES} plates of {PLATE_STRIDE} floats"
+        )));
+    }
+    if plates.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("plates must be finite"));
+    }
+    let count = plates.len() / PLATE_STRIDE;
+    if plate_ids.iter().any(|&id| id as usize >= count) {
+        return Err(JsValue::from_str("plate ids must index the plates"));
+    }
+    let mut totals = vec![(0.0_f64, 0_usize); count];
+    for (&id, &h) in plate_ids.iter().zip(heightmap) {
+        totals[id as usize].0 += h as f64;
+        totals[id as usize].1 += 1;
+    }
+    let plates: Vec<Plate> = plates
+        .chunks_exact(PLATE_STRIDE)
+        .zip(totals)
+        .map(|(p, (sum, cells))| Plate {
+            velocity: (p[4] * params.step_scale, p[5] * params.step_scale),
+            oceanic: p[3] >= 0.5,
+            buoyancy: (sum / cells.max(1) as f64) as f32,
+        })
+        .collect();
+    Ok(DriftTimeLapse {
+        heights: heightmap.to_vec(),
+        plate_ids: plate_ids.to_vec(),
+        plates,
+        params: *params,
+        next: 0,
+    })
+}
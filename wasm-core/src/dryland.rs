@@ -0,0 +1,79 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::grid::CELL_COUNT;
+
+pub(crate) const DRYLAND_NONE: u8 = 0;
+pub(crate) const DRYLAND_HOT_DESERT: u8 = 1;
+pub(crate) const DRYLAND_COLD_DESERT: u8 = 2;
+
+/// UNEP "arid" ceiling on the aridity index (precipitation / PET).
+pub(crate) const DESERT_ARIDITY: f32 = 0.2;
+/// Upper bounds of the UNEP aridity classes: hyper-arid, arid, semi-arid, dry sub-humid;
+/// anything wetter is humid.
+pub(crate) const UNEP_ARIDITY_BOUNDS: [f32; 4] = [0.05, DESERT_ARIDITY, 0.5, 0.65];
+pub(crate) const UNEP_ARIDITY_CLASSES: [&str; 5] =
+    ["hyper_arid", "arid", "semi_arid", "dry_subhumid", "humid"];
+/// The aridity layer is clamped here; PET vanishes over ice, sending the raw ratio to infinity.
+pub(crate) const ARIDITY_CAP: f32 = 10.0;
+/// Köppen h/k split: hot deserts have a mean annual temperature of at least 18 °C.
+const HOT_DESERT_MIN_C: f32 = 18.0;
+
+/// Aridity index P / PET; unbounded (infinite) where PET is zero.
+pub(crate) fn aridity_index(precip_mm: f32, pet_mm: f32) -> f32 {
+    if pet_mm <= 0.0 {
+        return f32::INFINITY;
+    }
+    precip_mm / pet_mm
+}
+
+/// Index into `UNEP_ARIDITY_CLASSES` for an aridity index value.
+pub(crate) fn unep_aridity_class(aridity: f32) -> usize {
+    UNEP_ARIDITY_BOUNDS
+        .iter()
+        .position(|&bound| aridity < bound)
+        .unwrap_or(UNEP_ARIDITY_BOUNDS.len())
+}
+
+/// Deserts are placed purely by moisture deficit: rainfall too small to meet evaporative
+/// demand, split hot/cold by mean temperature.
+pub(crate) fn dryland_class(temp_c: f32, precip_mm: f32, pet_mm: f32) -> u8 {
+    if aridity_index(precip_mm, pet_mm) >= DESERT_ARIDITY {
+        DRYLAND_NONE
+    } else if temp_c >= HOT_DESERT_MIN_C {
+        DRYLAND_HOT_DESERT
+    } else {
+        DRYLAND_COLD_DESERT
+    }
+}
+
+/// Dryland mask per cell: 0 = not desert (or water), 1 = hot desert, 2 = cold desert.
+/// Aridity is an annual measure, so `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn dryland_mask(climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("dryland_mask")?;
+    let mut mask = vec![DRYLAND_NONE; CELL_COUNT];
+    for (idx, m) in mask.iter_mut().enumerate() {
+        if climate.water_distance[idx] > 0.0 {
+            *m = dryland_class(
+                climate.temperature[idx],
+                climate.precipitation[idx],
+                climate.pet[idx],
+            );
+        }
+    }
+    Ok(mask.into_boxed_slice())
+}
+
+/// UNEP aridity index (precipitation / PET) per cell, clamped to [0, 10]; water cells are 0.
+/// Below 0.65 is dryland: hyper-arid < 0.05 ≤ arid < 0.2 ≤ semi-arid < 0.5 ≤ dry sub-humid.
+#[wasm_bindgen]
+pub fn aridity_index_layer(climate: &Climate) -> Box<[f32]> {
+    let mut layer = vec![0.0; CELL_COUNT];
+    for (idx, a) in layer.iter_mut().enumerate() {
+        if climate.water_distance[idx] > 0.0 {
+            *a = aridity_index(climate.precipitation[idx], climate.pet[idx]).min(ARIDITY_CAP);
+        }
+    }
+    layer.into_boxed_slice()
+}
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{BIOME_WATER, BIOMES, whittaker};
+use crate::climate::Climate;
+use crate::grid::CELL_COUNT;
+
+/// Half-width of the climate-space neighbourhood sampled around each cell.
+const ECOTONE_TEMP_C: f32 = 2.5;
+const ECOTONE_PRECIP_FRACTION: f32 = 0.25;
+/// Samples per axis; the classifier is evaluated on an N×N lattice with tent weights.
+const ECOTONE_SAMPLES: usize = 5;
+
+/// Top-two biomes per cell with the share of the secondary biome.
+#[wasm_bindgen]
+pub struct BiomeBlend {
+    primary: Vec<u8>,
+    secondary: Vec<u8>,
+    blend: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl BiomeBlend {
+    /// Dominant biome id per cell.
+    pub fn primary(&self) -> Box<[u8]> {
+        self.primary.clone().into_boxed_slice()
+    }
+
+    /// Runner-up biome id per cell (equal to `primary` away from any ecotone).
+    pub fn secondary(&self) -> Box<[u8]> {
+        self.secondary.clone().into_boxed_slice()
+    }
+
+    /// Weight of `secondary` in [0, 0.5]; 0 = pure primary, 0.5 = even mix.
+    pub fn blend(&self) -> Box<[f32]> {
+        self.blend.clone().into_boxed_slice()
+    }
+}
+
+fn tent(i: usize) -> f32 {
+    let centre = (ECOTONE_SAMPLES - 1) as f32 * 0.5;
+    1.0 - (i as f32 - centre).abs() / (centre + 1.0)
+}
+
+/// Ecotone weights for one cell: classifies a tent-weighted lattice of nearby climates and
+/// returns (primary, secondary, secondary share).
+pub(crate) fn blend_at(climate: &Climate, idx: usize) -> (u8, u8, f32) {
+    let temp = climate.temperature[idx];
+    let warmest = climate.warmest_month_at(idx);
+    let precip = climate.precipitation[idx];
+    let pet = climate.pet[idx];
+    let step = 2.0 / (ECOTONE_SAMPLES - 1) as f32;
+
+    let mut votes = [0.0_f32; BIOMES.len()];
+    for i in 0..ECOTONE_SAMPLES {
+        let dt = (i as f32 * step - 1.0) * ECOTONE_TEMP_C;
+        for j in 0..ECOTONE_SAMPLES {
+            let dp = 1.0 + (j as f32 * step - 1.0) * ECOTONE_PRECIP_FRACTION;
+            let biome = whittaker(temp + dt, warmest + dt, precip * dp, pet);
+            votes[biome as usize] += tent(i) * tent(j);
+        }
+    }
+
+    let mut ranked: Vec<usize> = (0..votes.len()).collect();
+    ranked.sort_by(|&a, &b| votes[b].total_cmp(&votes[a]));
+    let (first, second) = (ranked[0], ranked[1]);
+    if votes[second] == 0.0 {
+        return (first as u8, first as u8, 0.0);
+    }
+    let share = votes[second] / (votes[first] + votes[second]);
+    (first as u8, second as u8, share)
+}
+
+/// Per-cell biome blending for soft ecotones between adjacent Whittaker biomes, from an
+/// annual climate run.
+#[wasm_bindgen]
+pub fn biome_ecotones(climate: &Climate) -> Result<BiomeBlend, JsValue> {
+    climate.require_annual("biome_ecotones")?;
+    let mut primary = vec![BIOME_WATER; CELL_COUNT];
+    let mut secondary = vec![BIOME_WATER; CELL_COUNT];
+    let mut blend = vec![0.0; CELL_COUNT];
+    for idx in 0..CELL_COUNT {
+        if climate.water_distance[idx] > 0.0 {
+            (primary[idx], secondary[idx], blend[idx]) = blend_at(climate, idx);
+        }
+    }
+    Ok(BiomeBlend {
+        primary,
+        secondary,
+        blend,
+    })
+}
@@ -0,0 +1,965 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::NO_BIOME_OVERRIDE;
+use crate::climate::RELIEF_METRES;
+use crate::grid::{
+    CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, clamp_y, sample_bilinear, sample_wrapped,
+    smoothstep, wrap_x, wrapped_distance_field,
+};
+use crate::history::{History, union_rect};
+use crate::json::{self, Json};
+use crate::lake_fill::{LakeFill, fill_basin};
+use crate::noise::perlin;
+use crate::project::Project;
+use crate::render::ColorRamp;
+use crate::renoise::{RenoiseParams, renoise};
+use crate::river_draw::{
+    DrawnRiver, RiverParams, bank_cells, channel_levels, rasterize_stroke, route_river,
+};
+use crate::selection::Selection;
+use crate::snapshots::{Snapshot, unpack_heights};
+use crate::stamps::{StampBlend, StampParams, StampShape};
+
+/// Largest brush radius, cells.
+const MAX_BRUSH_RADIUS: f32 = 512.0;
+/// Height a full-strength raise, lower or noise dab moves the brush centre: about 190 m
+/// with the default sea level.
+const MAX_DAB_STEP: f32 = 0.02;
+
+/// Brush shape and strength for `TerrainEditor` operations.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct BrushParams {
+    /// Radius in grid cells, up to 512. The brush is round on the grid, so it covers less
+    /// ground east–west toward the poles.
+    pub radius: f32,
+    /// 0 to 1. Raise, lower and noise dabs move the centre by up to 0.02; smooth and
+    /// flatten move it this fraction of the way to their target.
+    pub strength: f32,
+    /// Fraction of the radius, from the rim inward, over which the brush fades out: 0 is
+    /// a hard edge, 1 fades all the way from the centre.
+    pub falloff: f32,
+    /// Feature size of the noise brush, cells.
+    pub noise_scale: f32,
+    pub seed: u32,
+}
+
+impl Default for BrushParams {
+    fn default() -> Self {
+        Self {
+            radius: 16.0,
+            strength: 0.5,
+            falloff: 0.5,
+            noise_scale: 8.0,
+            seed: crate::DEFAULT_SEED,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl BrushParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BrushParams {
+        Self::default()
+    }
+}
+
+impl BrushParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(self.radius > 0.0 && self.radius <= MAX_BRUSH_RADIUS) {
+            return Err(JsValue::from_str(&format!(
+                "radius must be within (0, {MAX_BRUSH_RADIUS}]"
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.strength) || !(0.0..=1.0).contains(&self.falloff) {
+            return Err(JsValue::from_str(
+                "strength and falloff must be within [0, 1]",
+            ));
+        }
+        if !(self.noise_scale.is_finite() && self.noise_scale >= 1.0) {
+            return Err(JsValue::from_str("noise_scale must be >= 1"));
+        }
+        Ok(())
+    }
+
+    /// Brush weight at `distance` cells from the centre.
+    fn weight(&self, distance: f32) -> f32 {
+        let inner = self.radius * (1.0 - self.falloff);
+        if distance > self.radius {
+            0.0
+        } else if distance <= inner {
+            1.0
+        } else {
+            let t = (self.radius - distance) / (self.radius - inner);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+/// Rejects brush and stamp centres (and river stroke points) that are not finite or lie
+/// more than a grid's width or height beyond its edges; centres that far out would overflow
+/// the footprint bounds.
+fn check_centre(x: f32, y: f32, what: &str) -> Result<(), JsValue> {
+    let (reach_x, reach_y) = (2 * WIDTH, 2 * HEIGHT);
+    if x.abs() <= reach_x as f32 && y.abs() <= reach_y as f32 {
+        Ok(())
+    } else {
+        Err(JsValue::from_str(&format!(
+            "{what} must be finite with |x| <= {reach_x} and |y| <= {reach_y}"
+        )))
+    }
+}
+
+/// Cells under a brush: rows `y0..y1` and columns `x0..x1` before wrapping.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Footprint {
+    centre: (f32, f32),
+    x0: i64,
+    x1: i64,
+    y0: usize,
+    y1: usize,
+}
+
+impl Footprint {
+    pub(crate) fn new((x, y): (f32, f32), radius: f32) -> Footprint {
+        let x0 = (x - radius).ceil() as i64;
+        Footprint {
+            centre: (x, y),
+            x0,
+            // Never more than once round the world.
+            x1: ((x + radius).floor() as i64 + 1).min(x0 + WIDTH as i64),
+            y0: clamp_y((y - radius).ceil() as i64),
+            y1: clamp_y((y + radius).floor() as i64) + 1,
+        }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        (self.x1 - self.x0) as usize
+    }
+
+    /// Each cell as (grid index, offset from the centre in cells), row by row.
+    pub(crate) fn offsets(self) -> impl Iterator<Item = (usize, (f32, f32))> {
+        let (cx, cy) = self.centre;
+        (self.y0..self.y1).flat_map(move |y| {
+            (self.x0..self.x1).map(move |x| (y * WIDTH + wrap_x(x), (x as f32 - cx, y as f32 - cy)))
+        })
+    }
+
+    /// Each cell as (grid index, distance from the centre in cells), row by row.
+    pub(crate) fn cells(self) -> impl Iterator<Item = (usize, f32)> {
+        self.offsets().map(|(idx, (dx, dy))| (idx, dx.hypot(dy)))
+    }
+
+    /// The footprint as `[x, y, width, height]` rectangles on the grid: one, or two where
+    /// it crosses the east–west seam.
+    pub(crate) fn rects(&self) -> Vec<[u32; 4]> {
+        let start = wrap_x(self.x0);
+        let (width, height) = (self.width(), self.y1 - self.y0);
+        let rect = |x: usize, w: usize| [x as u32, self.y0 as u32, w as u32, height as u32];
+        if start + width <= WIDTH {
+            vec![rect(start, width)]
+        } else {
+            vec![rect(start, WIDTH - start), rect(0, start + width - WIDTH)]
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Stroke {
+    Raise,
+    Lower,
+    Smooth,
+    Flatten(f32),
+    Noise,
+    /// Flatten that leaves water alone.
+    Site(f32),
+}
+
+/// A heightmap held in wasm memory and edited in place with brushes, so painting does not
+/// copy the whole grid across on every dab. Each operation returns the rectangles it
+/// touched; the editor also keeps their bounding box until `take_dirty`, so the app can
+/// upload just the changed region (`region`) to the GPU once per frame. Edits are undone
+/// a checkpoint at a time; see `checkpoint`.
+#[wasm_bindgen]
+pub struct TerrainEditor {
+    flat: Vec<f32>,
+    /// Dirty bounds `[x0, y0, x1, y1)` since the last `take_dirty`.
+    dirty: Option<[usize; 4]>,
+    history: History,
+    sea_level: f32,
+    /// Surface height of hand-filled lakes, 0 where there are none.
+    lakes: Vec<f32>,
+    /// Confines every edit when set.
+    selection: Option<Selection>,
+    /// Cells `regenerate` leaves alone.
+    locked: Vec<bool>,
+    /// Named snapshots, in the order saved.
+    snapshots: Vec<Snapshot>,
+    /// Hand-painted biome per cell, `NO_BIOME_OVERRIDE` where the classifier decides.
+    biome_overrides: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl TerrainEditor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(flat: &[f32]) -> Result<TerrainEditor, JsValue> {
+        check_grid_len(flat, "flat heightmap")?;
+        Ok(Self {
+            flat: flat.to_vec(),
+            dirty: None,
+            history: History::new(flat),
+            sea_level: SEA_LEVEL,
+            lakes: vec![0.0; CELL_COUNT],
+            selection: None,
+            locked: vec![false; CELL_COUNT],
+            snapshots: Vec::new(),
+            biome_overrides: vec![NO_BIOME_OVERRIDE; CELL_COUNT],
+        })
+    }
+
+    /// A copy of the whole heightmap.
+    pub fn heightmap(&self) -> Box<[f32]> {
+        self.flat.clone().into_boxed_slice()
+    }
+
+    /// Replaces the whole heightmap, e.g. after regenerating, and marks it all dirty. With
+    /// a selection active only the selected cells take the new heights, blended by weight.
+    pub fn set_heightmap(&mut self, flat: &[f32]) -> Result<(), JsValue> {
+        check_grid_len(flat, "flat heightmap")?;
+        for (idx, (h, &new)) in self.flat.iter_mut().zip(flat).enumerate() {
+            *h += (new - *h)
+                * self
+                    .selection
+                    .as_ref()
+                    .map_or(1.0, |s| s.weights_slice()[idx]);
+        }
+        self.mark_dirty([0, 0, WIDTH as u32, HEIGHT as u32]);
+        Ok(())
+    }
+
+    /// Height of cell `(x, y)`, e.g. to pick a flatten target under the cursor.
+    pub fn height_at(&self, x: u32, y: u32) -> Result<f32, JsValue> {
+        if x as usize >= WIDTH || y as usize >= HEIGHT {
+            return Err(JsValue::from_str("cell must lie within the grid"));
+        }
+        Ok(self.flat[y as usize * WIDTH + x as usize])
+    }
+
+    /// Cells `x..x + width` × `y..y + height` of the heightmap, row-major.
+    pub fn region(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Box<[f32]>, JsValue> {
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+        // Checked so huge offsets cannot wrap past the bounds on 32-bit targets.
+        if width == 0
+            || height == 0
+            || x.checked_add(width).is_none_or(|end| end > WIDTH)
+            || y.checked_add(height).is_none_or(|end| end > HEIGHT)
+        {
+            return Err(JsValue::from_str(
+                "region must be non-empty and within the grid",
+            ));
+        }
+        Ok((y..y + height)
+            .flat_map(|row| &self.flat[row * WIDTH + x..row * WIDTH + x + width])
+            .copied()
+            .collect())
+    }
+
+    /// `[x, y, width, height]` bounding every cell changed since the last call, or empty if
+    /// nothing has; clears it. Edits either side of the seam give a full-width band.
+    pub fn take_dirty(&mut self) -> Box<[u32]> {
+        match self.dirty.take() {
+            Some([x0, y0, x1, y1]) => {
+                Box::new([x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32])
+            }
+            None => Box::new([]),
+        }
+    }
+
+    /// Raises the ground under a brush centred on cell coordinates `(x, y)`. Like the other
+    /// brush operations it returns the rectangles touched as `[x, y, width, height]`
+    /// quadruples: one, or two where the brush crosses the east–west seam.
+    pub fn raise(&mut self, x: f32, y: f32, brush: &BrushParams) -> Result<Box<[u32]>, JsValue> {
+        self.apply(x, y, brush, Stroke::Raise)
+    }
+
+    pub fn lower(&mut self, x: f32, y: f32, brush: &BrushParams) -> Result<Box<[u32]>, JsValue> {
+        self.apply(x, y, brush, Stroke::Lower)
+    }
+
+    /// Eases heights toward their local mean over a quarter of the brush radius.
+    pub fn smooth(&mut self, x: f32, y: f32, brush: &BrushParams) -> Result<Box<[u32]>, JsValue> {
+        self.apply(x, y, brush, Stroke::Smooth)
+    }
+
+    /// Eases heights toward `target`, a heightmap value in [0, 1].
+    pub fn flatten(
+        &mut self,
+        x: f32,
+        y: f32,
+        target: f32,
+        brush: &BrushParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        if !(0.0..=1.0).contains(&target) {
+            return Err(JsValue::from_str("target must be within [0, 1]"));
+        }
+        self.apply(x, y, brush, Stroke::Flatten(target))
+    }
+
+    /// Adds Perlin detail of `noise_scale` cells, fixed in place for a given seed so repeated
+    /// dabs deepen the same pattern rather than churning it.
+    pub fn noise(&mut self, x: f32, y: f32, brush: &BrushParams) -> Result<Box<[u32]>, JsValue> {
+        self.apply(x, y, brush, Stroke::Noise)
+    }
+
+    /// Levels a settlement site: eases land under the brush toward its mean height, taken
+    /// with the brush's own weights, so the core ends up flat and the falloff ramps into
+    /// the surrounding terrain. At strength 1 the core is levelled in one call. Cells below
+    /// sea level are neither counted nor changed, so a harbour keeps its water.
+    pub fn flatten_site(
+        &mut self,
+        x: f32,
+        y: f32,
+        brush: &BrushParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        brush.validate()?;
+        check_centre(x, y, "brush centre")?;
+        let (mut sum, mut total) = (0.0, 0.0);
+        for (idx, distance) in Footprint::new((x, y), brush.radius).cells() {
+            let h = self.flat[idx];
+            if h >= self.sea_level {
+                let weight = brush.weight(distance) * self.selected(idx);
+                sum += h * weight;
+                total += weight;
+            }
+        }
+        if total == 0.0 {
+            return Err(JsValue::from_str("no land under the brush"));
+        }
+        self.apply(x, y, brush, Stroke::Site(sum / total))
+    }
+
+    /// Blends a parametric landform centred on `(x, y)` into the terrain. `shape` is
+    /// `"volcano"`, `"shield"` (shield mountain), `"crater"`, `"island"` or `"plateau"`;
+    /// `mode` is `"add"` to stack it on the ground, `"max"` to raise the ground to it, or
+    /// `"replace"`, the last two setting it on the height under its centre. Returns the
+    /// rectangles touched, as the brushes do.
+    pub fn stamp(
+        &mut self,
+        shape: &str,
+        x: f32,
+        y: f32,
+        mode: &str,
+        params: &StampParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        let shape = StampShape::parse(shape).map_err(|e| JsValue::from_str(&e))?;
+        let blend = StampBlend::parse(mode).map_err(|e| JsValue::from_str(&e))?;
+        params.validate()?;
+        check_centre(x, y, "stamp centre")?;
+        let base = sample_bilinear(&self.flat, x, y);
+        // Outline noise can push the shape a little past its nominal reach.
+        let reach = params.radius * params.elongation * (1.0 + params.roughness);
+        let footprint = Footprint::new((x, y), reach);
+        for (idx, offset) in footprint.offsets() {
+            if let Some((relief, weight)) = params.sample(shape, offset) {
+                let weight = weight * self.selected(idx);
+                let h = blend.apply(self.flat[idx], base, relief, weight);
+                self.flat[idx] = h.clamp(0.0, 1.0);
+            }
+        }
+        let rects = footprint.rects();
+        for &rect in &rects {
+            self.mark_dirty(rect);
+        }
+        Ok(rects.into_iter().flatten().collect())
+    }
+
+    /// Closes the current undo step: every edit since the last checkpoint becomes one step,
+    /// so a brush stroke is usually checkpointed when the pointer lifts. `params_json` is
+    /// the application's parameter object after the step (a JSON object), or empty if
+    /// unchanged; steps that only change parameters are recorded too. Returns false if
+    /// there was nothing to record. Checkpointing after an undo discards the redo steps.
+    pub fn checkpoint(&mut self, label: &str, params_json: &str) -> Result<bool, JsValue> {
+        let params = if params_json.is_empty() {
+            None
+        } else {
+            match json::parse(params_json).map_err(|e| JsValue::from_str(&e))? {
+                params @ Json::Object(_) => Some(params),
+                _ => return Err(JsValue::from_str("params must be a JSON object")),
+            }
+        };
+        Ok(self.history.checkpoint(&self.flat, label, params))
+    }
+
+    /// Undoes the last step, checkpointing pending edits first so that they are what gets
+    /// undone. Returns false at the start of history; restored cells are marked dirty.
+    pub fn undo(&mut self) -> Result<bool, JsValue> {
+        if !self.history.can_undo() {
+            return Ok(false);
+        }
+        let bounds = self
+            .history
+            .undo(&mut self.flat)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.mark_restored(bounds);
+        Ok(true)
+    }
+
+    /// Redoes the step last undone; false if there is none.
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        if !self.history.can_redo() {
+            return Ok(false);
+        }
+        let bounds = self
+            .history
+            .redo(&mut self.flat)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.mark_restored(bounds);
+        Ok(true)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Parameters as of the current step, for the app to restore after undo or redo;
+    /// `null` before any were given.
+    pub fn params_json(&self) -> String {
+        self.history.params().to_string()
+    }
+
+    /// Steps held and their compressed sizes: `{"cursor","bytes","budget","steps":[{"label",
+    /// "bytes","rect"},...]}`, oldest first. Steps before `cursor` can be undone and the
+    /// rest redone; `rect` is `[x, y, width, height]`, or null for parameter-only steps.
+    pub fn history_json(&self) -> String {
+        self.history.to_json()
+    }
+
+    /// Caps compressed history at `bytes` (64 MiB by default), dropping the oldest steps
+    /// beyond it; the latest step is always kept. The editor also holds one uncompressed
+    /// copy of the heightmap to diff edits against.
+    pub fn set_history_budget(&mut self, bytes: u32) {
+        self.history.set_budget(bytes as usize);
+    }
+
+    /// Forgets all undo steps, keeping the current heightmap and parameters.
+    pub fn clear_history(&mut self) {
+        self.history.clear(&self.flat);
+    }
+
+    /// Saves the current heightmap, sea level and parameters as a snapshot called `name`,
+    /// e.g. "before erosion", replacing any snapshot of that name. Its 256 × 128 thumbnail
+    /// is tinted through `ramp_json` as in `render_thumbnail_rgba` (empty for the atlas
+    /// ramp). Pending edits stay pending.
+    pub fn save_snapshot(&mut self, name: &str, ramp_json: &str) -> Result<(), JsValue> {
+        if name.is_empty() {
+            return Err(JsValue::from_str("snapshot name must not be empty"));
+        }
+        let ramp = ColorRamp::parse(ramp_json).map_err(|e| JsValue::from_str(&e))?;
+        let snapshot = Snapshot::capture(
+            name,
+            &self.flat,
+            self.sea_level,
+            self.history.params(),
+            &ramp,
+        );
+        match self.snapshots.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = snapshot,
+            None => self.snapshots.push(snapshot),
+        }
+        Ok(())
+    }
+
+    /// Snapshots in the order saved: `[{"name","sea_level","bytes","params"},...]`, where
+    /// `bytes` is the memory each takes compressed.
+    pub fn snapshots_json(&self) -> String {
+        json::array(self.snapshots.iter().map(Snapshot::to_json))
+    }
+
+    /// Thumbnail of snapshot `name` as a PNG file, ready to show as an image.
+    pub fn snapshot_thumbnail(&self, name: &str) -> Result<Box<[u8]>, JsValue> {
+        Ok(self.snapshot(name)?.thumbnail.clone().into_boxed_slice())
+    }
+
+    /// Returns to snapshot `name`: its heights replace the whole map, selection or not,
+    /// and its sea level and parameters come back too. Restoring is one undo step,
+    /// labelled `restore <name>`, after any pending edits are checkpointed; undoing it
+    /// brings back the heights and parameters but not the sea level.
+    pub fn restore_snapshot(&mut self, name: &str) -> Result<(), JsValue> {
+        let snapshot = self.snapshot(name)?;
+        let heights = unpack_heights(&snapshot.heights).map_err(|e| JsValue::from_str(&e))?;
+        let (sea_level, params) = (snapshot.sea_level, snapshot.params.clone());
+        self.history.checkpoint(&self.flat, "edit", None);
+        self.flat = heights;
+        self.sea_level = sea_level;
+        self.mark_dirty([0, 0, WIDTH as u32, HEIGHT as u32]);
+        self.history
+            .checkpoint(&self.flat, &format!("restore {name}"), Some(params));
+        Ok(())
+    }
+
+    /// Deletes snapshot `name`; false if there is none.
+    pub fn delete_snapshot(&mut self, name: &str) -> bool {
+        let count = self.snapshots.len();
+        self.snapshots.retain(|s| s.name != name);
+        self.snapshots.len() < count
+    }
+
+    /// The editor as a project file: heightmap, sea level, parameters, biome overrides and
+    /// every snapshot, compressed, to save and reopen later with `import_project`. Undo
+    /// history, lakes, locks and the selection are not included.
+    pub fn export_project(&self) -> Box<[u8]> {
+        Project {
+            heights: self.flat.clone(),
+            sea_level: self.sea_level,
+            params: self.history.params().clone(),
+            snapshots: self.snapshots.clone(),
+            biome_overrides: self.biome_overrides.clone(),
+        }
+        .to_bytes()
+        .into_boxed_slice()
+    }
+
+    /// Sea level the editor's water tools work to (0.15 by default).
+    #[wasm_bindgen(getter)]
+    pub fn sea_level(&self) -> f32 {
+        self.sea_level
+    }
+
+    pub fn set_sea_level(&mut self, sea_level: f32) -> Result<(), JsValue> {
+        if !sea_level.is_finite() || sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        self.sea_level = sea_level;
+        Ok(())
+    }
+
+    /// Fills the depression that water at cell `(x, y)` runs down into with a lake up to
+    /// `target_level` (heightmap units), or to the spill point if that comes first; pass
+    /// NaN to fill right to the spill point. Water spreads between all 8 neighbours and
+    /// drains into the sea, so the point must be on land, and a point draining to the sea
+    /// fills nothing. The lake joins the editor's water layer (`lake_mask`,
+    /// `lake_levels`); the heightmap itself is unchanged.
+    pub fn fill_lake_at(&mut self, x: u32, y: u32, target_level: f32) -> Result<LakeFill, JsValue> {
+        if x as usize >= WIDTH || y as usize >= HEIGHT {
+            return Err(JsValue::from_str("cell must lie within the grid"));
+        }
+        let target = if target_level.is_nan() {
+            None
+        } else if (0.0..=1.0).contains(&target_level) {
+            Some(target_level)
+        } else {
+            return Err(JsValue::from_str(
+                "target_level must be within [0, 1] or NaN",
+            ));
+        };
+        let seed = y as usize * WIDTH + x as usize;
+        if self.flat[seed] < self.sea_level {
+            return Err(JsValue::from_str("cell lies below sea level"));
+        }
+        let (cells, fill) = fill_basin(&self.flat, seed, target, self.sea_level, |idx| {
+            self.selected(idx) > 0.0
+        });
+        for idx in cells {
+            self.lakes[idx] = fill.level();
+        }
+        Ok(fill)
+    }
+
+    /// 1 where a filled lake's surface lies above the ground, else 0. Ground raised
+    /// through a lake after filling dries out.
+    pub fn lake_mask(&self) -> Box<[u8]> {
+        self.lakes
+            .iter()
+            .zip(&self.flat)
+            .map(|(&level, &h)| u8::from(level > h))
+            .collect()
+    }
+
+    /// Lake surface height of each cell under a filled lake, else 0.
+    pub fn lake_levels(&self) -> Box<[f32]> {
+        self.lakes
+            .iter()
+            .zip(&self.flat)
+            .map(|(&level, &h)| if level > h { level } else { 0.0 })
+            .collect()
+    }
+
+    /// Drains every filled lake.
+    pub fn clear_lakes(&mut self) {
+        self.lakes.fill(0.0);
+    }
+
+    /// Confines every later edit to `selection`: brushes, stamps and site levelling scale
+    /// their effect by its weights, lakes fill only selected cells, and `set_heightmap`
+    /// blends regenerated terrain in by weight. The editor keeps its own copy.
+    pub fn set_selection(&mut self, selection: &Selection) {
+        self.selection = Some(selection.clone());
+    }
+
+    /// Lets edits reach the whole grid again.
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn has_selection(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    /// Locks every cell `selection` touches against `regenerate`.
+    pub fn lock(&mut self, selection: &Selection) {
+        for (locked, &w) in self.locked.iter_mut().zip(selection.weights_slice()) {
+            *locked |= w > 0.0;
+        }
+    }
+
+    /// Unlocks every cell `selection` touches.
+    pub fn unlock(&mut self, selection: &Selection) {
+        for (locked, &w) in self.locked.iter_mut().zip(selection.weights_slice()) {
+            *locked &= w == 0.0;
+        }
+    }
+
+    pub fn clear_locks(&mut self) {
+        self.locked.fill(false);
+    }
+
+    /// 1 for locked cells, else 0.
+    pub fn lock_mask(&self) -> Box<[u8]> {
+        self.locked.iter().map(|&l| u8::from(l)).collect()
+    }
+
+    /// Takes freshly generated terrain (e.g. the same parameters under a new seed) into the
+    /// unlocked part of the map. Locked cells keep their heights exactly; within `feather`
+    /// cells of a lock the new terrain fades in with a smoothstep, so the join is a slope
+    /// rather than a cliff. An active selection further limits what changes, as for
+    /// `set_heightmap`. Returns false, changing nothing, when every cell is locked.
+    pub fn regenerate(&mut self, flat: &[f32], feather: u32) -> Result<bool, JsValue> {
+        check_grid_len(flat, "flat heightmap")?;
+        if feather as usize > WIDTH / 4 {
+            return Err(JsValue::from_str(
+                "feather must be at most a quarter of the grid width",
+            ));
+        }
+        if self.locked.iter().all(|&l| l) {
+            return Ok(false);
+        }
+        let distance = if self.locked.iter().any(|&l| l) {
+            wrapped_distance_field(&self.locked)
+        } else {
+            vec![f32::INFINITY; CELL_COUNT]
+        };
+        for (idx, &new) in flat.iter().enumerate() {
+            let blend = if feather == 0 {
+                if self.locked[idx] { 0.0 } else { 1.0 }
+            } else {
+                smoothstep(0.0, feather as f32, distance[idx])
+            };
+            let weight = blend * self.selected(idx);
+            self.flat[idx] += (new - self.flat[idx]) * weight;
+        }
+        self.mark_dirty([0, 0, WIDTH as u32, HEIGHT as u32]);
+        Ok(true)
+    }
+
+    /// Rerolls the terrain inside the selection, e.g. to try another shape for one
+    /// peninsula, leaving the rest of the map alone: detail finer than `params.scale` is
+    /// replaced with fresh noise of the same ruggedness, fading in over `params.falloff`
+    /// cells inside the selection's edge. Locked cells keep their heights, and the new
+    /// terrain fades away from them the same way. Needs a selection; returns false,
+    /// changing nothing, when none of it is unlocked.
+    pub fn renoise(&mut self, params: &RenoiseParams) -> Result<bool, JsValue> {
+        params.validate()?;
+        let Some(selection) = &self.selection else {
+            return Err(JsValue::from_str("select the area to re-noise first"));
+        };
+        let weights = selection.weights_slice();
+        let fixed: Vec<bool> = (0..CELL_COUNT)
+            .map(|idx| weights[idx] == 0.0 || self.locked[idx])
+            .collect();
+        if fixed.iter().all(|&f| f) {
+            return Ok(false);
+        }
+        let falloff = params.falloff as f32;
+        let distance = if falloff > 0.0 {
+            wrapped_distance_field(&fixed)
+        } else {
+            Vec::new()
+        };
+        let blend: Vec<f32> = (0..CELL_COUNT)
+            .map(|idx| {
+                if fixed[idx] {
+                    0.0
+                } else if falloff > 0.0 {
+                    weights[idx] * smoothstep(0.0, falloff, distance[idx])
+                } else {
+                    weights[idx]
+                }
+            })
+            .collect();
+        self.flat = renoise(&self.flat, &blend, params);
+        let (mut x0, mut y0, mut x1, mut y1) = (WIDTH, HEIGHT, 0, 0);
+        for idx in (0..CELL_COUNT).filter(|&i| blend[i] > 0.0) {
+            let (x, y) = (idx % WIDTH, idx / WIDTH);
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1));
+        }
+        self.mark_dirty([x0, y0, x1 - x0, y1 - y0].map(|v| v as u32));
+        Ok(true)
+    }
+
+    /// Cuts a river along a hand-drawn stroke of `[x0, y0, x1, y1, ...]` cell coordinates
+    /// (at least two points; they may run up to a grid's width past the east or west edge)
+    /// and joins it to the drainage network. The stroke runs downhill from whichever end is
+    /// higher, stops where it first reaches the sea, a hand-filled lake or an existing
+    /// river, and otherwise carries on down the existing drainage until it does. The
+    /// channel is cut `depth_metres` deep and made to fall all the way to its mouth, with
+    /// banks rising back to the ground over `width` cells. Every layer worked out from the
+    /// heightmap (drainage, rivers, climate moisture, analytics) follows the new river when
+    /// next computed, and the edit is marked dirty and undoable like a brush stroke.
+    pub fn draw_river(
+        &mut self,
+        points: &[f32],
+        params: &RiverParams,
+    ) -> Result<DrawnRiver, JsValue> {
+        params.validate()?;
+        if points.len() < 4 || !points.len().is_multiple_of(2) {
+            return Err(JsValue::from_str(
+                "points must hold at least two x, y pairs",
+            ));
+        }
+        // Bounded like brush centres, so a stray point cannot make the stroke endless.
+        for point in points.chunks_exact(2) {
+            check_centre(point[0], point[1], "river point")?;
+        }
+        let stroke = rasterize_stroke(points);
+        if stroke.iter().all(|&idx| self.flat[idx] < self.sea_level) {
+            return Err(JsValue::from_str("river must be drawn over land"));
+        }
+        let lakes = &self.lakes;
+        let flat = &self.flat;
+        let (course, mouth) = route_river(
+            flat,
+            self.sea_level,
+            |idx| lakes[idx] > flat[idx],
+            stroke,
+            params.join_area_km2,
+        );
+        let depth = params.depth_metres / (RELIEF_METRES / (1.0 - self.sea_level));
+        let levels = channel_levels(&self.flat, &course, depth);
+        let width = params.width as f32;
+        for (&idx, &level) in course.iter().zip(&levels) {
+            for (cell, distance) in bank_cells(idx, params.width) {
+                let bank = level + depth * (distance / width.max(1.0)).powi(2);
+                if bank < self.flat[cell] {
+                    self.flat[cell] += (bank - self.flat[cell]) * self.selected(cell);
+                }
+            }
+        }
+        // Set the bed last, so no bank overlaps it and dips are filled.
+        for (&idx, &level) in course.iter().zip(&levels) {
+            self.flat[idx] += (level - self.flat[idx]) * self.selected(idx);
+            let (x, y) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+            for rect in Footprint::new((x, y), width).rects() {
+                self.mark_dirty(rect);
+            }
+        }
+        Ok(DrawnRiver::new(course, mouth))
+    }
+
+    /// Paints biome id `biome` into the override layer wherever the brush weight (times
+    /// any selection) reaches one half; `apply_biome_overrides` then lays the layer over
+    /// the classifier's map, so "forest here" holds whatever the climate says. Biomes are
+    /// categories, so strength plays no part and falloff only narrows the painted disc.
+    /// Ids follow `biome_legend_json` or a rule table's legend; 255 is reserved for "no
+    /// override". Returns the rectangles touched, for redrawing the overlay. The heights
+    /// are untouched, so painting is neither marked dirty nor undoable.
+    pub fn paint_biome(
+        &mut self,
+        x: f32,
+        y: f32,
+        biome: u8,
+        brush: &BrushParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        if biome == NO_BIOME_OVERRIDE {
+            return Err(JsValue::from_str(
+                "biome 255 is reserved; use erase_biome to remove overrides",
+            ));
+        }
+        self.paint_overrides(x, y, biome, brush)
+    }
+
+    /// Removes overrides under the brush, handing the cells back to the classifier.
+    pub fn erase_biome(
+        &mut self,
+        x: f32,
+        y: f32,
+        brush: &BrushParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        self.paint_overrides(x, y, NO_BIOME_OVERRIDE, brush)
+    }
+
+    /// Hand-painted biome id per cell, 255 where there is none.
+    pub fn biome_overrides(&self) -> Box<[u8]> {
+        self.biome_overrides.clone().into_boxed_slice()
+    }
+
+    pub fn clear_biome_overrides(&mut self) {
+        self.biome_overrides.fill(NO_BIOME_OVERRIDE);
+    }
+}
+
+impl TerrainEditor {
+    pub(crate) fn from_project(project: Project) -> TerrainEditor {
+        let history = History::new(&project.heights).with_params(project.params);
+        TerrainEditor {
+            dirty: None,
+            history,
+            sea_level: project.sea_level,
+            lakes: vec![0.0; CELL_COUNT],
+            selection: None,
+            locked: vec![false; CELL_COUNT],
+            snapshots: project.snapshots,
+            biome_overrides: project.biome_overrides,
+            flat: project.heights,
+        }
+    }
+
+    fn snapshot(&self, name: &str) -> Result<&Snapshot, JsValue> {
+        self.snapshots
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| JsValue::from_str(&format!("no snapshot named {name}")))
+    }
+
+    /// Selection weight of `idx`, 1 with no selection.
+    fn selected(&self, idx: usize) -> f32 {
+        self.selection
+            .as_ref()
+            .map_or(1.0, |s| s.weights_slice()[idx])
+    }
+
+    /// Records an edit over `rect` for both the next upload and the next checkpoint.
+    pub(crate) fn mark_dirty(&mut self, rect: [u32; 4]) {
+        union_rect(&mut self.dirty, rect);
+        self.history.touch(rect);
+    }
+
+    fn paint_overrides(
+        &mut self,
+        x: f32,
+        y: f32,
+        value: u8,
+        brush: &BrushParams,
+    ) -> Result<Box<[u32]>, JsValue> {
+        brush.validate()?;
+        check_centre(x, y, "brush centre")?;
+        let footprint = Footprint::new((x, y), brush.radius);
+        for (idx, distance) in footprint.cells() {
+            if brush.weight(distance) * self.selected(idx) >= 0.5 {
+                self.biome_overrides[idx] = value;
+            }
+        }
+        Ok(footprint.rects().into_iter().flatten().collect())
+    }
+
+    /// Marks bounds restored by undo or redo for upload.
+    fn mark_restored(&mut self, bounds: Option<[usize; 4]>) {
+        if let Some([x0, y0, x1, y1]) = bounds {
+            let rect = [x0, y0, x1 - x0, y1 - y0].map(|v| v as u32);
+            union_rect(&mut self.dirty, rect);
+        }
+    }
+
+    fn apply(
+        &mut self,
+        x: f32,
+        y: f32,
+        brush: &BrushParams,
+        stroke: Stroke,
+    ) -> Result<Box<[u32]>, JsValue> {
+        brush.validate()?;
+        check_centre(x, y, "brush centre")?;
+        let footprint = Footprint::new((x, y), brush.radius);
+        let means = match stroke {
+            Stroke::Smooth => {
+                self.local_means(&footprint, (brush.radius / 4.0).round().max(1.0) as usize)
+            }
+            _ => Vec::new(),
+        };
+        let period = (WIDTH as f32 / brush.noise_scale).round().max(1.0);
+        for (i, (idx, distance)) in footprint.cells().enumerate() {
+            let weight = brush.weight(distance) * brush.strength * self.selected(idx);
+            if weight == 0.0 {
+                continue;
+            }
+            let h = self.flat[idx];
+            let next = match stroke {
+                Stroke::Raise => h + weight * MAX_DAB_STEP,
+                Stroke::Lower => h - weight * MAX_DAB_STEP,
+                Stroke::Smooth => h + (means[i] - h) * weight,
+                Stroke::Flatten(target) => h + (target - h) * weight,
+                Stroke::Site(_) if h < self.sea_level => continue,
+                Stroke::Site(target) => h + (target - h) * weight,
+                Stroke::Noise => {
+                    let (cx, cy) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+                    let scale = period / WIDTH as f32;
+                    let n = perlin(cx * scale, cy * scale, brush.seed, period as i32);
+                    h + n * 2.0 * weight * MAX_DAB_STEP
+                }
+            };
+            self.flat[idx] = next.clamp(0.0, 1.0);
+        }
+        let rects = footprint.rects();
+        for &rect in &rects {
+            self.mark_dirty(rect);
+        }
+        Ok(rects.into_iter().flatten().collect())
+    }
+
+    /// Mean height over a `(2 * kernel + 1)²` box around each footprint cell, in
+    /// `Footprint::cells` order, taken before any of them change.
+    fn local_means(&self, footprint: &Footprint, kernel: usize) -> Vec<f32> {
+        let span = 2 * kernel + 1;
+        let width = footprint.width();
+        let window_width = width + 2 * kernel;
+        let reach = kernel as i64;
+        let window: Vec<f32> = (footprint.y0 as i64 - reach..footprint.y1 as i64 + reach)
+            .flat_map(|y| {
+                (footprint.x0 - reach..footprint.x1 + reach)
+                    .map(move |x| sample_wrapped(&self.flat, x, y))
+            })
+            .collect();
+        let box_sums = |line: &[f32]| -> Vec<f32> {
+            let mut sum: f32 = line[..span].iter().sum();
+            let mut out = Vec::with_capacity(line.len() + 1 - span);
+            out.push(sum);
+            for i in span..line.len() {
+                sum += line[i] - line[i - span];
+                out.push(sum);
+            }
+            out
+        };
+        let rows: Vec<f32> = window
+            .chunks_exact(window_width)
+            .flat_map(box_sums)
+            .collect();
+        let height = footprint.y1 - footprint.y0;
+        let mut means = vec![0.0; height * width];
+        let area = (span * span) as f32;
+        for x in 0..width {
+            let column: Vec<f32> = (0..height + 2 * kernel)
+                .map(|y| rows[y * width + x])
+                .collect();
+            for (y, sum) in box_sums(&column).into_iter().enumerate() {
+                means[y * width + x] = sum / area;
+            }
+        }
+        means
+    }
+}
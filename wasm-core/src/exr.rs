@@ -0,0 +1,138 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::deflate::zlib_compress;
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+/// Single-part scanline file, no long names.
+const VERSION: [u8; 4] = [2, 0, 0, 0];
+const PIXEL_FLOAT: i32 = 2;
+const ZIP_COMPRESSION: u8 = 3;
+/// Scanlines per chunk under ZIP compression.
+const ZIP_LINES: usize = 16;
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend(name.as_bytes());
+    out.push(0);
+    out.extend(kind.as_bytes());
+    out.push(0);
+    out.extend((value.len() as i32).to_le_bytes());
+    out.extend(value);
+}
+
+/// ZIP chunk preprocessing: bytes split into even and odd halves, then delta-coded, which
+/// groups the similar high bytes of neighbouring floats for deflate.
+fn zip_predict(raw: &[u8]) -> Vec<u8> {
+    let half = raw.len().div_ceil(2);
+    let mut split = vec![0_u8; raw.len()];
+    for (i, &b) in raw.iter().enumerate() {
+        split[if i % 2 == 0 { i / 2 } else { half + i / 2 }] = b;
+    }
+    let mut previous = split.first().copied().unwrap_or(0);
+    for b in split.iter_mut().skip(1) {
+        let current = *b;
+        *b = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+    split
+}
+
+/// Scanline OpenEXR file of 32-bit float `channels` (name, row-major values), ZIP
+/// compressed in 16-line chunks. Channels are stored in name order, as the format requires.
+pub(crate) fn encode_exr(width: usize, height: usize, channels: &[(&str, &[f32])]) -> Vec<u8> {
+    let mut channels = channels.to_vec();
+    channels.sort_by_key(|&(name, _)| name.as_bytes());
+
+    let mut chlist = Vec::new();
+    for &(name, _) in &channels {
+        chlist.extend(name.as_bytes());
+        chlist.push(0);
+        chlist.extend(PIXEL_FLOAT.to_le_bytes());
+        // pLinear and reserved bytes, then x and y sampling.
+        chlist.extend([0, 0, 0, 0]);
+        chlist.extend(1_i32.to_le_bytes());
+        chlist.extend(1_i32.to_le_bytes());
+    }
+    chlist.push(0);
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+
+    let mut out = MAGIC.to_vec();
+    out.extend(VERSION);
+    attribute(&mut out, "channels", "chlist", &chlist);
+    attribute(&mut out, "compression", "compression", &[ZIP_COMPRESSION]);
+    attribute(&mut out, "dataWindow", "box2i", &window);
+    attribute(&mut out, "displayWindow", "box2i", &window);
+    attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut out,
+        "pixelAspectRatio",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    attribute(&mut out, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut out,
+        "screenWindowWidth",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    out.push(0);
+
+    let chunk_count = height.div_ceil(ZIP_LINES);
+    let table_start = out.len();
+    out.resize(table_start + 8 * chunk_count, 0);
+    for chunk in 0..chunk_count {
+        let offset = out.len() as u64;
+        out[table_start + 8 * chunk..table_start + 8 * (chunk + 1)]
+            .copy_from_slice(&offset.to_le_bytes());
+        let y0 = chunk * ZIP_LINES;
+        let mut raw = Vec::new();
+        for y in y0..(y0 + ZIP_LINES).min(height) {
+            for &(_, values) in &channels {
+                raw.extend(
+                    values[y * width..(y + 1) * width]
+                        .iter()
+                        .flat_map(|v| v.to_le_bytes()),
+                );
+            }
+        }
+        // Chunks that would not shrink are stored as they are.
+        let packed = zlib_compress(&zip_predict(&raw));
+        let data = if packed.len() < raw.len() {
+            packed
+        } else {
+            raw
+        };
+        out.extend((y0 as i32).to_le_bytes());
+        out.extend((data.len() as i32).to_le_bytes());
+        out.extend(data);
+    }
+    out
+}
+
+/// Lossless 32-bit float OpenEXR of the heightmap for Blender and VFX tools, in a single
+/// `Y` channel so viewers show it as grayscale. Values are the raw heightmap units.
+#[wasm_bindgen]
+pub fn export_heightmap_exr(flat: &[f32]) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    Ok(encode_exr(WIDTH, HEIGHT, &[("Y", flat)]).into_boxed_slice())
+}
+
+/// `export_heightmap_exr` with the climate layers alongside the height in the same file:
+/// `moisture`, `precipitation` (mm/year), and `temperature` (°C) channels, as `Climate`
+/// reports them.
+#[wasm_bindgen]
+pub fn export_climate_exr(flat: &[f32], climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let channels = [
+        ("Y", flat),
+        ("moisture", &climate.moisture[..]),
+        ("precipitation", &climate.precipitation[..]),
+        ("temperature", &climate.temperature[..]),
+    ];
+    Ok(encode_exr(WIDTH, HEIGHT, &channels).into_boxed_slice())
+}
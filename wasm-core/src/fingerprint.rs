@@ -0,0 +1,44 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |h, &b| (h ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// `h` clamped to [0, 1] and rounded to the nearest of `2^bits` evenly spaced levels.
+/// NaN quantizes to 0.
+pub(crate) fn quantize(h: f32, bits: u32) -> u16 {
+    let levels = ((1_u32 << bits) - 1) as f32;
+    let q = (h.clamp(0.0, 1.0) * levels).round();
+    if q.is_nan() { 0 } else { q as u16 }
+}
+
+/// 64-bit FNV-1a over the grid size, `bits`, and every cell quantized with `quantize`
+/// (little-endian u16, row-major).
+pub(crate) fn fingerprint(flat: &[f32], bits: u32) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for header in [WIDTH as u32, HEIGHT as u32, bits] {
+        hash = fnv1a(hash, &header.to_le_bytes());
+    }
+    flat.iter()
+        .fold(hash, |h, &v| fnv1a(h, &quantize(v, bits).to_le_bytes()))
+}
+
+/// Stable content hash of a heightmap as 16 lowercase hex digits. Each cell is clamped to
+/// [0, 1] and rounded to `bits` bits (1–16; 16 keeps steps of 1/65535), so results agree
+/// across browsers and GPUs whenever their outputs differ by less than half a step. Lower
+/// `bits` tolerates more noise; a value landing near a rounding boundary can still flip.
+#[wasm_bindgen]
+pub fn heightmap_fingerprint(flat: &[f32], bits: u32) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(1..=16).contains(&bits) {
+        return Err(JsValue::from_str("bits must be within [1, 16]"));
+    }
+    Ok(format!("{:016x}", fingerprint(flat, bits)))
+}
@@ -0,0 +1,163 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH};
+
+/// The 8 neighbours of `idx` clockwise from north, wrapping east–west; `None` beyond a pole.
+pub(crate) fn ring(idx: usize) -> [Option<usize>; 8] {
+    let (x, y) = (idx % WIDTH, idx / WIDTH);
+    let (west, east) = ((x + WIDTH - 1) % WIDTH, (x + 1) % WIDTH);
+    let row = |y: Option<usize>, x: usize| y.map(|y| y * WIDTH + x);
+    let north = y.checked_sub(1);
+    let south = (y + 1 < HEIGHT).then_some(y + 1);
+    [
+        row(north, x),
+        row(north, east),
+        row(Some(y), east),
+        row(south, east),
+        row(south, x),
+        row(south, west),
+        row(Some(y), west),
+        row(north, west),
+    ]
+}
+
+/// Strict total order on cells: by value, then by index, so flat areas still have a
+/// deterministic downhill direction.
+pub(crate) fn cell_order(field: &[f32], a: usize, b: usize) -> Ordering {
+    field[a].total_cmp(&field[b]).then(a.cmp(&b))
+}
+
+pub(crate) fn is_below(field: &[f32], a: usize, b: usize) -> bool {
+    cell_order(field, a, b).is_lt()
+}
+
+/// Steepest-descent (D8) receiver of every cell under `is_below`, with diagonal steps
+/// √2 cells long. A cell with no lower neighbour (a pit) receives itself.
+pub(crate) fn d8_receivers(field: &[f32]) -> Vec<u32> {
+    (0..CELL_COUNT)
+        .map(|idx| {
+            let mut best = (f32::NEG_INFINITY, idx);
+            for (i, n) in ring(idx).into_iter().enumerate() {
+                let Some(n) = n.filter(|&n| is_below(field, n, idx)) else {
+                    continue;
+                };
+                let distance = if i % 2 == 0 {
+                    1.0
+                } else {
+                    std::f32::consts::SQRT_2
+                };
+                let drop = (field[idx] - field[n]) / distance;
+                if drop > best.0 || (drop == best.0 && is_below(field, n, best.1)) {
+                    best = (drop, n);
+                }
+            }
+            best.1 as u32
+        })
+        .collect()
+}
+
+/// Cell in the priority-flood queue, ordered like `cell_order`.
+#[derive(PartialEq)]
+pub(crate) struct Flooded(pub(crate) f32, pub(crate) usize);
+
+impl Eq for Flooded {}
+
+impl PartialOrd for Flooded {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Flooded {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// Priority-flood depression filling: raises every closed basin to its spill point, plus
+/// the smallest step above the cell it drains to, so each cell not in `outlet` has a
+/// strictly lower neighbour and D8 flow reaches an outlet. The polar rows drain off the
+/// map when no outlet is given.
+pub(crate) fn fill_depressions(field: &[f32], outlet: impl Fn(usize) -> bool) -> Vec<f32> {
+    let mut filled = field.to_vec();
+    let mut done = vec![false; CELL_COUNT];
+    let mut queue = BinaryHeap::new();
+    let mut seeds: Vec<usize> = (0..CELL_COUNT).filter(|&i| outlet(i)).collect();
+    if seeds.is_empty() {
+        seeds.extend((0..WIDTH).chain(CELL_COUNT - WIDTH..CELL_COUNT));
+    }
+    for idx in seeds {
+        done[idx] = true;
+        queue.push(Reverse(Flooded(filled[idx], idx)));
+    }
+    while let Some(Reverse(Flooded(level, idx))) = queue.pop() {
+        for n in ring(idx).into_iter().flatten() {
+            if done[n] {
+                continue;
+            }
+            done[n] = true;
+            filled[n] = filled[n].max(level.next_up());
+            queue.push(Reverse(Flooded(filled[n], n)));
+        }
+    }
+    filled
+}
+
+/// Total `weight` of every cell draining through each cell, itself included.
+pub(crate) fn flow_accumulation(receivers: &[u32], weight: impl Fn(usize) -> f32) -> Vec<f32> {
+    let mut accumulation: Vec<f32> = (0..CELL_COUNT).map(weight).collect();
+    let mut donors = vec![0_u8; CELL_COUNT];
+    for (idx, &r) in receivers.iter().enumerate() {
+        if r as usize != idx {
+            donors[r as usize] += 1;
+        }
+    }
+    // Pass each cell on once all its donors have reported (Kahn's algorithm).
+    let mut ready: Vec<usize> = (0..CELL_COUNT).filter(|&i| donors[i] == 0).collect();
+    while let Some(idx) = ready.pop() {
+        let r = receivers[idx] as usize;
+        if r == idx {
+            continue;
+        }
+        accumulation[r] += accumulation[idx];
+        donors[r] -= 1;
+        if donors[r] == 0 {
+            ready.push(r);
+        }
+    }
+    accumulation
+}
+
+/// Splits the drainage network of cells where `member` is set into chains of cell indices
+/// running downstream: each starts at a head or a confluence and ends at the next
+/// confluence, or at the network's outlet.
+pub(crate) fn network_segments(receivers: &[u32], member: &[bool]) -> Vec<Vec<usize>> {
+    let mut donors = vec![0_u8; CELL_COUNT];
+    for idx in (0..CELL_COUNT).filter(|&i| member[i]) {
+        let r = receivers[idx] as usize;
+        if r != idx && member[r] {
+            donors[r] += 1;
+        }
+    }
+    let mut segments = Vec::new();
+    for start in (0..CELL_COUNT).filter(|&i| member[i] && donors[i] != 1) {
+        let mut segment = vec![start];
+        let mut current = start;
+        loop {
+            let next = receivers[current] as usize;
+            if next == current || !member[next] {
+                break;
+            }
+            segment.push(next);
+            if donors[next] != 1 {
+                break;
+            }
+            current = next;
+        }
+        if segment.len() > 1 {
+            segments.push(segment);
+        }
+    }
+    segments
+}
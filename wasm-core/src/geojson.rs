@@ -0,0 +1,210 @@
+use wasm_bindgen::prelude::*;
+
+use crate::contours::simplified_isolines;
+use crate::grid::{SEA_LEVEL, WIDTH, check_grid_len};
+use crate::hydrology::Hydrology;
+use crate::vector::{
+    cell_area_km2, cell_path_polylines, exterior_and_holes, label_regions, line_to_geojson,
+    polygon_geometry_json, simplify_ring, trace_region_rings,
+};
+use crate::zones::climate_zone_polygons_json;
+
+/// Extraction settings for `export_vector_features`; the drainage settings match
+/// `SvgParams`, so both exports agree.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct GeoJsonParams {
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km².
+    pub river_min_area_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes, as do
+    /// inland seas below sea level.
+    pub lake_min_depth_metres: f32,
+    pub lake_min_cells: u32,
+    /// Biome regions smaller than this many cells are dropped.
+    pub biome_min_cells: u32,
+    /// Douglas–Peucker tolerance, cells.
+    pub tolerance: f32,
+}
+
+impl Default for GeoJsonParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 50_000.0,
+            lake_min_depth_metres: 20.0,
+            lake_min_cells: 4,
+            biome_min_cells: 16,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GeoJsonParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GeoJsonParams {
+        Self::default()
+    }
+}
+
+impl GeoJsonParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        let non_negative = [
+            self.river_min_area_km2,
+            self.lake_min_depth_metres,
+            self.tolerance,
+        ];
+        if non_negative.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "river_min_area_km2, lake_min_depth_metres and tolerance must be >= 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// GeoJSON FeatureCollections from `export_vector_features`, in lon/lat degrees.
+#[wasm_bindgen]
+pub struct VectorFeatures {
+    coastlines: String,
+    rivers: String,
+    lakes: String,
+    biomes: String,
+}
+
+#[wasm_bindgen]
+impl VectorFeatures {
+    /// LineStrings along the sea's edge, with `closed` set on whole islands and shores;
+    /// open lines end at the map edges and the east–west seam.
+    #[wasm_bindgen(getter)]
+    pub fn coastlines(&self) -> String {
+        self.coastlines.clone()
+    }
+
+    /// LineStrings between confluences, running downstream into the sea or a lake, with
+    /// `strahler` stream order and the `area_km2` drained at their lower end.
+    #[wasm_bindgen(getter)]
+    pub fn rivers(&self) -> String {
+        self.rivers.clone()
+    }
+
+    /// Polygons with holes for islands, with `cells`, `area_km2` and `max_depth_m`.
+    #[wasm_bindgen(getter)]
+    pub fn lakes(&self) -> String {
+        self.lakes.clone()
+    }
+
+    /// Biome regions as in `climate_zone_polygons_json`, `class` holding the biome id;
+    /// empty without a biome map.
+    #[wasm_bindgen(getter)]
+    pub fn biomes(&self) -> String {
+        self.biomes.clone()
+    }
+}
+
+pub(crate) fn feature_collection(features: &[String]) -> String {
+    format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}",
+        features.join(",")
+    )
+}
+
+pub(crate) fn line_feature(properties: &str, points: &[(f32, f32)]) -> String {
+    format!(
+        "{{\"type\":\"Feature\",\"properties\":{properties},\"geometry\":{{\"type\":\"LineString\",\"coordinates\":{}}}}}",
+        line_to_geojson(points)
+    )
+}
+
+/// Coastlines, rivers, lakes and biome regions as GeoJSON layers for web maps such as
+/// MapLibre or Leaflet. Rivers and lakes come from depression-filled D8 drainage, as in
+/// `export_map_svg`. Pass an empty `biome_map` to skip the biome layer. Political borders
+/// come from `Nations::borders_geojson`.
+#[wasm_bindgen]
+pub fn export_vector_features(
+    flat: &[f32],
+    biome_map: &[u8],
+    params: &GeoJsonParams,
+) -> Result<VectorFeatures, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let biomes = if biome_map.is_empty() {
+        feature_collection(&[])
+    } else {
+        check_grid_len(biome_map, "biome map")?;
+        climate_zone_polygons_json(biome_map, params.biome_min_cells, params.tolerance)?
+    };
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+
+    let coastlines: Vec<String> = simplified_isolines(
+        &hydrology.coastline_field(flat),
+        params.sea_level,
+        params.tolerance,
+    )
+    .into_iter()
+    .map(|(closed, mut points)| {
+        if closed {
+            points.push(points[0]);
+        }
+        line_feature(&format!("{{\"closed\":{closed}}}"), &points)
+    })
+    .collect();
+
+    let rivers: Vec<String> = hydrology
+        .rivers(params.river_min_area_km2)
+        .iter()
+        .flat_map(|river| {
+            let properties = format!(
+                "{{\"strahler\":{},\"area_km2\":{:.0}}}",
+                river.order, river.area_km2
+            );
+            cell_path_polylines(&river.cells, params.tolerance)
+                .into_iter()
+                .map(move |line| line_feature(&properties, &line))
+        })
+        .collect();
+
+    let (labels, count) = label_regions(&hydrology.lakes, |l| l);
+    let mut cells = vec![0_u32; count as usize];
+    let mut area = vec![0.0_f64; count as usize];
+    let mut depth = vec![0.0_f32; count as usize];
+    for (idx, &label) in labels.iter().enumerate() {
+        if label != 0 {
+            let r = label as usize - 1;
+            cells[r] += 1;
+            area[r] += cell_area_km2(idx / WIDTH);
+            depth[r] = depth[r].max(hydrology.lake_depth_metres[idx]);
+        }
+    }
+    let mut lakes = Vec::new();
+    for (r, rings) in trace_region_rings(&labels, count).into_iter().enumerate() {
+        if cells[r] < params.lake_min_cells.max(1) {
+            continue;
+        }
+        let rings = rings
+            .iter()
+            .map(|ring| simplify_ring(ring, params.tolerance))
+            .collect();
+        let Some((exterior, holes)) = exterior_and_holes(rings) else {
+            continue;
+        };
+        lakes.push(format!(
+            "{{\"type\":\"Feature\",\"properties\":{{\"cells\":{},\"area_km2\":{:.1},\"max_depth_m\":{:.0}}},\"geometry\":{}}}",
+            cells[r],
+            area[r],
+            depth[r],
+            polygon_geometry_json(&exterior, &holes),
+        ));
+    }
+
+    Ok(VectorFeatures {
+        coastlines: feature_collection(&coastlines),
+        rivers: feature_collection(&rivers),
+        lakes: feature_collection(&lakes),
+        biomes,
+    })
+}
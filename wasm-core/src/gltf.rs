@@ -0,0 +1,212 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::check_grid_len;
+use crate::json::{self, ObjectWriter};
+use crate::mesh::{MeshParams, TerrainMesh};
+use crate::render::{parse_palette, surface_color};
+
+const FLOAT: u64 = 5126;
+const UNSIGNED_INT: u64 = 5125;
+const ARRAY_BUFFER: u64 = 34962;
+const ELEMENT_ARRAY_BUFFER: u64 = 34963;
+
+/// sRGB-encoded channel in [0, 255] to the linear value glTF vertex colours use.
+fn srgb_to_linear(c: f32) -> f32 {
+    let c = c / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Binary chunk of a GLB with one buffer view and accessor per attribute.
+struct GlbBuffer {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl GlbBuffer {
+    /// Appends `values` as a tightly packed accessor of `kind` (`"VEC3"`, `"SCALAR"`, ...)
+    /// and returns its index. POSITION needs `bounds`.
+    fn push(
+        &mut self,
+        bytes: Vec<u8>,
+        count: usize,
+        component: u64,
+        kind: &str,
+        target: u64,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let offset = self.bin.len();
+        let length = bytes.len();
+        self.bin.extend(bytes);
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+        self.views.push(
+            ObjectWriter::new()
+                .integer("buffer", 0)
+                .integer("byteOffset", offset as u64)
+                .integer("byteLength", length as u64)
+                .integer("target", target)
+                .finish(),
+        );
+        let mut accessor = ObjectWriter::new();
+        accessor
+            .integer("bufferView", (self.views.len() - 1) as u64)
+            .integer("componentType", component)
+            .integer("count", count as u64)
+            .raw("type", &json::quote(kind));
+        if let Some((min, max)) = bounds {
+            accessor
+                .raw("min", &json::array(min))
+                .raw("max", &json::array(max));
+        }
+        self.accessors.push(accessor.finish());
+        self.accessors.len() - 1
+    }
+}
+
+fn float_bytes<const N: usize>(values: &[[f32; N]]) -> Vec<u8> {
+    values
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+/// Binary glTF 2.0 (`.glb`) of the terrain as one triangle mesh with positions, normals,
+/// and UVs (for draping an exported map image), laid out as described on `MeshParams`.
+/// With a `biome_map`, vertices also get the unlit biome and ocean colours of
+/// `render_biome_rgba`, using `palette` (legend JSON, or empty for the built-in colours);
+/// pass an empty `biome_map` to leave colours out.
+#[wasm_bindgen]
+pub fn export_terrain_glb(
+    flat: &[f32],
+    params: &MeshParams,
+    biome_map: &[u8],
+    palette: &str,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let colors = if biome_map.is_empty() {
+        None
+    } else {
+        check_grid_len(biome_map, "biome map")?;
+        Some(parse_palette(palette).map_err(|e| JsValue::from_str(&e))?)
+    };
+
+    let mesh = TerrainMesh::build(flat, params);
+    let count = mesh.positions.len();
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for p in &mesh.positions {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+
+    let mut buffer = GlbBuffer {
+        bin: Vec::new(),
+        views: Vec::new(),
+        accessors: Vec::new(),
+    };
+    let mut attributes = ObjectWriter::new();
+    let position = buffer.push(
+        float_bytes(&mesh.positions),
+        count,
+        FLOAT,
+        "VEC3",
+        ARRAY_BUFFER,
+        Some((min, max)),
+    );
+    attributes.integer("POSITION", position as u64);
+    let normal = buffer.push(
+        float_bytes(&mesh.normals),
+        count,
+        FLOAT,
+        "VEC3",
+        ARRAY_BUFFER,
+        None,
+    );
+    attributes.integer("NORMAL", normal as u64);
+    let uv = buffer.push(
+        float_bytes(&mesh.uvs),
+        count,
+        FLOAT,
+        "VEC2",
+        ARRAY_BUFFER,
+        None,
+    );
+    attributes.integer("TEXCOORD_0", uv as u64);
+    if let Some(colors) = colors {
+        let vertex_colors: Vec<[f32; 3]> = mesh
+            .cells
+            .iter()
+            .map(|&idx| surface_color(flat[idx], biome_map[idx], &colors).map(srgb_to_linear))
+            .collect();
+        let color = buffer.push(
+            float_bytes(&vertex_colors),
+            count,
+            FLOAT,
+            "VEC3",
+            ARRAY_BUFFER,
+            None,
+        );
+        attributes.integer("COLOR_0", color as u64);
+    }
+    let triangles = &mesh.triangles;
+    let indices = buffer.push(
+        triangles
+            .iter()
+            .flatten()
+            .flat_map(|i| i.to_le_bytes())
+            .collect(),
+        triangles.len() * 3,
+        UNSIGNED_INT,
+        "SCALAR",
+        ELEMENT_ARRAY_BUFFER,
+        None,
+    );
+
+    let primitive = ObjectWriter::new()
+        .raw("attributes", &attributes.finish())
+        .integer("indices", indices as u64)
+        .integer("mode", 4)
+        .finish();
+    let document = ObjectWriter::new()
+        .raw(
+            "asset",
+            r#"{"version":"2.0","generator":"Continent-Generator"}"#,
+        )
+        .integer("scene", 0)
+        .raw("scenes", r#"[{"nodes":[0]}]"#)
+        .raw("nodes", r#"[{"name":"Terrain","mesh":0}]"#)
+        .raw(
+            "meshes",
+            &format!(r#"[{{"name":"Terrain","primitives":[{primitive}]}}]"#),
+        )
+        .raw(
+            "buffers",
+            &format!(r#"[{{"byteLength":{}}}]"#, buffer.bin.len()),
+        )
+        .raw("bufferViews", &json::array(&buffer.views))
+        .raw("accessors", &json::array(&buffer.accessors))
+        .finish();
+
+    let mut json_chunk = document.into_bytes();
+    json_chunk.resize(json_chunk.len().next_multiple_of(4), b' ');
+    let total = 12 + 8 + json_chunk.len() + 8 + buffer.bin.len();
+    let mut out = Vec::with_capacity(total);
+    out.extend(b"glTF");
+    out.extend(2_u32.to_le_bytes());
+    out.extend((total as u32).to_le_bytes());
+    out.extend((json_chunk.len() as u32).to_le_bytes());
+    out.extend(b"JSON");
+    out.extend(json_chunk);
+    out.extend((buffer.bin.len() as u32).to_le_bytes());
+    out.extend(b"BIN\0");
+    out.extend(buffer.bin);
+    Ok(out.into_boxed_slice())
+}
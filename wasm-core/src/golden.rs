@@ -0,0 +1,198 @@
+use wasm_bindgen::prelude::*;
+
+use crate::analytics::{ANALYTICS_SCHEMA_VERSION, Analytics, source_of_truth};
+use crate::fingerprint::fingerprint;
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+use crate::json::{self, Json, ObjectWriter};
+
+/// Bumped when the baseline layout changes.
+const GOLDEN_VERSION: u32 = 1;
+
+/// Coarse enough that last-bit GPU differences rarely change the hash.
+const GOLDEN_FINGERPRINT_BITS: u32 = 12;
+
+type Metric = fn(&Analytics) -> f64;
+
+/// Analytics compared by the golden check, with the default absolute tolerance recorded
+/// into new baselines.
+const GOLDEN_METRICS: [(&str, Metric, f64); 7] = [
+    ("sinuosity_index", |a| a.sinuosity_index(), 0.0005),
+    ("hydro_drainage_pct", |a| a.hydro_drainage_pct(), 0.5),
+    ("hypsometric_integral", |a| a.hypsometric_integral(), 0.005),
+    ("tri_land_mean", |a| a.tri_land_mean(), 0.0001),
+    ("landmass_count", |a| a.landmass_count() as f64, 2.0),
+    ("largest_landmass_pct", |a| a.largest_landmass_pct(), 0.5),
+    (
+        "coastline_fractal_dimension",
+        |a| a.coastline_fractal_dimension(),
+        0.01,
+    ),
+];
+
+/// Records a golden baseline for the map generated from `seed`:
+/// `{"golden_version","seed","width","height","analytics_schema_version","fingerprint_bits",
+/// "fingerprint","metrics":[{"name","value","tolerance"},...]}`. Tolerances are absolute and
+/// may be edited by hand; set `"fingerprint"` to `null` to skip the exact-content check.
+#[wasm_bindgen]
+pub fn record_golden_baseline(seed: u32, flat: &[f32]) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let analytics = source_of_truth(flat, 0.0)?;
+    let metrics = json::array(GOLDEN_METRICS.iter().map(|(name, metric, tolerance)| {
+        ObjectWriter::new()
+            .raw("name", &json::quote(name))
+            .number("value", metric(&analytics), 6)
+            .number("tolerance", *tolerance, 6)
+            .finish()
+    }));
+    Ok(ObjectWriter::new()
+        .integer("golden_version", GOLDEN_VERSION as u64)
+        .integer("seed", seed as u64)
+        .integer("width", WIDTH as u64)
+        .integer("height", HEIGHT as u64)
+        .integer("analytics_schema_version", ANALYTICS_SCHEMA_VERSION as u64)
+        .integer("fingerprint_bits", GOLDEN_FINGERPRINT_BITS as u64)
+        .raw(
+            "fingerprint",
+            &json::quote(&format!(
+                "{:016x}",
+                fingerprint(flat, GOLDEN_FINGERPRINT_BITS)
+            )),
+        )
+        .raw("metrics", &metrics)
+        .finish())
+}
+
+/// One line of the verification report.
+struct Check {
+    name: String,
+    expected: String,
+    actual: String,
+    tolerance: Option<f64>,
+    passed: bool,
+}
+
+impl Check {
+    fn exact(name: &str, expected: String, actual: String) -> Self {
+        let passed = expected == actual;
+        Self {
+            name: name.to_owned(),
+            expected,
+            actual,
+            tolerance: None,
+            passed,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let mut out = ObjectWriter::new();
+        out.raw("name", &json::quote(&self.name))
+            .raw("expected", &self.expected)
+            .raw("actual", &self.actual);
+        if let Some(tolerance) = self.tolerance {
+            out.number("tolerance", tolerance, 6);
+        }
+        out.raw("passed", if self.passed { "true" } else { "false" })
+            .finish()
+    }
+}
+
+fn baseline_integer(baseline: &Json, key: &str) -> Result<u64, String> {
+    baseline
+        .get(key)
+        .and_then(Json::as_f64)
+        .filter(|v| v.fract() == 0.0 && *v >= 0.0)
+        .map(|v| v as u64)
+        .ok_or_else(|| format!("baseline needs an integer {key}"))
+}
+
+fn verify(baseline_json: &str, flat: &[f32]) -> Result<String, String> {
+    let baseline = json::parse(baseline_json)?;
+    let seed = baseline_integer(&baseline, "seed")?;
+    let version = baseline_integer(&baseline, "golden_version")?;
+    if version != GOLDEN_VERSION as u64 {
+        return Err(format!("unsupported golden_version {version}"));
+    }
+
+    let mut checks = vec![
+        Check::exact(
+            "grid",
+            format!(
+                "[{},{}]",
+                baseline_integer(&baseline, "width")?,
+                baseline_integer(&baseline, "height")?
+            ),
+            format!("[{WIDTH},{HEIGHT}]"),
+        ),
+        Check::exact(
+            "analytics_schema_version",
+            baseline_integer(&baseline, "analytics_schema_version")?.to_string(),
+            ANALYTICS_SCHEMA_VERSION.to_string(),
+        ),
+    ];
+    match baseline.get("fingerprint") {
+        Some(Json::Null) => {}
+        Some(Json::String(expected)) => {
+            let bits = baseline_integer(&baseline, "fingerprint_bits")?;
+            if !(1..=16).contains(&bits) {
+                return Err("fingerprint_bits must be within [1, 16]".to_owned());
+            }
+            let actual = format!("{:016x}", fingerprint(flat, bits as u32));
+            checks.push(Check::exact(
+                "fingerprint",
+                json::quote(expected),
+                json::quote(&actual),
+            ));
+        }
+        _ => return Err("baseline needs a string or null fingerprint".to_owned()),
+    }
+
+    let analytics = source_of_truth(flat, 0.0).map_err(|_| "analytics failed".to_owned())?;
+    let metrics = baseline
+        .get("metrics")
+        .and_then(Json::as_array)
+        .ok_or("baseline needs a metrics array")?;
+    for entry in metrics {
+        let name = entry
+            .get("name")
+            .and_then(Json::as_str)
+            .ok_or("each metric needs a string name")?;
+        let (_, metric, _) = GOLDEN_METRICS
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .ok_or_else(|| format!("unknown metric {name}"))?;
+        let number = |key: &str| {
+            entry
+                .get(key)
+                .and_then(Json::as_f64)
+                .ok_or_else(|| format!("metric {name} needs a numeric {key}"))
+        };
+        let (expected, tolerance) = (number("value")?, number("tolerance")?);
+        let actual = metric(&analytics);
+        checks.push(Check {
+            name: name.to_owned(),
+            expected: format!("{expected:.6}"),
+            actual: format!("{actual:.6}"),
+            tolerance: Some(tolerance),
+            passed: (actual - expected).abs() <= tolerance,
+        });
+    }
+
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    Ok(ObjectWriter::new()
+        .integer("seed", seed)
+        .raw("passed", if failed == 0 { "true" } else { "false" })
+        .integer("failed", failed as u64)
+        .raw("checks", &json::array(checks.iter().map(Check::to_json)))
+        .finish())
+}
+
+/// Checks a heightmap regenerated from the baseline's seed against a baseline from
+/// `record_golden_baseline`: `{"seed","passed","failed","checks":[{"name","expected",
+/// "actual","tolerance"?,"passed"},...]}`. Grid size, analytics schema and fingerprint must
+/// match exactly; metrics must fall within their tolerances. Malformed baselines are
+/// errors rather than failed checks.
+#[wasm_bindgen]
+pub fn verify_golden_baseline(baseline_json: &str, flat: &[f32]) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    verify(baseline_json, flat).map_err(|e| JsValue::from_str(&e))
+}
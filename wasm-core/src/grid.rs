@@ -0,0 +1,152 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{GRID_CELL_COUNT, GRID_HEIGHT, GRID_WIDTH};
+
+pub(crate) const WIDTH: usize = GRID_WIDTH as usize;
+pub(crate) const HEIGHT: usize = GRID_HEIGHT as usize;
+pub(crate) const CELL_COUNT: usize = GRID_CELL_COUNT as usize;
+
+/// Matches `ocean_threshold` in pass7: elevation below this renders as water.
+pub(crate) const SEA_LEVEL: f32 = 0.15;
+
+pub(crate) fn check_grid_len<T>(flat: &[T], name: &str) -> Result<(), JsValue> {
+    if flat.len() != CELL_COUNT {
+        return Err(JsValue::from_str(&format!("{name} length mismatch")));
+    }
+    Ok(())
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Latitude in degrees at the centre of row `y` (+90 at the top row, −90 at the bottom).
+pub(crate) fn latitude_deg(y: usize) -> f32 {
+    90.0 - (y as f32 + 0.5) * (180.0 / HEIGHT as f32)
+}
+
+/// Columns wrap east–west; rows clamp at the poles.
+pub(crate) fn wrap_x(x: i64) -> usize {
+    x.rem_euclid(WIDTH as i64) as usize
+}
+
+pub(crate) fn clamp_y(y: i64) -> usize {
+    y.clamp(0, HEIGHT as i64 - 1) as usize
+}
+
+pub(crate) fn sample_wrapped(field: &[f32], x: i64, y: i64) -> f32 {
+    field[clamp_y(y) * WIDTH + wrap_x(x)]
+}
+
+/// Bilinear sample at fractional cell coordinates (wrapping in x, clamping in y).
+pub(crate) fn sample_bilinear(field: &[f32], fx: f32, fy: f32) -> f32 {
+    let x0 = fx.floor();
+    let y0 = fy.floor();
+    let tx = fx - x0;
+    let ty = fy - y0;
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let a = sample_wrapped(field, x0, y0);
+    let b = sample_wrapped(field, x0 + 1, y0);
+    let c = sample_wrapped(field, x0, y0 + 1);
+    let d = sample_wrapped(field, x0 + 1, y0 + 1);
+    let top = a + (b - a) * tx;
+    let bottom = c + (d - c) * tx;
+    top + (bottom - top) * ty
+}
+
+/// Approximate Euclidean distance (cells) to the nearest `true` cell, using a two-pass
+/// chamfer sweep. Cells with no source anywhere on the grid report `f32::INFINITY`.
+pub(crate) fn distance_field(sources: &[bool]) -> Vec<f32> {
+    const DIAGONAL: f32 = std::f32::consts::SQRT_2;
+    let mut dist: Vec<f32> = sources
+        .iter()
+        .map(|&s| if s { 0.0 } else { f32::INFINITY })
+        .collect();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let idx = y * WIDTH + x;
+            let mut d = dist[idx];
+            if x > 0 {
+                d = d.min(dist[idx - 1] + 1.0);
+            }
+            if y > 0 {
+                d = d.min(dist[idx - WIDTH] + 1.0);
+                if x > 0 {
+                    d = d.min(dist[idx - WIDTH - 1] + DIAGONAL);
+                }
+                if x + 1 < WIDTH {
+                    d = d.min(dist[idx - WIDTH + 1] + DIAGONAL);
+                }
+            }
+            dist[idx] = d;
+        }
+    }
+    for y in (0..HEIGHT).rev() {
+        for x in (0..WIDTH).rev() {
+            let idx = y * WIDTH + x;
+            let mut d = dist[idx];
+            if x + 1 < WIDTH {
+                d = d.min(dist[idx + 1] + 1.0);
+            }
+            if y + 1 < HEIGHT {
+                d = d.min(dist[idx + WIDTH] + 1.0);
+                if x + 1 < WIDTH {
+                    d = d.min(dist[idx + WIDTH + 1] + DIAGONAL);
+                }
+                if x > 0 {
+                    d = d.min(dist[idx + WIDTH - 1] + DIAGONAL);
+                }
+            }
+            dist[idx] = d;
+        }
+    }
+    dist
+}
+
+/// `distance_field` joined across the east–west seam: the lesser of the plain field and
+/// one taken on the grid rolled half way round, so paths crossing the seam are measured
+/// too. Exact wherever the nearest source lies under half the grid width away.
+pub(crate) fn wrapped_distance_field(sources: &[bool]) -> Vec<f32> {
+    let roll = |idx: usize| idx - idx % WIDTH + (idx % WIDTH + WIDTH / 2) % WIDTH;
+    let mut rolled = vec![false; CELL_COUNT];
+    for (idx, &source) in sources.iter().enumerate() {
+        rolled[roll(idx)] = source;
+    }
+    let across = distance_field(&rolled);
+    let mut dist = distance_field(sources);
+    for (idx, d) in dist.iter_mut().enumerate() {
+        *d = d.min(across[roll(idx)]);
+    }
+    dist
+}
+
+/// Separable box blur of `radius` cells (wrapping in x, clamping in y).
+pub(crate) fn box_blur(field: &[f32], radius: usize) -> Vec<f32> {
+    let span = (2 * radius + 1) as f32;
+    let mut rows = vec![0.0; CELL_COUNT];
+    for y in 0..HEIGHT {
+        let row = &field[y * WIDTH..(y + 1) * WIDTH];
+        let mut sum: f32 = (-(radius as i64)..=radius as i64)
+            .map(|dx| row[wrap_x(dx)])
+            .sum();
+        for x in 0..WIDTH {
+            rows[y * WIDTH + x] = sum / span;
+            sum +=
+                row[wrap_x(x as i64 + radius as i64 + 1)] - row[wrap_x(x as i64 - radius as i64)];
+        }
+    }
+    let mut out = vec![0.0; CELL_COUNT];
+    for x in 0..WIDTH {
+        let mut sum: f32 = (-(radius as i64)..=radius as i64)
+            .map(|dy| rows[clamp_y(dy) * WIDTH + x])
+            .sum();
+        for y in 0..HEIGHT {
+            out[y * WIDTH + x] = sum / span;
+            sum += rows[clamp_y(y as i64 + radius as i64 + 1) * WIDTH + x]
+                - rows[clamp_y(y as i64 - radius as i64) * WIDTH + x];
+        }
+    }
+    out
+}
@@ -0,0 +1,36 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::grid::CELL_COUNT;
+
+/// Months of the year spent above `threshold_c` on a sinusoidal annual cycle.
+pub(crate) fn months_above(mean_c: f32, amplitude_c: f32, threshold_c: f32) -> f32 {
+    if amplitude_c <= 0.0 {
+        return if mean_c > threshold_c { 12.0 } else { 0.0 };
+    }
+    let c = ((threshold_c - mean_c) / amplitude_c).clamp(-1.0, 1.0);
+    12.0 * c.acos() / std::f32::consts::PI
+}
+
+/// Growing season length per cell: months (0–12, rounded) with mean temperature above
+/// `threshold_c`. Water cells are 0. 5 °C is the usual crop base temperature. The annual
+/// cycle is centred on the annual mean, so `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn growing_season_months(climate: &Climate, threshold_c: f32) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("growing_season_months")?;
+    if !threshold_c.is_finite() {
+        return Err(JsValue::from_str("threshold_c must be finite"));
+    }
+    let mut months = vec![0_u8; CELL_COUNT];
+    for (idx, m) in months.iter_mut().enumerate() {
+        if climate.water_distance[idx] > 0.0 {
+            let length = months_above(
+                climate.temperature[idx],
+                climate.amplitude_at(idx),
+                threshold_c,
+            );
+            *m = length.round() as u8;
+        }
+    }
+    Ok(months.into_boxed_slice())
+}
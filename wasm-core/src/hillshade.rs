@@ -0,0 +1,136 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+/// Weight of the sun itself in the multidirectional blend.
+const MDOW_SUN_WEIGHT: f32 = 0.55;
+/// The preview's MDOW fill lights: (azimuth offset from the sun, degrees; altitude as
+/// height over a unit horizontal run; weight).
+const MDOW_FILL_LIGHTS: [(f32, f32, f32); 3] =
+    [(90.0, 0.70, 0.25), (45.0, 1.00, 0.12), (180.0, 2.50, 0.08)];
+
+/// Lighting for `hillshade`, defaulting to the GPU preview's render uniforms.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct HillshadeParams {
+    /// Light direction in degrees, in the preview's convention: the light vector is
+    /// (cos, sin) in grid space with x east and y south.
+    pub sun_angle: f32,
+    /// Sun height above the horizon, degrees (the preview's primary light sits at ~25°).
+    pub sun_altitude: f32,
+    pub elevation_scale: f32,
+    pub vertical_exaggeration: f32,
+    /// Blends the sun with three steeper fill lights from other azimuths, as the preview's
+    /// multidirectional oblique-weighted (MDOW) shading does, so ridges parallel to the sun
+    /// still read and enclosed valleys don't go black. On by default to match the preview;
+    /// off gives classic single-light shading.
+    pub multidirectional: bool,
+}
+
+impl Default for HillshadeParams {
+    fn default() -> Self {
+        Self {
+            sun_angle: 315.0,
+            sun_altitude: 0.47_f32.atan().to_degrees(),
+            elevation_scale: 10.0,
+            vertical_exaggeration: 5.5,
+            multidirectional: true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl HillshadeParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HillshadeParams {
+        Self::default()
+    }
+}
+
+impl HillshadeParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        let values = [
+            self.sun_angle,
+            self.elevation_scale,
+            self.vertical_exaggeration,
+        ];
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("hillshade parameters must be finite"));
+        }
+        if !(self.sun_altitude > 0.0 && self.sun_altitude <= 90.0) {
+            return Err(JsValue::from_str("sun_altitude must be within (0, 90]"));
+        }
+        Ok(())
+    }
+}
+
+/// Unit vector toward a light at `azimuth_deg` (preview convention) and `altitude_deg`.
+fn light_vector(azimuth_deg: f32, altitude_deg: f32) -> [f32; 3] {
+    let (azimuth, altitude) = (azimuth_deg.to_radians(), altitude_deg.to_radians());
+    [
+        azimuth.cos() * altitude.cos(),
+        azimuth.sin() * altitude.cos(),
+        altitude.sin(),
+    ]
+}
+
+impl HillshadeParams {
+    /// Unit vectors toward each light, with their weights.
+    pub(crate) fn lights(&self) -> Vec<([f32; 3], f32)> {
+        let mut lights = vec![(light_vector(self.sun_angle, self.sun_altitude), 1.0)];
+        if self.multidirectional {
+            lights[0].1 = MDOW_SUN_WEIGHT;
+            lights.extend(MDOW_FILL_LIGHTS.map(|(offset, rise, weight)| {
+                let altitude = rise.atan().to_degrees();
+                (light_vector(self.sun_angle + offset, altitude), weight)
+            }));
+        }
+        lights
+    }
+}
+
+/// Surface normal at `idx` of a `width` × `height` field as the preview computes it:
+/// neighbour differences scaled by `relief`, clamped at every edge.
+fn preview_normal(field: &[f32], width: usize, height: usize, idx: usize, relief: f32) -> [f32; 3] {
+    let (x, y) = (idx % width, idx / width);
+    let at = |x: usize, y: usize| field[y * width + x];
+    let dx = (at((x + 1).min(width - 1), y) - at(x.saturating_sub(1), y)) * relief;
+    let dy = (at(x, (y + 1).min(height - 1)) - at(x, y.saturating_sub(1))) * relief;
+    let length = (dx * dx + dy * dy + 1.0).sqrt();
+    [-dx / length, -dy / length, 1.0 / length]
+}
+
+/// Weighted light reaching each sample of a `width` × `height` field, 0 to 1 for weights
+/// summing to 1.
+pub(crate) fn shade(
+    field: &[f32],
+    width: usize,
+    height: usize,
+    relief: f32,
+    lights: &[([f32; 3], f32)],
+) -> Vec<f32> {
+    (0..width * height)
+        .map(|idx| {
+            let n = preview_normal(field, width, height, idx, relief);
+            lights
+                .iter()
+                .map(|(l, weight)| (n[0] * l[0] + n[1] * l[1] + n[2] * l[2]).max(0.0) * weight)
+                .sum()
+        })
+        .collect()
+}
+
+/// Grayscale hillshade, one byte per cell: 255 facing the sun, 0 turned away from it.
+/// Normals and relief scale are the GPU preview's, so thumbnails and exports are lit like
+/// it. Water is shaded like land, showing the sea floor.
+#[wasm_bindgen]
+pub fn hillshade(flat: &[f32], params: &HillshadeParams) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let relief = params.elevation_scale * params.vertical_exaggeration;
+    let shade: Vec<u8> = shade(flat, WIDTH, HEIGHT, relief, &params.lights())
+        .into_iter()
+        .map(|lit| (lit * 255.0).round().min(255.0) as u8)
+        .collect();
+    Ok(shade.into_boxed_slice())
+}
@@ -0,0 +1,251 @@
+use crate::deflate::{zlib_compress, zlib_decompress};
+use crate::grid::WIDTH;
+use crate::json::{self, Json, ObjectWriter};
+
+/// Default memory budget for compressed history, bytes.
+pub(crate) const DEFAULT_HISTORY_BUDGET: usize = 64 << 20;
+
+/// Grows `bounds` (`[x0, y0, x1, y1)`) to cover `rect` (`[x, y, width, height]`).
+pub(crate) fn union_rect(bounds: &mut Option<[usize; 4]>, [x, y, width, height]: [u32; 4]) {
+    let rect = [
+        x as usize,
+        y as usize,
+        (x + width) as usize,
+        (y + height) as usize,
+    ];
+    *bounds = Some(match *bounds {
+        Some(b) => [
+            b[0].min(rect[0]),
+            b[1].min(rect[1]),
+            b[2].max(rect[2]),
+            b[3].max(rect[3]),
+        ],
+        None => rect,
+    });
+}
+
+/// Heights changed by one step over the rectangle `[x0, y0, x1, y1)`: the XOR of the bits
+/// before and after, split into byte planes and deflated. Unchanged cells XOR to zero, so
+/// a brush dab costs little however large its rectangle, and applying the same delta
+/// either undoes or redoes the step.
+struct Delta {
+    bounds: [usize; 4],
+    packed: Vec<u8>,
+}
+
+impl Delta {
+    fn cells(&self) -> impl Iterator<Item = usize> + use<> {
+        let [x0, y0, x1, y1] = self.bounds;
+        (y0..y1).flat_map(move |y| (x0..x1).map(move |x| y * WIDTH + x))
+    }
+
+    fn new(before: &[f32], after: &[f32], bounds: [usize; 4]) -> Delta {
+        let mut delta = Delta {
+            bounds,
+            packed: Vec::new(),
+        };
+        let count = (bounds[2] - bounds[0]) * (bounds[3] - bounds[1]);
+        let mut planes = vec![0_u8; count * 4];
+        for (i, idx) in delta.cells().enumerate() {
+            let bits = before[idx].to_bits() ^ after[idx].to_bits();
+            for (plane, b) in bits.to_le_bytes().into_iter().enumerate() {
+                planes[plane * count + i] = b;
+            }
+        }
+        delta.packed = zlib_compress(&planes);
+        delta
+    }
+
+    /// Flips every field in `fields` between its before and after state.
+    fn apply(&self, fields: [&mut [f32]; 2]) -> Result<(), String> {
+        let count = (self.bounds[2] - self.bounds[0]) * (self.bounds[3] - self.bounds[1]);
+        let planes = zlib_decompress(&self.packed, count * 4)?;
+        if planes.len() != count * 4 {
+            return Err("history delta length mismatch".to_string());
+        }
+        for field in fields {
+            for (i, idx) in self.cells().enumerate() {
+                let bits = u32::from_le_bytes(std::array::from_fn(|p| planes[p * count + i]));
+                field[idx] = f32::from_bits(field[idx].to_bits() ^ bits);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One undoable step: the heights it changed, if any, and the parameters either side.
+struct Step {
+    label: String,
+    delta: Option<Delta>,
+    params_before: Json,
+    params_after: Json,
+}
+
+impl Step {
+    fn bytes(&self) -> usize {
+        self.delta.as_ref().map_or(0, |d| d.packed.len())
+    }
+}
+
+/// Undo history of a `TerrainEditor`. Edits accumulate against a copy of the heightmap as
+/// of the last checkpoint; a checkpoint turns everything changed since into one step.
+pub(crate) struct History {
+    /// The heightmap as of the last checkpoint.
+    baseline: Vec<f32>,
+    /// Bounds of cells edited since the last checkpoint.
+    pending: Option<[usize; 4]>,
+    /// Application parameters as of the last checkpoint.
+    params: Json,
+    steps: Vec<Step>,
+    /// Steps before this index are applied; those from it on can be redone.
+    cursor: usize,
+    budget: usize,
+}
+
+impl History {
+    pub(crate) fn new(flat: &[f32]) -> History {
+        History {
+            baseline: flat.to_vec(),
+            pending: None,
+            params: Json::Null,
+            steps: Vec::new(),
+            cursor: 0,
+            budget: DEFAULT_HISTORY_BUDGET,
+        }
+    }
+
+    /// Starts from `params` rather than `null`, as when a project is opened.
+    pub(crate) fn with_params(mut self, params: Json) -> History {
+        self.params = params;
+        self
+    }
+
+    pub(crate) fn touch(&mut self, rect: [u32; 4]) {
+        union_rect(&mut self.pending, rect);
+    }
+
+    pub(crate) fn params(&self) -> &Json {
+        &self.params
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        self.cursor > 0 || self.pending.is_some()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        self.cursor < self.steps.len() && self.pending.is_none()
+    }
+
+    /// Records edits since the last checkpoint, and a change to `params` if given, as one
+    /// step; false if there was nothing to record. Discards anything that could be redone.
+    pub(crate) fn checkpoint(&mut self, flat: &[f32], label: &str, params: Option<Json>) -> bool {
+        let params_after = params.unwrap_or_else(|| self.params.clone());
+        let delta = self.pending.take().map(|bounds| {
+            let delta = Delta::new(&self.baseline, flat, bounds);
+            for idx in delta.cells() {
+                self.baseline[idx] = flat[idx];
+            }
+            delta
+        });
+        if delta.is_none() && params_after == self.params {
+            return false;
+        }
+        self.steps.truncate(self.cursor);
+        self.steps.push(Step {
+            label: label.to_owned(),
+            delta,
+            params_before: std::mem::replace(&mut self.params, params_after.clone()),
+            params_after,
+        });
+        self.cursor = self.steps.len();
+        self.trim();
+        true
+    }
+
+    /// Steps back once, committing any pending edits first so they can be redone. Returns
+    /// the bounds of the heights restored, if any.
+    pub(crate) fn undo(&mut self, flat: &mut [f32]) -> Result<Option<[usize; 4]>, String> {
+        self.checkpoint(flat, "edit", None);
+        if self.cursor == 0 {
+            return Ok(None);
+        }
+        self.cursor -= 1;
+        let step = &self.steps[self.cursor];
+        self.params = step.params_before.clone();
+        self.flip(flat, self.cursor)
+    }
+
+    /// Reapplies the step last undone. Edits since the undo discard the redo steps, so
+    /// there is nothing to redo while any are pending.
+    pub(crate) fn redo(&mut self, flat: &mut [f32]) -> Result<Option<[usize; 4]>, String> {
+        if !self.can_redo() {
+            return Ok(None);
+        }
+        let step = &self.steps[self.cursor];
+        self.params = step.params_after.clone();
+        let bounds = self.flip(flat, self.cursor);
+        self.cursor += 1;
+        bounds
+    }
+
+    fn flip(&mut self, flat: &mut [f32], index: usize) -> Result<Option<[usize; 4]>, String> {
+        match &self.steps[index].delta {
+            Some(delta) => {
+                delta.apply([flat, &mut self.baseline])?;
+                Ok(Some(delta.bounds))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Forgets every step, keeping the current heightmap and parameters.
+    pub(crate) fn clear(&mut self, flat: &[f32]) {
+        self.baseline.copy_from_slice(flat);
+        self.pending = None;
+        self.steps.clear();
+        self.cursor = 0;
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.trim();
+    }
+
+    /// Drops the oldest steps until the rest fit the budget, always keeping the latest.
+    fn trim(&mut self) {
+        let mut total: usize = self.steps.iter().map(Step::bytes).sum();
+        let mut dropped = 0;
+        while total > self.budget && dropped + 1 < self.cursor {
+            total -= self.steps[dropped].bytes();
+            dropped += 1;
+        }
+        self.steps.drain(..dropped);
+        self.cursor -= dropped;
+    }
+
+    /// `{"cursor","bytes","budget","steps":[{"label","bytes","rect"},...]}`, oldest first;
+    /// steps from `cursor` on can be redone, and `rect` is `[x, y, width, height]` or null
+    /// for a parameter-only step.
+    pub(crate) fn to_json(&self) -> String {
+        let steps = self.steps.iter().map(|step| {
+            let rect = step.delta.as_ref().map_or("null".to_string(), |d| {
+                let [x0, y0, x1, y1] = d.bounds;
+                json::array([x0, y0, x1 - x0, y1 - y0])
+            });
+            ObjectWriter::new()
+                .raw("label", &json::quote(&step.label))
+                .integer("bytes", step.bytes() as u64)
+                .raw("rect", &rect)
+                .finish()
+        });
+        ObjectWriter::new()
+            .integer("cursor", self.cursor as u64)
+            .integer(
+                "bytes",
+                self.steps.iter().map(Step::bytes).sum::<usize>() as u64,
+            )
+            .integer("budget", self.budget as u64)
+            .raw("steps", &json::array(steps))
+            .finish()
+    }
+}
@@ -0,0 +1,149 @@
+use crate::climate::RELIEF_METRES;
+use crate::flow::{d8_receivers, fill_depressions, flow_accumulation, network_segments};
+use crate::grid::{CELL_COUNT, WIDTH};
+use crate::vector::{cell_area_km2, label_regions};
+
+/// Surface drainage of a heightmap for the vector exports: depressions filled to their
+/// spill points, D8 flow over the filled surface, and the lakes it implies.
+pub(crate) struct Hydrology {
+    sea_level: f32,
+    water: Vec<bool>,
+    /// Filled land basins at least the minimum depth, and water below sea level cut off
+    /// from the map's largest body of water.
+    pub(crate) lakes: Vec<bool>,
+    /// Depth of each lake cell below its basin's spill point, metres (0 elsewhere).
+    pub(crate) lake_depth_metres: Vec<f32>,
+    receivers: Vec<u32>,
+    /// Drainage area through each cell, km², itself included.
+    pub(crate) area_km2: Vec<f32>,
+}
+
+/// A stretch of river between confluences, running downstream.
+pub(crate) struct River {
+    /// Cell path, continued one cell into the sea or lake it drains into.
+    pub(crate) cells: Vec<usize>,
+    /// Strahler stream order: 1 for headwaters, rising by one where two rivers of equal
+    /// order meet.
+    pub(crate) order: u32,
+    /// Drainage area at the downstream end, km².
+    pub(crate) area_km2: f32,
+}
+
+impl Hydrology {
+    pub(crate) fn build(flat: &[f32], sea_level: f32, lake_min_depth_metres: f32) -> Hydrology {
+        let water: Vec<bool> = flat.iter().map(|&h| h < sea_level).collect();
+        let filled = fill_depressions(flat, |i| water[i]);
+        let metres_per_unit = RELIEF_METRES / (1.0 - sea_level);
+        let min_depth = (lake_min_depth_metres / metres_per_unit).max(f32::MIN_POSITIVE);
+
+        let (labels, count) = label_regions(&water, |w| w);
+        let mut sizes = vec![0_usize; count as usize + 1];
+        for &label in &labels {
+            sizes[label as usize] += 1;
+        }
+        let ocean = (1..sizes.len()).max_by_key(|&l| sizes[l]).unwrap_or(0) as u32;
+        let lakes: Vec<bool> = (0..CELL_COUNT)
+            .map(|i| {
+                if water[i] {
+                    labels[i] != ocean
+                } else {
+                    filled[i] - flat[i] >= min_depth
+                }
+            })
+            .collect();
+        let lake_depth_metres = (0..CELL_COUNT)
+            .map(|i| {
+                if lakes[i] {
+                    (filled[i].max(sea_level) - flat[i]) * metres_per_unit
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let receivers = d8_receivers(&filled);
+        let area_km2 = flow_accumulation(&receivers, |i| cell_area_km2(i / WIDTH) as f32);
+        Hydrology {
+            sea_level,
+            water,
+            lakes,
+            lake_depth_metres,
+            receivers,
+            area_km2,
+        }
+    }
+
+    /// Heights with lakes raised to sea level, so the sea-level isoline traces only the
+    /// sea's edge.
+    pub(crate) fn coastline_field(&self, flat: &[f32]) -> Vec<f32> {
+        (0..CELL_COUNT)
+            .map(|i| {
+                if self.lakes[i] {
+                    flat[i].max(self.sea_level)
+                } else {
+                    flat[i]
+                }
+            })
+            .collect()
+    }
+
+    /// Rivers over land and outside lakes wherever at least `min_area_km2` drains through.
+    pub(crate) fn rivers(&self, min_area_km2: f32) -> Vec<River> {
+        let min_area = min_area_km2.max(f32::MIN_POSITIVE);
+        let member: Vec<bool> = (0..CELL_COUNT)
+            .map(|i| !self.water[i] && !self.lakes[i] && self.area_km2[i] >= min_area)
+            .collect();
+
+        // Strahler order, heads first (Kahn's algorithm over the river cells).
+        let mut donors = vec![0_u8; CELL_COUNT];
+        for idx in (0..CELL_COUNT).filter(|&i| member[i]) {
+            let r = self.receivers[idx] as usize;
+            if r != idx && member[r] {
+                donors[r] += 1;
+            }
+        }
+        let mut order = vec![0_u32; CELL_COUNT];
+        let mut max_inflow = vec![(0_u32, 0_u8); CELL_COUNT];
+        let mut ready: Vec<usize> = (0..CELL_COUNT)
+            .filter(|&i| member[i] && donors[i] == 0)
+            .collect();
+        while let Some(idx) = ready.pop() {
+            let (highest, count) = max_inflow[idx];
+            order[idx] = match count {
+                0 => 1,
+                1 => highest,
+                _ => highest + 1,
+            };
+            let r = self.receivers[idx] as usize;
+            if r == idx || !member[r] {
+                continue;
+            }
+            let inflow = &mut max_inflow[r];
+            if order[idx] > inflow.0 {
+                *inflow = (order[idx], 1);
+            } else if order[idx] == inflow.0 {
+                inflow.1 += 1;
+            }
+            donors[r] -= 1;
+            if donors[r] == 0 {
+                ready.push(r);
+            }
+        }
+
+        network_segments(&self.receivers, &member)
+            .into_iter()
+            .map(|mut cells| {
+                let outlet = *cells.last().expect("segments have two or more cells");
+                let mouth = self.receivers[outlet] as usize;
+                if mouth != outlet && !member[mouth] {
+                    cells.push(mouth);
+                }
+                River {
+                    order: order[cells[0]],
+                    area_km2: self.area_km2[outlet],
+                    cells,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,88 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH};
+use crate::png::decode_png_gray;
+use crate::resample::{Filter, resample_field};
+
+/// Largest raw side accepted, matching `export_heightmap_raw`.
+const MAX_RAW_SIDE: usize = 8193;
+
+/// Width and height of a headerless raw file holding `count` samples: the grid itself, a
+/// square (Unity and Unreal terrains), or a 2:1 equirectangular map.
+fn raw_dimensions(count: usize) -> Result<(usize, usize), String> {
+    if count == CELL_COUNT {
+        return Ok((WIDTH, HEIGHT));
+    }
+    let side = (count as f64).sqrt().round() as usize;
+    if side * side == count && side <= MAX_RAW_SIDE {
+        return Ok((side, side));
+    }
+    let half = ((count / 2) as f64).sqrt().round() as usize;
+    if 2 * half * half == count && 2 * half <= MAX_RAW_SIDE {
+        return Ok((2 * half, half));
+    }
+    Err(format!(
+        "cannot infer the size of {count} raw samples; expected a square or 2:1 image"
+    ))
+}
+
+/// Samples of a little-endian raw file, `size` bytes each.
+fn raw_samples(bytes: &[u8], size: usize) -> Result<Vec<&[u8]>, String> {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(size) {
+        return Err(format!(
+            "raw data must be a non-empty multiple of {size} bytes"
+        ));
+    }
+    Ok(bytes.chunks_exact(size).collect())
+}
+
+/// Heightmap read from an external file and resampled to the grid (2048 × 1024), so
+/// real-world DEMs or maps painted in other tools can be analysed, shaded and exported.
+/// `format` is one of:
+/// - `"png"`: 8- or 16-bit PNG, grey or colour (the mean of red, green and blue), its
+///   full sample range mapped to [0, 1] as `export_heightmap_png` writes it;
+/// - `"r16"`: headerless little-endian u16 over [0, 1], as Unity and Unreal export;
+/// - `"r32"`: headerless little-endian f32 heightmap values, kept as they are.
+///
+/// Raw sizes are inferred from the sample count: the grid size, a square, or 2:1. The
+/// image is stretched to the grid's 2:1 shape, filtered down along an axis larger than
+/// the grid and bilinearly interpolated along one smaller, as `resample` does.
+#[wasm_bindgen]
+pub fn import_heightmap(bytes: &[u8], format: &str) -> Result<Box<[f32]>, JsValue> {
+    let decoded = match format {
+        "png" => decode_png_gray(bytes).map(|image| (image.width, image.height, image.values)),
+        "r16" => raw_samples(bytes, 2).and_then(|samples| {
+            let (width, height) = raw_dimensions(samples.len())?;
+            let values = samples
+                .iter()
+                .map(|s| u16::from_le_bytes([s[0], s[1]]) as f32 / 65535.0)
+                .collect();
+            Ok((width, height, values))
+        }),
+        "r32" => raw_samples(bytes, 4).and_then(|samples| {
+            let (width, height) = raw_dimensions(samples.len())?;
+            let values: Vec<f32> = samples
+                .iter()
+                .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+                .collect();
+            if values.iter().any(|v| !v.is_finite()) {
+                return Err("raw data contains non-finite heights".to_string());
+            }
+            Ok((width, height, values))
+        }),
+        _ => {
+            return Err(JsValue::from_str(
+                "format must be \"png\", \"r16\" or \"r32\"",
+            ));
+        }
+    };
+    let (width, height, values) = decoded.map_err(|e| JsValue::from_str(&e))?;
+    Ok(resample_field(
+        &values,
+        (width, height),
+        (WIDTH, HEIGHT),
+        Filter::Bilinear,
+        false,
+    )
+    .into_boxed_slice())
+}
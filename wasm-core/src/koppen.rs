@@ -0,0 +1,199 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::{Climate, continentality};
+use crate::grid::{CELL_COUNT, WIDTH, latitude_deg};
+
+/// Class id 0 marks water; land classes index `KOPPEN_CLASSES` from 1.
+pub(crate) const KOPPEN_WATER: u8 = 0;
+
+/// (code, name, legend colour) in class-id order starting at 1.
+pub(crate) const KOPPEN_CLASSES: [(&str, &str, &str); 31] = [
+    ("Af", "Tropical rainforest", "#0000ff"),
+    ("Am", "Tropical monsoon", "#0078ff"),
+    ("Aw", "Tropical savanna, dry winter", "#46aafa"),
+    ("As", "Tropical savanna, dry summer", "#7ab4fa"),
+    ("BWh", "Hot desert", "#ff0000"),
+    ("BWk", "Cold desert", "#ff9696"),
+    ("BSh", "Hot semi-arid", "#f5a500"),
+    ("BSk", "Cold semi-arid", "#ffdc64"),
+    ("Csa", "Hot-summer Mediterranean", "#ffff00"),
+    ("Csb", "Warm-summer Mediterranean", "#c8c800"),
+    ("Csc", "Cold-summer Mediterranean", "#969600"),
+    ("Cwa", "Monsoon-influenced humid subtropical", "#96ff96"),
+    ("Cwb", "Subtropical highland, dry winter", "#64c864"),
+    ("Cwc", "Cold subtropical highland", "#329632"),
+    ("Cfa", "Humid subtropical", "#c8ff50"),
+    ("Cfb", "Temperate oceanic", "#64ff50"),
+    ("Cfc", "Subpolar oceanic", "#32c800"),
+    ("Dsa", "Hot-summer continental, dry summer", "#ff00ff"),
+    ("Dsb", "Warm-summer continental, dry summer", "#c800c8"),
+    ("Dsc", "Subarctic, dry summer", "#963296"),
+    ("Dsd", "Extremely cold subarctic, dry summer", "#966496"),
+    ("Dwa", "Hot-summer continental, dry winter", "#aaafff"),
+    ("Dwb", "Warm-summer continental, dry winter", "#5a78dc"),
+    ("Dwc", "Subarctic, dry winter", "#4b50b4"),
+    ("Dwd", "Extremely cold subarctic, dry winter", "#320087"),
+    ("Dfa", "Hot-summer humid continental", "#00ffff"),
+    ("Dfb", "Warm-summer humid continental", "#37c8ff"),
+    ("Dfc", "Subarctic", "#007d7d"),
+    ("Dfd", "Extremely cold subarctic", "#00465f"),
+    ("ET", "Tundra", "#b2b2b2"),
+    ("EF", "Ice cap", "#666666"),
+];
+
+/// Temperature anomaly (°C) below which an adjacent cold current makes summers dry.
+const COLD_CURRENT_DRY_SUMMER_C: f32 = -0.5;
+
+fn class_id(code: &str) -> u8 {
+    KOPPEN_CLASSES
+        .iter()
+        .position(|(c, _, _)| *c == code)
+        .map(|i| i as u8 + 1)
+        .unwrap_or(KOPPEN_WATER)
+}
+
+/// Precipitation seasonality in [-1, 1]: positive = summer-wet, negative = summer-dry.
+/// Annual-mean layers carry no seasonal signal, so this follows the classic zonal pattern:
+/// monsoonal tropics, dry-summer subtropical coasts beside cold currents, and summer
+/// convection in continental interiors.
+fn precipitation_seasonality(lat_deg: f32, current_anomaly: f32, continentality: f32) -> f32 {
+    let a = lat_deg.abs();
+    if (8.0..26.0).contains(&a) {
+        0.6
+    } else if (28.0..44.0).contains(&a) && current_anomaly < COLD_CURRENT_DRY_SUMMER_C {
+        -0.7
+    } else if a >= 26.0 {
+        0.5 * continentality
+    } else {
+        0.0
+    }
+}
+
+pub(crate) struct SeasonalSummary {
+    pub(crate) mean_c: f32,
+    pub(crate) warmest_c: f32,
+    pub(crate) coldest_c: f32,
+    pub(crate) months_above_10c: u32,
+    pub(crate) annual_mm: f32,
+    pub(crate) driest_month_mm: f32,
+    pub(crate) seasonality: f32,
+}
+
+pub(crate) fn seasonal_summary(climate: &Climate, idx: usize) -> SeasonalSummary {
+    let lat = latitude_deg(idx / WIDTH);
+    let mean_c = climate.temperature[idx];
+    let distance = climate.water_distance[idx];
+    let amplitude = climate.amplitude_at(idx);
+    let months_above_10c = (0..12)
+        .filter(|&m| {
+            let phase = std::f32::consts::TAU * (m as f32 + 0.5) / 12.0;
+            mean_c + amplitude * phase.cos() > 10.0
+        })
+        .count() as u32;
+    let seasonality =
+        precipitation_seasonality(lat, climate.current_anomaly[idx], continentality(distance));
+    let annual_mm = climate.precipitation[idx];
+    SeasonalSummary {
+        mean_c,
+        warmest_c: mean_c + amplitude,
+        coldest_c: mean_c - amplitude,
+        months_above_10c,
+        annual_mm,
+        driest_month_mm: annual_mm / 12.0 * (1.0 - seasonality.abs() * 0.9),
+        seasonality,
+    }
+}
+
+fn temperature_letter(s: &SeasonalSummary) -> char {
+    if s.warmest_c >= 22.0 {
+        'a'
+    } else if s.months_above_10c >= 4 {
+        'b'
+    } else if s.coldest_c < -38.0 {
+        'd'
+    } else {
+        'c'
+    }
+}
+
+pub(crate) fn classify(s: &SeasonalSummary) -> u8 {
+    if s.warmest_c < 10.0 {
+        return class_id(if s.warmest_c > 0.0 { "ET" } else { "EF" });
+    }
+
+    let summer_share = 0.5 + 0.35 * s.seasonality;
+    let dryness_offset = if summer_share >= 0.7 {
+        280.0
+    } else if summer_share <= 0.3 {
+        0.0
+    } else {
+        140.0
+    };
+    let arid_threshold = 20.0 * s.mean_c + dryness_offset;
+    if s.annual_mm < arid_threshold {
+        let kind = if s.annual_mm < arid_threshold * 0.5 {
+            "BW"
+        } else {
+            "BS"
+        };
+        let heat = if s.mean_c >= 18.0 { "h" } else { "k" };
+        return class_id(&format!("{kind}{heat}"));
+    }
+
+    if s.coldest_c >= 18.0 {
+        let code = if s.driest_month_mm >= 60.0 {
+            "Af"
+        } else if s.driest_month_mm >= 100.0 - s.annual_mm / 25.0 {
+            "Am"
+        } else if s.seasonality < 0.0 {
+            "As"
+        } else {
+            "Aw"
+        };
+        return class_id(code);
+    }
+
+    let group = if s.coldest_c > -3.0 { 'C' } else { 'D' };
+    let precip = if s.seasonality <= -0.4 {
+        's'
+    } else if s.seasonality >= 0.5 {
+        'w'
+    } else {
+        'f'
+    };
+    let mut heat = temperature_letter(s);
+    if group == 'C' && heat == 'd' {
+        heat = 'c';
+    }
+    class_id(&format!("{group}{precip}{heat}"))
+}
+
+/// Köppen–Geiger class per cell (0 = water, otherwise 1-based index into the legend).
+/// The monthly cycle is reconstructed from annual means, so `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn koppen_classes(climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("koppen_classes")?;
+    let mut classes = vec![KOPPEN_WATER; CELL_COUNT];
+    for (idx, class) in classes.iter_mut().enumerate() {
+        if climate.water_distance[idx] > 0.0 {
+            *class = classify(&seasonal_summary(climate, idx));
+        }
+    }
+    Ok(classes.into_boxed_slice())
+}
+
+/// Legend for `koppen_classes`: `[{"id","code","name","color"}, ...]`.
+#[wasm_bindgen]
+pub fn koppen_legend_json() -> String {
+    let entries: Vec<String> = KOPPEN_CLASSES
+        .iter()
+        .enumerate()
+        .map(|(i, (code, name, color))| {
+            format!(
+                "{{\"id\":{id},\"code\":\"{code}\",\"name\":\"{name}\",\"color\":\"{color}\"}}",
+                id = i + 1
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
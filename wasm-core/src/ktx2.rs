@@ -0,0 +1,928 @@
+use wasm_bindgen::prelude::*;
+
+use crate::deflate::zlib_compress;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, check_grid_len};
+use crate::render::RELIEF_SCALE;
+use crate::terrain::gradient;
+
+const IDENTIFIER: [u8; 12] = [
+    0xab, b'K', b'T', b'X', b' ', b'2', b'0', 0xbb, b'\r', b'\n', 0x1a, b'\n',
+];
+const SUPERCOMPRESSION_ZLIB: u32 = 3;
+/// Data format descriptor colour models, primaries and transfer functions (Khronos DFD).
+const MODEL_RGBSDA: u32 = 1;
+const MODEL_BC1A: u32 = 128;
+const MODEL_BC5: u32 = 132;
+const MODEL_UASTC: u32 = 166;
+const PRIMARIES_BT709: u32 = 1;
+const TRANSFER_LINEAR: u32 = 1;
+const TRANSFER_SRGB: u32 = 2;
+/// Sample qualifier marking a channel linear inside an sRGB format (alpha).
+const QUALIFIER_LINEAR: u32 = 0x10;
+const CHANNEL_ALPHA: u32 = 15;
+/// UASTC channel ids: opaque RGB, and RGBA (which also covers luminance plus alpha).
+const CHANNEL_UASTC_RGB: u32 = 0;
+const CHANNEL_UASTC_RGBA: u32 = 3;
+
+/// Data format descriptor sample: bit offset, bit length, channel id and qualifiers, and
+/// upper value.
+type Sample = (u32, u32, u32, u32);
+/// Mip level: width, height and texels, row-major.
+type Level<T> = (usize, usize, Vec<T>);
+
+/// Texel layout of an exported texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TextureFormat {
+    /// `VK_FORMAT_R8G8B8A8_SRGB`.
+    Rgba8Srgb,
+    /// `VK_FORMAT_R8G8B8A8_UNORM`.
+    Rgba8Unorm,
+    /// `VK_FORMAT_BC1_RGB_SRGB_BLOCK`: 4 × 4 blocks of two RGB565 endpoints, 4 bits a texel.
+    Bc1Srgb,
+    /// `VK_FORMAT_BC5_UNORM_BLOCK`: two BC4 channels (red and green), 8 bits a texel.
+    Bc5Unorm,
+    /// Basis Universal UASTC, RGB in sRGB: 4 × 4 blocks of 128 bits (mode 0) that
+    /// transcoders turn into whatever the GPU supports (ASTC, BC7, ETC, BC1, ...).
+    UastcRgbSrgb,
+    /// UASTC luminance plus alpha (mode 15), linear: the first channel in RGB and the
+    /// second in alpha, which transcoders map to BC5 or EAC RG11.
+    UastcLaUnorm,
+}
+
+impl TextureFormat {
+    fn vk_format(self) -> u32 {
+        match self {
+            TextureFormat::Rgba8Unorm => 37,
+            TextureFormat::Rgba8Srgb => 43,
+            TextureFormat::Bc1Srgb => 132,
+            TextureFormat::Bc5Unorm => 141,
+            // `VK_FORMAT_UNDEFINED`: the data format descriptor names the UASTC model.
+            TextureFormat::UastcRgbSrgb | TextureFormat::UastcLaUnorm => 0,
+        }
+    }
+
+    /// Texels per block side.
+    fn block_side(self) -> usize {
+        match self {
+            TextureFormat::Rgba8Srgb | TextureFormat::Rgba8Unorm => 1,
+            TextureFormat::Bc1Srgb
+            | TextureFormat::Bc5Unorm
+            | TextureFormat::UastcRgbSrgb
+            | TextureFormat::UastcLaUnorm => 4,
+        }
+    }
+
+    fn is_uastc(self) -> bool {
+        matches!(
+            self,
+            TextureFormat::UastcRgbSrgb | TextureFormat::UastcLaUnorm
+        )
+    }
+
+    fn block_bytes(self) -> usize {
+        match self {
+            TextureFormat::Rgba8Srgb | TextureFormat::Rgba8Unorm => 4,
+            TextureFormat::Bc1Srgb => 8,
+            TextureFormat::Bc5Unorm | TextureFormat::UastcRgbSrgb | TextureFormat::UastcLaUnorm => {
+                16
+            }
+        }
+    }
+
+    /// Colour model, transfer function and samples of the basic data format descriptor.
+    fn descriptor(self) -> (u32, u32, Vec<Sample>) {
+        let rgba = |transfer, alpha| {
+            let samples = (0..3)
+                .map(|c| (8 * c, 8, c, 255))
+                .chain([(24, 8, alpha, 255)])
+                .collect();
+            (MODEL_RGBSDA, transfer, samples)
+        };
+        match self {
+            TextureFormat::Rgba8Srgb => rgba(TRANSFER_SRGB, CHANNEL_ALPHA | QUALIFIER_LINEAR),
+            TextureFormat::Rgba8Unorm => rgba(TRANSFER_LINEAR, CHANNEL_ALPHA),
+            TextureFormat::Bc1Srgb => (MODEL_BC1A, TRANSFER_SRGB, vec![(0, 64, 0, u32::MAX)]),
+            TextureFormat::Bc5Unorm => (
+                MODEL_BC5,
+                TRANSFER_LINEAR,
+                vec![(0, 64, 0, u32::MAX), (64, 64, 1, u32::MAX)],
+            ),
+            TextureFormat::UastcRgbSrgb => (
+                MODEL_UASTC,
+                TRANSFER_SRGB,
+                vec![(0, 128, CHANNEL_UASTC_RGB, u32::MAX)],
+            ),
+            TextureFormat::UastcLaUnorm => (
+                MODEL_UASTC,
+                TRANSFER_LINEAR,
+                vec![(0, 128, CHANNEL_UASTC_RGBA, u32::MAX)],
+            ),
+        }
+    }
+
+    /// The data format descriptor, with its leading total size.
+    fn dfd(self) -> Vec<u8> {
+        let (model, transfer, samples) = self.descriptor();
+        let side = self.block_side() as u32 - 1;
+        let block_size = 24 + 16 * samples.len() as u32;
+        let mut words = vec![
+            4 + block_size,
+            0,
+            2 | block_size << 16,
+            model | PRIMARIES_BT709 << 8 | transfer << 16,
+            side | side << 8,
+            self.block_bytes() as u32,
+            0,
+        ];
+        for (offset, length, channel, upper) in samples {
+            words.extend([offset | (length - 1) << 16 | channel << 24, 0, 0, upper]);
+        }
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    /// One mip level of `width` × `height` RGBA texels encoded in this format, edge texels
+    /// repeated to fill partial blocks.
+    fn encode(self, width: usize, height: usize, texels: &[[u8; 4]]) -> Vec<u8> {
+        if self.block_side() == 1 {
+            return texels.iter().flatten().copied().collect();
+        }
+        let mut out =
+            Vec::with_capacity(width.div_ceil(4) * height.div_ceil(4) * self.block_bytes());
+        for by in (0..height).step_by(4) {
+            for bx in (0..width).step_by(4) {
+                let block: [[u8; 4]; 16] = std::array::from_fn(|i| {
+                    let (x, y) = ((bx + i % 4).min(width - 1), (by + i / 4).min(height - 1));
+                    texels[y * width + x]
+                });
+                match self {
+                    TextureFormat::Bc1Srgb => out.extend(bc1_block(&block)),
+                    TextureFormat::Bc5Unorm => {
+                        out.extend(bc4_block(&block.map(|t| t[0])));
+                        out.extend(bc4_block(&block.map(|t| t[1])));
+                    }
+                    TextureFormat::UastcRgbSrgb => out.extend(uastc_rgb_block(&block)),
+                    TextureFormat::UastcLaUnorm => out.extend(uastc_la_block(&block)),
+                    TextureFormat::Rgba8Srgb | TextureFormat::Rgba8Unorm => {
+                        unreachable!("texel formats return before blocking")
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Mean and principal axis (unit length, by power iteration) of a block's texels.
+fn principal_axis<const C: usize>(points: &[[f32; C]; 16]) -> ([f32; C], [f32; C]) {
+    let mean: [f32; C] = std::array::from_fn(|k| points.iter().map(|p| p[k]).sum::<f32>() / 16.0);
+    let mut covariance = [[0.0_f32; C]; C];
+    for p in points {
+        for (i, row) in covariance.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v += (p[i] - mean[i]) * (p[j] - mean[j]);
+            }
+        }
+    }
+    let mut axis = [(C as f32).sqrt().recip(); C];
+    for _ in 0..8 {
+        let next = covariance.map(|row| row.iter().zip(&axis).map(|(v, a)| v * a).sum::<f32>());
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+    (mean, axis)
+}
+
+/// BC1 block in four-colour mode: endpoints at the ends of the colours' principal axis,
+/// each texel taking the nearest of the four palette colours.
+fn bc1_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let colors = block.map(|t| [t[0] as f32, t[1] as f32, t[2] as f32]);
+    let (_, axis) = principal_axis(&colors);
+    let project = |c: &[f32; 3]| c.iter().zip(&axis).map(|(v, a)| v * a).sum::<f32>();
+    let low = colors
+        .iter()
+        .min_by(|a, b| project(a).total_cmp(&project(b)));
+    let high = colors
+        .iter()
+        .max_by(|a, b| project(a).total_cmp(&project(b)));
+    let pack = |c: &[f32; 3]| {
+        let q = |v: f32, bits: u32| (v / 255.0 * ((1 << bits) - 1) as f32).round() as u16;
+        q(c[0], 5) << 11 | q(c[1], 6) << 5 | q(c[2], 5)
+    };
+    let (mut c0, mut c1) = (
+        pack(high.expect("block has 16 texels")),
+        pack(low.expect("block has 16 texels")),
+    );
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+    let unpack = |c: u16| {
+        let (r, g, b) = ((c >> 11) as u32, (c >> 5 & 63) as u32, (c & 31) as u32);
+        [
+            (r << 3 | r >> 2) as f32,
+            (g << 2 | g >> 4) as f32,
+            (b << 3 | b >> 2) as f32,
+        ]
+    };
+    let (p0, p1) = (unpack(c0), unpack(c1));
+    let mix = |a: f32, b: f32| std::array::from_fn::<f32, 3, _>(|i| (a * p0[i] + b * p1[i]) / 3.0);
+    // With equal endpoints the block is in three-colour mode, but index 0 is all it needs.
+    let palette = [p0, p1, mix(2.0, 1.0), mix(1.0, 2.0)];
+    let mut indices = 0_u32;
+    if c0 != c1 {
+        for (i, c) in colors.iter().enumerate() {
+            let distance = |p: &[f32; 3]| (0..3).map(|k| (c[k] - p[k]).powi(2)).sum::<f32>();
+            let best = (0..4)
+                .min_by(|&a, &b| distance(&palette[a]).total_cmp(&distance(&palette[b])))
+                .unwrap_or(0);
+            indices |= (best as u32) << (2 * i);
+        }
+    }
+    let mut out = [0_u8; 8];
+    out[..2].copy_from_slice(&c0.to_le_bytes());
+    out[2..4].copy_from_slice(&c1.to_le_bytes());
+    out[4..].copy_from_slice(&indices.to_le_bytes());
+    out
+}
+
+/// BC4 block in eight-value mode between the block's extremes.
+fn bc4_block(values: &[u8; 16]) -> [u8; 8] {
+    let (high, low) = (
+        *values.iter().max().unwrap_or(&0),
+        *values.iter().min().unwrap_or(&0),
+    );
+    let mut bits = 0_u64;
+    if high != low {
+        let palette: [f32; 8] = std::array::from_fn(|i| match i {
+            0 => high as f32,
+            1 => low as f32,
+            _ => ((8 - i) as f32 * high as f32 + (i - 1) as f32 * low as f32) / 7.0,
+        });
+        for (i, &v) in values.iter().enumerate() {
+            let best = (0..8)
+                .min_by(|&a, &b| {
+                    (palette[a] - v as f32)
+                        .abs()
+                        .total_cmp(&(palette[b] - v as f32).abs())
+                })
+                .unwrap_or(0);
+            bits |= (best as u64) << (3 * i);
+        }
+    }
+    let mut out = [0_u8; 8];
+    out[0] = high;
+    out[1] = low;
+    out[2..].copy_from_slice(&bits.to_le_bytes()[..6]);
+    out
+}
+
+/// ASTC weights of the 16 UASTC weight levels, out of 64.
+const UASTC_WEIGHTS: [u32; 16] = [0, 4, 8, 12, 17, 21, 25, 29, 35, 39, 43, 47, 52, 56, 60, 64];
+/// ETC1 intensity modifier tables.
+const ETC1_MODIFIERS: [[i32; 4]; 8] = [
+    [-8, -2, 2, 8],
+    [-17, -5, 5, 17],
+    [-29, -9, 9, 29],
+    [-42, -13, 13, 42],
+    [-60, -18, 18, 60],
+    [-80, -24, 24, 80],
+    [-106, -33, 33, 106],
+    [-183, -47, 47, 183],
+];
+/// ETC2 EAC alpha modifier tables; entries 3 and 7 are each table's extremes.
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// ASTC unquantisation of a 192-level endpoint (a trit above six bits) to 0–255.
+const fn unquantise_trit_endpoint(value: usize) -> u8 {
+    let (bits, trit) = (value & 63, value >> 6);
+    let a = if bits & 1 == 1 { 511 } else { 0 };
+    // Bit pattern "fedcb000f" of the ASTC specification, a being the lowest bit.
+    let b = ((bits >> 5 & 1) * 0x101) | ((bits >> 1 & 15) << 4);
+    let v = (trit * 5 + b) ^ a;
+    ((a & 0x80) | v >> 2) as u8
+}
+
+/// The 192 endpoint levels of UASTC mode 0, by encoded value.
+const TRIT_ENDPOINTS: [u8; 192] = {
+    let mut table = [0; 192];
+    let mut v = 0;
+    while v < 192 {
+        table[v] = unquantise_trit_endpoint(v);
+        v += 1;
+    }
+    table
+};
+
+/// Nearest mode 0 endpoint level for each byte.
+const TRIT_QUANTISE: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut target = 0;
+    while target < 256 {
+        let mut best = 0;
+        let mut v = 1;
+        while v < 192 {
+            if (TRIT_ENDPOINTS[v] as i32 - target as i32).abs()
+                < (TRIT_ENDPOINTS[best] as i32 - target as i32).abs()
+            {
+                best = v;
+            }
+            v += 1;
+        }
+        table[target] = best as u8;
+        target += 1;
+    }
+    table
+};
+
+/// Bits of a UASTC block, filled from the least significant end.
+#[derive(Default)]
+struct UastcBits {
+    value: u128,
+    len: u32,
+}
+
+impl UastcBits {
+    fn write(&mut self, value: u32, len: u32) {
+        self.value |= (value as u128) << self.len;
+        self.len += len;
+    }
+
+    fn finish(self) -> [u8; 16] {
+        debug_assert!(self.len <= 128);
+        self.value.to_le_bytes()
+    }
+}
+
+/// A single-subset UASTC fit of `points`: per channel, the low and high endpoints as
+/// (encoded value, level out of 255) from `quantise`, and each texel's weight level.
+/// The first texel's weight is stored without its top bit, so the block is mirrored
+/// when that bit would be set.
+fn uastc_fit<const C: usize>(
+    points: &[[f32; C]; 16],
+    quantise: impl Fn(f32) -> (u32, u8),
+) -> ([[(u32, u8); 2]; C], [u32; 16]) {
+    let (mean, axis) = principal_axis(points);
+    let project = |p: &[f32; C]| (0..C).map(|k| (p[k] - mean[k]) * axis[k]).sum::<f32>();
+    let (low, high) = points
+        .iter()
+        .map(project)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(l, h), t| {
+            (l.min(t), h.max(t))
+        });
+    let mut endpoints: [[(u32, u8); 2]; C] = std::array::from_fn(|k| {
+        [low, high].map(|t| quantise((mean[k] + axis[k] * t).clamp(0.0, 255.0)))
+    });
+    let direction: [f32; C] =
+        std::array::from_fn(|k| endpoints[k][1].1 as f32 - endpoints[k][0].1 as f32);
+    let length = direction.iter().map(|d| d * d).sum::<f32>();
+    let mut weights = points.map(|p| {
+        let along = if length > 0.0 {
+            (0..C)
+                .map(|k| (p[k] - endpoints[k][0].1 as f32) * direction[k])
+                .sum::<f32>()
+                / length
+        } else {
+            0.0
+        };
+        let target = along * 64.0;
+        (0..16)
+            .min_by(|&a, &b| {
+                (UASTC_WEIGHTS[a] as f32 - target)
+                    .abs()
+                    .total_cmp(&(UASTC_WEIGHTS[b] as f32 - target).abs())
+            })
+            .unwrap_or(0) as u32
+    });
+    if weights[0] >= 8 {
+        weights = weights.map(|w| 15 - w);
+        for pair in &mut endpoints {
+            pair.swap(0, 1);
+        }
+    }
+    (endpoints, weights)
+}
+
+/// Texel a UASTC decoder produces between endpoint levels `low` and `high`.
+fn uastc_interpolate(low: u8, high: u8, weight: u32) -> u8 {
+    let (low, high) = (low as u32 * 257, high as u32 * 257);
+    ((low * (64 - weight) + high * weight + 32) >> 14) as u8
+}
+
+/// The transcoder's ETC1 bias on `channel` of `subblock`: mostly ±1 nudges, with bias 13
+/// neutral away from the ends of the range.
+fn etc1_bias_delta(bias: u32, channel: usize, subblock: usize) -> i32 {
+    let on = |s: usize, c: usize, delta: i32| {
+        if subblock == s && channel == c {
+            delta
+        } else {
+            0
+        }
+    };
+    match bias {
+        2 => on(0, 0, -1),
+        5 => on(0, 1, -1),
+        6 => on(0, 2, -1),
+        7 => on(0, 0, 1),
+        11 => on(0, 1, 1),
+        15 => on(0, 2, 1),
+        18 => on(1, 0, -1),
+        19 => on(1, 1, -1),
+        20 => on(1, 2, -1),
+        21 => on(1, 0, 1),
+        24 => on(1, 1, 1),
+        8 => on(1, 2, 1),
+        10 => -2,
+        27 => [-1, 0][subblock],
+        28 => [1, -1][subblock],
+        29 => [0, 1][subblock],
+        30 => [0, -1][subblock],
+        31 => [1, 0][subblock],
+        _ => (bias / [1, 3, 9][channel] % 3) as i32 - 1,
+    }
+}
+
+/// Subblock colour `v` (0–`limit`) after a bias `delta`, kept in range as the transcoder does.
+fn apply_etc1_bias(v: i32, delta: i32, limit: i32) -> i32 {
+    if v == 0 {
+        if delta == -2 { 3 } else { delta + 1 }
+    } else if v == limit {
+        v + delta - 1
+    } else if (0..=limit).contains(&(v + delta)) {
+        v + delta
+    } else {
+        v - delta
+    }
+}
+
+/// ETC1 hints (flip, differential, intensity table per subblock, bias) for `rgb`, the
+/// decoded block. Transcoders to ETC1 take the subblock means at `limit` precision, bias
+/// them, and only pick selectors, so the search mirrors that reconstruction.
+fn etc1_hints(rgb: &[[u8; 3]; 16]) -> [u32; 5] {
+    let mut best = (u32::MAX, [0, 0, 0, 0, 13]);
+    for flip in 0..2 {
+        let subblock = |s: usize| {
+            (0..8).map(move |j| {
+                let (a, b) = (2 * s + j / 4, j % 4);
+                if flip == 0 { b * 4 + a } else { a * 4 + b }
+            })
+        };
+        let sums: [[u32; 3]; 2] = std::array::from_fn(|s| {
+            std::array::from_fn(|c| subblock(s).map(|i| rgb[i][c] as u32).sum())
+        });
+        for diff in [1, 0] {
+            let limit = if diff == 1 { 31 } else { 15 };
+            let colors = sums.map(|sum| sum.map(|v| ((v * limit + 1020) / 2040) as i32));
+            let biased = |bias| -> [[i32; 3]; 2] {
+                std::array::from_fn(|s| {
+                    std::array::from_fn(|c| {
+                        apply_etc1_bias(colors[s][c], etc1_bias_delta(bias, c, s), limit as i32)
+                    })
+                })
+            };
+            let bias = std::iter::once(13)
+                .chain(0..32)
+                .min_by_key(|&bias| {
+                    let b = biased(bias);
+                    (0..6)
+                        .map(|i| (b[i / 3][i % 3] - colors[i / 3][i % 3]).abs())
+                        .sum::<i32>()
+                })
+                .unwrap_or(13);
+            let mut bases = biased(bias);
+            if diff == 1 {
+                if (0..3).any(|c| !(-4..=3).contains(&(bases[1][c] - bases[0][c]))) {
+                    continue;
+                }
+                bases = bases.map(|b| b.map(|v| v << 3 | v >> 2));
+            } else {
+                bases = bases.map(|b| b.map(|v| v << 4 | v));
+            }
+            let mut tables = [0; 2];
+            let mut error = 0;
+            for (s, table) in tables.iter_mut().enumerate() {
+                let cost = |modifiers: &[i32; 4]| {
+                    subblock(s)
+                        .map(|i| {
+                            modifiers
+                                .iter()
+                                .map(|m| {
+                                    (0..3)
+                                        .map(|c| {
+                                            ((bases[s][c] + m).clamp(0, 255) - rgb[i][c] as i32)
+                                                .pow(2)
+                                                as u32
+                                        })
+                                        .sum::<u32>()
+                                })
+                                .min()
+                                .unwrap_or(0)
+                        })
+                        .sum::<u32>()
+                };
+                let (t, e) = (0..8)
+                    .map(|t| (t, cost(&ETC1_MODIFIERS[t])))
+                    .min_by_key(|&(_, e)| e)
+                    .unwrap_or((0, 0));
+                *table = t as u32;
+                error += e;
+            }
+            if error < best.0 {
+                best = (error, [flip as u32, diff, tables[0], tables[1], bias]);
+            }
+            break;
+        }
+    }
+    best.1
+}
+
+/// ETC2 EAC alpha hint for `alpha`, the decoded block: table in the low four bits and
+/// multiplier (1–15) above, as transcoders to EAC spread the table over the block's range.
+fn eac_hint(alpha: &[u8; 16]) -> u32 {
+    let (min, max) = (
+        *alpha.iter().min().unwrap_or(&0) as i32,
+        *alpha.iter().max().unwrap_or(&0) as i32,
+    );
+    if min == max {
+        return 13 | 1 << 4;
+    }
+    let error = |table: usize| {
+        let modifiers = &EAC_MODIFIERS[table];
+        let span = (modifiers[7] - modifiers[3]) as f32;
+        let center = (min as f32 + (max - min) as f32 * -modifiers[3] as f32 / span).round() as i32;
+        let multiplier = (((max - min) as f32 / span).round() as i32).clamp(1, 15);
+        let error = alpha
+            .iter()
+            .map(|&a| {
+                modifiers
+                    .iter()
+                    .map(|m| ((center + m * multiplier).clamp(0, 255) - a as i32).pow(2))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum::<i32>();
+        (error, table as u32 | (multiplier as u32) << 4)
+    };
+    (0..16)
+        .map(error)
+        .min()
+        .map_or(13 | 1 << 4, |(_, hint)| hint)
+}
+
+/// Hint fields shared by modes 0 and 15: no BC1 hints (transcoders then search BC1
+/// endpoints themselves) and the ETC1 hints of the decoded block.
+fn write_uastc_hints(bits: &mut UastcBits, rgb: &[[u8; 3]; 16]) {
+    let [flip, diff, table0, table1, bias] = etc1_hints(rgb);
+    bits.write(0, 2);
+    bits.write(flip, 1);
+    bits.write(diff, 1);
+    bits.write(table0, 3);
+    bits.write(table1, 3);
+    bits.write(bias, 5);
+}
+
+/// Weight levels, the first without its (clear) top bit.
+fn write_uastc_weights(bits: &mut UastcBits, weights: &[u32; 16]) {
+    bits.write(weights[0], 3);
+    for &w in &weights[1..] {
+        bits.write(w, 4);
+    }
+}
+
+/// UASTC mode 0 block: one RGB subset with 192-level endpoints and 16 weight levels.
+fn uastc_rgb_block(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let points = block.map(|t| [t[0] as f32, t[1] as f32, t[2] as f32]);
+    let (endpoints, weights) = uastc_fit(&points, |v| {
+        let encoded = TRIT_QUANTISE[v.round() as usize];
+        (encoded as u32, TRIT_ENDPOINTS[encoded as usize])
+    });
+    let decoded = weights.map(|w| {
+        endpoints.map(|[low, high]| uastc_interpolate(low.1, high.1, UASTC_WEIGHTS[w as usize]))
+    });
+    let values = endpoints.as_flattened().iter().map(|(encoded, _)| *encoded);
+    let mut bits = UastcBits::default();
+    bits.write(0x1, 4);
+    write_uastc_hints(&mut bits, &decoded);
+    // Endpoints as RRGGBB: their trits packed five to a byte, then the low six bits each.
+    let trits: Vec<u32> = values.clone().map(|v| v >> 6).collect();
+    bits.write(trits[..5].iter().rev().fold(0, |acc, t| acc * 3 + t), 8);
+    bits.write(trits[5], 2);
+    for v in values {
+        bits.write(v & 63, 6);
+    }
+    write_uastc_weights(&mut bits, &weights);
+    bits.finish()
+}
+
+/// UASTC mode 15 block: luminance from the first channel and alpha from the second,
+/// one subset with 8-bit endpoints and 16 weight levels.
+fn uastc_la_block(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let points = block.map(|t| [t[0] as f32, t[1] as f32]);
+    let (endpoints, weights) = uastc_fit(&points, |v| {
+        let level = v.round() as u8;
+        (level as u32, level)
+    });
+    let decoded = weights.map(|w| {
+        endpoints.map(|[low, high]| uastc_interpolate(low.1, high.1, UASTC_WEIGHTS[w as usize]))
+    });
+    let mut bits = UastcBits::default();
+    bits.write(0x5, 7);
+    write_uastc_hints(&mut bits, &decoded.map(|[l, _]| [l; 3]));
+    bits.write(eac_hint(&decoded.map(|[_, a]| a)), 8);
+    // Endpoints as LLAA.
+    for (encoded, _) in endpoints.as_flattened() {
+        bits.write(*encoded, 8);
+    }
+    write_uastc_weights(&mut bits, &weights);
+    bits.finish()
+}
+
+/// Mip chain of `base` (`width` × `height` texels of four channels) by 2 × 2 averaging,
+/// down to 1 × 1; `normalise` rescales the first three channels to unit length at every
+/// level below the base, for normal maps.
+fn mip_chain(
+    base: Vec<[f32; 4]>,
+    width: usize,
+    height: usize,
+    normalise: bool,
+) -> Vec<Level<[f32; 4]>> {
+    let mut levels = vec![(width, height, base)];
+    while let Some((w, h, texels)) = levels.last().filter(|(w, h, _)| *w > 1 || *h > 1) {
+        let (out_w, out_h) = ((w / 2).max(1), (h / 2).max(1));
+        let (sx, sy) = (w / out_w, h / out_h);
+        let pooled = (0..out_w * out_h)
+            .map(|i| {
+                let (x, y) = (sx * (i % out_w), sy * (i / out_w));
+                let mut sum = [0.0_f32; 4];
+                for dy in 0..sy {
+                    for dx in 0..sx {
+                        let t = texels[(y + dy) * w + x + dx];
+                        sum = std::array::from_fn(|c| sum[c] + t[c]);
+                    }
+                }
+                let mut mean = sum.map(|v| v / (sx * sy) as f32);
+                if normalise {
+                    let length = mean[..3]
+                        .iter()
+                        .map(|v| v * v)
+                        .sum::<f32>()
+                        .sqrt()
+                        .max(1e-6);
+                    for v in &mut mean[..3] {
+                        *v /= length;
+                    }
+                }
+                mean
+            })
+            .collect();
+        levels.push((out_w, out_h, pooled));
+    }
+    levels
+}
+
+/// Options shared by the KTX2 exports.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Ktx2Params {
+    /// Stores the full mip chain down to 1 × 1 (12 levels) instead of the base level only.
+    pub mipmaps: bool,
+    /// Supercompresses each level with zlib (KTX2 scheme 3), for smaller downloads;
+    /// loaders inflate it before upload.
+    pub zlib: bool,
+    /// Mesh units per heightmap unit for the normal map, as `MeshParams::vertical_scale`,
+    /// so it matches a terrain mesh exported with the same value.
+    pub vertical_scale: f32,
+}
+
+impl Default for Ktx2Params {
+    fn default() -> Self {
+        Self {
+            mipmaps: true,
+            zlib: false,
+            vertical_scale: RELIEF_SCALE,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Ktx2Params {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Ktx2Params {
+        Self::default()
+    }
+}
+
+impl Ktx2Params {
+    fn validate(&self, format: TextureFormat) -> Result<(), JsValue> {
+        if !(self.vertical_scale.is_finite() && self.vertical_scale > 0.0) {
+            return Err(JsValue::from_str("vertical_scale must be > 0"));
+        }
+        // Basis Universal transcoders only read UASTC stored raw or with Zstandard.
+        if self.zlib && format.is_uastc() {
+            return Err(JsValue::from_str(
+                "zlib supercompression is not available for uastc",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Key/value entry: length, key and value NUL-terminated, padded to 4 bytes.
+fn key_value(out: &mut Vec<u8>, key: &str, value: &str) {
+    let length = key.len() + value.len() + 2;
+    out.extend((length as u32).to_le_bytes());
+    out.extend(key.as_bytes());
+    out.push(0);
+    out.extend(value.as_bytes());
+    out.push(0);
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+/// KTX2 file of a 2D texture (`levels` largest first), rows stored north to south.
+fn encode_ktx2(format: TextureFormat, levels: &[Level<[u8; 4]>], zlib: bool) -> Vec<u8> {
+    let encoded: Vec<(Vec<u8>, usize)> = levels
+        .iter()
+        .map(|(w, h, texels)| {
+            let raw = format.encode(*w, *h, texels);
+            let length = raw.len();
+            (if zlib { zlib_compress(&raw) } else { raw }, length)
+        })
+        .collect();
+    let dfd = format.dfd();
+    let mut kvd = Vec::new();
+    key_value(&mut kvd, "KTXorientation", "rd");
+    key_value(&mut kvd, "KTXwriter", "continent-generator");
+
+    let dfd_offset = 80 + 24 * levels.len();
+    let kvd_offset = dfd_offset + dfd.len();
+    let alignment = if zlib {
+        1
+    } else {
+        format.block_bytes().next_multiple_of(4)
+    };
+    // Level data follows the key/value data, smallest level first.
+    let mut offsets = vec![0; levels.len()];
+    let mut end = kvd_offset + kvd.len();
+    for (level, (data, _)) in encoded.iter().enumerate().rev() {
+        end = end.next_multiple_of(alignment);
+        offsets[level] = end;
+        end += data.len();
+    }
+
+    let mut out = Vec::with_capacity(end);
+    out.extend(IDENTIFIER);
+    let (width, height) = (levels[0].0, levels[0].1);
+    for value in [
+        format.vk_format(),
+        1,
+        width as u32,
+        height as u32,
+        0,
+        0,
+        1,
+        levels.len() as u32,
+        if zlib { SUPERCOMPRESSION_ZLIB } else { 0 },
+        dfd_offset as u32,
+        dfd.len() as u32,
+        kvd_offset as u32,
+        kvd.len() as u32,
+    ] {
+        out.extend(value.to_le_bytes());
+    }
+    // No supercompression global data.
+    out.extend([0_u8; 16]);
+    for ((data, length), offset) in encoded.iter().zip(&offsets) {
+        for value in [*offset, data.len(), *length] {
+            out.extend((value as u64).to_le_bytes());
+        }
+    }
+    out.extend(dfd);
+    out.extend(kvd);
+    for (level, (data, _)) in encoded.iter().enumerate().rev() {
+        out.resize(offsets[level], 0);
+        out.extend(data);
+    }
+    out
+}
+
+/// Texels of every exported level, rounded to bytes by `to_byte`.
+fn texel_levels(
+    base: Vec<[f32; 4]>,
+    params: &Ktx2Params,
+    normalise: bool,
+    to_byte: impl Fn(f32) -> u8,
+) -> Vec<Level<[u8; 4]>> {
+    let mut levels = mip_chain(base, WIDTH, HEIGHT, normalise);
+    if !params.mipmaps {
+        levels.truncate(1);
+    }
+    levels
+        .into_iter()
+        .map(|(w, h, texels)| (w, h, texels.iter().map(|t| t.map(&to_byte)).collect()))
+        .collect()
+}
+
+/// A rendered map (RGBA, as from `render_biome_rgba` or `render_hypsometric_rgba`) as a
+/// KTX2 texture that game engines upload as is, mipmapped by default. `format` is
+/// `"rgba8"` (sRGB, lossless), `"bc1"` (sRGB BC1 block compression at 4 bits a texel,
+/// opaque, for desktop GPUs) or `"uastc"` (Basis Universal UASTC at 8 bits a texel,
+/// opaque, which loaders transcode for desktop and mobile GPUs alike; not with `zlib`).
+#[wasm_bindgen]
+pub fn export_color_ktx2(
+    rgba: &[u8],
+    format: &str,
+    params: &Ktx2Params,
+) -> Result<Box<[u8]>, JsValue> {
+    if rgba.len() != CELL_COUNT * 4 {
+        return Err(JsValue::from_str("rgba must be 2048x1024x4 bytes"));
+    }
+    let format = match format {
+        "rgba8" => TextureFormat::Rgba8Srgb,
+        "bc1" => TextureFormat::Bc1Srgb,
+        "uastc" => TextureFormat::UastcRgbSrgb,
+        _ => return Err(JsValue::from_str("format must be rgba8, bc1 or uastc")),
+    };
+    params.validate(format)?;
+    let base = rgba
+        .chunks_exact(4)
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32])
+        .collect();
+    let levels = texel_levels(base, params, false, |v| v.round().clamp(0.0, 255.0) as u8);
+    Ok(encode_ktx2(format, &levels, params.zlib).into_boxed_slice())
+}
+
+/// Tangent-space normal map of the heightmap as a KTX2 texture: x east in red, y north
+/// in green (OpenGL convention), z up in blue, each mapped from [−1, 1] to [0, 255].
+/// `format` is `"rgba8"` (linear), `"bc5"` (red and green only, 8 bits a texel; shaders
+/// rebuild z as √(1 − x² − y²); desktop GPUs only, as for BC1 in `export_color_ktx2`) or
+/// `"uastc"` (UASTC with x in RGB and y in alpha, z rebuilt likewise; transcoders target
+/// BC5 or EAC RG11 from those channels). Lower mip levels average and renormalise the
+/// normals.
+#[wasm_bindgen]
+pub fn export_normal_ktx2(
+    flat: &[f32],
+    format: &str,
+    params: &Ktx2Params,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let format = match format {
+        "rgba8" => TextureFormat::Rgba8Unorm,
+        "bc5" => TextureFormat::Bc5Unorm,
+        "uastc" => TextureFormat::UastcLaUnorm,
+        _ => return Err(JsValue::from_str("format must be rgba8, bc5 or uastc")),
+    };
+    params.validate(format)?;
+    let base = (0..CELL_COUNT)
+        .map(|idx| {
+            let (dx, dy) = gradient(flat, idx);
+            let (nx, ny) = (-dx * params.vertical_scale, dy * params.vertical_scale);
+            let length = (nx * nx + ny * ny + 1.0).sqrt();
+            [nx / length, ny / length, 1.0 / length, 1.0]
+        })
+        .collect();
+    let levels = texel_levels(base, params, true, |v| {
+        ((v * 0.5 + 0.5) * 255.0).round().clamp(0.0, 255.0) as u8
+    });
+    Ok(encode_ktx2(format, &levels, params.zlib).into_boxed_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trit_endpoints_cover_every_byte() {
+        assert_eq!((TRIT_ENDPOINTS[0], TRIT_ENDPOINTS[1]), (0, 255));
+        for v in 0..=255 {
+            let level = TRIT_ENDPOINTS[TRIT_QUANTISE[v as usize] as usize];
+            assert!(level.abs_diff(v) <= 1, "{v} -> {level}");
+        }
+    }
+
+    #[test]
+    fn uastc_blocks_carry_their_mode() {
+        let block: [[u8; 4]; 16] =
+            std::array::from_fn(|i| [16 * i as u8, 40, 255 - 8 * i as u8, 255]);
+        // Mode codes, least significant bit first: 0b0001 for mode 0, 0b0000101 for mode 15.
+        assert_eq!(uastc_rgb_block(&block)[0] & 0xf, 0x1);
+        assert_eq!(uastc_la_block(&block)[0] & 0x7f, 0x5);
+    }
+}
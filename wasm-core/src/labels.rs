@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, SEA_LEVEL, WIDTH, check_grid_len, distance_field};
+use crate::hydrology::{Hydrology, River};
+use crate::json::{self, ObjectWriter};
+use crate::landmass::label_landmasses;
+use crate::vector::{cell_area_km2, grid_to_lon_lat, label_regions};
+
+/// Earth's surface area, km²; features this large would get priority 1.
+const WORLD_AREA_KM2: f64 = 510_072_000.0;
+/// River stretches shorter than this many cells have no room for a label.
+const MIN_RIVER_CELLS: usize = 8;
+/// Cells either side of a river label's anchor used for its angle.
+const RIVER_ANGLE_SPAN: usize = 3;
+
+/// Which features get labels, for `label_anchors_json`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct LabelParams {
+    pub sea_level: f32,
+    /// Smallest landmass labelled, km².
+    pub min_landmass_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes.
+    pub lake_min_depth_metres: f32,
+    /// Smallest lake labelled, km².
+    pub min_lake_km2: f32,
+    /// Drainage area at which a river starts, km², as in the map exports.
+    pub river_min_area_km2: f32,
+    /// Closest two river labels may be, cells.
+    pub river_spacing: f32,
+    /// Heightmap value above which land counts as mountains for range labels (~1500 m,
+    /// as `TerrainClassParams::mountains`).
+    pub range_level: f32,
+    /// Smallest mountain range labelled, km².
+    pub min_range_km2: f32,
+}
+
+impl Default for LabelParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            min_landmass_km2: 10_000.0,
+            lake_min_depth_metres: 20.0,
+            min_lake_km2: 5_000.0,
+            river_min_area_km2: 100_000.0,
+            river_spacing: 32.0,
+            range_level: 0.31,
+            min_range_km2: 20_000.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl LabelParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> LabelParams {
+        Self::default()
+    }
+}
+
+impl LabelParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !self.range_level.is_finite() {
+            return Err(JsValue::from_str("range_level must be finite"));
+        }
+        let limits = [
+            self.min_landmass_km2,
+            self.lake_min_depth_metres,
+            self.min_lake_km2,
+            self.river_min_area_km2,
+            self.river_spacing,
+            self.min_range_km2,
+        ];
+        if limits.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "label areas, depths and spacing must be >= 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct Anchor {
+    pub(crate) kind: &'static str,
+    pub(crate) idx: usize,
+    pub(crate) area_km2: f64,
+    /// Free room around the anchor, cells.
+    clearance: f32,
+    /// Degrees clockwise from east in grid space, within (−90, 90].
+    angle: f32,
+}
+
+impl Anchor {
+    fn priority(&self) -> f64 {
+        ((1.0 + self.area_km2).ln() / (1.0 + WORLD_AREA_KM2).ln()).clamp(0.0, 1.0)
+    }
+
+    fn to_json(&self) -> String {
+        let (x, y) = (
+            (self.idx % WIDTH) as f32 + 0.5,
+            (self.idx / WIDTH) as f32 + 0.5,
+        );
+        let (lon, lat) = grid_to_lon_lat(x, y);
+        ObjectWriter::new()
+            .raw("kind", &json::quote(self.kind))
+            .number("x", x as f64, 1)
+            .number("y", y as f64, 1)
+            .number("lon", lon as f64, 3)
+            .number("lat", lat as f64, 3)
+            .number("priority", self.priority(), 4)
+            .number("area_km2", self.area_km2, 0)
+            .number("clearance", self.clearance as f64, 1)
+            .number("angle", self.angle as f64, 1)
+            .finish()
+    }
+}
+
+/// Angle of direction (`dx`, `dy`) folded into (−90, 90], so text never reads upside down.
+fn reading_angle(dx: f32, dy: f32) -> f32 {
+    let angle = dy.atan2(dx).to_degrees();
+    if angle > 90.0 {
+        angle - 180.0
+    } else if angle <= -90.0 {
+        angle + 180.0
+    } else {
+        angle
+    }
+}
+
+/// East–west step from cell `a` to cell `b`, taken the short way across the seam.
+fn wrapped_dx(a: usize, b: usize) -> f32 {
+    let dx = (b % WIDTH) as f32 - (a % WIDTH) as f32;
+    if dx > WIDTH as f32 / 2.0 {
+        dx - WIDTH as f32
+    } else if dx < -(WIDTH as f32) / 2.0 {
+        dx + WIDTH as f32
+    } else {
+        dx
+    }
+}
+
+/// Pole of inaccessibility of every labelled region: its cell farthest from any cell
+/// outside it, with that distance. Indexed by `label - 1`.
+fn poles(labels: &[u32], count: usize) -> Vec<(usize, f32)> {
+    let outside: Vec<bool> = labels.iter().map(|&l| l == 0).collect();
+    let distance = distance_field(&outside);
+    let mut best = vec![(0, -1.0_f32); count];
+    for (idx, &label) in labels.iter().enumerate() {
+        if label != 0 && distance[idx] > best[label as usize - 1].1 {
+            best[label as usize - 1] = (idx, distance[idx]);
+        }
+    }
+    best
+}
+
+/// Areas of every labelled region, km², indexed by `label - 1`.
+fn region_areas(labels: &[u32], count: usize) -> Vec<f64> {
+    let mut areas = vec![0.0; count];
+    for (idx, &label) in labels.iter().enumerate() {
+        if label != 0 {
+            areas[label as usize - 1] += cell_area_km2(idx / WIDTH);
+        }
+    }
+    areas
+}
+
+/// Whole rivers from the stretches between confluences, each followed downstream for as
+/// long as it is the larger branch at every confluence (the main stem), with the drainage
+/// area where it ends. Courses stop short of the sea or lake (`water`) they drain into; a
+/// tributary's includes the confluence cell on the river it joins.
+fn river_courses(rivers: &[River], water: impl Fn(usize) -> bool) -> Vec<(Vec<usize>, f32)> {
+    let starting_at: HashMap<usize, usize> = rivers
+        .iter()
+        .enumerate()
+        .map(|(i, river)| (river.cells[0], i))
+        .collect();
+    let downstream: Vec<Option<usize>> = rivers
+        .iter()
+        .map(|river| starting_at.get(river.cells.last()?).copied())
+        .collect();
+    // The largest stretch flowing into each confluence carries the river on.
+    let mut main_branch: Vec<Option<usize>> = vec![None; rivers.len()];
+    for (i, down) in downstream.iter().enumerate() {
+        if let Some(d) = *down {
+            let current = main_branch[d];
+            if current.is_none_or(|c| rivers[i].area_km2 > rivers[c].area_km2) {
+                main_branch[d] = Some(i);
+            }
+        }
+    }
+    let mut fed = vec![false; rivers.len()];
+    for d in downstream.iter().flatten() {
+        fed[*d] = true;
+    }
+    (0..rivers.len())
+        .filter(|&head| !fed[head])
+        .map(|head| {
+            let mut course = rivers[head].cells.clone();
+            let mut current = head;
+            while let Some(d) = downstream[current].filter(|&d| main_branch[d] == Some(current)) {
+                course.extend(&rivers[d].cells[1..]);
+                current = d;
+            }
+            if course.last().is_some_and(|&idx| water(idx)) {
+                course.pop();
+            }
+            (course, rivers[current].area_km2)
+        })
+        .collect()
+}
+
+/// Anchor points for map labels, best first: `{"anchors":[{"kind","x","y","lon","lat",
+/// "priority","area_km2","clearance","angle"},...]}`, with `x`, `y` in cell-centre grid
+/// coordinates. Landmasses and lakes (`"landmass"`, `"lake"`) are anchored at their pole of
+/// inaccessibility, the point farthest from their shore, so labels centred there stay off
+/// the coast for `clearance` cells; rivers (`"river"`, followed along their main stem and
+/// kept `river_spacing` apart) at the middle of their course, and mountain ranges
+/// (`"range"`) at the cell nearest their centre, both with `angle` (degrees clockwise from
+/// east, within (−90, 90]) to run the text along them. `priority` in [0, 1] grows with the
+/// feature's area (drainage area for rivers) on a log scale, for placing labels greedily or
+/// by zoom.
+#[wasm_bindgen]
+pub fn label_anchors_json(flat: &[f32], params: &LabelParams) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let anchors = label_anchors(flat, params);
+    Ok(ObjectWriter::new()
+        .raw("anchors", &json::array(anchors.iter().map(Anchor::to_json)))
+        .finish())
+}
+
+/// The anchors behind `label_anchors_json`, best first, for validated inputs.
+pub(crate) fn label_anchors(flat: &[f32], params: &LabelParams) -> Vec<Anchor> {
+    let mut anchors = Vec::new();
+
+    let (land_labels, islands) = label_landmasses(flat, params.sea_level);
+    for ((idx, clearance), island) in poles(&land_labels, islands.len()).into_iter().zip(&islands) {
+        if island.area_km2 >= params.min_landmass_km2 as f64 {
+            anchors.push(Anchor {
+                kind: "landmass",
+                idx,
+                area_km2: island.area_km2,
+                clearance,
+                angle: 0.0,
+            });
+        }
+    }
+
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+    let (lake_labels, lake_count) = label_regions(&hydrology.lakes, |lake| lake);
+    let lake_areas = region_areas(&lake_labels, lake_count as usize);
+    for ((idx, clearance), area_km2) in poles(&lake_labels, lake_count as usize)
+        .into_iter()
+        .zip(lake_areas)
+    {
+        if area_km2 >= params.min_lake_km2 as f64 {
+            anchors.push(Anchor {
+                kind: "lake",
+                idx,
+                area_km2,
+                clearance,
+                angle: 0.0,
+            });
+        }
+    }
+
+    let water: Vec<bool> = (0..CELL_COUNT)
+        .map(|i| flat[i] < params.sea_level || hydrology.lakes[i])
+        .collect();
+    let to_water = distance_field(&water);
+    let rivers = hydrology.rivers(params.river_min_area_km2);
+    let mut courses = river_courses(&rivers, |idx| water[idx]);
+    courses.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut placed: Vec<usize> = Vec::new();
+    for (course, area_km2) in courses {
+        if course.len() < MIN_RIVER_CELLS {
+            continue;
+        }
+        let middle = course.len() / 2;
+        // Larger rivers go first; smaller ones too close to them are left unlabelled.
+        let crowded = placed.iter().any(|&other| {
+            let dy = (other / WIDTH) as f32 - (course[middle] / WIDTH) as f32;
+            wrapped_dx(course[middle], other).hypot(dy) < params.river_spacing
+        });
+        if crowded {
+            continue;
+        }
+        placed.push(course[middle]);
+        let (from, to) = (
+            course[middle - RIVER_ANGLE_SPAN],
+            course[(middle + RIVER_ANGLE_SPAN).min(course.len() - 1)],
+        );
+        let dy = (to / WIDTH) as f32 - (from / WIDTH) as f32;
+        anchors.push(Anchor {
+            kind: "river",
+            idx: course[middle],
+            area_km2: area_km2 as f64,
+            clearance: to_water[course[middle]],
+            angle: reading_angle(wrapped_dx(from, to), dy),
+        });
+    }
+
+    let mountains: Vec<bool> = flat.iter().map(|&h| h >= params.range_level).collect();
+    let (range_labels, range_count) = label_regions(&mountains, |m| m);
+    let mut members = vec![Vec::new(); range_count as usize];
+    for (idx, &label) in range_labels.iter().enumerate() {
+        if label != 0 {
+            members[label as usize - 1].push(idx);
+        }
+    }
+    for cells in members {
+        let area_km2: f64 = cells.iter().map(|&i| cell_area_km2(i / WIDTH)).sum();
+        if area_km2 < params.min_range_km2 as f64 {
+            continue;
+        }
+        // Principal axis of the cells, measured from the first so seam-crossing ranges
+        // stay in one piece.
+        let origin = cells[0];
+        let offsets: Vec<(f32, f32)> = cells
+            .iter()
+            .map(|&i| {
+                (
+                    wrapped_dx(origin, i),
+                    (i / WIDTH) as f32 - (origin / WIDTH) as f32,
+                )
+            })
+            .collect();
+        let n = offsets.len() as f32;
+        let (mx, my) = offsets
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x / n, sy + y / n));
+        let (mut cxx, mut cyy, mut cxy) = (0.0, 0.0, 0.0);
+        for &(x, y) in &offsets {
+            cxx += (x - mx) * (x - mx);
+            cyy += (y - my) * (y - my);
+            cxy += (x - mx) * (y - my);
+        }
+        let axis = 0.5 * (2.0 * cxy).atan2(cxx - cyy);
+        let nearest = (0..cells.len())
+            .min_by(|&a, &b| {
+                let d = |i: usize| (offsets[i].0 - mx).powi(2) + (offsets[i].1 - my).powi(2);
+                d(a).total_cmp(&d(b))
+            })
+            .unwrap_or(0);
+        anchors.push(Anchor {
+            kind: "range",
+            idx: cells[nearest],
+            area_km2,
+            clearance: to_water[cells[nearest]],
+            angle: reading_angle(axis.cos(), axis.sin()),
+        });
+    }
+
+    anchors.sort_by(|a, b| b.priority().total_cmp(&a.priority()));
+    anchors
+}
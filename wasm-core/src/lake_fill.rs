@@ -0,0 +1,162 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::flow::{Flooded, cell_order, is_below, ring};
+use crate::grid::{CELL_COUNT, WIDTH};
+use crate::vector::cell_area_km2;
+
+/// A lake filled by `TerrainEditor::fill_lake_at`.
+#[wasm_bindgen]
+pub struct LakeFill {
+    level: f32,
+    spilled: bool,
+    cell_count: u32,
+    area_km2: f64,
+    volume_km3: f64,
+    rect: [u32; 4],
+}
+
+#[wasm_bindgen]
+impl LakeFill {
+    /// Water surface, heightmap units.
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Whether the level was capped at the spill point, where the lake would overflow.
+    #[wasm_bindgen(getter)]
+    pub fn spilled(&self) -> bool {
+        self.spilled
+    }
+
+    /// Cells under water; 0 where the point drains to the sea.
+    #[wasm_bindgen(getter)]
+    pub fn cell_count(&self) -> u32 {
+        self.cell_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn area_km2(&self) -> f64 {
+        self.area_km2
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn volume_km3(&self) -> f64 {
+        self.volume_km3
+    }
+
+    /// `[x, y, width, height]` bounding the lake; a full-width band if it crosses the
+    /// east–west seam, and empty for an empty lake.
+    pub fn rect(&self) -> Box<[u32]> {
+        if self.cell_count == 0 {
+            Box::new([])
+        } else {
+            Box::new(self.rect)
+        }
+    }
+}
+
+/// Floods the depression holding `seed` up to `target`, or to its spill point if that is
+/// lower or `target` is `None`. Water runs down from the seed to the pit it drains to, then
+/// rises in order of height from there (a priority flood); it spills as soon as the next
+/// cell reached is lower than the highest so far, since the water would run out over it.
+/// A seed draining to the sea gives an empty lake. Water only spreads through `allowed`
+/// cells. Returns the lake's cells and the level.
+pub(crate) fn fill_basin(
+    flat: &[f32],
+    seed: usize,
+    target: Option<f32>,
+    sea_level: f32,
+    allowed: impl Fn(usize) -> bool,
+) -> (Vec<usize>, LakeFill) {
+    let mut seed = seed;
+    while let Some(lower) = ring(seed)
+        .into_iter()
+        .flatten()
+        .filter(|&n| is_below(flat, n, seed))
+        .min_by(|&a, &b| cell_order(flat, a, b))
+    {
+        seed = lower;
+    }
+    let mut reached = vec![false; CELL_COUNT];
+    let mut queue = BinaryHeap::new();
+    reached[seed] = true;
+    queue.push(Reverse(Flooded(flat[seed], seed)));
+    let mut highest = flat[seed];
+    let (mut level, mut spilled) = (highest, true);
+    while let Some(Reverse(Flooded(h, idx))) = queue.pop() {
+        if let Some(target) = target.filter(|&t| h >= t) {
+            (level, spilled) = (target, false);
+            break;
+        }
+        if h < highest {
+            level = highest;
+            break;
+        }
+        highest = h;
+        level = h;
+        for n in ring(idx).into_iter().flatten() {
+            if !reached[n] {
+                reached[n] = true;
+                queue.push(Reverse(Flooded(flat[n], n)));
+            }
+        }
+    }
+
+    // The lake is everything below the level still connected to the seed.
+    let mut cells = Vec::new();
+    reached.fill(false);
+    if flat[seed] < sea_level {
+        (level, spilled) = (sea_level, true);
+    } else if flat[seed] < level && allowed(seed) {
+        reached[seed] = true;
+        cells.push(seed);
+    }
+    let mut next = 0;
+    while next < cells.len() {
+        for n in ring(cells[next]).into_iter().flatten() {
+            if !reached[n] && flat[n] < level && allowed(n) {
+                reached[n] = true;
+                cells.push(n);
+            }
+        }
+        next += 1;
+    }
+
+    let metres_per_unit = (RELIEF_METRES / (1.0 - sea_level)) as f64;
+    let (mut area_km2, mut volume_km3) = (0.0, 0.0);
+    let (mut top, mut bottom) = (usize::MAX, 0);
+    let mut columns = vec![false; WIDTH];
+    for &idx in &cells {
+        let (x, y) = (idx % WIDTH, idx / WIDTH);
+        let area = cell_area_km2(y);
+        area_km2 += area;
+        volume_km3 += area * (level - flat[idx]) as f64 * metres_per_unit / 1000.0;
+        columns[x] = true;
+        (top, bottom) = (top.min(y), bottom.max(y + 1));
+    }
+    let rect = match (
+        columns.iter().position(|&c| c),
+        columns.iter().rposition(|&c| c),
+    ) {
+        (Some(first), Some(last)) if first > 0 || last < WIDTH - 1 => {
+            [first, top, last + 1 - first, bottom - top]
+        }
+        // Touching both edges: across the seam, or all the way round.
+        (Some(_), _) => [0, top, WIDTH, bottom - top],
+        _ => [0; 4],
+    };
+    let fill = LakeFill {
+        level,
+        spilled,
+        cell_count: cells.len() as u32,
+        area_km2,
+        volume_km3,
+        rect: rect.map(|v| v as u32),
+    };
+    (cells, fill)
+}
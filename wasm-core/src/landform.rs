@@ -0,0 +1,95 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, SEA_LEVEL, box_blur, check_grid_len};
+use crate::terrain::slope_degrees;
+
+pub(crate) const LANDFORM_WATER: u8 = 0;
+pub(crate) const LANDFORM_VALLEY: u8 = 1;
+pub(crate) const LANDFORM_FLAT: u8 = 2;
+pub(crate) const LANDFORM_SLOPE: u8 = 3;
+pub(crate) const LANDFORM_RIDGE: u8 = 4;
+pub(crate) const LANDFORM_PEAK: u8 = 5;
+
+/// (name, legend colour) indexed by landform id.
+pub(crate) const LANDFORMS: [(&str, &str); 6] = [
+    ("Water", "#2e5a88"),
+    ("Valley", "#3f7fbf"),
+    ("Flat", "#d9d9a3"),
+    ("Slope", "#a3a36b"),
+    ("Ridge", "#a0522d"),
+    ("Peak", "#5a2d0c"),
+];
+
+/// Neighbourhood radii (cells) for fine and broad topographic position.
+const TPI_SMALL_RADIUS: usize = 3;
+const TPI_LARGE_RADIUS: usize = 24;
+/// Standardised TPI beyond which a cell stands clearly above or below its surroundings.
+const TPI_THRESHOLD: f32 = 1.0;
+/// Ground gentler than this is flat rather than slope. Cells are ~20 km across, so even
+/// mountain flanks average well under a degree at this resolution.
+const FLAT_SLOPE_DEG: f32 = 0.05;
+
+/// Topographic position index at `radius`: elevation minus the neighbourhood mean,
+/// standardised by its spread over land.
+fn standardised_tpi(flat: &[f32], radius: usize) -> Vec<f32> {
+    let mean = box_blur(flat, radius);
+    let tpi: Vec<f32> = flat.iter().zip(&mean).map(|(h, m)| h - m).collect();
+    let land: Vec<f32> = (0..CELL_COUNT)
+        .filter(|&i| flat[i] >= SEA_LEVEL)
+        .map(|i| tpi[i])
+        .collect();
+    let n = land.len().max(1) as f32;
+    let avg = land.iter().sum::<f32>() / n;
+    let sd = (land.iter().map(|t| (t - avg).powi(2)).sum::<f32>() / n).sqrt();
+    if sd <= 0.0 {
+        return vec![0.0; CELL_COUNT];
+    }
+    tpi.into_iter().map(|t| (t - avg) / sd).collect()
+}
+
+/// Weiss-style landform from fine and broad standardised TPI and local slope.
+fn classify(small: f32, large: f32, slope_deg: f32) -> u8 {
+    if small > TPI_THRESHOLD && large > TPI_THRESHOLD {
+        LANDFORM_PEAK
+    } else if small > TPI_THRESHOLD || large > TPI_THRESHOLD {
+        LANDFORM_RIDGE
+    } else if small < -TPI_THRESHOLD || large < -TPI_THRESHOLD {
+        LANDFORM_VALLEY
+    } else if slope_deg < FLAT_SLOPE_DEG {
+        LANDFORM_FLAT
+    } else {
+        LANDFORM_SLOPE
+    }
+}
+
+/// Landform class per cell from Topographic Position Index at two scales: 0 = water,
+/// 1 = valley, 2 = flat, 3 = slope, 4 = ridge, 5 = peak. See `landform_legend_json`.
+#[wasm_bindgen]
+pub fn landform_classes(flat: &[f32]) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let small = standardised_tpi(flat, TPI_SMALL_RADIUS);
+    let large = standardised_tpi(flat, TPI_LARGE_RADIUS);
+    let classes: Vec<u8> = (0..CELL_COUNT)
+        .map(|idx| {
+            if flat[idx] < SEA_LEVEL {
+                LANDFORM_WATER
+            } else {
+                classify(small[idx], large[idx], slope_degrees(flat, idx))
+            }
+        })
+        .collect();
+    Ok(classes.into_boxed_slice())
+}
+
+/// Legend for `landform_classes`: `[{"id","name","color"}, ...]`.
+#[wasm_bindgen]
+pub fn landform_legend_json() -> String {
+    let entries: Vec<String> = LANDFORMS
+        .iter()
+        .enumerate()
+        .map(|(id, (name, color))| {
+            format!("{{\"id\":{id},\"name\":\"{name}\",\"color\":\"{color}\"}}")
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
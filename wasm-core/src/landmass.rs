@@ -0,0 +1,243 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, check_grid_len};
+use crate::vector::cell_area_km2;
+
+pub(crate) struct Island {
+    pub(crate) cells: u32,
+    pub(crate) area_km2: f64,
+    /// Inclusive cell bounds; `west > east` when the island crosses the east–west seam.
+    pub(crate) west: u32,
+    pub(crate) north: u32,
+    pub(crate) east: u32,
+    pub(crate) south: u32,
+}
+
+/// Westernmost and easternmost occupied columns, taking the widest empty run of columns as
+/// the outside so seam-crossing islands get `west > east`.
+fn wrapped_extent(columns: &[bool]) -> (u32, u32) {
+    let (mut best_start, mut best_len) = (0, 0);
+    let mut run = 0;
+    // Walk twice around so a gap spanning the seam is measured whole.
+    for i in 0..2 * WIDTH {
+        if columns[i % WIDTH] {
+            run = 0;
+        } else {
+            run += 1;
+            if run > best_len && run <= WIDTH {
+                best_len = run;
+                best_start = i + 1 - run;
+            }
+        }
+    }
+    if best_len == 0 {
+        return (0, WIDTH as u32 - 1);
+    }
+    let west = (best_start + best_len) % WIDTH;
+    let east = (best_start + WIDTH - 1) % WIDTH;
+    (west as u32, east as u32)
+}
+
+/// Labels 4-connected land (elevation ≥ `sea_level`), joining across the east–west seam.
+/// Labels start at 1 in order of decreasing area; 0 is water.
+pub(crate) fn label_landmasses(flat: &[f32], sea_level: f32) -> (Vec<u32>, Vec<Island>) {
+    let land: Vec<bool> = flat.iter().map(|&h| h >= sea_level).collect();
+    label_land(&land)
+}
+
+/// `label_landmasses` over an explicit land mask.
+pub(crate) fn label_land(land: &[bool]) -> (Vec<u32>, Vec<Island>) {
+    let mut labels = vec![0_u32; CELL_COUNT];
+    let mut islands = Vec::new();
+    let mut members = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..CELL_COUNT {
+        if labels[start] != 0 || !land[start] {
+            continue;
+        }
+        let label = islands.len() as u32 + 1;
+        labels[start] = label;
+        stack.push(start);
+        members.clear();
+        while let Some(idx) = stack.pop() {
+            members.push(idx);
+            let (x, y) = (idx % WIDTH, idx / WIDTH);
+            let row = y * WIDTH;
+            let west = Some(row + (x + WIDTH - 1) % WIDTH);
+            let east = Some(row + (x + 1) % WIDTH);
+            let north = (y > 0).then(|| idx - WIDTH);
+            let south = (y + 1 < HEIGHT).then(|| idx + WIDTH);
+            for n in [west, east, north, south].into_iter().flatten() {
+                if labels[n] == 0 && land[n] {
+                    labels[n] = label;
+                    stack.push(n);
+                }
+            }
+        }
+        let mut columns = vec![false; WIDTH];
+        let (mut north, mut south, mut area) = (u32::MAX, 0, 0.0);
+        for &idx in &members {
+            let y = (idx / WIDTH) as u32;
+            columns[idx % WIDTH] = true;
+            north = north.min(y);
+            south = south.max(y);
+            area += cell_area_km2(idx / WIDTH);
+        }
+        let (west, east) = wrapped_extent(&columns);
+        islands.push(Island {
+            cells: members.len() as u32,
+            area_km2: area,
+            west,
+            north,
+            east,
+            south,
+        });
+    }
+
+    let mut order: Vec<usize> = (0..islands.len()).collect();
+    order.sort_by(|&a, &b| islands[b].area_km2.total_cmp(&islands[a].area_km2));
+    let mut relabel = vec![0_u32; islands.len() + 1];
+    for (rank, &old) in order.iter().enumerate() {
+        relabel[old + 1] = rank as u32 + 1;
+    }
+    for label in labels.iter_mut() {
+        *label = relabel[*label as usize];
+    }
+    let mut slots: Vec<Option<Island>> = islands.into_iter().map(Some).collect();
+    let islands = order.iter().filter_map(|&old| slots[old].take()).collect();
+    (labels, islands)
+}
+
+/// Landmass areas without a full label grid: union–find over provisional labels, fed one
+/// land row at a time from north to south. Holds only the previous row's labels.
+pub(crate) struct LandmassStream {
+    parent: Vec<u32>,
+    area_km2: Vec<f64>,
+    above: Vec<u32>,
+    next_row: usize,
+}
+
+impl LandmassStream {
+    pub(crate) fn new() -> Self {
+        Self {
+            parent: Vec::new(),
+            area_km2: Vec::new(),
+            above: vec![u32::MAX; WIDTH],
+            next_row: 0,
+        }
+    }
+
+    fn find(&mut self, mut label: u32) -> u32 {
+        while self.parent[label as usize] != label {
+            let grandparent = self.parent[self.parent[label as usize] as usize];
+            self.parent[label as usize] = grandparent;
+            label = grandparent;
+        }
+        label
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b as usize] = a;
+            self.area_km2[a as usize] += self.area_km2[b as usize];
+        }
+    }
+
+    pub(crate) fn push_row(&mut self, land: &[bool]) {
+        let cell_area = cell_area_km2(self.next_row);
+        self.next_row += 1;
+        let mut labels = vec![u32::MAX; WIDTH];
+        for x in 0..WIDTH {
+            if !land[x] {
+                continue;
+            }
+            let label = if x > 0 && land[x - 1] {
+                labels[x - 1]
+            } else {
+                self.parent.push(self.parent.len() as u32);
+                self.area_km2.push(0.0);
+                self.parent.len() as u32 - 1
+            };
+            labels[x] = label;
+            let root = self.find(label);
+            self.area_km2[root as usize] += cell_area;
+            if self.above[x] != u32::MAX {
+                self.union(label, self.above[x]);
+            }
+        }
+        if land[0] && land[WIDTH - 1] {
+            self.union(labels[0], labels[WIDTH - 1]);
+        }
+        self.above = labels;
+    }
+
+    /// Landmass areas in km², largest first.
+    pub(crate) fn finish(mut self) -> Vec<f64> {
+        let mut areas: Vec<f64> = (0..self.parent.len() as u32)
+            .filter(|&label| self.find(label) == label)
+            .collect::<Vec<u32>>()
+            .into_iter()
+            .map(|label| self.area_km2[label as usize])
+            .collect();
+        areas.sort_by(|a, b| b.total_cmp(a));
+        areas
+    }
+}
+
+/// Connected landmasses from `label_landmasses`.
+#[wasm_bindgen]
+pub struct Landmasses {
+    labels: Vec<u16>,
+    islands: Vec<Island>,
+}
+
+#[wasm_bindgen]
+impl Landmasses {
+    /// Landmass id per cell (0 = water, 1 = largest landmass, ...).
+    pub fn labels(&self) -> Box<[u16]> {
+        self.labels.clone().into_boxed_slice()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.islands.len() as u32
+    }
+
+    /// Area of each landmass in km², indexed by `id - 1`.
+    pub fn areas_km2(&self) -> Box<[f64]> {
+        self.islands.iter().map(|i| i.area_km2).collect()
+    }
+
+    /// Cell count of each landmass, indexed by `id - 1`.
+    pub fn cell_counts(&self) -> Box<[u32]> {
+        self.islands.iter().map(|i| i.cells).collect()
+    }
+
+    /// Inclusive cell bounds `[west, north, east, south]` per landmass, flattened. A
+    /// landmass crossing the east–west seam has `west > east`.
+    pub fn bounding_boxes(&self) -> Box<[u32]> {
+        self.islands
+            .iter()
+            .flat_map(|i| [i.west, i.north, i.east, i.south])
+            .collect()
+    }
+}
+
+/// Connected-component labelling of land (elevation ≥ `sea_level`, 4-connected, wrapping
+/// east–west). Ids are assigned by decreasing area.
+#[wasm_bindgen]
+pub fn landmasses(flat: &[f32], sea_level: f32) -> Result<Landmasses, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(0.0..=1.0).contains(&sea_level) {
+        return Err(JsValue::from_str("sea_level must be within [0.0, 1.0]"));
+    }
+    let (labels, islands) = label_landmasses(flat, sea_level);
+    if islands.len() > u16::MAX as usize {
+        return Err(JsValue::from_str("more than 65535 landmasses"));
+    }
+    Ok(Landmasses {
+        labels: labels.into_iter().map(|l| l as u16).collect(),
+        islands,
+    })
+}
@@ -1,5 +1,183 @@
 use wasm_bindgen::prelude::*;
 
+mod analytics;
+mod analytics_series;
+mod analytics_stream;
+mod autotile;
+mod biome;
+mod biome_rules;
+mod cliffs;
+mod climate;
+mod climate_analytics;
+mod clouds;
+mod coastline;
+mod compare;
+mod contours;
+mod currents;
+mod deflate;
+mod drift;
+mod dryland;
+mod ecotone;
+mod editor;
+mod exr;
+mod fingerprint;
+mod flow;
+mod geojson;
+mod gltf;
+mod golden;
+mod grid;
+mod growing_season;
+mod hillshade;
+mod history;
+mod hydrology;
+mod import;
+mod json;
+mod koppen;
+mod ktx2;
+mod labels;
+mod lake_fill;
+mod landform;
+mod landmass;
+mod mesh;
+mod mip;
+mod monsoon;
+mod morph;
+mod morphology;
+mod names;
+mod nations;
+mod navmesh;
+mod noise;
+mod obj;
+mod occlusion;
+mod permafrost;
+mod ply;
+mod png;
+mod project;
+mod projection;
+mod prominence;
+mod provinces;
+mod raw;
+mod render;
+mod renoise;
+mod resample;
+mod river_draw;
+mod roads;
+mod sea_level;
+mod selection;
+mod settlement;
+mod settlement_hierarchy;
+mod snapshots;
+mod splatmap;
+mod srtm;
+mod stamps;
+mod stats;
+mod stl;
+mod storms;
+mod stream;
+mod svg;
+mod ter;
+mod terrain;
+mod terrain_classes;
+mod thumbnail;
+mod tilemap;
+mod tiles;
+mod timeline;
+mod tin;
+mod trade;
+mod vector;
+mod vegetation;
+mod viewshed;
+mod wind;
+mod zones;
+
+pub use analytics::{
+    Analytics, AnalyticsOptions, elevation_stats_json, hypsometric_curve_json,
+    land_by_latitude_json, masked_analytics, region_analytics, source_of_truth,
+    source_of_truth_array, source_of_truth_json, source_of_truth_with_options,
+};
+pub use analytics_series::AnalyticsSeries;
+pub use analytics_stream::AnalyticsAccumulator;
+pub use autotile::{AutotileParams, autotile_map};
+pub use biome::{apply_biome_overrides, biome_legend_json, whittaker_biomes};
+pub use biome_rules::{BiomeRuleTable, classify_biomes_with_rules};
+pub use cliffs::{Cliffs, detect_cliffs};
+pub use climate::{Climate, ClimateParams, simulate_climate};
+pub use climate_analytics::climate_analytics_json;
+pub use clouds::{CloudParams, cloud_layer};
+pub use compare::{HeightmapComparison, compare_heightmaps};
+pub use contours::{ContourParams, Contours, trace_contours};
+pub use drift::{DriftParams, DriftTimeLapse, drift_time_lapse};
+pub use dryland::{aridity_index_layer, dryland_mask};
+pub use ecotone::{BiomeBlend, biome_ecotones};
+pub use editor::{BrushParams, TerrainEditor};
+pub use exr::{export_climate_exr, export_heightmap_exr};
+pub use fingerprint::heightmap_fingerprint;
+pub use geojson::{GeoJsonParams, VectorFeatures, export_vector_features};
+pub use gltf::export_terrain_glb;
+pub use golden::{record_golden_baseline, verify_golden_baseline};
+pub use growing_season::growing_season_months;
+pub use hillshade::{HillshadeParams, hillshade};
+pub use import::import_heightmap;
+pub use koppen::{koppen_classes, koppen_legend_json};
+pub use ktx2::{Ktx2Params, export_color_ktx2, export_normal_ktx2};
+pub use labels::{LabelParams, label_anchors_json};
+pub use lake_fill::LakeFill;
+pub use landform::{landform_classes, landform_legend_json};
+pub use landmass::{Landmasses, landmasses};
+pub use mesh::MeshParams;
+pub use mip::{MipChain, build_mip_chain};
+pub use morph::{HeightmapMorph, morph_heightmaps, seed_morph_plan_json};
+pub use morphology::{TerrainFeatureParams, TerrainFeatures, detect_terrain_features};
+pub use names::{NameParams, fantasy_name, name_features_json};
+pub use nations::{NationParams, Nations, grow_nations};
+pub use navmesh::{Navmesh, NavmeshParams, build_navmesh};
+pub use obj::{TerrainObj, export_terrain_obj};
+pub use occlusion::{SkyViewParams, blend_ambient_occlusion, sky_view_factor};
+pub use permafrost::{permafrost_zones, treeline_boundary};
+pub use ply::export_terrain_ply;
+pub use png::export_heightmap_png;
+pub use project::import_project;
+pub use projection::{
+    GlobeParams, render_globe_rgba, reproject_rgba_web_mercator, reproject_web_mercator,
+};
+pub use provinces::{ProvinceParams, Provinces, partition_provinces};
+pub use raw::{PackedLayer, export_heightmap_raw, pack_layer, unpack_layer};
+pub use render::{hypsometric_ramp_json, render_biome_rgba, render_hypsometric_rgba};
+pub use renoise::RenoiseParams;
+pub use resample::resample;
+pub use river_draw::{DrawnRiver, RiverParams};
+pub use roads::{RoadNetwork, RoadParams, build_roads};
+pub use sea_level::{SeaLevelUpdate, apply_sea_level};
+pub use selection::{
+    Selection, select_all, select_biome, select_elevation_range, select_landmass, select_lasso,
+    select_rectangle,
+};
+pub use settlement::{SettlementParams, SettlementSites, score_settlement_sites};
+pub use settlement_hierarchy::{SettlementHierarchy, SettlementHierarchyParams, place_settlements};
+pub use splatmap::{SplatParams, splatmap_rgba};
+pub use srtm::{SrtmMosaic, SrtmParams, import_hgt};
+pub use stamps::StampParams;
+pub use stl::{StlParams, export_terrain_stl};
+pub use storms::{storm_risk, storm_track_polygons_json};
+pub use stream::{HeightmapStream, StreamParams, stream_heightmap_raw};
+pub use svg::{SvgParams, export_map_svg, svg_style_json};
+pub use ter::{TerParams, export_heightmap_ter};
+pub use terrain::{
+    Curvature, SlopeAspect, compute_curvature, compute_slope_aspect, terrain_ruggedness,
+};
+pub use terrain_classes::{TerrainClassParams, terrain_class_legend_json, terrain_class_map};
+pub use thumbnail::render_thumbnail_rgba;
+pub use tilemap::{
+    HexParams, HexTiles, SquareTileParams, SquareTiles, export_hex_tiles, export_square_tiles,
+};
+pub use tiles::{TileParams, TileSet, export_heightmap_tiles, export_map_tiles};
+pub use timeline::{ParamTimeline, param_timeline_from_json};
+pub use trade::{TradeNetwork, TradeParams, build_trade_routes};
+pub use vegetation::vegetation_density;
+pub use viewshed::viewshed;
+pub use wind::wind_grid_json;
+pub use zones::climate_zone_polygons_json;
+
 const GRID_WIDTH: u32 = 2048;
 const GRID_HEIGHT: u32 = 1024;
 const GRID_CELL_COUNT: u32 = GRID_WIDTH * GRID_HEIGHT;
@@ -81,54 +259,3 @@ pub fn n_pass_dispatch(flat_cell_count: u32, n: u32) -> Result<Box<[u32]>, JsVal
     let (_, dispatch_x) = compute_dispatch(flat_cell_count, 1.0)?;
     Ok(vec![dispatch_x; n as usize].into_boxed_slice())
 }
-
-#[wasm_bindgen]
-pub fn source_of_truth_json(flat: &[f32], latency_ms: f64) -> Result<String, JsValue> {
-    if flat.len() != GRID_CELL_COUNT as usize {
-        return Err(JsValue::from_str("flat heightmap length mismatch"));
-    }
-
-    let width = GRID_WIDTH as usize;
-    let height = GRID_HEIGHT as usize;
-    let mut turn_count: u64 = 0;
-    let mut straight_count: u64 = 0;
-    let mut drainage_cells: u64 = 0;
-
-    for y in 0..height {
-        let row_start = y * width;
-        let mut previous_delta = 0.0_f32;
-
-        for x in 0..width {
-            let idx = row_start + x;
-            let value = flat[idx].clamp(0.0, 1.0);
-
-            if value < 0.42 {
-                drainage_cells += 1;
-            }
-
-            if x > 0 {
-                let delta = value - flat[idx - 1].clamp(0.0, 1.0);
-                if x > 1 {
-                    if (delta - previous_delta).abs() > 0.0035 {
-                        turn_count += 1;
-                    } else {
-                        straight_count += 1;
-                    }
-                }
-                previous_delta = delta;
-            }
-        }
-    }
-
-    let straight_to_turn_ratio = straight_count as f64 / (turn_count.max(1) as f64);
-    let sinuosity_index = 1.0 + ((turn_count as f64) / (straight_count.max(1) as f64)) * 0.1;
-    let hydro_drainage_pct = (drainage_cells as f64 / GRID_CELL_COUNT as f64) * 100.0;
-
-    Ok(format!(
-        "{{\"sinuosity_index\":{sinuosity:.6},\"straight_to_turn_ratio\":{ratio:.6},\"hydro_drainage_pct\":{drainage:.6},\"latency_ms\":{latency:.6}}}",
-        sinuosity = sinuosity_index,
-        ratio = straight_to_turn_ratio,
-        drainage = hydro_drainage_pct,
-        latency = latency_ms
-    ))
-}
@@ -0,0 +1,179 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH, box_blur, clamp_y, sample_bilinear, wrap_x};
+use crate::render::RELIEF_SCALE;
+use crate::tin::build_tin;
+
+/// Shape of the terrain meshes built by the mesh exporters.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct MeshParams {
+    /// Upper bound on vertices; the grid spacing is chosen to stay within it (at least 4).
+    /// An adaptive mesh stops refining when it reaches it, at about twice as many
+    /// triangles.
+    pub max_vertices: u32,
+    /// Mesh units per heightmap unit, with one unit per grid cell horizontally. The default
+    /// matches the relief of the shaded preview.
+    pub vertical_scale: f32,
+    /// Heightmap value placed at y = 0.
+    pub sea_level: f32,
+    /// Above 0, the glTF and OBJ exports build an adaptive triangulated irregular network
+    /// instead of the regular grid, refined until no cell corner is farther than this from
+    /// the surface (or `max_vertices` is reached). Far fewer triangles for the same
+    /// accuracy; STL and PLY exports always use the grid.
+    pub max_error_metres: f32,
+}
+
+impl Default for MeshParams {
+    fn default() -> Self {
+        Self {
+            max_vertices: 512 * 256,
+            vertical_scale: RELIEF_SCALE,
+            sea_level: SEA_LEVEL,
+            max_error_metres: 0.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MeshParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MeshParams {
+        Self::default()
+    }
+}
+
+impl MeshParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if self.max_vertices < 4 {
+            return Err(JsValue::from_str("max_vertices must be >= 4"));
+        }
+        if !self.vertical_scale.is_finite() || !self.sea_level.is_finite() {
+            return Err(JsValue::from_str(
+                "vertical_scale and sea_level must be finite",
+            ));
+        }
+        if !(self.max_error_metres.is_finite() && self.max_error_metres >= 0.0) {
+            return Err(JsValue::from_str("max_error_metres must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Regular-grid terrain mesh: `cols` × `rows` vertices spanning the whole map, row-major
+/// from the north-west corner. Y is up, x runs east and z south, centred on the origin; the
+/// east column repeats the west one's heights so the edges meet when wrapped.
+pub(crate) struct GridMesh {
+    pub(crate) cols: usize,
+    pub(crate) rows: usize,
+    pub(crate) positions: Vec<[f32; 3]>,
+    pub(crate) normals: Vec<[f32; 3]>,
+    /// Texture coordinates with (0, 0) at the north-west corner of an exported map image.
+    pub(crate) uvs: Vec<[f32; 2]>,
+    /// Grid cell under each vertex, for per-vertex attributes such as colour.
+    pub(crate) cells: Vec<usize>,
+}
+
+impl GridMesh {
+    pub(crate) fn build(flat: &[f32], params: &MeshParams) -> GridMesh {
+        let budget = params.max_vertices as f64;
+        let cols = ((budget * WIDTH as f64 / HEIGHT as f64).sqrt() as usize).clamp(2, WIDTH + 1);
+        let rows = (params.max_vertices as usize / cols).clamp(2, HEIGHT + 1);
+        let (dx, dz) = (
+            WIDTH as f32 / (cols - 1) as f32,
+            HEIGHT as f32 / (rows - 1) as f32,
+        );
+        // Average over each vertex's footprint before sampling so coarse meshes don't alias.
+        let radius = (dx.max(dz) / 2.0) as usize;
+        let blurred;
+        let field = if radius > 0 {
+            blurred = box_blur(flat, radius);
+            &blurred
+        } else {
+            flat
+        };
+
+        let count = cols * rows;
+        let mut positions = Vec::with_capacity(count);
+        let mut uvs = Vec::with_capacity(count);
+        let mut cells = Vec::with_capacity(count);
+        for j in 0..rows {
+            for i in 0..cols {
+                let (x, y) = (i as f32 * dx, j as f32 * dz);
+                let h = sample_bilinear(field, x - 0.5, y - 0.5);
+                positions.push([
+                    x - WIDTH as f32 / 2.0,
+                    (h - params.sea_level) * params.vertical_scale,
+                    y - HEIGHT as f32 / 2.0,
+                ]);
+                uvs.push([x / WIDTH as f32, y / HEIGHT as f32]);
+                cells.push(clamp_y(y as i64) * WIDTH + wrap_x(x as i64));
+            }
+        }
+
+        let height = |i: usize, j: usize| positions[j * cols + i][1];
+        let mut normals = Vec::with_capacity(count);
+        for j in 0..rows {
+            let (up, down) = (j.saturating_sub(1), (j + 1).min(rows - 1));
+            for i in 0..cols {
+                // The first and last columns coincide, so neighbours skip across the seam.
+                let left = if i == 0 { cols - 2 } else { i - 1 };
+                let right = if i == cols - 1 { 1 } else { i + 1 };
+                let slope_x = (height(right, j) - height(left, j)) / (2.0 * dx);
+                let slope_z = (height(i, down) - height(i, up)) / ((down - up) as f32 * dz);
+                let length = (slope_x * slope_x + 1.0 + slope_z * slope_z).sqrt();
+                normals.push([-slope_x / length, 1.0 / length, -slope_z / length]);
+            }
+        }
+        GridMesh {
+            cols,
+            rows,
+            positions,
+            normals,
+            uvs,
+            cells,
+        }
+    }
+
+    /// Two triangles per grid quad, counter-clockwise seen from above.
+    pub(crate) fn triangles(&self) -> Vec<[u32; 3]> {
+        let mut triangles = Vec::with_capacity(2 * (self.cols - 1) * (self.rows - 1));
+        for j in 0..self.rows - 1 {
+            for i in 0..self.cols - 1 {
+                let a = (j * self.cols + i) as u32;
+                let (b, c) = (a + 1, a + self.cols as u32);
+                triangles.push([a, c, b]);
+                triangles.push([b, c, c + 1]);
+            }
+        }
+        triangles
+    }
+}
+
+/// Vertices and triangles of a terrain mesh from either mesher, laid out as `GridMesh`.
+pub(crate) struct TerrainMesh {
+    pub(crate) positions: Vec<[f32; 3]>,
+    pub(crate) normals: Vec<[f32; 3]>,
+    pub(crate) uvs: Vec<[f32; 2]>,
+    pub(crate) cells: Vec<usize>,
+    /// Counter-clockwise seen from above.
+    pub(crate) triangles: Vec<[u32; 3]>,
+}
+
+impl TerrainMesh {
+    /// The adaptive mesh when `params.max_error_metres` is set, else the regular grid.
+    pub(crate) fn build(flat: &[f32], params: &MeshParams) -> TerrainMesh {
+        if params.max_error_metres > 0.0 {
+            return build_tin(flat, params);
+        }
+        let grid = GridMesh::build(flat, params);
+        let triangles = grid.triangles();
+        TerrainMesh {
+            positions: grid.positions,
+            normals: grid.normals,
+            uvs: grid.uvs,
+            cells: grid.cells,
+            triangles,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+/// One mip level: side lengths and its average- and max-pooled samples.
+struct MipLevel {
+    width: usize,
+    height: usize,
+    average: Vec<f32>,
+    max: Vec<f32>,
+}
+
+/// `field` (`width` × `height`) pooled over 2 × 2 blocks; a side of 1 pools pairs along
+/// the other side only.
+fn pool(field: &[f32], width: usize, height: usize, combine: fn(&[f32]) -> f32) -> Vec<f32> {
+    let (out_width, out_height) = ((width / 2).max(1), (height / 2).max(1));
+    let (sx, sy) = (width / out_width, height / out_height);
+    let mut block = Vec::with_capacity(4);
+    (0..out_width * out_height)
+        .map(|i| {
+            let (x, y) = (sx * (i % out_width), sy * (i / out_width));
+            block.clear();
+            for dy in 0..sy {
+                block.extend_from_slice(&field[(y + dy) * width + x..][..sx]);
+            }
+            combine(&block)
+        })
+        .collect()
+}
+
+/// Mip chain of a heightmap from `build_mip_chain`: level 0 is the grid, each level after
+/// it halves both sides down to 1 × 1 (12 levels), in two variants pooled from the level
+/// before. Average pooling suits rendering and mean statistics; max pooling keeps every
+/// peak, for conservative LOD bounds, culling and visibility checks.
+#[wasm_bindgen]
+pub struct MipChain {
+    levels: Vec<MipLevel>,
+}
+
+#[wasm_bindgen]
+impl MipChain {
+    #[wasm_bindgen(getter)]
+    pub fn level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    pub fn width(&self, level: u32) -> Result<u32, JsValue> {
+        Ok(self.level(level)?.width as u32)
+    }
+
+    pub fn height(&self, level: u32) -> Result<u32, JsValue> {
+        Ok(self.level(level)?.height as u32)
+    }
+
+    /// Mean of the cells under each sample of `level`, row-major.
+    pub fn average(&self, level: u32) -> Result<Box<[f32]>, JsValue> {
+        Ok(self.level(level)?.average.clone().into_boxed_slice())
+    }
+
+    /// Highest cell under each sample of `level`, row-major.
+    pub fn max(&self, level: u32) -> Result<Box<[f32]>, JsValue> {
+        Ok(self.level(level)?.max.clone().into_boxed_slice())
+    }
+}
+
+impl MipChain {
+    fn level(&self, level: u32) -> Result<&MipLevel, JsValue> {
+        self.levels
+            .get(level as usize)
+            .ok_or_else(|| JsValue::from_str("mip level out of range"))
+    }
+}
+
+/// Every mip level of the heightmap in one call, both average- and max-pooled.
+#[wasm_bindgen]
+pub fn build_mip_chain(flat: &[f32]) -> Result<MipChain, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let mut levels = vec![MipLevel {
+        width: WIDTH,
+        height: HEIGHT,
+        average: flat.to_vec(),
+        max: flat.to_vec(),
+    }];
+    while let Some(last) = levels.last().filter(|l| l.width > 1 || l.height > 1) {
+        let (width, height) = (last.width, last.height);
+        let average = pool(&last.average, width, height, |b| {
+            b.iter().sum::<f32>() / b.len() as f32
+        });
+        let max = pool(&last.max, width, height, |b| {
+            b.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+        });
+        levels.push(MipLevel {
+            width: (width / 2).max(1),
+            height: (height / 2).max(1),
+            average,
+            max,
+        });
+    }
+    Ok(MipChain { levels })
+}
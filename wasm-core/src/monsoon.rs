@@ -0,0 +1,54 @@
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, box_blur, latitude_deg, sample_wrapped};
+
+/// Blur radius (cells) over which land must dominate to build a seasonal heat low.
+const HEAT_LOW_RADIUS: usize = 64;
+/// Peak monsoon wind relative to the prevailing trades at strength 1.
+const MONSOON_WIND: f32 = 1.2;
+
+/// Monsoon belt weighting: strongest in the tropics and subtropics, fading by 40°.
+fn monsoon_latitude_weight(lat_deg: f32) -> f32 {
+    let a = lat_deg.abs();
+    if a >= 40.0 {
+        return 0.0;
+    }
+    (std::f32::consts::PI * a / 40.0)
+        .sin()
+        .max(0.35 * (1.0 - a / 40.0))
+}
+
+/// Seasonal wind perturbation (grid space, +x east, +y south) from land–sea thermal
+/// contrast. Large landmasses in the summer hemisphere draw air onshore toward their
+/// interiors; in winter the flow reverses offshore. `seasonal_swing` is declination / tilt:
+/// +1 at the June solstice, −1 at the December solstice, 0 for the annual mean.
+pub(crate) fn monsoon_winds(water: &[bool], seasonal_swing: f32, strength: f32) -> Vec<(f32, f32)> {
+    let mut winds = vec![(0.0, 0.0); CELL_COUNT];
+    if seasonal_swing == 0.0 || strength == 0.0 {
+        return winds;
+    }
+
+    let land: Vec<f32> = water.iter().map(|&w| if w { 0.0 } else { 1.0 }).collect();
+    let land_fraction = box_blur(&box_blur(&land, HEAT_LOW_RADIUS / 2), HEAT_LOW_RADIUS / 2);
+    let reach = (HEAT_LOW_RADIUS / 2) as i64;
+    // Gradient of land fraction spans ~1 over the blur width; rescale to order one.
+    let gain = HEAT_LOW_RADIUS as f32 * MONSOON_WIND * strength;
+    for y in 0..HEIGHT {
+        let lat = latitude_deg(y);
+        let weight = monsoon_latitude_weight(lat);
+        if weight == 0.0 {
+            continue;
+        }
+        let hemisphere_swing = seasonal_swing * lat.signum() * weight * gain;
+        let yi = y as i64;
+        for x in 0..WIDTH {
+            let xi = x as i64;
+            let gx = (sample_wrapped(&land_fraction, xi + reach, yi)
+                - sample_wrapped(&land_fraction, xi - reach, yi))
+                / (2 * reach) as f32;
+            let gy = (sample_wrapped(&land_fraction, xi, yi + reach)
+                - sample_wrapped(&land_fraction, xi, yi - reach))
+                / (2 * reach) as f32;
+            winds[y * WIDTH + x] = (gx * hemisphere_swing, gy * hemisphere_swing);
+        }
+    }
+    winds
+}
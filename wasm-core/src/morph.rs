@@ -0,0 +1,240 @@
+use std::f32::consts::FRAC_PI_2;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::check_grid_len;
+use crate::json::{self, Json, ObjectWriter};
+
+/// Most frames a morph may have.
+const MAX_MORPH_FRAMES: u32 = 10_000;
+
+/// Timing curve from 0 to 1.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    /// Smoothstep: starts and ends at rest.
+    EaseInOut,
+}
+
+impl Easing {
+    pub(crate) fn parse(easing: &str) -> Result<Easing, String> {
+        match easing {
+            "linear" => Ok(Easing::Linear),
+            "ease_in" => Ok(Easing::EaseIn),
+            "ease_out" => Ok(Easing::EaseOut),
+            "ease_in_out" => Ok(Easing::EaseInOut),
+            _ => Err("easing must be linear, ease_in, ease_out or ease_in_out".to_string()),
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease_in",
+            Easing::EaseOut => "ease_out",
+            Easing::EaseInOut => "ease_in_out",
+        }
+    }
+
+    pub(crate) fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Eased progress of frame `index` of `frames`, from 0 at the first to 1 at the last.
+fn frame_progress(index: u32, frames: u32, easing: Easing) -> f64 {
+    easing.apply(index as f64 / (frames - 1) as f64)
+}
+
+/// Weights of the start and end noise at progress `u`: a quarter turn from one to the
+/// other, so the blend keeps the variance of two independent noise fields instead of
+/// flattening half way as a straight cross-fade does.
+fn noise_weights(u: f64) -> (f32, f32) {
+    let angle = u as f32 * FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
+fn check_frames(frames: u32) -> Result<(), JsValue> {
+    if !(2..=MAX_MORPH_FRAMES).contains(&frames) {
+        return Err(JsValue::from_str(&format!(
+            "frames must be within [2, {MAX_MORPH_FRAMES}]"
+        )));
+    }
+    Ok(())
+}
+
+/// Frames morphing one heightmap into another, made one at a time by
+/// `morph_heightmaps`.
+#[wasm_bindgen]
+pub struct HeightmapMorph {
+    from: Vec<f32>,
+    to: Vec<f32>,
+    from_mean: f32,
+    to_mean: f32,
+    frames: u32,
+    easing: Easing,
+    next: u32,
+}
+
+#[wasm_bindgen]
+impl HeightmapMorph {
+    #[wasm_bindgen(getter)]
+    pub fn frame_count(&self) -> u32 {
+        self.frames
+    }
+
+    /// The next frame in order, or `undefined` after the last.
+    pub fn next_frame(&mut self) -> Option<Box<[f32]>> {
+        let index = self.next;
+        (index < self.frames).then(|| {
+            self.next += 1;
+            self.render(index)
+        })
+    }
+
+    /// Frame `index` (0 to `frame_count` − 1), independent of `next_frame`.
+    pub fn frame(&self, index: u32) -> Result<Box<[f32]>, JsValue> {
+        if index >= self.frames {
+            return Err(JsValue::from_str("frame index out of range"));
+        }
+        Ok(self.render(index))
+    }
+}
+
+impl HeightmapMorph {
+    fn render(&self, index: u32) -> Box<[f32]> {
+        let u = frame_progress(index, self.frames, self.easing);
+        let (from_weight, to_weight) = noise_weights(u);
+        let mean = self.from_mean + (self.to_mean - self.from_mean) * u as f32;
+        self.from
+            .iter()
+            .zip(&self.to)
+            .map(|(&a, &b)| {
+                let h = mean + (a - self.from_mean) * from_weight + (b - self.to_mean) * to_weight;
+                h.clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+}
+
+/// `frames` heightmaps morphing `from` into `to` (both included), e.g. the same
+/// parameters under two seeds, for a "world evolving" animation. Each is the mean height
+/// moving linearly plus both maps' relief about their means, blended by a quarter turn
+/// (cos, sin) of eased progress: independent noise mixed that way keeps its contrast
+/// throughout, where a cross-fade would go flat and hazy half way. `easing` is
+/// `"linear"`, `"ease_in"`, `"ease_out"` or `"ease_in_out"`.
+#[wasm_bindgen]
+pub fn morph_heightmaps(
+    from: &[f32],
+    to: &[f32],
+    frames: u32,
+    easing: &str,
+) -> Result<HeightmapMorph, JsValue> {
+    check_grid_len(from, "from heightmap")?;
+    check_grid_len(to, "to heightmap")?;
+    check_frames(frames)?;
+    let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e))?;
+    let mean =
+        |field: &[f32]| (field.iter().map(|&h| h as f64).sum::<f64>() / field.len() as f64) as f32;
+    Ok(HeightmapMorph {
+        from_mean: mean(from),
+        to_mean: mean(to),
+        from: from.to_vec(),
+        to: to.to_vec(),
+        frames,
+        easing,
+        next: 0,
+    })
+}
+
+/// Parameter value `u` of the way from `a` to `b`: numbers move linearly, other values
+/// switch over half way.
+pub(crate) fn interpolate_value(a: &Json, b: &Json, u: f64) -> Json {
+    match (a, b) {
+        (Json::Number(a), Json::Number(b)) => Json::Number(a + (b - a) * u),
+        _ if u >= 0.5 => b.clone(),
+        _ => a.clone(),
+    }
+}
+
+/// Parameter object with each value `u` of the way from `from` to `to`, as
+/// `interpolate_value` moves it. Keys only on one side keep their value throughout.
+fn interpolate_params(from: &[(String, Json)], to: &[(String, Json)], u: f64) -> Json {
+    let mut fields: Vec<(String, Json)> = from
+        .iter()
+        .map(|(key, a)| {
+            let value = match to.iter().find(|(k, _)| k == key) {
+                Some((_, b)) => interpolate_value(a, b, u),
+                None => a.clone(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+    for (key, b) in to {
+        if !from.iter().any(|(k, _)| k == key) {
+            fields.push((key.clone(), b.clone()));
+        }
+    }
+    Json::Object(fields)
+}
+
+/// Per-frame dispatch plan for morphing on the GPU between two generation parameter
+/// objects (flat JSON, e.g. `{"seed":1337,"mountain_height":0.4}`):
+/// `{"frames":[{"index","t","progress","seeds":[from,to],"weights":[from,to],"params"},
+/// ...]}`. Seeds cannot be interpolated, so each frame evaluates noise under both
+/// `seeds` and mixes it with `weights`, the same quarter-turn blend `morph_heightmaps`
+/// uses; every other number in `params` moves with eased `progress`, and other values
+/// switch half way. A `"seed"` key missing from either object counts as the default seed.
+#[wasm_bindgen]
+pub fn seed_morph_plan_json(
+    from_params_json: &str,
+    to_params_json: &str,
+    frames: u32,
+    easing: &str,
+) -> Result<String, JsValue> {
+    check_frames(frames)?;
+    let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e))?;
+    let parse = |text: &str| match json::parse(text).map_err(|e| JsValue::from_str(&e))? {
+        Json::Object(mut fields) => {
+            let seed = match fields.iter().position(|(k, _)| k == "seed") {
+                Some(i) => match fields.remove(i).1 {
+                    Json::Number(n) if n >= 0.0 && n <= u32::MAX as f64 && n.fract() == 0.0 => {
+                        n as u32
+                    }
+                    _ => return Err(JsValue::from_str("seed must be a u32")),
+                },
+                None => crate::DEFAULT_SEED,
+            };
+            Ok((seed, fields))
+        }
+        _ => Err(JsValue::from_str("params must be a JSON object")),
+    };
+    let (from_seed, from) = parse(from_params_json)?;
+    let (to_seed, to) = parse(to_params_json)?;
+    let frames = (0..frames).map(|index| {
+        let u = frame_progress(index, frames, easing);
+        let (from_weight, to_weight) = noise_weights(u);
+        ObjectWriter::new()
+            .integer("index", index as u64)
+            .number("t", index as f64 / (frames - 1) as f64, 6)
+            .number("progress", u, 6)
+            .raw("seeds", &json::array([from_seed, to_seed]))
+            .raw(
+                "weights",
+                &format!("[{:.6},{:.6}]", from_weight.max(0.0), to_weight),
+            )
+            .raw("params", &interpolate_params(&from, &to, u).to_string())
+            .finish()
+    });
+    Ok(ObjectWriter::new()
+        .raw("frames", &json::array(frames))
+        .finish())
+}
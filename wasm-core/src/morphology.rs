@@ -0,0 +1,225 @@
+use wasm_bindgen::prelude::*;
+
+use crate::flow::{cell_order, d8_receivers, flow_accumulation, is_below, network_segments, ring};
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, clamp_y, wrap_x};
+use crate::prominence::{peak_isolation_km, peak_prominence};
+use crate::vector::{cell_path_polylines, polylines_json};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainFeatureParams {
+    /// Features are only reported on land (elevation ≥ `sea_level`).
+    pub sea_level: f32,
+    /// A peak must be the highest cell, and a pit the lowest, within this many cells.
+    pub radius: u32,
+    /// A saddle's surrounding ridges must rise, and its valleys fall, by at least this much
+    /// (elevation units) within its 8 neighbours.
+    pub min_relief: f32,
+    /// Land cells whose steepest-ascent paths must converge on a cell for it to lie on a
+    /// ridge line; larger values keep only the main ridges.
+    pub min_ridge_cells: u32,
+    /// Douglas–Peucker tolerance for ridge lines, cells (0 keeps every cell).
+    pub ridge_tolerance: f32,
+}
+
+impl Default for TerrainFeatureParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            radius: 8,
+            min_relief: 0.005,
+            min_ridge_cells: 200,
+            ridge_tolerance: 0.75,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TerrainFeatureParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TerrainFeatureParams {
+        Self::default()
+    }
+}
+
+impl TerrainFeatureParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(0.0..=1.0).contains(&self.sea_level) {
+            return Err(JsValue::from_str("sea_level must be within [0.0, 1.0]"));
+        }
+        if self.radius as usize >= HEIGHT / 2 {
+            return Err(JsValue::from_str(
+                "radius must be below half the grid height",
+            ));
+        }
+        if !self.min_relief.is_finite() || self.min_relief < 0.0 {
+            return Err(JsValue::from_str("min_relief must be >= 0"));
+        }
+        if self.min_ridge_cells == 0 {
+            return Err(JsValue::from_str("min_ridge_cells must be >= 1"));
+        }
+        if !self.ridge_tolerance.is_finite() || self.ridge_tolerance < 0.0 {
+            return Err(JsValue::from_str("ridge_tolerance must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Peaks (with prominence and isolation), pits, saddles and ridge lines from
+/// `detect_terrain_features`. Points are cell indices (`y * width + x`).
+#[wasm_bindgen]
+pub struct TerrainFeatures {
+    peaks: Vec<u32>,
+    pits: Vec<u32>,
+    saddles: Vec<u32>,
+    peak_elevations: Vec<f32>,
+    pit_elevations: Vec<f32>,
+    saddle_elevations: Vec<f32>,
+    peak_prominence: Vec<f32>,
+    peak_isolation_km: Vec<f32>,
+    ridges: Vec<Vec<(f32, f32)>>,
+}
+
+#[wasm_bindgen]
+impl TerrainFeatures {
+    /// Peak cells, highest first.
+    pub fn peaks(&self) -> Box<[u32]> {
+        self.peaks.clone().into_boxed_slice()
+    }
+
+    pub fn peak_elevations(&self) -> Box<[f32]> {
+        self.peak_elevations.clone().into_boxed_slice()
+    }
+
+    /// Prominence of each peak in elevation units: its height above the highest col
+    /// connecting it to higher ground (sea level for island high points and the summit).
+    pub fn peak_prominence(&self) -> Box<[f32]> {
+        self.peak_prominence.clone().into_boxed_slice()
+    }
+
+    /// Isolation of each peak: great-circle distance (km) to the nearest higher ground;
+    /// infinite for the highest point on the map.
+    pub fn peak_isolation_km(&self) -> Box<[f32]> {
+        self.peak_isolation_km.clone().into_boxed_slice()
+    }
+
+    /// Pit cells (closed land depressions), lowest first.
+    pub fn pits(&self) -> Box<[u32]> {
+        self.pits.clone().into_boxed_slice()
+    }
+
+    pub fn pit_elevations(&self) -> Box<[f32]> {
+        self.pit_elevations.clone().into_boxed_slice()
+    }
+
+    /// Saddle cells (passes), lowest first.
+    pub fn saddles(&self) -> Box<[u32]> {
+        self.saddles.clone().into_boxed_slice()
+    }
+
+    pub fn saddle_elevations(&self) -> Box<[f32]> {
+        self.saddle_elevations.clone().into_boxed_slice()
+    }
+
+    /// Ridge lines as `[[[x,y],...],...]` in cell-centre grid coordinates, running uphill.
+    /// Lines are split where they cross the east–west seam.
+    pub fn ridges_json(&self) -> String {
+        polylines_json(&self.ridges)
+    }
+}
+
+/// Whether `idx` is above (`higher`) or below every cell within `radius`, under the
+/// total order of `is_below`.
+fn is_extremum(flat: &[f32], idx: usize, radius: usize, higher: bool) -> bool {
+    let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+    let r = radius as i64;
+    for ny in (y - r).max(0)..=(y + r).min(HEIGHT as i64 - 1) {
+        for nx in x - r..=x + r {
+            let n = clamp_y(ny) * WIDTH + wrap_x(nx);
+            if n != idx && is_below(flat, idx, n) == higher {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Saddle test: the 8-neighbour ring alternates between higher and lower at least twice
+/// each way, and both sides clear `min_relief`.
+fn is_saddle(flat: &[f32], idx: usize, min_relief: f32) -> bool {
+    let ring = ring(idx);
+    if ring.iter().any(Option::is_none) {
+        return false;
+    }
+    let ring = ring.map(Option::unwrap);
+    let above = ring.map(|n| is_below(flat, idx, n));
+    let changes = (0..8).filter(|&i| above[i] != above[(i + 1) % 8]).count();
+    if changes < 4 {
+        return false;
+    }
+    let h = flat[idx];
+    let rise = ring.iter().map(|&n| flat[n] - h).fold(f32::MIN, f32::max);
+    let fall = ring.iter().map(|&n| h - flat[n]).fold(f32::MIN, f32::max);
+    rise >= min_relief && fall >= min_relief
+}
+
+/// Ridge lines: the steepest-ascent network over land, kept where at least `min_cells`
+/// land cells feed it.
+fn ridge_lines(flat: &[f32], land: &[bool], params: &TerrainFeatureParams) -> Vec<Vec<(f32, f32)>> {
+    let inverted: Vec<f32> = flat.iter().map(|&h| -h).collect();
+    let receivers = d8_receivers(&inverted);
+    let upslope = flow_accumulation(&receivers, |i| if land[i] { 1.0 } else { 0.0 });
+    let member: Vec<bool> = (0..CELL_COUNT)
+        .map(|i| land[i] && upslope[i] >= params.min_ridge_cells as f32)
+        .collect();
+    network_segments(&receivers, &member)
+        .iter()
+        .flat_map(|segment| cell_path_polylines(segment, params.ridge_tolerance))
+        .collect()
+}
+
+/// Peaks (local maxima standing highest within `radius`), pits (closed depressions, lowest
+/// within `radius`), saddles (passes between higher ground on two sides) and ridge lines,
+/// all on land. Polar rows are skipped for saddles, which need a full neighbour ring.
+#[wasm_bindgen]
+pub fn detect_terrain_features(
+    flat: &[f32],
+    params: &TerrainFeatureParams,
+) -> Result<TerrainFeatures, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let land: Vec<bool> = flat.iter().map(|&h| h >= params.sea_level).collect();
+    let radius = params.radius.max(1) as usize;
+    let mut peaks = Vec::new();
+    let mut pits = Vec::new();
+    let mut saddles = Vec::new();
+    for idx in (0..CELL_COUNT).filter(|&i| land[i]) {
+        if is_extremum(flat, idx, 1, true) {
+            if is_extremum(flat, idx, radius, true) {
+                peaks.push(idx as u32);
+            }
+        } else if is_extremum(flat, idx, 1, false) {
+            if is_extremum(flat, idx, radius, false) {
+                pits.push(idx as u32);
+            }
+        } else if is_saddle(flat, idx, params.min_relief) {
+            saddles.push(idx as u32);
+        }
+    }
+    let ascending = |a: &u32, b: &u32| cell_order(flat, *a as usize, *b as usize);
+    peaks.sort_by(|a, b| ascending(b, a));
+    pits.sort_by(ascending);
+    saddles.sort_by(ascending);
+    let elevations = |cells: &[u32]| cells.iter().map(|&c| flat[c as usize]).collect();
+    Ok(TerrainFeatures {
+        peak_elevations: elevations(&peaks),
+        pit_elevations: elevations(&pits),
+        saddle_elevations: elevations(&saddles),
+        peak_prominence: peak_prominence(flat, params.sea_level, &peaks),
+        peak_isolation_km: peak_isolation_km(flat, &peaks),
+        peaks,
+        pits,
+        saddles,
+        ridges: ridge_lines(flat, &land, params),
+    })
+}
@@ -0,0 +1,442 @@
+use std::collections::{HashSet, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use crate::flow::ring;
+use crate::grid::{CELL_COUNT, WIDTH, check_grid_len};
+use crate::json::{self, ObjectWriter};
+use crate::labels::{LabelParams, label_anchors};
+use crate::landmass::{label_land, label_landmasses};
+use crate::noise::{hash_u32, seeded_hash_2d};
+use crate::provinces::voronoi_partition;
+use crate::vector::grid_to_lon_lat;
+
+/// Relaxation rounds for the sea basins; they only need to look even, not balance.
+const SEA_ITERATIONS: u32 = 2;
+/// Longest root, letters; a settlement ending may add one more.
+const MAX_LETTERS: usize = 10;
+/// Attempts at a root before a culture gives up on finding one not yet used.
+const MAX_ATTEMPTS: u32 = 32;
+
+/// A syllable grammar: a root is a few syllables of onset, nucleus and optional coda.
+struct Culture {
+    name: &'static str,
+    /// Syllable openings; `""` lets the first syllable start on its vowel.
+    onsets: &'static [&'static str],
+    nuclei: &'static [&'static str],
+    codas: &'static [&'static str],
+    /// Chance out of 256 that a syllable closes with a coda.
+    coda_chance: u32,
+    /// Fewest and most syllables in a root.
+    syllables: (u32, u32),
+    /// Endings that turn a root into a settlement name; `""` leaves it bare.
+    settlement_endings: &'static [&'static str],
+    /// Ending that turns a root into a continent name.
+    land_ending: &'static str,
+}
+
+const CULTURES: [Culture; 6] = [
+    Culture {
+        name: "nordic",
+        onsets: &[
+            "", "b", "d", "f", "g", "h", "k", "l", "m", "n", "r", "s", "t", "v", "j", "sk", "st",
+            "br", "fr", "gr", "hr", "sv", "th",
+        ],
+        nuclei: &["a", "e", "i", "o", "u", "y", "ei", "au"],
+        codas: &["n", "r", "l", "k", "g", "m", "rd", "nd", "rn", "ld", "st"],
+        coda_chance: 150,
+        syllables: (1, 2),
+        settlement_endings: &["", "by", "heim", "vik", "stad", "holm", "dal"],
+        land_ending: "land",
+    },
+    Culture {
+        name: "latin",
+        onsets: &[
+            "", "c", "d", "f", "l", "m", "n", "p", "r", "s", "t", "v", "qu", "br", "tr", "gr",
+        ],
+        nuclei: &["a", "e", "i", "o", "u", "ia", "ae"],
+        codas: &["s", "n", "r", "m", "x", "l"],
+        coda_chance: 70,
+        syllables: (2, 3),
+        settlement_endings: &["", "um", "a", "ium", "ona"],
+        land_ending: "ia",
+    },
+    Culture {
+        name: "desert",
+        onsets: &[
+            "", "b", "d", "f", "h", "j", "k", "m", "n", "q", "r", "s", "t", "z", "kh", "sh",
+        ],
+        nuclei: &["a", "i", "u", "aa", "ou", "ei"],
+        codas: &["r", "n", "m", "d", "l", "b", "z", "sh"],
+        coda_chance: 110,
+        syllables: (2, 3),
+        settlement_endings: &["", "abad", "an", "ar", "iya"],
+        land_ending: "ara",
+    },
+    Culture {
+        name: "steppe",
+        onsets: &[
+            "", "b", "d", "g", "j", "k", "m", "n", "s", "t", "y", "z", "ch", "kh", "ts",
+        ],
+        nuclei: &["a", "e", "i", "o", "u", "aa", "uu"],
+        codas: &["n", "r", "l", "g", "t", "s", "k", "ng"],
+        coda_chance: 110,
+        syllables: (2, 3),
+        settlement_endings: &["", "khan", "tai", "gol", "kent"],
+        land_ending: "ai",
+    },
+    Culture {
+        name: "sylvan",
+        onsets: &[
+            "", "c", "f", "g", "l", "m", "n", "r", "s", "v", "th", "gl", "ll",
+        ],
+        nuclei: &["a", "e", "i", "o", "ae", "ie", "ia", "ea"],
+        codas: &["l", "n", "r", "s", "th", "nd", "ll"],
+        coda_chance: 100,
+        syllables: (2, 3),
+        settlement_endings: &["", "ion", "iel", "eth", "ond", "ost"],
+        land_ending: "or",
+    },
+    Culture {
+        name: "isles",
+        onsets: &["", "h", "k", "l", "m", "n", "p", "r", "t", "v", "w"],
+        nuclei: &["a", "e", "i", "o", "u", "ai", "au", "oa"],
+        codas: &[],
+        coda_chance: 0,
+        syllables: (2, 4),
+        settlement_endings: &["", "nui", "lani"],
+        land_ending: "ua",
+    },
+];
+
+/// Deterministic stream of hashes for one name.
+struct NameRng(u32);
+
+impl NameRng {
+    fn new(seed: u32, kind: &str, key: u32) -> Self {
+        let salt = kind.bytes().fold(0x811c9dc5, |h, b| hash_u32(h ^ b as u32));
+        Self(seeded_hash_2d(key, salt, seed))
+    }
+
+    fn next(&mut self) -> u32 {
+        self.0 = hash_u32(self.0.wrapping_add(0x9e3779b9));
+        self.0
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+/// Whether `word` reads badly: too short or long, a letter three times running, or more
+/// than three consonants or two vowels in a row.
+fn awkward(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    if !(3..=MAX_LETTERS).contains(&chars.len()) {
+        return true;
+    }
+    if chars.windows(3).any(|w| w[0] == w[1] && w[1] == w[2]) {
+        return true;
+    }
+    let (mut vowels, mut consonants) = (0, 0);
+    for &c in &chars {
+        if is_vowel(c) {
+            (vowels, consonants) = (vowels + 1, 0);
+        } else {
+            (vowels, consonants) = (0, consonants + 1);
+        }
+        if vowels > 2 || consonants > 3 {
+            return true;
+        }
+    }
+    false
+}
+
+impl Culture {
+    /// A lowercase root from this culture's grammar.
+    fn root(&self, rng: &mut NameRng) -> String {
+        let mut word = String::new();
+        for _ in 0..MAX_ATTEMPTS {
+            word.clear();
+            let (fewest, most) = self.syllables;
+            let count = fewest + rng.next() % (most - fewest + 1);
+            for syllable in 0..count {
+                // Later syllables always open on a consonant so vowels do not pile up.
+                let mut onset = rng.pick(self.onsets);
+                while syllable > 0 && onset.is_empty() {
+                    onset = rng.pick(self.onsets);
+                }
+                word.push_str(onset);
+                word.push_str(rng.pick(self.nuclei));
+                if !self.codas.is_empty() && rng.next() % 256 < self.coda_chance {
+                    word.push_str(rng.pick(self.codas));
+                }
+            }
+            if !awkward(&word) {
+                break;
+            }
+        }
+        word
+    }
+}
+
+/// `root` + `ending`, dropping the root's final vowels before an ending that starts with
+/// one.
+fn join(root: &str, ending: &str) -> String {
+    let mut word = root.to_string();
+    while ending.starts_with(is_vowel) && word.ends_with(is_vowel) && word.len() > 3 {
+        word.pop();
+    }
+    word.push_str(ending);
+    word
+}
+
+fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Finished name of `kind` from `root`, in the culture's style.
+fn compose(culture: &Culture, kind: &str, root: &str, rng: &mut NameRng) -> String {
+    let word = |ending: &str| capitalise(&join(root, ending));
+    let plain = capitalise(root);
+    match kind {
+        "continent" => word(culture.land_ending),
+        "settlement" => {
+            let ending = rng.pick(culture.settlement_endings);
+            if root.len() + ending.len() > MAX_LETTERS + 1 {
+                plain
+            } else {
+                word(ending)
+            }
+        }
+        "island" => rng.pick(&["{} Island", "Isle of {}"]).replace("{}", &plain),
+        "sea" => rng.pick(&["{} Sea", "Sea of {}"]).replace("{}", &plain),
+        "lake" => rng.pick(&["Lake {}", "{} Lake"]).replace("{}", &plain),
+        "river" => rng.pick(&["{} River", "River {}"]).replace("{}", &plain),
+        "range" => rng
+            .pick(&["{} Mountains", "{} Range", "{} Peaks"])
+            .replace("{}", &plain),
+        _ => plain,
+    }
+}
+
+/// Hands out names keyed by feature, never repeating a root within one world.
+struct Namer {
+    seed: u32,
+    used: HashSet<String>,
+}
+
+impl Namer {
+    fn name(&mut self, culture: &Culture, kind: &str, key: u32) -> String {
+        let mut rng = NameRng::new(self.seed, kind, key);
+        let mut root = culture.root(&mut rng);
+        for _ in 0..MAX_ATTEMPTS {
+            if !self.used.contains(&root) {
+                break;
+            }
+            root = culture.root(&mut rng);
+        }
+        self.used.insert(root.clone());
+        compose(culture, kind, &root, &mut rng)
+    }
+}
+
+/// Culture index for each cell: land takes its region's culture, picked from the seed, and
+/// water that of the nearest land.
+fn culture_map(region: &[u32], land: &[bool], seed: u32) -> Vec<u8> {
+    let pick = |region: u32| (hash_u32(hash_u32(region) ^ seed) % CULTURES.len() as u32) as u8;
+    let mut culture = vec![u8::MAX; CELL_COUNT];
+    let mut queue = VecDeque::new();
+    for idx in 0..CELL_COUNT {
+        if land[idx] {
+            culture[idx] = pick(region[idx]);
+            queue.push_back(idx);
+        }
+    }
+    while let Some(idx) = queue.pop_front() {
+        for n in ring(idx).into_iter().flatten() {
+            if culture[n] == u8::MAX {
+                culture[n] = culture[idx];
+                queue.push_back(n);
+            }
+        }
+    }
+    // A world without land still needs a voice.
+    for c in culture.iter_mut().filter(|c| **c == u8::MAX) {
+        *c = pick(0);
+    }
+    culture
+}
+
+/// Seed and sea layout for `name_features_json`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct NameParams {
+    /// Drives every name and each region's culture; the same seed on the same map gives
+    /// the same names.
+    pub seed: u32,
+    /// Landmasses at least this large are named as continents, smaller ones as islands,
+    /// km².
+    pub continent_min_km2: f32,
+    /// Bodies of sea are split into named seas of about this area, km².
+    pub sea_area_km2: f32,
+    /// Bodies of sea smaller than this go unnamed, km².
+    pub min_sea_km2: f32,
+}
+
+impl Default for NameParams {
+    fn default() -> Self {
+        Self {
+            seed: crate::DEFAULT_SEED,
+            continent_min_km2: 1_000_000.0,
+            sea_area_km2: 8_000_000.0,
+            min_sea_km2: 50_000.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl NameParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NameParams {
+        Self::default()
+    }
+}
+
+impl NameParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        let areas = [self.continent_min_km2, self.min_sea_km2];
+        if areas.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str("continent and sea minimums must be >= 0"));
+        }
+        if !self.sea_area_km2.is_finite() || self.sea_area_km2 <= 0.0 {
+            return Err(JsValue::from_str("sea_area_km2 must be > 0"));
+        }
+        Ok(())
+    }
+}
+
+fn feature_json(kind: &str, name: &str, culture: u8, idx: usize) -> String {
+    let (x, y) = ((idx % WIDTH) as f32 + 0.5, (idx / WIDTH) as f32 + 0.5);
+    let (lon, lat) = grid_to_lon_lat(x, y);
+    ObjectWriter::new()
+        .raw("kind", &json::quote(kind))
+        .raw("name", &json::quote(name))
+        .raw("culture", &json::quote(CULTURES[culture as usize].name))
+        .number("x", x as f64, 1)
+        .number("y", y as f64, 1)
+        .number("lon", lon as f64, 3)
+        .number("lat", lat as f64, 3)
+        .finish()
+}
+
+/// A bare fantasy name (e.g. for a nation or province) from `culture`'s syllable grammar:
+/// one of `"nordic"`, `"latin"`, `"desert"`, `"steppe"`, `"sylvan"` or `"isles"`. The same
+/// `seed`, `culture` and `key` always give the same name.
+#[wasm_bindgen]
+pub fn fantasy_name(seed: u32, culture: &str, key: u32) -> Result<String, JsValue> {
+    let Some(culture) = CULTURES.iter().find(|c| c.name == culture) else {
+        let names: Vec<&str> = CULTURES.iter().map(|c| c.name).collect();
+        return Err(JsValue::from_str(&format!(
+            "unknown culture; expected one of {}",
+            names.join(", ")
+        )));
+    };
+    let mut rng = NameRng::new(seed, "name", key);
+    Ok(capitalise(&culture.root(&mut rng)))
+}
+
+/// Names for the map's features, all drawn from `params.seed`:
+/// `{"features":[{"kind","name","culture","x","y","lon","lat"},...],"settlements":[{"name",
+/// "culture"},...]}`. Features are the anchors of `label_anchors_json` under `labels`, best
+/// first, with landmasses split into `"continent"` and `"island"`, then `"sea"` at the
+/// middle of each sea basin, largest body first; `x`, `y` are the anchor's cell centre.
+/// Settlements get names in the order of `settlements` (cell indices, e.g.
+/// `SettlementHierarchy::cells`). Each region (the nation map from `Nations::nation_map`,
+/// or every landmass when `regions` is empty) speaks one of the cultures of `fantasy_name`,
+/// picked by the seed, and features take the culture of the land they lie on or nearest
+/// to, so names sound alike within a nation. A feature's name follows from the seed and
+/// where it is, rerolled only when its root is already taken elsewhere on the map.
+#[wasm_bindgen]
+pub fn name_features_json(
+    flat: &[f32],
+    regions: &[u16],
+    settlements: &[u32],
+    labels: &LabelParams,
+    params: &NameParams,
+) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    labels.validate()?;
+    params.validate()?;
+    if settlements.iter().any(|&c| c as usize >= CELL_COUNT) {
+        return Err(JsValue::from_str("settlement cell out of range"));
+    }
+    let land: Vec<bool> = flat.iter().map(|&h| h >= labels.sea_level).collect();
+    let region: Vec<u32> = if regions.is_empty() {
+        label_landmasses(flat, labels.sea_level).0
+    } else {
+        check_grid_len(regions, "region map")?;
+        regions.iter().map(|&r| r as u32).collect()
+    };
+    let culture = culture_map(&region, &land, params.seed);
+    let mut namer = Namer {
+        seed: params.seed,
+        used: HashSet::new(),
+    };
+
+    let mut features = Vec::new();
+    for anchor in label_anchors(flat, labels) {
+        let kind = match anchor.kind {
+            "landmass" if anchor.area_km2 >= params.continent_min_km2 as f64 => "continent",
+            "landmass" => "island",
+            kind => kind,
+        };
+        let c = culture[anchor.idx];
+        let name = namer.name(&CULTURES[c as usize], kind, anchor.idx as u32);
+        features.push(feature_json(kind, &name, c, anchor.idx));
+    }
+
+    let sea: Vec<bool> = land.iter().map(|&l| !l).collect();
+    let (mut bodies, body_info) = label_land(&sea);
+    for body in bodies.iter_mut() {
+        if *body != 0 && body_info[*body as usize - 1].area_km2 < params.min_sea_km2 as f64 {
+            *body = 0;
+        }
+    }
+    let (_, mut seats) = voronoi_partition(
+        &bodies,
+        params.sea_area_km2 as f64,
+        SEA_ITERATIONS,
+        params.seed,
+    )
+    .ok_or_else(|| JsValue::from_str("too many seas; raise sea_area_km2"))?;
+    // Bodies are numbered largest first.
+    seats.sort_by_key(|&seat| bodies[seat]);
+    for seat in seats {
+        let c = culture[seat];
+        let name = namer.name(&CULTURES[c as usize], "sea", seat as u32);
+        features.push(feature_json("sea", &name, c, seat));
+    }
+
+    let named_settlements = settlements.iter().map(|&cell| {
+        let c = culture[cell as usize];
+        let name = namer.name(&CULTURES[c as usize], "settlement", cell);
+        ObjectWriter::new()
+            .raw("name", &json::quote(&name))
+            .raw("culture", &json::quote(CULTURES[c as usize].name))
+            .finish()
+    });
+    let settlements_json = json::array(named_settlements);
+    Ok(ObjectWriter::new()
+        .raw("features", &json::array(features))
+        .raw("settlements", &settlements_json)
+        .finish())
+}
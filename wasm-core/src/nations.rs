@@ -0,0 +1,349 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::flow::{Flooded, d8_receivers, fill_depressions, flow_accumulation, ring};
+use crate::geojson::{feature_collection, line_feature};
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::json::{self, ObjectWriter};
+use crate::vector::{cell_area_km2, polylines_json, simplify_polyline};
+
+/// Most nations `grow_nations` grows.
+const MAX_NATIONS: usize = 4096;
+
+/// Region-growing costs for `grow_nations`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct NationParams {
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km²; the vector exports draw rivers from
+    /// 50 000 km² by default, so borders follow the rivers on the map.
+    pub river_min_area_km2: f32,
+    /// Cost of crossing a river of that size, in cells of level ground; it grows with the
+    /// square root of the drainage area, so great rivers make the firmest borders.
+    pub river_cost: f32,
+    /// Cost of climbing or descending a kilometre, in cells of level ground; fronts meet on
+    /// the crests, so mountain ridges become borders.
+    pub climb_cost: f32,
+    /// Cost of a cell of sea crossed to claim an island, in cells of level ground.
+    pub sea_cost: f32,
+    /// Cost beyond which land stays unclaimed; NaN for no limit.
+    pub max_cost: f32,
+    /// Douglas–Peucker tolerance for the border lines, cells.
+    pub tolerance: f32,
+}
+
+impl Default for NationParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 50_000.0,
+            river_cost: 8.0,
+            climb_cost: 20.0,
+            sea_cost: 4.0,
+            max_cost: f32::NAN,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl NationParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NationParams {
+        Self::default()
+    }
+}
+
+impl NationParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        let costs = [
+            self.river_min_area_km2,
+            self.river_cost,
+            self.climb_cost,
+            self.sea_cost,
+            self.tolerance,
+        ];
+        if costs.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "river area, costs and tolerance must be >= 0",
+            ));
+        }
+        if !self.max_cost.is_nan() && self.max_cost < 0.0 {
+            return Err(JsValue::from_str("max_cost must be >= 0 or NaN"));
+        }
+        Ok(())
+    }
+}
+
+type Vertex = (i32, i32);
+/// Border lines between a pair of neighbouring nations, lower id first.
+type Border = ((u16, u16), Vec<Vec<(f32, f32)>>);
+
+/// Chains undirected cell-edge segments into polylines, breaking at junctions and loose
+/// ends; loops come out closed, their first point repeated.
+fn chain_segments(segments: &[(Vertex, Vertex)]) -> Vec<Vec<(f32, f32)>> {
+    let mut at: HashMap<Vertex, Vec<usize>> = HashMap::new();
+    for (i, &(a, b)) in segments.iter().enumerate() {
+        at.entry(a).or_default().push(i);
+        at.entry(b).or_default().push(i);
+    }
+    let mut used = vec![false; segments.len()];
+    let mut lines = Vec::new();
+    let mut walk = |start: Vertex, first: usize, used: &mut Vec<bool>| {
+        let mut line = vec![start];
+        let (mut vertex, mut segment) = (start, first);
+        loop {
+            used[segment] = true;
+            let (a, b) = segments[segment];
+            vertex = if a == vertex { b } else { a };
+            line.push(vertex);
+            let through = &at[&vertex];
+            if through.len() != 2 {
+                break;
+            }
+            match through.iter().copied().find(|&s| !used[s]) {
+                Some(next) => segment = next,
+                None => break,
+            }
+        }
+        lines.push(line);
+    };
+    // Open lines first, from their ends and junctions, then whatever loops are left.
+    let mut ends: Vec<Vertex> = at
+        .iter()
+        .filter(|(_, s)| s.len() != 2)
+        .map(|(&v, _)| v)
+        .collect();
+    ends.sort_unstable();
+    for vertex in ends {
+        for &segment in &at[&vertex] {
+            if !used[segment] {
+                walk(vertex, segment, &mut used);
+            }
+        }
+    }
+    for segment in 0..segments.len() {
+        if !used[segment] {
+            walk(segments[segment].0, segment, &mut used);
+        }
+    }
+    lines
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|(x, y)| (x as f32, y as f32))
+                .collect()
+        })
+        .collect()
+}
+
+/// Territory grown by `grow_nations`.
+#[wasm_bindgen]
+pub struct Nations {
+    /// 1 + index of the owning capital per cell; 0 for sea and unclaimed land.
+    owner: Vec<u16>,
+    capitals: Vec<usize>,
+    borders: Vec<Border>,
+}
+
+#[wasm_bindgen]
+impl Nations {
+    /// Nation id per cell: 1 + the index of its capital in the list given to
+    /// `grow_nations`, 0 for sea and unclaimed land.
+    pub fn nation_map(&self) -> Box<[u16]> {
+        self.owner.clone().into_boxed_slice()
+    }
+
+    /// `[{"id","x","y","cells","area_km2"},...]` in id order, with the capital at `x`, `y`
+    /// in cell-centre grid coordinates.
+    pub fn nations_json(&self) -> String {
+        let mut cells = vec![0_u64; self.capitals.len()];
+        let mut area = vec![0.0_f64; self.capitals.len()];
+        for (idx, &id) in self.owner.iter().enumerate() {
+            if id != 0 {
+                cells[id as usize - 1] += 1;
+                area[id as usize - 1] += cell_area_km2(idx / WIDTH);
+            }
+        }
+        json::array(self.capitals.iter().enumerate().map(|(i, &idx)| {
+            ObjectWriter::new()
+                .integer("id", i as u64 + 1)
+                .number("x", (idx % WIDTH) as f64 + 0.5, 1)
+                .number("y", (idx / WIDTH) as f64 + 0.5, 1)
+                .integer("cells", cells[i])
+                .number("area_km2", area[i], 0)
+                .finish()
+        }))
+    }
+
+    /// `[{"a","b","lines":[[[x,y],...],...]},...]`: the land border between nations `a` and
+    /// `b` (`a < b`) as polylines along cell edges in grid coordinates, split where it
+    /// crosses the east–west seam.
+    pub fn borders_json(&self) -> String {
+        json::array(self.borders.iter().map(|((a, b), lines)| {
+            ObjectWriter::new()
+                .integer("a", *a as u64)
+                .integer("b", *b as u64)
+                .raw("lines", &polylines_json(lines))
+                .finish()
+        }))
+    }
+
+    /// The borders as a GeoJSON FeatureCollection of LineStrings in lon/lat, with the two
+    /// nation ids `a` and `b`.
+    pub fn borders_geojson(&self) -> String {
+        let features: Vec<String> = self
+            .borders
+            .iter()
+            .flat_map(|((a, b), lines)| {
+                let properties = format!("{{\"a\":{a},\"b\":{b}}}");
+                lines
+                    .iter()
+                    .map(move |line| line_feature(&properties, line))
+            })
+            .collect();
+        feature_collection(&features)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.capitals.len() as u32
+    }
+}
+
+/// Grows a nation from each capital cell (e.g. the capitals from `place_settlements`, with
+/// `capitals` set to the number of nations wanted) by cost-weighted region growing: every
+/// cell goes to the capital it is cheapest to reach, paying for distance, climbing and
+/// river crossings, so borders settle along great rivers and mountain crests. Fronts can
+/// cross narrow seas at `sea_cost` per cell to claim islands, but sea cells stay unowned.
+#[wasm_bindgen]
+pub fn grow_nations(
+    flat: &[f32],
+    capitals: &[u32],
+    params: &NationParams,
+) -> Result<Nations, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    if capitals.is_empty() || capitals.len() > MAX_NATIONS {
+        return Err(JsValue::from_str(&format!(
+            "between 1 and {MAX_NATIONS} capitals required"
+        )));
+    }
+    if capitals.iter().any(|&c| c as usize >= CELL_COUNT) {
+        return Err(JsValue::from_str("capital cell out of range"));
+    }
+
+    let sea: Vec<bool> = flat.iter().map(|&h| h < params.sea_level).collect();
+    let filled = fill_depressions(flat, |i| sea[i]);
+    let area = flow_accumulation(&d8_receivers(&filled), |i| cell_area_km2(i / WIDTH) as f32);
+    let min_area = params.river_min_area_km2.max(f32::MIN_POSITIVE);
+    let crossing: Vec<f32> = (0..CELL_COUNT)
+        .map(|i| {
+            if sea[i] || area[i] < min_area {
+                0.0
+            } else {
+                params.river_cost * (area[i] / min_area).sqrt()
+            }
+        })
+        .collect();
+    let km_per_unit = RELIEF_METRES / (1.0 - params.sea_level) / 1000.0;
+    let max_cost = if params.max_cost.is_nan() {
+        f32::INFINITY
+    } else {
+        params.max_cost
+    };
+
+    let mut cost = vec![f32::INFINITY; CELL_COUNT];
+    let mut owner = vec![0_u16; CELL_COUNT];
+    let mut queue = BinaryHeap::new();
+    for (i, &capital) in capitals.iter().enumerate() {
+        let idx = capital as usize;
+        if cost[idx] > 0.0 {
+            cost[idx] = 0.0;
+            owner[idx] = i as u16 + 1;
+            queue.push(Reverse(Flooded(0.0, idx)));
+        }
+    }
+    while let Some(Reverse(Flooded(c, idx))) = queue.pop() {
+        if c > cost[idx] {
+            continue;
+        }
+        for (i, n) in ring(idx).into_iter().enumerate() {
+            let Some(n) = n else {
+                continue;
+            };
+            let distance = if i % 2 == 1 {
+                std::f32::consts::SQRT_2
+            } else {
+                1.0
+            };
+            let mut step = distance * if sea[n] { params.sea_cost } else { 1.0 }
+                + params.climb_cost * (flat[n] - flat[idx]).abs() * km_per_unit;
+            if crossing[idx] == 0.0 {
+                step += crossing[n];
+            }
+            let next = c + step;
+            if next < cost[n] && next <= max_cost {
+                cost[n] = next;
+                owner[n] = owner[idx];
+                queue.push(Reverse(Flooded(next, n)));
+            }
+        }
+    }
+    for (idx, id) in owner.iter_mut().enumerate() {
+        if sea[idx] {
+            *id = 0;
+        }
+    }
+
+    // Cell edges between two nations, east and south of each cell; the seam edge sits at
+    // x = WIDTH so lines break there.
+    let mut segments: HashMap<(u16, u16), Vec<(Vertex, Vertex)>> = HashMap::new();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let here = owner[y * WIDTH + x];
+            if here == 0 {
+                continue;
+            }
+            let (xi, yi) = (x as i32, y as i32);
+            let east = owner[y * WIDTH + (x + 1) % WIDTH];
+            if east != 0 && east != here {
+                segments
+                    .entry((here.min(east), here.max(east)))
+                    .or_default()
+                    .push(((xi + 1, yi), (xi + 1, yi + 1)));
+            }
+            if y + 1 < HEIGHT {
+                let south = owner[(y + 1) * WIDTH + x];
+                if south != 0 && south != here {
+                    segments
+                        .entry((here.min(south), here.max(south)))
+                        .or_default()
+                        .push(((xi, yi + 1), (xi + 1, yi + 1)));
+                }
+            }
+        }
+    }
+    let mut borders: Vec<Border> = segments
+        .into_iter()
+        .map(|(pair, segments)| {
+            let lines = chain_segments(&segments)
+                .iter()
+                .map(|line| simplify_polyline(line, params.tolerance))
+                .collect();
+            (pair, lines)
+        })
+        .collect();
+    borders.sort_by_key(|(pair, _)| *pair);
+    Ok(Nations {
+        owner,
+        capitals: capitals.iter().map(|&c| c as usize).collect(),
+        borders,
+    })
+}
@@ -0,0 +1,268 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, sample_bilinear};
+use crate::hydrology::Hydrology;
+use crate::json::{self, ObjectWriter};
+use crate::render::RELIEF_SCALE;
+use crate::terrain::slope_aspect;
+
+/// Basins this deep become lakes when `avoid_lakes` is set, as in the map exports.
+const LAKE_MIN_DEPTH_METRES: f32 = 20.0;
+const NAVMESH_VERSION: u32 = 1;
+
+/// Walkability rules and resolution for `build_navmesh`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct NavmeshParams {
+    /// Heightmap value placed at y = 0, and the shoreline: cells below it are water.
+    pub sea_level: f32,
+    /// Mesh units per heightmap unit, as `MeshParams::vertical_scale`; slopes are measured
+    /// in the same space, so the navmesh fits a terrain exported with the same value.
+    pub vertical_scale: f32,
+    /// Steepest walkable ground, degrees.
+    pub max_slope_degrees: f32,
+    /// Grid cells per navmesh polygon side, 1 to 64.
+    pub cell_size: u32,
+    /// Keeps agents out of lakes as well as the sea.
+    pub avoid_lakes: bool,
+}
+
+impl Default for NavmeshParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            vertical_scale: RELIEF_SCALE,
+            max_slope_degrees: 35.0,
+            cell_size: 4,
+            avoid_lakes: true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl NavmeshParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NavmeshParams {
+        Self::default()
+    }
+}
+
+impl NavmeshParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !(self.vertical_scale.is_finite() && self.vertical_scale > 0.0) {
+            return Err(JsValue::from_str("vertical_scale must be > 0"));
+        }
+        if !(self.max_slope_degrees > 0.0 && self.max_slope_degrees <= 90.0) {
+            return Err(JsValue::from_str(
+                "max_slope_degrees must be within (0, 90]",
+            ));
+        }
+        if !(1..=64).contains(&self.cell_size) {
+            return Err(JsValue::from_str("cell_size must be within [1, 64]"));
+        }
+        Ok(())
+    }
+}
+
+/// Walkable ground from `build_navmesh` as an indexed polygon soup: one quad per walkable
+/// block of `cell_size` × `cell_size` cells, in the terrain mesh's space (x east, y up, z
+/// south, one unit per cell, centred on the origin). Quads list their corners
+/// counter-clockwise seen from above, north-west first, so edge k runs from corner k to
+/// corner k + 1: west, south, east, north. Each edge records the quad across it, or −1
+/// at water, slopes and the map edges; links do not cross the east–west seam. Quads
+/// reachable from one another share a region id, so agents can skip impossible paths.
+#[wasm_bindgen]
+pub struct Navmesh {
+    cell_size: u32,
+    vertices: Vec<[f32; 3]>,
+    polygons: Vec<[u32; 4]>,
+    neighbours: Vec<[i32; 4]>,
+    regions: Vec<u32>,
+    region_count: u32,
+}
+
+#[wasm_bindgen]
+impl Navmesh {
+    #[wasm_bindgen(getter)]
+    pub fn vertex_count(&self) -> u32 {
+        self.vertices.len() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn polygon_count(&self) -> u32 {
+        self.polygons.len() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn region_count(&self) -> u32 {
+        self.region_count
+    }
+
+    /// The navmesh as JSON: `{"format":"continent-navmesh","version":1,"cell_size",
+    /// "vertices":[[x,y,z],...],"polygons":[[a,b,c,d],...],"neighbours":[[w,s,e,n],...],
+    /// "regions":[...]}`, with the arrays indexed by polygon.
+    pub fn to_json(&self) -> String {
+        let vertices = json::array(
+            self.vertices
+                .iter()
+                .map(|v| json::array(v.map(|c| format!("{:.3}", c)))),
+        );
+        ObjectWriter::new()
+            .raw("format", &json::quote("continent-navmesh"))
+            .integer("version", NAVMESH_VERSION as u64)
+            .integer("cell_size", self.cell_size as u64)
+            .raw("vertices", &vertices)
+            .raw(
+                "polygons",
+                &json::array(self.polygons.iter().map(|p| json::array(*p))),
+            )
+            .raw(
+                "neighbours",
+                &json::array(self.neighbours.iter().map(|n| json::array(*n))),
+            )
+            .raw("regions", &json::array(&self.regions))
+            .finish()
+    }
+
+    /// The navmesh as little-endian binary: the magic `CGNM`, then u32 version, cell size,
+    /// vertex count and polygon count; vertices as three f32 each; then per polygon four
+    /// u32 corner indices, four i32 neighbours and a u32 region.
+    pub fn to_binary(&self) -> Box<[u8]> {
+        let mut out = Vec::with_capacity(20 + 12 * self.vertices.len() + 36 * self.polygons.len());
+        out.extend(b"CGNM");
+        for value in [
+            NAVMESH_VERSION,
+            self.cell_size,
+            self.vertices.len() as u32,
+            self.polygons.len() as u32,
+        ] {
+            out.extend(value.to_le_bytes());
+        }
+        for v in &self.vertices {
+            out.extend(v.iter().flat_map(|c| c.to_le_bytes()));
+        }
+        for ((polygon, neighbours), region) in self
+            .polygons
+            .iter()
+            .zip(&self.neighbours)
+            .zip(&self.regions)
+        {
+            out.extend(polygon.iter().flat_map(|v| v.to_le_bytes()));
+            out.extend(neighbours.iter().flat_map(|n| n.to_le_bytes()));
+            out.extend(region.to_le_bytes());
+        }
+        out.into_boxed_slice()
+    }
+}
+
+/// Navmesh of the walkable ground for game AI: blocks of cells on land, out of water and
+/// no steeper than `max_slope_degrees` anywhere become quads linked to their neighbours.
+/// See `Navmesh` for the layout and its JSON and binary forms.
+#[wasm_bindgen]
+pub fn build_navmesh(flat: &[f32], params: &NavmeshParams) -> Result<Navmesh, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let lakes = params
+        .avoid_lakes
+        .then(|| Hydrology::build(flat, params.sea_level, LAKE_MIN_DEPTH_METRES).lakes);
+    let walkable_cell = |idx: usize| {
+        flat[idx] >= params.sea_level
+            && !lakes.as_ref().is_some_and(|lakes| lakes[idx])
+            && slope_aspect(flat, idx, 1.0 / params.vertical_scale).0 <= params.max_slope_degrees
+    };
+
+    let k = params.cell_size as usize;
+    let (cols, rows) = (WIDTH.div_ceil(k), HEIGHT.div_ceil(k));
+    // Polygon index of each walkable block.
+    let mut block_polygon = vec![u32::MAX; cols * rows];
+    let mut polygon_blocks = Vec::new();
+    for (block, slot) in block_polygon.iter_mut().enumerate() {
+        let (bx, by) = (block % cols * k, block / cols * k);
+        let walkable = (by..(by + k).min(HEIGHT))
+            .all(|y| (bx..(bx + k).min(WIDTH)).all(|x| walkable_cell(y * WIDTH + x)));
+        if walkable {
+            *slot = polygon_blocks.len() as u32;
+            polygon_blocks.push(block);
+        }
+    }
+
+    let mut corner_vertex = vec![u32::MAX; (cols + 1) * (rows + 1)];
+    let mut vertices = Vec::new();
+    let mut vertex = |ci: usize, cj: usize| {
+        let slot = &mut corner_vertex[cj * (cols + 1) + ci];
+        if *slot == u32::MAX {
+            let (x, z) = ((ci * k).min(WIDTH) as f32, (cj * k).min(HEIGHT) as f32);
+            let h = sample_bilinear(flat, x - 0.5, z - 0.5);
+            *slot = vertices.len() as u32;
+            vertices.push([
+                x - WIDTH as f32 / 2.0,
+                (h - params.sea_level) * params.vertical_scale,
+                z - HEIGHT as f32 / 2.0,
+            ]);
+        }
+        *slot
+    };
+    let polygons: Vec<[u32; 4]> = polygon_blocks
+        .iter()
+        .map(|&block| {
+            let (i, j) = (block % cols, block / cols);
+            [
+                vertex(i, j),
+                vertex(i, j + 1),
+                vertex(i + 1, j + 1),
+                vertex(i + 1, j),
+            ]
+        })
+        .collect();
+    let neighbours: Vec<[i32; 4]> = polygon_blocks
+        .iter()
+        .map(|&block| {
+            let (i, j) = (block % cols, block / cols);
+            let link = |ok: bool, other: usize| {
+                if ok && block_polygon[other] != u32::MAX {
+                    block_polygon[other] as i32
+                } else {
+                    -1
+                }
+            };
+            [
+                link(i > 0, block.wrapping_sub(1)),
+                link(j + 1 < rows, block + cols),
+                link(i + 1 < cols, block + 1),
+                link(j > 0, block.wrapping_sub(cols)),
+            ]
+        })
+        .collect();
+
+    let mut regions = vec![u32::MAX; polygons.len()];
+    let mut region_count = 0;
+    let mut stack = Vec::new();
+    for start in 0..polygons.len() {
+        if regions[start] != u32::MAX {
+            continue;
+        }
+        regions[start] = region_count;
+        stack.push(start);
+        while let Some(p) = stack.pop() {
+            for &n in &neighbours[p] {
+                if n >= 0 && regions[n as usize] == u32::MAX {
+                    regions[n as usize] = region_count;
+                    stack.push(n as usize);
+                }
+            }
+        }
+        region_count += 1;
+    }
+
+    Ok(Navmesh {
+        cell_size: params.cell_size,
+        vertices,
+        polygons,
+        neighbours,
+        regions,
+        region_count,
+    })
+}
@@ -0,0 +1,95 @@
+//! CPU ports of the gradient noise in `pass6_elevation.wgsl`, so CPU layers hash identically
+//! to the GPU passes for the same seed.
+
+pub(crate) fn hash_u32(x: u32) -> u32 {
+    let mut h = x;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846ca68b);
+    h ^= h >> 16;
+    h
+}
+
+pub(crate) fn seeded_hash_2d(x: u32, y: u32, seed: u32) -> u32 {
+    let h = x
+        .wrapping_mul(374761393)
+        .wrapping_add(y.wrapping_mul(668265263));
+    hash_u32(h ^ seed.wrapping_mul(2246822519).wrapping_add(3266489917))
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// Literals copied from the shader so both sides round identically.
+#[allow(clippy::approx_constant)]
+const GRADIENTS: [(f32, f32); 16] = [
+    (1.0, 0.0),
+    (0.9238795, 0.3826834),
+    (0.7071068, 0.7071068),
+    (0.3826834, 0.9238795),
+    (0.0, 1.0),
+    (-0.3826834, 0.9238795),
+    (-0.7071068, 0.7071068),
+    (-0.9238795, 0.3826834),
+    (-1.0, 0.0),
+    (-0.9238795, -0.3826834),
+    (-0.7071068, -0.7071068),
+    (-0.3826834, -0.9238795),
+    (0.0, -1.0),
+    (0.3826834, -0.9238795),
+    (0.7071068, -0.7071068),
+    (0.9238795, -0.3826834),
+];
+
+/// Perlin noise in roughly [-0.7, 0.7]. With `period_x > 0` the lattice wraps every
+/// `period_x` cells in x, making the noise seamless across that period; with 0 it matches
+/// the shader's `perlin` exactly.
+pub(crate) fn perlin(px: f32, py: f32, seed: u32, period_x: i32) -> f32 {
+    let (cx, cy) = (px.floor(), py.floor());
+    let (fx, fy) = (px - cx, py - cy);
+    let (cx, cy) = (cx as i32, cy as i32);
+    let lattice_x = |x: i32| {
+        if period_x > 0 {
+            x.rem_euclid(period_x) as u32
+        } else {
+            x as u32
+        }
+    };
+    let corner = |x: i32, y: i32, dx: f32, dy: f32| {
+        let (gx, gy) = GRADIENTS[(seeded_hash_2d(lattice_x(x), y as u32, seed) & 15) as usize];
+        gx * dx + gy * dy
+    };
+    let n00 = corner(cx, cy, fx, fy);
+    let n10 = corner(cx + 1, cy, fx - 1.0, fy);
+    let n01 = corner(cx, cy + 1, fx, fy - 1.0);
+    let n11 = corner(cx + 1, cy + 1, fx - 1.0, fy - 1.0);
+    let (ux, uy) = (fade(fx), fade(fy));
+    let top = n00 + (n10 - n00) * ux;
+    let bottom = n01 + (n11 - n01) * ux;
+    top + (bottom - top) * uy
+}
+
+/// Fractal sum of `perlin` in [0, 1], wrapping every `period_x` lattice cells of the first
+/// octave (each octave doubles frequency and period, so the sum stays seamless). Unlike the
+/// shader's `fbm` there is no per-octave rotation, which would break the wrap.
+pub(crate) fn tiled_fbm(
+    px: f32,
+    py: f32,
+    period_x: i32,
+    roughness: f32,
+    octaves: u32,
+    seed: u32,
+) -> f32 {
+    let (mut amp, mut freq, mut sum, mut div) = (0.5, 1.0, 0.0, 0.0);
+    for i in 0..octaves {
+        let octave_seed = seed.wrapping_add(i.wrapping_mul(0x9e3779b9));
+        let n = perlin(px * freq, py * freq, octave_seed, period_x * freq as i32);
+        sum += (n * 0.5 + 0.5) * amp;
+        div += amp;
+        freq *= 2.0;
+        amp *= roughness;
+    }
+    sum / f32::max(div, 0.00001)
+}
@@ -0,0 +1,83 @@
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::check_grid_len;
+use crate::mesh::{MeshParams, TerrainMesh};
+
+/// Text files from `export_terrain_obj`.
+#[wasm_bindgen]
+pub struct TerrainObj {
+    obj: String,
+    mtl: String,
+}
+
+#[wasm_bindgen]
+impl TerrainObj {
+    /// Wavefront OBJ text.
+    #[wasm_bindgen(getter)]
+    pub fn obj(&self) -> String {
+        self.obj.clone()
+    }
+
+    /// Material library to save under the requested `mtl_file` name; empty without one.
+    #[wasm_bindgen(getter)]
+    pub fn mtl(&self) -> String {
+        self.mtl.clone()
+    }
+}
+
+/// The terrain mesh of `export_terrain_glb` as Wavefront OBJ with normals and texture
+/// coordinates. With an `mtl_file` name the OBJ links it and `mtl` holds a matte material
+/// draping `texture_file` (e.g. a saved `render_biome_rgba` image) over the terrain; leave
+/// `mtl_file` empty for geometry only.
+#[wasm_bindgen]
+pub fn export_terrain_obj(
+    flat: &[f32],
+    params: &MeshParams,
+    mtl_file: &str,
+    texture_file: &str,
+) -> Result<TerrainObj, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let unsafe_name = |name: &str| name.chars().any(|c| c.is_control());
+    if unsafe_name(mtl_file) || unsafe_name(texture_file) {
+        return Err(JsValue::from_str(
+            "file names must not contain control characters",
+        ));
+    }
+    let mesh = TerrainMesh::build(flat, params);
+
+    let mut obj = String::from("# Continent-Generator terrain\n");
+    if !mtl_file.is_empty() {
+        let _ = writeln!(obj, "mtllib {mtl_file}");
+    }
+    obj.push_str("o Terrain\n");
+    for [x, y, z] in &mesh.positions {
+        let _ = writeln!(obj, "v {x:.4} {y:.4} {z:.4}");
+    }
+    // OBJ puts the texture origin at the bottom left.
+    for [u, v] in &mesh.uvs {
+        let _ = writeln!(obj, "vt {u:.6} {:.6}", 1.0 - v);
+    }
+    for [x, y, z] in &mesh.normals {
+        let _ = writeln!(obj, "vn {x:.4} {y:.4} {z:.4}");
+    }
+    if !mtl_file.is_empty() {
+        obj.push_str("usemtl terrain\n");
+    }
+    obj.push_str("s 1\n");
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle.map(|i| i + 1);
+        let _ = writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}");
+    }
+
+    let mut mtl = String::new();
+    if !mtl_file.is_empty() {
+        mtl.push_str("newmtl terrain\nKa 1 1 1\nKd 1 1 1\nKs 0 0 0\nillum 1\n");
+        if !texture_file.is_empty() {
+            let _ = writeln!(mtl, "map_Kd {texture_file}");
+        }
+    }
+    Ok(TerrainObj { obj, mtl })
+}
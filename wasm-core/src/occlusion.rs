@@ -0,0 +1,135 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, SEA_LEVEL, WIDTH, check_grid_len, sample_wrapped};
+use crate::render::RELIEF_SCALE;
+
+/// Horizon search for `sky_view_factor`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SkyViewParams {
+    /// Azimuths searched per cell.
+    pub directions: u32,
+    /// Search distance in cells; wider radii darken broad valleys, not just gullies.
+    pub radius: u32,
+    /// Cells of height per heightmap unit, as `elevation_scale * vertical_exaggeration`
+    /// in the preview; more relief deepens the occlusion.
+    pub relief_scale: f32,
+}
+
+impl Default for SkyViewParams {
+    fn default() -> Self {
+        Self {
+            directions: 16,
+            radius: 32,
+            relief_scale: RELIEF_SCALE,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SkyViewParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SkyViewParams {
+        Self::default()
+    }
+}
+
+impl SkyViewParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(4..=64).contains(&self.directions) {
+            return Err(JsValue::from_str("directions must be within [4, 64]"));
+        }
+        if !(1..=256).contains(&self.radius) {
+            return Err(JsValue::from_str("radius must be within [1, 256]"));
+        }
+        if !self.relief_scale.is_finite() || self.relief_scale <= 0.0 {
+            return Err(JsValue::from_str("relief_scale must be > 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Sample distances along each ray: every cell nearby, then spreading out geometrically,
+/// which keeps the cost logarithmic in `radius` while still catching distant ridges.
+fn ray_steps(radius: u32) -> Vec<f32> {
+    let mut steps = Vec::new();
+    let mut d = 1.0_f32;
+    while d <= radius as f32 {
+        steps.push(d);
+        d = (d + 1.0).max(d * 1.25);
+    }
+    steps
+}
+
+/// Sky-view factor per cell, 255 = open sky over the whole hemisphere down to 0 = fully
+/// enclosed: one minus the mean sine of the horizon angle over `directions` azimuths.
+/// Valleys and gullies darken, ridges and plains stay bright, so multiplying it into a
+/// hillshade (see `blend_ambient_occlusion`) gives the soft ambient-occlusion look of
+/// printed relief maps. Water counts as a flat surface at sea level.
+#[wasm_bindgen]
+pub fn sky_view_factor(flat: &[f32], params: &SkyViewParams) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let surface: Vec<f32> = flat
+        .iter()
+        .map(|&h| h.max(SEA_LEVEL) * params.relief_scale)
+        .collect();
+    // Cell offsets and distances along each azimuth, shared by every cell.
+    let steps = ray_steps(params.radius);
+    let rays: Vec<Vec<(i64, i64, f32)>> = (0..params.directions)
+        .map(|k| {
+            let a = k as f32 / params.directions as f32 * std::f32::consts::TAU;
+            let (dx, dy) = (a.cos(), a.sin());
+            steps
+                .iter()
+                .map(|&d| ((dx * d).round() as i64, (dy * d).round() as i64, d))
+                .collect()
+        })
+        .collect();
+    let svf: Vec<u8> = (0..CELL_COUNT)
+        .map(|idx| {
+            let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+            let here = surface[idx];
+            let mut occluded = 0.0;
+            for ray in &rays {
+                let mut max_slope = 0.0_f32;
+                for &(ox, oy, d) in ray {
+                    let rise = sample_wrapped(&surface, x + ox, y + oy) - here;
+                    max_slope = max_slope.max(rise / d);
+                }
+                // sin(atan(slope)) without the trigonometry.
+                occluded += max_slope / (1.0 + max_slope * max_slope).sqrt();
+            }
+            let open = 1.0 - occluded / rays.len() as f32;
+            (open * 255.0).round() as u8
+        })
+        .collect();
+    Ok(svf.into_boxed_slice())
+}
+
+/// Darkens `image` (grayscale, or RGBA with alpha left alone) by `occlusion` from
+/// `sky_view_factor`: each pixel scales by `1 − strength × (1 − svf)`, so `strength` 0
+/// leaves it unchanged and 1 applies the full occlusion.
+#[wasm_bindgen]
+pub fn blend_ambient_occlusion(
+    image: &[u8],
+    occlusion: &[u8],
+    strength: f32,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(occlusion, "occlusion")?;
+    let channels = image.len() / CELL_COUNT;
+    if !matches!(channels, 1 | 4) || image.len() != channels * CELL_COUNT {
+        return Err(JsValue::from_str("image must be grayscale or RGBA"));
+    }
+    if !(0.0..=1.0).contains(&strength) {
+        return Err(JsValue::from_str("strength must be within [0, 1]"));
+    }
+    let mut out = image.to_vec();
+    for (px, &svf) in out.chunks_exact_mut(channels).zip(occlusion) {
+        let factor = 1.0 - strength * (1.0 - svf as f32 / 255.0);
+        for c in px.iter_mut().take(3) {
+            *c = (*c as f32 * factor).round() as u8;
+        }
+    }
+    Ok(out.into_boxed_slice())
+}
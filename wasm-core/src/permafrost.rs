@@ -0,0 +1,78 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, wrap_x};
+
+pub(crate) const PERMAFROST_NONE: u8 = 0;
+pub(crate) const PERMAFROST_SPORADIC: u8 = 1;
+pub(crate) const PERMAFROST_DISCONTINUOUS: u8 = 2;
+pub(crate) const PERMAFROST_CONTINUOUS: u8 = 3;
+
+/// Trees need a warmest month of at least 10 °C; colder land is tundra.
+pub(crate) const TREELINE_WARMEST_MONTH_C: f32 = 10.0;
+
+/// Permafrost class from mean annual air temperature (°C).
+pub(crate) fn permafrost_class(mean_c: f32) -> u8 {
+    if mean_c <= -8.0 {
+        PERMAFROST_CONTINUOUS
+    } else if mean_c <= -4.0 {
+        PERMAFROST_DISCONTINUOUS
+    } else if mean_c <= -1.0 {
+        PERMAFROST_SPORADIC
+    } else {
+        PERMAFROST_NONE
+    }
+}
+
+fn is_land(climate: &Climate, idx: usize) -> bool {
+    climate.water_distance[idx] > 0.0
+}
+
+/// Permafrost extent per land cell: 0 = none, 1 = sporadic, 2 = discontinuous,
+/// 3 = continuous. `climate` must be an annual run (`season` = 0).
+#[wasm_bindgen]
+pub fn permafrost_zones(climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("permafrost_zones")?;
+    let mut zones = vec![PERMAFROST_NONE; CELL_COUNT];
+    for (idx, zone) in zones.iter_mut().enumerate() {
+        if is_land(climate, idx) {
+            *zone = permafrost_class(climate.temperature[idx]);
+        }
+    }
+    Ok(zones.into_boxed_slice())
+}
+
+/// Tundra/taiga boundary: 1 on tundra cells (warmest month below the treeline
+/// temperature) that touch a forested-climate land cell, else 0. `climate` must be an
+/// annual run.
+#[wasm_bindgen]
+pub fn treeline_boundary(climate: &Climate) -> Result<Box<[u8]>, JsValue> {
+    climate.require_annual("treeline_boundary")?;
+    let tundra: Vec<bool> = (0..CELL_COUNT)
+        .map(|idx| {
+            is_land(climate, idx) && climate.warmest_month_at(idx) < TREELINE_WARMEST_MONTH_C
+        })
+        .collect();
+    let mut boundary = vec![0_u8; CELL_COUNT];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let idx = y * WIDTH + x;
+            if !tundra[idx] {
+                continue;
+            }
+            let neighbours = [
+                y * WIDTH + wrap_x(x as i64 - 1),
+                y * WIDTH + wrap_x(x as i64 + 1),
+                y.saturating_sub(1) * WIDTH + x,
+                (y + 1).min(HEIGHT - 1) * WIDTH + x,
+            ];
+            if neighbours
+                .iter()
+                .any(|&n| is_land(climate, n) && !tundra[n])
+            {
+                boundary[idx] = 1;
+            }
+        }
+    }
+    Ok(boundary.into_boxed_slice())
+}
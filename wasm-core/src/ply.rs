@@ -0,0 +1,57 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::check_grid_len;
+use crate::mesh::{GridMesh, MeshParams};
+use crate::render::{parse_palette, surface_color};
+
+/// Binary little-endian PLY point cloud of the terrain mesh's vertices (positions and
+/// normals laid out as on `MeshParams`), for MeshLab or CloudCompare. With a `biome_map`,
+/// points carry the unlit colours of `render_biome_rgba` from `palette` (legend JSON, or
+/// empty for the built-in colours); pass an empty `biome_map` for uncoloured points. The
+/// mesh's repeated east column is left out.
+#[wasm_bindgen]
+pub fn export_terrain_ply(
+    flat: &[f32],
+    params: &MeshParams,
+    biome_map: &[u8],
+    palette: &str,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let colors = if biome_map.is_empty() {
+        None
+    } else {
+        check_grid_len(biome_map, "biome map")?;
+        Some(parse_palette(palette).map_err(|e| JsValue::from_str(&e))?)
+    };
+    let mesh = GridMesh::build(flat, params);
+    let points: Vec<usize> = (0..mesh.positions.len())
+        .filter(|v| v % mesh.cols != mesh.cols - 1)
+        .collect();
+
+    let mut header = format!(
+        "ply\nformat binary_little_endian 1.0\ncomment Continent-Generator terrain\n\
+         element vertex {}\nproperty float x\nproperty float y\nproperty float z\n\
+         property float nx\nproperty float ny\nproperty float nz\n",
+        points.len()
+    );
+    if colors.is_some() {
+        header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\n");
+    }
+    header.push_str("end_header\n");
+
+    let stride = 24 + if colors.is_some() { 3 } else { 0 };
+    let mut out = header.into_bytes();
+    out.reserve(stride * points.len());
+    for &v in &points {
+        for value in mesh.positions[v].iter().chain(&mesh.normals[v]) {
+            out.extend(value.to_le_bytes());
+        }
+        if let Some(colors) = &colors {
+            let idx = mesh.cells[v];
+            let rgb = surface_color(flat[idx], biome_map[idx], colors);
+            out.extend(rgb.map(|c| c.round().clamp(0.0, 255.0) as u8));
+        }
+    }
+    Ok(out.into_boxed_slice())
+}
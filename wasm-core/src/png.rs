@@ -0,0 +1,364 @@
+use wasm_bindgen::prelude::*;
+
+use crate::deflate::{crc32, zlib_compress, zlib_decompress};
+use crate::fingerprint::quantize;
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+pub(crate) const COLOR_GRAY: u8 = 0;
+pub(crate) const COLOR_RGBA: u8 = 6;
+/// Largest side accepted when decoding.
+const MAX_DECODE_SIDE: usize = 16384;
+/// Most pixels accepted when decoding, an 8192 × 8192 image: about the largest raw import.
+const MAX_DECODE_PIXELS: usize = 8192 * 8192;
+/// Most filtered image data accepted when decoding, so a small IDAT cannot inflate to
+/// gigabytes (a 16384 × 16384 16-bit RGBA header alone would allow 2 GiB).
+const MAX_DECODE_BYTES: usize = 256 << 20;
+/// Longest chunk the PNG spec allows.
+const MAX_CHUNK_LENGTH: u32 = 0x7fff_ffff;
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// `row` filtered with PNG filter `kind` (0 none, 1 sub, 2 up, 3 average, 4 Paeth), where
+/// `bpp` is bytes per pixel and `above` the previous unfiltered row (zeros for the first).
+fn filter_row(kind: u8, row: &[u8], above: &[u8], bpp: usize) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = above[i];
+            let c = if i >= bpp { above[i - bpp] } else { 0 };
+            let predictor = match kind {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            row[i].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+/// PNG file for `pixels`: row-major samples, big-endian for 16-bit, with `color_type`'s
+/// channels interleaved. Each row takes the filter with the smallest sum of absolute
+/// residuals, the usual heuristic for photographic and elevation data.
+pub(crate) fn encode_png(
+    width: usize,
+    height: usize,
+    color_type: u8,
+    bit_depth: u8,
+    pixels: &[u8],
+) -> Vec<u8> {
+    // Grey, RGB, and RGBA.
+    let channels = match color_type {
+        2 => 3,
+        6 => 4,
+        _ => 1,
+    };
+    let bpp = (channels * bit_depth as usize).div_ceil(8);
+    let stride = (width * channels * bit_depth as usize).div_ceil(8);
+    debug_assert_eq!(pixels.len(), stride * height);
+
+    let mut filtered = Vec::with_capacity((stride + 1) * height);
+    let zeros = vec![0_u8; stride];
+    for y in 0..height {
+        let row = &pixels[y * stride..(y + 1) * stride];
+        let above = if y == 0 {
+            &zeros[..]
+        } else {
+            &pixels[(y - 1) * stride..y * stride]
+        };
+        let (kind, best) = (0..=4)
+            .map(|kind| (kind, filter_row(kind, row, above, bpp)))
+            .min_by_key(|(_, f)| {
+                f.iter()
+                    .map(|&r| (r as i8).unsigned_abs() as u64)
+                    .sum::<u64>()
+            })
+            .expect("five filters");
+        filtered.push(kind);
+        filtered.extend(best);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // Bit depth, colour type, deflate compression, adaptive filtering, no interlace.
+    header.extend([bit_depth, color_type, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib_compress(&filtered));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// A decoded image reduced to one channel in [0, 1], row-major.
+pub(crate) struct GrayImage {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) values: Vec<f32>,
+}
+
+/// Decodes an 8- or 16-bit, non-interlaced PNG to gray: grey images as stored, colour ones
+/// as the mean of red, green and blue (heightmaps saved as RGB are grey anyway); alpha is
+/// ignored.
+pub(crate) fn decode_png_gray(bytes: &[u8]) -> Result<GrayImage, String> {
+    if bytes.get(..8) != Some(&SIGNATURE[..]) {
+        return Err("not a PNG file".to_string());
+    }
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut idat = Vec::new();
+    let mut pos = 8;
+    loop {
+        let length = bytes
+            .get(pos..pos + 4)
+            .ok_or("PNG ends before IEND")?
+            .try_into()
+            .map(u32::from_be_bytes)
+            .expect("slice is four bytes");
+        // The PNG spec caps chunk lengths at 2³¹ − 1; larger ones are corrupt and would
+        // wrap the offsets below on 32-bit targets.
+        if length > MAX_CHUNK_LENGTH {
+            return Err("PNG chunk length out of range".to_string());
+        }
+        let length = length as usize;
+        let chunk_end = pos
+            .checked_add(12)
+            .and_then(|end| end.checked_add(length))
+            .ok_or("PNG chunk truncated")?;
+        let chunk = bytes.get(pos + 4..chunk_end).ok_or("PNG chunk truncated")?;
+        let (kind, data) = (&chunk[..4], &chunk[4..4 + length]);
+        let crc = u32::from_be_bytes(chunk[4 + length..].try_into().expect("four bytes"));
+        if crc32(&chunk[..4 + length]) != crc {
+            return Err(format!(
+                "PNG {} chunk CRC mismatch",
+                String::from_utf8_lossy(kind)
+            ));
+        }
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data),
+            b"PLTE" => palette = data,
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos = chunk_end;
+    }
+    let header = header.ok_or("PNG has no IHDR chunk")?;
+    let dimension = |i: usize| u32::from_be_bytes(header[i..i + 4].try_into().expect("four bytes"));
+    let (width, height) = (dimension(0) as usize, dimension(4) as usize);
+    let (bit_depth, color_type, interlace) = (header[8], header[9], header[12]);
+    if !(1..=MAX_DECODE_SIDE).contains(&width) || !(1..=MAX_DECODE_SIDE).contains(&height) {
+        return Err(format!("PNG sides must be within [1, {MAX_DECODE_SIDE}]"));
+    }
+    if width * height > MAX_DECODE_PIXELS {
+        return Err(format!("PNG has more than {MAX_DECODE_PIXELS} pixels"));
+    }
+    if interlace != 0 {
+        return Err("interlaced PNGs are not supported".to_string());
+    }
+    let channels = match color_type {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => return Err(format!("unknown PNG colour type {color_type}")),
+    };
+    if !(bit_depth == 8 || bit_depth == 16 && color_type != 3) {
+        return Err("only 8- and 16-bit PNGs are supported".to_string());
+    }
+    if color_type == 3 && palette.len() < 3 {
+        return Err("palette PNG has no PLTE chunk".to_string());
+    }
+    let bpp = channels * bit_depth as usize / 8;
+    let stride = width * bpp;
+    let raw_len = height
+        .checked_mul(stride + 1)
+        .filter(|&len| len <= MAX_DECODE_BYTES)
+        .ok_or(format!("PNG image data exceeds {MAX_DECODE_BYTES} bytes"))?;
+    let raw = zlib_decompress(&idat, raw_len)?;
+    if raw.len() != raw_len {
+        return Err("PNG image data has the wrong size".to_string());
+    }
+
+    let mut pixels = vec![0_u8; height * stride];
+    for y in 0..height {
+        let kind = raw[y * (stride + 1)];
+        if kind > 4 {
+            return Err(format!("unknown PNG filter type {kind}"));
+        }
+        let filtered = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, rest) = pixels.split_at_mut(y * stride);
+        let above = if y == 0 {
+            None
+        } else {
+            Some(&done[(y - 1) * stride..])
+        };
+        let row = &mut rest[..stride];
+        for i in 0..stride {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = above.map_or(0, |above| above[i]);
+            let c = if i >= bpp {
+                above.map_or(0, |above| above[i - bpp])
+            } else {
+                0
+            };
+            let predictor = match kind {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            row[i] = filtered[i].wrapping_add(predictor);
+        }
+    }
+
+    let max = if bit_depth == 16 { 65535.0 } else { 255.0 };
+    let sample = |i: usize| -> f32 {
+        if bit_depth == 16 {
+            u16::from_be_bytes([pixels[2 * i], pixels[2 * i + 1]]) as f32
+        } else {
+            pixels[i] as f32
+        }
+    };
+    let values = (0..width * height)
+        .map(|p| match color_type {
+            3 => {
+                let entry = pixels[p] as usize * 3;
+                let rgb = palette.get(entry..entry + 3).unwrap_or(&[0, 0, 0]);
+                rgb.iter().map(|&v| v as f32).sum::<f32>() / 3.0 / 255.0
+            }
+            2 | 6 => (0..3).map(|c| sample(p * channels + c)).sum::<f32>() / 3.0 / max,
+            _ => sample(p * channels) / max,
+        })
+        .collect();
+    Ok(GrayImage {
+        width,
+        height,
+        values,
+    })
+}
+
+/// Grayscale PNG of the heightmap at full grid resolution, ready to download. Heights are
+/// clamped to [0, 1] and spread over the full sample range: 16-bit keeps steps of 1/65535
+/// (what terrain tools expect), 8-bit suits previews. Sea level stays at 0.15 of full scale.
+#[wasm_bindgen]
+pub fn export_heightmap_png(flat: &[f32], bit_depth: u32) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    let pixels: Vec<u8> = match bit_depth {
+        8 => flat.iter().map(|&h| quantize(h, 8) as u8).collect(),
+        16 => flat
+            .iter()
+            .flat_map(|&h| quantize(h, 16).to_be_bytes())
+            .collect(),
+        _ => return Err(JsValue::from_str("bit_depth must be 8 or 16")),
+    };
+    Ok(encode_png(WIDTH, HEIGHT, COLOR_GRAY, bit_depth as u8, &pixels).into_boxed_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit grey 3×2 image with the given samples.
+    fn gray16(samples: [u16; 6]) -> Vec<u8> {
+        let pixels: Vec<u8> = samples.iter().flat_map(|v| v.to_be_bytes()).collect();
+        encode_png(3, 2, COLOR_GRAY, 16, &pixels)
+    }
+
+    #[test]
+    fn round_trips_16_bit_gray() {
+        let samples = [0, 1, 1000, 32768, 65534, 65535];
+        let image = decode_png_gray(&gray16(samples)).expect("valid PNG");
+        assert_eq!((image.width, image.height), (3, 2));
+        for (value, sample) in image.values.iter().zip(samples) {
+            assert!((value - sample as f32 / 65535.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn round_trips_8_bit_rgba() {
+        let pixels: Vec<u8> = (0..4 * 4 * 3).map(|i| (i * 7) as u8).collect();
+        let image = decode_png_gray(&encode_png(4, 3, COLOR_RGBA, 8, &pixels)).expect("valid PNG");
+        assert_eq!((image.width, image.height), (4, 3));
+        for (value, px) in image.values.iter().zip(pixels.chunks(4)) {
+            let mean = (px[0] as f32 + px[1] as f32 + px[2] as f32) / 3.0 / 255.0;
+            assert!((value - mean).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let png = gray16([1, 2, 3, 4, 5, 6]);
+        for len in [0, 7, 8, 12, 20, 33, png.len() - 1] {
+            assert!(decode_png_gray(&png[..len]).is_err(), "length {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_chunks() {
+        let png = gray16([1, 2, 3, 4, 5, 6]);
+        // A flipped bit in the IHDR body fails its CRC.
+        let mut bad_crc = png.clone();
+        bad_crc[16] ^= 1;
+        assert!(decode_png_gray(&bad_crc).is_err());
+        // Chunk lengths past 2³¹ − 1, or past the end of the file, must not panic.
+        for length in [u32::MAX, 0xffff_fff8, 0x8000_0000, 0x7fff_ffff] {
+            let mut huge = png.clone();
+            huge[8..12].copy_from_slice(&length.to_be_bytes());
+            assert!(decode_png_gray(&huge).is_err(), "length {length:#x}");
+        }
+        // Garbage image data with a valid CRC.
+        let idat = png.windows(4).position(|w| w == b"IDAT").expect("IDAT") - 4;
+        let length = u32::from_be_bytes(png[idat..idat + 4].try_into().unwrap()) as usize;
+        let mut garbage = png[..idat].to_vec();
+        chunk(&mut garbage, b"IDAT", &vec![0xff; length]);
+        chunk(&mut garbage, b"IEND", &[]);
+        assert!(decode_png_gray(&garbage).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        let image = |width: u32, height: u32, bit_depth: u8, color_type: u8| {
+            let mut header = Vec::new();
+            header.extend(width.to_be_bytes());
+            header.extend(height.to_be_bytes());
+            header.extend([bit_depth, color_type, 0, 0, 0]);
+            let mut png = SIGNATURE.to_vec();
+            chunk(&mut png, b"IHDR", &header);
+            chunk(&mut png, b"IDAT", &zlib_compress(&[0; 16]));
+            chunk(&mut png, b"IEND", &[]);
+            decode_png_gray(&png).err().unwrap_or_default()
+        };
+        assert!(image(16384, 16384, 16, COLOR_RGBA).contains("pixels"));
+        assert!(image(8192, 8192, 16, COLOR_RGBA).contains("exceeds"));
+        // Within both caps, the tiny IDAT is merely the wrong size.
+        assert!(image(4096, 4096, 8, COLOR_GRAY).contains("wrong size"));
+    }
+}
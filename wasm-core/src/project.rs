@@ -0,0 +1,158 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::NO_BIOME_OVERRIDE;
+use crate::deflate::{zlib_compress, zlib_decompress};
+use crate::editor::TerrainEditor;
+use crate::grid::CELL_COUNT;
+use crate::json::{self, Json, ObjectWriter};
+use crate::snapshots::{Snapshot, pack_heights, unpack_heights};
+
+const PROJECT_MAGIC: &[u8; 4] = b"CGPJ";
+const PROJECT_VERSION: u8 = 1;
+const PROJECT_HEADER_LEN: usize = 12;
+
+/// What a project file holds: the editor's heightmap, sea level, parameters and biome
+/// overrides, and its named snapshots. Undo history is not saved.
+pub(crate) struct Project {
+    pub(crate) heights: Vec<f32>,
+    pub(crate) sea_level: f32,
+    pub(crate) params: Json,
+    pub(crate) snapshots: Vec<Snapshot>,
+    pub(crate) biome_overrides: Vec<u8>,
+}
+
+/// Appends `bytes` to `blobs` and returns its `[offset, length]` there as JSON.
+fn push_blob(blobs: &mut Vec<u8>, bytes: &[u8]) -> String {
+    let span = json::array([blobs.len(), bytes.len()]);
+    blobs.extend(bytes);
+    span
+}
+
+impl Project {
+    /// The magic `CGPJ`, u8 version (1), three zero bytes and the u32 length of a JSON
+    /// manifest, then the manifest and the binary blobs it points into by
+    /// `[offset, length]` from the end of the manifest:
+    /// `{"sea_level","params","heightmap","biome_overrides","snapshots":[{"name",
+    /// "sea_level","params","heights","thumbnail"},...]}`. Heightmaps are packed as
+    /// `pack_heights` does, biome overrides are a zlib stream of one byte per cell, and
+    /// thumbnails are PNG files. Files without `biome_overrides` open with none.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut blobs = Vec::new();
+        let heightmap = push_blob(&mut blobs, &pack_heights(&self.heights));
+        let overrides = push_blob(&mut blobs, &zlib_compress(&self.biome_overrides));
+        let snapshots: Vec<String> = self
+            .snapshots
+            .iter()
+            .map(|s| {
+                ObjectWriter::new()
+                    .raw("name", &json::quote(&s.name))
+                    .raw("sea_level", &s.sea_level.to_string())
+                    .raw("params", &s.params.to_string())
+                    .raw("heights", &push_blob(&mut blobs, &s.heights))
+                    .raw("thumbnail", &push_blob(&mut blobs, &s.thumbnail))
+                    .finish()
+            })
+            .collect();
+        let manifest = ObjectWriter::new()
+            .raw("sea_level", &self.sea_level.to_string())
+            .raw("params", &self.params.to_string())
+            .raw("heightmap", &heightmap)
+            .raw("biome_overrides", &overrides)
+            .raw("snapshots", &json::array(snapshots))
+            .finish();
+        let mut out = Vec::with_capacity(PROJECT_HEADER_LEN + manifest.len() + blobs.len());
+        out.extend(PROJECT_MAGIC);
+        out.extend([PROJECT_VERSION, 0, 0, 0]);
+        out.extend((manifest.len() as u32).to_le_bytes());
+        out.extend(manifest.as_bytes());
+        out.extend(blobs);
+        out
+    }
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Project, String> {
+        let header = bytes
+            .get(..PROJECT_HEADER_LEN)
+            .filter(|h| &h[..4] == PROJECT_MAGIC)
+            .ok_or("not a project file")?;
+        if header[4] != PROJECT_VERSION {
+            return Err("unsupported project version".to_string());
+        }
+        let manifest_len = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        let manifest_end = PROJECT_HEADER_LEN + manifest_len as usize;
+        let manifest = bytes
+            .get(PROJECT_HEADER_LEN..manifest_end)
+            .and_then(|m| std::str::from_utf8(m).ok())
+            .ok_or("project manifest truncated")?;
+        let manifest = json::parse(manifest)?;
+        let blobs = &bytes[manifest_end..];
+        let blob = |span: Option<&Json>| -> Result<&[u8], String> {
+            let span = span.and_then(Json::as_array).unwrap_or_default();
+            match span {
+                [Json::Number(offset), Json::Number(len)] => {
+                    let (offset, len) = (*offset as usize, *len as usize);
+                    blobs
+                        .get(offset..offset.saturating_add(len))
+                        .ok_or_else(|| "project blob out of range".to_string())
+                }
+                _ => Err("project blob must be [offset, length]".to_string()),
+            }
+        };
+        let sea_level = |value: &Json| {
+            value
+                .get("sea_level")
+                .and_then(Json::as_f64)
+                .filter(|s| s.is_finite() && *s < 1.0)
+                .map(|s| s as f32)
+                .ok_or("project sea_level must be a number below 1")
+        };
+        let params = |value: &Json| value.get("params").cloned().unwrap_or(Json::Null);
+        let snapshots = manifest
+            .get("snapshots")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .map(|s| {
+                let heights = blob(s.get("heights"))?;
+                // Unpack once so a damaged snapshot fails the import, not a later restore.
+                unpack_heights(heights)?;
+                Ok(Snapshot {
+                    name: s
+                        .get("name")
+                        .and_then(Json::as_str)
+                        .ok_or("snapshot name must be a string")?
+                        .to_owned(),
+                    sea_level: sea_level(s)?,
+                    params: params(s),
+                    heights: heights.to_vec(),
+                    thumbnail: blob(s.get("thumbnail"))?.to_vec(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let biome_overrides = match manifest.get("biome_overrides") {
+            Some(span) => {
+                let overrides = zlib_decompress(blob(Some(span))?, CELL_COUNT)?;
+                if overrides.len() != CELL_COUNT {
+                    return Err("biome overrides length mismatch".to_string());
+                }
+                overrides
+            }
+            None => vec![NO_BIOME_OVERRIDE; CELL_COUNT],
+        };
+        Ok(Project {
+            heights: unpack_heights(blob(manifest.get("heightmap"))?)?,
+            sea_level: sea_level(&manifest)?,
+            params: params(&manifest),
+            snapshots,
+            biome_overrides,
+        })
+    }
+}
+
+/// Opens a project file written by `TerrainEditor::export_project` as a new editor, with
+/// its heightmap, sea level, parameters, biome overrides and snapshots and an empty undo
+/// history.
+#[wasm_bindgen]
+pub fn import_project(bytes: &[u8]) -> Result<TerrainEditor, JsValue> {
+    let project = Project::parse(bytes).map_err(|e| JsValue::from_str(&e))?;
+    Ok(TerrainEditor::from_project(project))
+}
@@ -0,0 +1,183 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, check_grid_len, sample_bilinear};
+
+/// Latitude where the Web Mercator world becomes square, degrees.
+pub(crate) const WEB_MERCATOR_MAX_LATITUDE: f32 = 85.051_13;
+
+/// `plane` (grid-sized) resampled bilinearly onto a `width` × `height` Web Mercator image
+/// spanning every longitude and latitudes up to ±`max_latitude`, north row first.
+pub(crate) fn mercator_resample(
+    plane: &[f32],
+    width: usize,
+    height: usize,
+    max_latitude: f32,
+) -> Vec<f32> {
+    let y_max = max_latitude.to_radians().tan().asinh();
+    // Grid row of each output row, and grid column of each output column.
+    let rows: Vec<f32> = (0..height)
+        .map(|py| {
+            let y = y_max * (1.0 - 2.0 * (py as f32 + 0.5) / height as f32);
+            let lat = y.sinh().atan().to_degrees();
+            (90.0 - lat) / 180.0 * HEIGHT as f32 - 0.5
+        })
+        .collect();
+    let sx = WIDTH as f32 / width as f32;
+    (0..width * height)
+        .map(|i| {
+            let (px, py) = (i % width, i / width);
+            sample_bilinear(plane, (px as f32 + 0.5) * sx - 0.5, rows[py])
+        })
+        .collect()
+}
+
+fn check_mercator_size(width: u32, height: u32, max_latitude: f32) -> Result<(), JsValue> {
+    if !(1..=8192).contains(&width) || !(1..=8192).contains(&height) {
+        return Err(JsValue::from_str(
+            "width and height must be within [1, 8192]",
+        ));
+    }
+    if !(max_latitude > 0.0 && max_latitude <= 89.9) {
+        return Err(JsValue::from_str("max_latitude must be within (0, 89.9]"));
+    }
+    Ok(())
+}
+
+/// Heightmap reprojected to Web Mercator (EPSG:3857) at `width` × `height`, covering
+/// latitudes up to ±`max_latitude`; the poles themselves are unreachable. With a square
+/// size and `max_latitude` 85.05113 this is the standard web-map world square that tile
+/// servers and GIS tools expect.
+#[wasm_bindgen]
+pub fn reproject_web_mercator(
+    flat: &[f32],
+    width: u32,
+    height: u32,
+    max_latitude: f32,
+) -> Result<Box<[f32]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_mercator_size(width, height, max_latitude)?;
+    Ok(mercator_resample(flat, width as usize, height as usize, max_latitude).into_boxed_slice())
+}
+
+/// RGBA image (as from `render_biome_rgba`) reprojected like `reproject_web_mercator`.
+#[wasm_bindgen]
+pub fn reproject_rgba_web_mercator(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    max_latitude: f32,
+) -> Result<Box<[u8]>, JsValue> {
+    if rgba.len() != CELL_COUNT * 4 {
+        return Err(JsValue::from_str("rgba must be 2048x1024x4 bytes"));
+    }
+    check_mercator_size(width, height, max_latitude)?;
+    let (width, height) = (width as usize, height as usize);
+    let planes: Vec<Vec<f32>> = (0..4)
+        .map(|c| {
+            let plane: Vec<f32> = rgba.iter().skip(c).step_by(4).map(|&v| v as f32).collect();
+            mercator_resample(&plane, width, height, max_latitude)
+        })
+        .collect();
+    let out: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            planes
+                .iter()
+                .map(move |p| p[i].round().clamp(0.0, 255.0) as u8)
+        })
+        .collect();
+    Ok(out.into_boxed_slice())
+}
+
+/// View for `render_globe_rgba`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct GlobeParams {
+    /// Side of the square output image, pixels; the globe fills it edge to edge.
+    pub size: u32,
+    /// Latitude and longitude at the centre of the view, degrees.
+    pub center_lat: f32,
+    pub center_lon: f32,
+    /// Darkening toward the limb, 0 (flat) to 1 (black at the edge), for a rounded look.
+    pub limb_shading: f32,
+}
+
+impl Default for GlobeParams {
+    fn default() -> Self {
+        Self {
+            size: 512,
+            center_lat: 20.0,
+            center_lon: 0.0,
+            limb_shading: 0.35,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GlobeParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GlobeParams {
+        Self::default()
+    }
+}
+
+impl GlobeParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(16..=4096).contains(&self.size) {
+            return Err(JsValue::from_str("size must be within [16, 4096]"));
+        }
+        if !(-90.0..=90.0).contains(&self.center_lat) || !self.center_lon.is_finite() {
+            return Err(JsValue::from_str(
+                "center_lat must be within [-90, 90] and center_lon finite",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.limb_shading) {
+            return Err(JsValue::from_str("limb_shading must be within [0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+/// The map (RGBA, as from `render_biome_rgba`) seen as a globe from far away: an
+/// orthographic projection centred on `center_lat`/`center_lon`, `size` pixels square,
+/// transparent outside the disc with an antialiased rim.
+#[wasm_bindgen]
+pub fn render_globe_rgba(rgba: &[u8], params: &GlobeParams) -> Result<Box<[u8]>, JsValue> {
+    if rgba.len() != CELL_COUNT * 4 {
+        return Err(JsValue::from_str("rgba must be 2048x1024x4 bytes"));
+    }
+    params.validate()?;
+    let planes: Vec<Vec<f32>> = (0..4)
+        .map(|c| rgba.iter().skip(c).step_by(4).map(|&v| v as f32).collect())
+        .collect();
+    let size = params.size as usize;
+    let radius = size as f32 / 2.0;
+    let (sin_lat0, cos_lat0) = params.center_lat.to_radians().sin_cos();
+    let mut out = vec![0_u8; size * size * 4];
+    for (i, px) in out.chunks_exact_mut(4).enumerate() {
+        // View-plane coordinates with y up, the globe's rim at distance 1.
+        let x = ((i % size) as f32 + 0.5 - radius) / radius;
+        let y = (radius - (i / size) as f32 - 0.5) / radius;
+        let rho = x.hypot(y);
+        let coverage = ((1.0 - rho) * radius + 0.5).clamp(0.0, 1.0);
+        if coverage == 0.0 {
+            continue;
+        }
+        let z = (1.0 - rho * rho).max(0.0).sqrt();
+        let lat = (z * sin_lat0 + y * cos_lat0)
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees();
+        let lon = params.center_lon + x.atan2(z * cos_lat0 - y * sin_lat0).to_degrees();
+        let fx = (lon + 180.0) / 360.0 * WIDTH as f32 - 0.5;
+        let fy = (90.0 - lat) / 180.0 * HEIGHT as f32 - 0.5;
+        let shade = 1.0 - params.limb_shading * (1.0 - z);
+        for c in 0..3 {
+            let v = sample_bilinear(&planes[c], fx, fy) * shade;
+            px[c] = v.round().clamp(0.0, 255.0) as u8;
+        }
+        px[3] = (sample_bilinear(&planes[3], fx, fy) * coverage)
+            .round()
+            .clamp(0.0, 255.0) as u8;
+    }
+    Ok(out.into_boxed_slice())
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::flow::{cell_order, is_below, ring};
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, latitude_deg};
+use crate::vector::{block_size_km, grid_to_lon_lat};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+fn find(parent: &mut [u32], mut idx: u32) -> u32 {
+    while parent[idx as usize] != idx {
+        let grandparent = parent[parent[idx as usize] as usize];
+        parent[idx as usize] = grandparent;
+        idx = grandparent;
+    }
+    idx
+}
+
+/// Topographic prominence of each of `peaks` (elevation units): its height above the
+/// highest col from which higher ground can be reached. Land is flooded from the top down
+/// (8-connected, wrapping east–west); where two flooded regions meet, the one with the
+/// lower summit records the meeting cell as its key col. Summits never joined to higher
+/// ground, including the highest one, take the sea as their col.
+pub(crate) fn peak_prominence(flat: &[f32], sea_level: f32, peaks: &[u32]) -> Vec<f32> {
+    let mut order: Vec<u32> = (0..CELL_COUNT as u32)
+        .filter(|&i| flat[i as usize] >= sea_level)
+        .collect();
+    order.sort_unstable_by(|&a, &b| cell_order(flat, b as usize, a as usize));
+    let mut parent: Vec<u32> = (0..CELL_COUNT as u32).collect();
+    let summit: Vec<u32> = (0..CELL_COUNT as u32).collect();
+    let mut flooded = vec![false; CELL_COUNT];
+    let mut prominence: HashMap<u32, f32> = HashMap::new();
+    for &cell in &order {
+        let mut roots: Vec<u32> = ring(cell as usize)
+            .into_iter()
+            .flatten()
+            .filter(|&n| flooded[n])
+            .map(|n| find(&mut parent, n as u32))
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+        flooded[cell as usize] = true;
+        let Some(&highest) = roots.iter().max_by(|&&a, &&b| {
+            cell_order(
+                flat,
+                summit[a as usize] as usize,
+                summit[b as usize] as usize,
+            )
+        }) else {
+            continue;
+        };
+        for &root in &roots {
+            if root != highest {
+                let top = summit[root as usize];
+                prominence.insert(top, flat[top as usize] - flat[cell as usize]);
+                parent[root as usize] = highest;
+            }
+        }
+        parent[cell as usize] = highest;
+    }
+    peaks
+        .iter()
+        .map(|&p| {
+            prominence
+                .get(&p)
+                .copied()
+                .unwrap_or(flat[p as usize] - sea_level)
+        })
+        .collect()
+}
+
+fn great_circle_km(a: usize, b: usize) -> f64 {
+    let centre = |i: usize| {
+        let (lon, lat) = grid_to_lon_lat((i % WIDTH) as f32 + 0.5, (i / WIDTH) as f32 + 0.5);
+        ((lon as f64).to_radians(), (lat as f64).to_radians())
+    };
+    let ((lon1, lat1), (lon2, lat2)) = (centre(a), centre(b));
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+}
+
+/// Topographic isolation of each of `peaks`: great-circle distance (km) to the nearest
+/// higher cell, searched in growing square rings until no closer cell can remain.
+/// The highest point on the map reports infinity.
+pub(crate) fn peak_isolation_km(flat: &[f32], peaks: &[u32]) -> Vec<f32> {
+    peaks
+        .iter()
+        .map(|&peak| {
+            let peak = peak as usize;
+            let (px, py) = ((peak % WIDTH) as i64, (peak / WIDTH) as i64);
+            let mut best = f64::INFINITY;
+            for r in 1..=(WIDTH / 2) as i64 {
+                // Cells in ring `r` are at least `r` rows or `r` columns away; columns are
+                // narrowest at whichever end row lies nearer a pole.
+                let (top, bottom) = ((py - r).max(0), (py + r).min(HEIGHT as i64 - 1));
+                let (_, row_km) = block_size_km(0.0, 1, 1);
+                let col_km = [top, bottom]
+                    .map(|y| block_size_km(latitude_deg(y as usize) as f64, 1, 1).0)
+                    .into_iter()
+                    .fold(f64::INFINITY, f64::min);
+                if r as f64 * row_km.min(col_km) >= best {
+                    break;
+                }
+                for ny in top..=bottom {
+                    let on_edge_row = (ny - py).abs() == r;
+                    let step = if on_edge_row { 1 } else { 2 * r as usize };
+                    for nx in (px - r..=px + r).step_by(step) {
+                        let n = ny as usize * WIDTH + nx.rem_euclid(WIDTH as i64) as usize;
+                        if is_below(flat, peak, n) {
+                            best = best.min(great_circle_km(peak, n));
+                        }
+                    }
+                }
+            }
+            best as f32
+        })
+        .collect()
+}
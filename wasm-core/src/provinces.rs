@@ -0,0 +1,389 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::f64::consts::TAU;
+
+use wasm_bindgen::prelude::*;
+
+use crate::flow::{Flooded, ring};
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, latitude_deg};
+use crate::json::{self, ObjectWriter};
+use crate::landmass::label_landmasses;
+use crate::noise::seeded_hash_2d;
+use crate::vector::{block_size_km, cell_area_km2, grid_to_lon_lat};
+
+/// Most provinces `partition_provinces` makes; ids must fit the `u16` province map.
+const MAX_PROVINCES: usize = u16::MAX as usize;
+
+/// Province size and relaxation for `partition_provinces`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct ProvinceParams {
+    pub sea_level: f32,
+    /// Area each province aims for, km²; every connected piece of a region gets
+    /// area / this provinces, rounded, and at least one.
+    pub province_area_km2: f32,
+    /// Relaxation rounds: each moves every province's seat towards its centroid and
+    /// reweights it towards the target area.
+    pub iterations: u32,
+    /// Picks the starting seats; the same seed gives the same provinces.
+    pub seed: u32,
+}
+
+impl Default for ProvinceParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            province_area_km2: 250_000.0,
+            iterations: 8,
+            seed: crate::DEFAULT_SEED,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ProvinceParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ProvinceParams {
+        Self::default()
+    }
+}
+
+impl ProvinceParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !self.province_area_km2.is_finite() || self.province_area_km2 <= 0.0 {
+            return Err(JsValue::from_str("province_area_km2 must be > 0"));
+        }
+        if self.iterations > 64 {
+            return Err(JsValue::from_str("iterations must be <= 64"));
+        }
+        Ok(())
+    }
+}
+
+/// Labels 8-connected pieces of equal nonzero region, joining across the east–west seam.
+/// Returns per-cell labels (0 = outside every region, pieces numbered from 1) and the
+/// piece count.
+fn label_parts(region: &[u32]) -> (Vec<u32>, u32) {
+    let mut parts = vec![0_u32; CELL_COUNT];
+    let mut count = 0;
+    let mut stack = Vec::new();
+    for start in 0..CELL_COUNT {
+        if parts[start] != 0 || region[start] == 0 {
+            continue;
+        }
+        count += 1;
+        parts[start] = count;
+        stack.push(start);
+        while let Some(idx) = stack.pop() {
+            for n in ring(idx).into_iter().flatten() {
+                if parts[n] == 0 && region[n] == region[idx] {
+                    parts[n] = count;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+    (parts, count)
+}
+
+/// Multiplicatively weighted geodesic Voronoi: every cell goes to the seat it is nearest
+/// to in km times the seat's weight, measured within the cell's own piece only.
+fn grow(parts: &[u32], seats: &[usize], weights: &[f32]) -> Vec<u16> {
+    let mut cost = vec![f32::INFINITY; CELL_COUNT];
+    let mut owner = vec![0_u16; CELL_COUNT];
+    let mut queue = BinaryHeap::new();
+    let row_km: Vec<(f32, f32)> = (0..HEIGHT)
+        .map(|y| {
+            let (ew, ns) = block_size_km(latitude_deg(y) as f64, 1, 1);
+            (ew as f32, ns as f32)
+        })
+        .collect();
+    for (i, &seat) in seats.iter().enumerate() {
+        cost[seat] = 0.0;
+        owner[seat] = i as u16 + 1;
+        queue.push(Reverse(Flooded(0.0, seat)));
+    }
+    while let Some(Reverse(Flooded(c, idx))) = queue.pop() {
+        if c > cost[idx] {
+            continue;
+        }
+        let weight = weights[owner[idx] as usize - 1];
+        for (i, n) in ring(idx).into_iter().enumerate() {
+            let Some(n) = n else {
+                continue;
+            };
+            if parts[n] != parts[idx] {
+                continue;
+            }
+            let (ew, ns) = row_km[idx / WIDTH];
+            let step = match i % 4 {
+                0 => ns,
+                2 => ew,
+                _ => ew.hypot(ns),
+            };
+            let next = c + step * weight;
+            if next < cost[n] {
+                cost[n] = next;
+                owner[n] = owner[idx];
+                queue.push(Reverse(Flooded(next, n)));
+            }
+        }
+    }
+    owner
+}
+
+struct Province {
+    seat: usize,
+    region: u32,
+    cells: u32,
+    area_km2: f64,
+}
+
+/// Provinces made by `partition_provinces`.
+#[wasm_bindgen]
+pub struct Provinces {
+    /// Province id per cell, from 1; 0 for sea and land outside every region.
+    labels: Vec<u16>,
+    provinces: Vec<Province>,
+    /// Neighbouring province pairs, lower id first, with the length of their shared edge.
+    adjacency: Vec<((u16, u16), f64)>,
+}
+
+#[wasm_bindgen]
+impl Provinces {
+    /// Province id per cell, from 1; 0 for sea and land outside every region.
+    pub fn province_map(&self) -> Box<[u16]> {
+        self.labels.clone().into_boxed_slice()
+    }
+
+    /// `[{"id","region","x","y","lon","lat","cells","area_km2"},...]` in id order, with the
+    /// province's seat (its most central cell) at `x`, `y` in cell-centre grid coordinates
+    /// and `region` the nation or landmass it subdivides.
+    pub fn provinces_json(&self) -> String {
+        json::array(self.provinces.iter().enumerate().map(|(i, p)| {
+            let (x, y) = ((p.seat % WIDTH) as f32 + 0.5, (p.seat / WIDTH) as f32 + 0.5);
+            let (lon, lat) = grid_to_lon_lat(x, y);
+            ObjectWriter::new()
+                .integer("id", i as u64 + 1)
+                .integer("region", p.region as u64)
+                .number("x", x as f64, 1)
+                .number("y", y as f64, 1)
+                .number("lon", lon as f64, 3)
+                .number("lat", lat as f64, 3)
+                .integer("cells", p.cells as u64)
+                .number("area_km2", p.area_km2, 0)
+                .finish()
+        }))
+    }
+
+    /// `[{"a","b","border_km"},...]`: every pair of provinces sharing a cell edge, `a < b`,
+    /// across region borders too, with the length of the shared edge.
+    pub fn adjacency_json(&self) -> String {
+        json::array(self.adjacency.iter().map(|&((a, b), km)| {
+            ObjectWriter::new()
+                .integer("a", a as u64)
+                .integer("b", b as u64)
+                .number("border_km", km, 1)
+                .finish()
+        }))
+    }
+
+    /// The adjacency graph as flat `a, b` pairs in the order of `adjacency_json`.
+    pub fn adjacency(&self) -> Box<[u16]> {
+        self.adjacency
+            .iter()
+            .flat_map(|&((a, b), _)| [a, b])
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.provinces.len() as u32
+    }
+}
+
+/// Divides each connected piece of each nonzero region into pieces of roughly `target`
+/// km² by a weighted Voronoi partition grown within the piece: seats start at seeded
+/// random cells and each relaxation round moves them towards their centroids while
+/// tuning each part's weight towards the target area. Returns the part id per cell (from
+/// 1, 0 outside every region) and each part's seat, or `None` past `MAX_PROVINCES` parts.
+pub(crate) fn voronoi_partition(
+    region: &[u32],
+    target: f64,
+    iterations: u32,
+    seed: u32,
+) -> Option<(Vec<u16>, Vec<usize>)> {
+    let (parts, part_count) = label_parts(region);
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); part_count as usize];
+    let mut part_area = vec![0.0_f64; part_count as usize];
+    for (idx, &part) in parts.iter().enumerate() {
+        if part != 0 {
+            members[part as usize - 1].push(idx);
+            part_area[part as usize - 1] += cell_area_km2(idx / WIDTH);
+        }
+    }
+    let counts: Vec<usize> = part_area
+        .iter()
+        .zip(&members)
+        .map(|(&area, cells)| ((area / target).round() as usize).clamp(1, cells.len()))
+        .collect();
+    if counts.iter().sum::<usize>() > MAX_PROVINCES {
+        return None;
+    }
+
+    // Starting seats: the cells of each piece in seeded random order.
+    let mut seats = Vec::new();
+    let mut province_part = Vec::new();
+    for (part, cells) in members.iter_mut().enumerate() {
+        let hash = |idx: usize| seeded_hash_2d((idx % WIDTH) as u32, (idx / WIDTH) as u32, seed);
+        cells.sort_unstable_by_key(|&idx| (hash(idx), idx));
+        seats.extend_from_slice(&cells[..counts[part]]);
+        province_part.extend(std::iter::repeat_n(part, counts[part]));
+    }
+    let targets: Vec<f64> = province_part
+        .iter()
+        .map(|&part| part_area[part] / counts[part] as f64)
+        .collect();
+    let mut weights = vec![1.0_f32; seats.len()];
+
+    let mut labels = grow(&parts, &seats, &weights);
+    for _ in 0..iterations {
+        // Area-weighted centroids, with x averaged round the circle so provinces on the
+        // seam stay whole.
+        let mut sums = vec![(0.0_f64, 0.0_f64, 0.0_f64, 0.0_f64); seats.len()];
+        for (idx, &id) in labels.iter().enumerate() {
+            if id == 0 {
+                continue;
+            }
+            let (x, y) = ((idx % WIDTH) as f64 + 0.5, (idx / WIDTH) as f64 + 0.5);
+            let area = cell_area_km2(idx / WIDTH);
+            let angle = x / WIDTH as f64 * TAU;
+            let sum = &mut sums[id as usize - 1];
+            sum.0 += area;
+            sum.1 += area * angle.cos();
+            sum.2 += area * angle.sin();
+            sum.3 += area * y;
+        }
+        let centroids: Vec<(f64, f64)> = sums
+            .iter()
+            .map(|&(area, cos, sin, y)| {
+                let x = (sin.atan2(cos) / TAU * WIDTH as f64).rem_euclid(WIDTH as f64);
+                (x, y / area.max(f64::MIN_POSITIVE))
+            })
+            .collect();
+        let mut nearest = vec![(f64::INFINITY, 0_usize); seats.len()];
+        for (idx, &id) in labels.iter().enumerate() {
+            if id == 0 {
+                continue;
+            }
+            let (cx, cy) = centroids[id as usize - 1];
+            let dx = ((idx % WIDTH) as f64 + 0.5 - cx).abs();
+            let dy = (idx / WIDTH) as f64 + 0.5 - cy;
+            let d = dx.min(WIDTH as f64 - dx).powi(2) + dy * dy;
+            let best = &mut nearest[id as usize - 1];
+            if d < best.0 {
+                *best = (d, idx);
+            }
+        }
+        for (i, &(area, ..)) in sums.iter().enumerate() {
+            if nearest[i].0.is_finite() {
+                seats[i] = nearest[i].1;
+            }
+            // A province's area goes with 1 / weight², so the square root of the overshoot
+            // would bring it to target at once; the fourth root lets neighbours settle
+            // together.
+            let ratio = (area / targets[i]).clamp(0.5, 2.0);
+            weights[i] *= ratio.sqrt().sqrt() as f32;
+        }
+        labels = grow(&parts, &seats, &weights);
+    }
+    Some((labels, seats))
+}
+
+/// Splits land into provinces of roughly `province_area_km2`: each connected piece of each
+/// region (the nation map from `Nations::nation_map`, or every landmass when `regions` is
+/// empty) is divided by a Voronoi partition grown over its own land, so provinces never
+/// straddle a region border or a strait. Seats start at seeded random cells and are
+/// relaxed towards their centroids while each province's weight is tuned towards the
+/// target area. Region 0 (unclaimed land) gets no provinces.
+#[wasm_bindgen]
+pub fn partition_provinces(
+    flat: &[f32],
+    regions: &[u16],
+    params: &ProvinceParams,
+) -> Result<Provinces, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let region: Vec<u32> = if regions.is_empty() {
+        label_landmasses(flat, params.sea_level).0
+    } else {
+        check_grid_len(regions, "region map")?;
+        (0..CELL_COUNT)
+            .map(|i| {
+                if flat[i] >= params.sea_level {
+                    regions[i] as u32
+                } else {
+                    0
+                }
+            })
+            .collect()
+    };
+
+    let Some((labels, seats)) = voronoi_partition(
+        &region,
+        params.province_area_km2 as f64,
+        params.iterations,
+        params.seed,
+    ) else {
+        return Err(JsValue::from_str(&format!(
+            "more than {MAX_PROVINCES} provinces; raise province_area_km2"
+        )));
+    };
+
+    let mut provinces: Vec<Province> = seats
+        .iter()
+        .map(|&seat| Province {
+            seat,
+            region: region[seat],
+            cells: 0,
+            area_km2: 0.0,
+        })
+        .collect();
+    let row_km: Vec<(f64, f64)> = (0..HEIGHT)
+        .map(|y| block_size_km(latitude_deg(y) as f64, 1, 1))
+        .collect();
+    let mut shared: HashMap<(u16, u16), f64> = HashMap::new();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let here = labels[y * WIDTH + x];
+            if here == 0 {
+                continue;
+            }
+            let province = &mut provinces[here as usize - 1];
+            province.cells += 1;
+            province.area_km2 += cell_area_km2(y);
+            let east = labels[y * WIDTH + (x + 1) % WIDTH];
+            if east != 0 && east != here {
+                *shared.entry((here.min(east), here.max(east))).or_default() += row_km[y].1;
+            }
+            if y + 1 < HEIGHT {
+                let south = labels[(y + 1) * WIDTH + x];
+                if south != 0 && south != here {
+                    *shared
+                        .entry((here.min(south), here.max(south)))
+                        .or_default() += (row_km[y].0 + row_km[y + 1].0) / 2.0;
+                }
+            }
+        }
+    }
+    let mut adjacency: Vec<((u16, u16), f64)> = shared.into_iter().collect();
+    adjacency.sort_by_key(|&(pair, _)| pair);
+    Ok(Provinces {
+        labels,
+        provinces,
+        adjacency,
+    })
+}
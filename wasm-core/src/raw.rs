@@ -0,0 +1,207 @@
+use wasm_bindgen::prelude::*;
+
+use crate::deflate::{zlib_compress, zlib_decompress};
+use crate::fingerprint::quantize;
+use crate::grid::check_grid_len;
+use crate::resample::{Filter, resample_grid};
+
+/// Largest side accepted by `export_heightmap_raw`, Unity's and Unreal's largest terrains.
+const MAX_RAW_SIDE: u32 = 8193;
+const PACKED_MAGIC: &[u8; 4] = b"CGRL";
+const PACKED_VERSION: u8 = 1;
+const PACKED_HEADER_LEN: usize = 16;
+/// Sample types of a packed layer: id, name and bytes per sample.
+const DTYPES: [(u8, &str, usize); 3] = [(1, "u8", 1), (2, "u16", 2), (3, "f32", 4)];
+const CODEC_NONE: u8 = 0;
+const CODEC_DEFLATE: u8 = 1;
+const FILTER_NONE: u8 = 0;
+const FILTER_DELTA_PLANES: u8 = 1;
+
+/// Headerless little-endian heightmap for game-engine terrain import, north row first:
+/// `bit_depth` 16 writes u16 over [0, 1] (Unity "16 bit, Windows" and Unreal `.r16`), 32
+/// writes f32 heightmap values (`.r32`). The map is resampled to `width` × `height`: Unity
+/// wants 2ⁿ + 1 squares such as 2049 × 2049, Unreal prefers its landscape sizes such as
+/// 2017 × 2017 or 4033 × 4033. Pass the grid size (2048 × 1024) to export it unchanged.
+#[wasm_bindgen]
+pub fn export_heightmap_raw(
+    flat: &[f32],
+    bit_depth: u32,
+    width: u32,
+    height: u32,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(1..=MAX_RAW_SIDE).contains(&width) || !(1..=MAX_RAW_SIDE).contains(&height) {
+        return Err(JsValue::from_str(&format!(
+            "width and height must be within [1, {MAX_RAW_SIDE}]"
+        )));
+    }
+    let samples = resample_grid(flat, width as usize, height as usize, Filter::Bilinear);
+    let bytes: Vec<u8> = match bit_depth {
+        16 => samples
+            .iter()
+            .flat_map(|&h| quantize(h, 16).to_le_bytes())
+            .collect(),
+        32 => samples.iter().flat_map(|h| h.to_le_bytes()).collect(),
+        _ => return Err(JsValue::from_str("bit_depth must be 16 or 32")),
+    };
+    Ok(bytes.into_boxed_slice())
+}
+
+/// Samples delta-coded along the array (wrapping, as `size`-byte unsigned integers), then
+/// split into byte planes, so that the near-constant high bytes of neighbouring samples
+/// line up for deflate.
+pub(crate) fn delta_planes(raw: &[u8], size: usize) -> Vec<u8> {
+    let count = raw.len() / size;
+    let mask = if size == 4 {
+        u32::MAX
+    } else {
+        (1 << (8 * size)) - 1
+    };
+    let mut planes = vec![0_u8; raw.len()];
+    let mut previous = 0_u32;
+    for (i, sample) in raw.chunks_exact(size).enumerate() {
+        let value = sample.iter().rev().fold(0_u32, |v, &b| v << 8 | b as u32);
+        let delta = value.wrapping_sub(previous) & mask;
+        previous = value;
+        for (plane, b) in delta.to_le_bytes()[..size].iter().enumerate() {
+            planes[plane * count + i] = *b;
+        }
+    }
+    planes
+}
+
+/// Inverse of `delta_planes`.
+pub(crate) fn undelta_planes(planes: &[u8], size: usize) -> Vec<u8> {
+    let count = planes.len() / size;
+    let mask = if size == 4 {
+        u32::MAX
+    } else {
+        (1 << (8 * size)) - 1
+    };
+    let mut raw = Vec::with_capacity(planes.len());
+    let mut previous = 0_u32;
+    for i in 0..count {
+        let delta = (0..size)
+            .rev()
+            .fold(0_u32, |v, plane| v << 8 | planes[plane * count + i] as u32);
+        previous = previous.wrapping_add(delta) & mask;
+        raw.extend(&previous.to_le_bytes()[..size]);
+    }
+    raw
+}
+
+/// Raw layer bytes (row-major little-endian samples of `dtype`: `"u8"`, `"u16"` or
+/// `"f32"`) behind a 16-byte header, optionally compressed, for smaller downloads of
+/// `export_heightmap_raw`, biome and class maps or any float layer. The header is the
+/// magic `CGRL`, then u8 version (1), dtype (1 u8, 2 u16, 3 f32), codec (0 none,
+/// 1 deflate) and filter, and u32 width and height. `codec` is `"none"` or `"deflate"`;
+/// deflate stores a zlib stream of the samples delta-coded in order and split into byte
+/// planes (filter 1), which usually shrinks heightmaps several times over. See
+/// `unpack_layer`.
+#[wasm_bindgen]
+pub fn pack_layer(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    dtype: &str,
+    codec: &str,
+) -> Result<Box<[u8]>, JsValue> {
+    let &(dtype_id, _, size) = DTYPES
+        .iter()
+        .find(|(_, name, _)| *name == dtype)
+        .ok_or_else(|| JsValue::from_str("dtype must be u8, u16 or f32"))?;
+    if width == 0 || height == 0 || bytes.len() != width as usize * height as usize * size {
+        return Err(JsValue::from_str("layer length mismatch"));
+    }
+    let (codec_id, filter, payload) = match codec {
+        "none" => (CODEC_NONE, FILTER_NONE, bytes.to_vec()),
+        "deflate" => (
+            CODEC_DEFLATE,
+            FILTER_DELTA_PLANES,
+            zlib_compress(&delta_planes(bytes, size)),
+        ),
+        _ => return Err(JsValue::from_str("codec must be none or deflate")),
+    };
+    let mut out = Vec::with_capacity(PACKED_HEADER_LEN + payload.len());
+    out.extend(PACKED_MAGIC);
+    out.extend([PACKED_VERSION, dtype_id, codec_id, filter]);
+    out.extend(width.to_le_bytes());
+    out.extend(height.to_le_bytes());
+    out.extend(payload);
+    Ok(out.into_boxed_slice())
+}
+
+/// Layer read back by `unpack_layer`.
+#[wasm_bindgen]
+pub struct PackedLayer {
+    width: u32,
+    height: u32,
+    dtype: String,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl PackedLayer {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// `"u8"`, `"u16"` or `"f32"`.
+    #[wasm_bindgen(getter)]
+    pub fn dtype(&self) -> String {
+        self.dtype.clone()
+    }
+
+    /// The raw little-endian samples, row-major.
+    pub fn data(&self) -> Box<[u8]> {
+        self.data.clone().into_boxed_slice()
+    }
+}
+
+/// Reads a `pack_layer` file back to its raw samples.
+#[wasm_bindgen]
+pub fn unpack_layer(bytes: &[u8]) -> Result<PackedLayer, JsValue> {
+    let header = bytes
+        .get(..PACKED_HEADER_LEN)
+        .filter(|h| &h[..4] == PACKED_MAGIC)
+        .ok_or_else(|| JsValue::from_str("not a packed layer"))?;
+    if header[4] != PACKED_VERSION {
+        return Err(JsValue::from_str("unsupported packed layer version"));
+    }
+    let &(_, dtype, size) = DTYPES
+        .iter()
+        .find(|(id, _, _)| *id == header[5])
+        .ok_or_else(|| JsValue::from_str("unknown packed layer dtype"))?;
+    let word = |at: usize| {
+        u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let (width, height) = (word(8), word(12));
+    let length = width as usize * height as usize * size;
+    let payload = &bytes[PACKED_HEADER_LEN..];
+    let data = match (header[6], header[7]) {
+        (CODEC_NONE, FILTER_NONE) => payload.to_vec(),
+        (CODEC_DEFLATE, FILTER_DELTA_PLANES) => {
+            let planes = zlib_decompress(payload, length).map_err(|e| JsValue::from_str(&e))?;
+            if planes.len() != length {
+                return Err(JsValue::from_str("layer length mismatch"));
+            }
+            undelta_planes(&planes, size)
+        }
+        _ => return Err(JsValue::from_str("unknown packed layer codec")),
+    };
+    if data.len() != length {
+        return Err(JsValue::from_str("layer length mismatch"));
+    }
+    Ok(PackedLayer {
+        width,
+        height,
+        dtype: dtype.to_string(),
+        data,
+    })
+}
@@ -0,0 +1,244 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{BIOME_WATER, BIOMES};
+use crate::climate::RELIEF_METRES;
+use crate::grid::{CELL_COUNT, SEA_LEVEL, check_grid_len, smoothstep};
+use crate::json::{self, Json};
+use crate::terrain::gradient;
+
+/// Matches pass7's `elevation_scale * vertical_exaggeration` (10 × 5.5).
+pub(crate) const RELIEF_SCALE: f32 = 55.0;
+/// Light from pass7's primary azimuth (315°) at ~25° altitude.
+const SUN_AZIMUTH_DEG: f32 = 315.0;
+const SUN_Z: f32 = 0.47;
+const AMBIENT: f32 = 0.25;
+/// Colour for biome ids missing from a custom palette.
+const UNKNOWN_COLOR: [u8; 3] = [255, 0, 255];
+
+/// pass7's `ocean_color` ramp, indexed by depth below sea level in [0, 1].
+fn ocean_color(depth_t: f32) -> [f32; 3] {
+    const SHALLOW: [f32; 3] = [0.376, 0.600, 0.690];
+    const MID: [f32; 3] = [0.122, 0.341, 0.502];
+    const DEEP: [f32; 3] = [0.039, 0.133, 0.259];
+    let mix = |a: [f32; 3], b: [f32; 3], t: f32| {
+        [
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ]
+    };
+    if depth_t < 0.35 {
+        mix(SHALLOW, MID, depth_t / 0.35)
+    } else {
+        mix(MID, DEEP, (depth_t - 0.35) / 0.65)
+    }
+}
+
+/// Parses `#rgb` or `#rrggbb`.
+pub(crate) fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.strip_prefix('#')?;
+    let channel = |i: usize, len: usize| u8::from_str_radix(hex.get(i..i + len)?, 16).ok();
+    match hex.len() {
+        3 => Some([
+            channel(0, 1)? * 17,
+            channel(1, 1)? * 17,
+            channel(2, 1)? * 17,
+        ]),
+        6 => Some([channel(0, 2)?, channel(2, 2)?, channel(4, 2)?]),
+        _ => None,
+    }
+}
+
+/// Colour per biome id: the built-in Whittaker colours, overridden by a legend-shaped palette
+/// (`[{"id","color"}, ...]`, as returned by `biome_legend_json` or
+/// `BiomeRuleTable::legend_json`). Ids in neither render magenta.
+pub(crate) fn parse_palette(palette: &str) -> Result<Vec<[u8; 3]>, String> {
+    let mut colors = vec![UNKNOWN_COLOR; 256];
+    for (id, (_, color)) in BIOMES.iter().enumerate() {
+        colors[id] = parse_hex_color(color).unwrap_or(UNKNOWN_COLOR);
+    }
+    if palette.trim().is_empty() {
+        return Ok(colors);
+    }
+    let root = json::parse(palette)?;
+    let entries = root.as_array().ok_or("palette must be a JSON array")?;
+    for entry in entries {
+        let id = entry
+            .get("id")
+            .and_then(Json::as_f64)
+            .filter(|id| id.fract() == 0.0 && (0.0..=255.0).contains(id))
+            .ok_or("palette id must be an integer in [0, 255]")?;
+        let color = entry
+            .get("color")
+            .and_then(Json::as_str)
+            .and_then(parse_hex_color)
+            .ok_or_else(|| format!("palette entry {id} needs a #rrggbb color"))?;
+        colors[id as usize] = color;
+    }
+    Ok(colors)
+}
+
+/// Lambert illumination in [AMBIENT, 1] from the pass7 primary light.
+fn illumination(flat: &[f32], idx: usize) -> f32 {
+    let (dx, dy) = gradient(flat, idx);
+    let (nx, ny, nz) = (-dx * 2.0 * RELIEF_SCALE, -dy * 2.0 * RELIEF_SCALE, 1.0);
+    let sun = SUN_AZIMUTH_DEG.to_radians();
+    let (lx, ly, lz) = (sun.cos(), sun.sin(), SUN_Z);
+    let dot = (nx * lx + ny * ly + nz * lz)
+        / ((nx * nx + ny * ny + nz * nz).sqrt() * (lx * lx + ly * ly + lz * lz).sqrt());
+    // Flat ground keeps its palette colour; slopes facing away darken toward the ambient.
+    let flat_dot = lz / (lx * lx + ly * ly + lz * lz).sqrt();
+    (AMBIENT + (1.0 - AMBIENT) * dot.max(0.0) / flat_dot).clamp(AMBIENT, 1.0)
+}
+
+pub(crate) fn is_water(h: f32, biome: u8) -> bool {
+    h < SEA_LEVEL || biome == BIOME_WATER
+}
+
+/// Unlit colour of a cell in [0, 255]: the depth-graded ocean tint on water, else the biome
+/// colour from `colors` (see `parse_palette`).
+pub(crate) fn surface_color(h: f32, biome: u8, colors: &[[u8; 3]]) -> [f32; 3] {
+    if is_water(h, biome) {
+        let depth_t = smoothstep(0.0, SEA_LEVEL, SEA_LEVEL - h);
+        ocean_color(depth_t).map(|c| c * 255.0)
+    } else {
+        colors[biome as usize].map(|c| c as f32)
+    }
+}
+
+/// RGBA8 map (row-major, 4 bytes per cell) combining biome colours with relief shading on
+/// land and a depth-graded tint on water. Cells below sea level or with biome 0 render as
+/// water. `palette` is a legend JSON string, or empty for the built-in biome colours.
+#[wasm_bindgen]
+pub fn render_biome_rgba(
+    heightmap: &[f32],
+    biome_map: &[u8],
+    palette: &str,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(heightmap, "flat heightmap")?;
+    check_grid_len(biome_map, "biome map")?;
+    let colors = parse_palette(palette).map_err(|e| JsValue::from_str(&e))?;
+
+    let mut rgba = vec![255_u8; CELL_COUNT * 4];
+    for (idx, px) in rgba.chunks_exact_mut(4).enumerate() {
+        let (h, biome) = (heightmap[idx], biome_map[idx]);
+        let mut rgb = surface_color(h, biome, &colors);
+        if !is_water(h, biome) {
+            let light = illumination(heightmap, idx);
+            rgb = rgb.map(|c| c * light);
+        }
+        for (out, c) in px.iter_mut().zip(rgb) {
+            *out = c.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    Ok(rgba.into_boxed_slice())
+}
+
+/// Classic atlas tints: bathymetric blues, then greens through browns to white peaks.
+const ATLAS_RAMP: &str = r##"{"mode":"linear","stops":[
+{"elevation":-6000,"color":"#08306b"},{"elevation":-2000,"color":"#2171b5"},
+{"elevation":-200,"color":"#6baed6"},{"elevation":-1,"color":"#c6dbef"},
+{"elevation":0,"color":"#4f8f4a"},{"elevation":200,"color":"#8fbf6a"},
+{"elevation":500,"color":"#d9d38c"},{"elevation":1000,"color":"#c9a66b"},
+{"elevation":2000,"color":"#a8734a"},{"elevation":3500,"color":"#8a6a5c"},
+{"elevation":5000,"color":"#f2f2f2"}]}"##;
+
+/// Elevation (metres from sea level) to colour stops, ascending.
+pub(crate) struct ColorRamp {
+    stops: Vec<(f32, [u8; 3])>,
+    /// Hard bands instead of blending between stops.
+    stepped: bool,
+}
+
+impl ColorRamp {
+    /// `{"mode":"linear"|"step","stops":[{"elevation","color"},...]}`; empty for the
+    /// atlas ramp.
+    pub(crate) fn parse(ramp: &str) -> Result<ColorRamp, String> {
+        let ramp = if ramp.trim().is_empty() {
+            ATLAS_RAMP
+        } else {
+            ramp
+        };
+        let root = json::parse(ramp)?;
+        let stepped = match root.get("mode").and_then(Json::as_str).unwrap_or("linear") {
+            "linear" => false,
+            "step" => true,
+            other => return Err(format!("unknown ramp mode {other:?}")),
+        };
+        let entries = root
+            .get("stops")
+            .and_then(Json::as_array)
+            .ok_or("ramp needs a stops array")?;
+        let mut stops = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let elevation = entry
+                .get("elevation")
+                .and_then(Json::as_f64)
+                .filter(|e| e.is_finite())
+                .ok_or("ramp stop needs a numeric elevation")?;
+            let color = entry
+                .get("color")
+                .and_then(Json::as_str)
+                .and_then(parse_hex_color)
+                .ok_or_else(|| format!("ramp stop at {elevation} needs a #rrggbb color"))?;
+            stops.push((elevation as f32, color));
+        }
+        if stops.is_empty() {
+            return Err("ramp needs at least one stop".into());
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(ColorRamp { stops, stepped })
+    }
+
+    /// Colour at `metres`, in [0, 255]; beyond the end stops the end colours hold. Stepped
+    /// ramps use the highest stop at or below `metres`.
+    pub(crate) fn color_at(&self, metres: f32) -> [f32; 3] {
+        let upper = self.stops.partition_point(|s| s.0 <= metres);
+        let rgb = |c: [u8; 3]| c.map(|v| v as f32);
+        if upper == 0 {
+            return rgb(self.stops[0].1);
+        }
+        let (e0, c0) = self.stops[upper - 1];
+        if self.stepped || upper == self.stops.len() {
+            return rgb(c0);
+        }
+        let (e1, c1) = self.stops[upper];
+        let t = (metres - e0) / (e1 - e0);
+        [0, 1, 2].map(|k| c0[k] as f32 + (c1[k] as f32 - c0[k] as f32) * t)
+    }
+}
+
+/// RGBA8 hypsometric tint of the heightmap through a user colour ramp:
+/// `{"mode","stops":[{"elevation","color"},...]}` with elevations in metres from
+/// `sea_level` (negative below it, on the same 8000 m relief scale as land), colours as
+/// `#rrggbb`, and mode `linear` (blend between stops) or `step` (flat bands). An empty
+/// `ramp_json` uses `hypsometric_ramp_json`. Combine with `hillshade` for shaded relief.
+#[wasm_bindgen]
+pub fn render_hypsometric_rgba(
+    heightmap: &[f32],
+    ramp_json: &str,
+    sea_level: f32,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(heightmap, "flat heightmap")?;
+    if !(sea_level.is_finite() && sea_level < 1.0) {
+        return Err(JsValue::from_str("sea_level must be below 1"));
+    }
+    let ramp = ColorRamp::parse(ramp_json).map_err(|e| JsValue::from_str(&e))?;
+    let mut rgba = vec![255_u8; CELL_COUNT * 4];
+    for (px, &h) in rgba.chunks_exact_mut(4).zip(heightmap) {
+        let metres = (h - sea_level) / (1.0 - sea_level) * RELIEF_METRES;
+        for (out, c) in px.iter_mut().zip(ramp.color_at(metres)) {
+            *out = c.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    Ok(rgba.into_boxed_slice())
+}
+
+/// The built-in atlas ramp used by `render_hypsometric_rgba`, as a starting point for
+/// custom ramps.
+#[wasm_bindgen]
+pub fn hypsometric_ramp_json() -> String {
+    json::parse(ATLAS_RAMP)
+        .map(|ramp| ramp.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,125 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{WIDTH, box_blur};
+use crate::noise::tiled_fbm;
+
+/// Most fbm octaves a re-noise may use.
+const MAX_RENOISE_OCTAVES: u32 = 12;
+
+/// Fresh noise for `TerrainEditor::renoise`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RenoiseParams {
+    /// Size of the largest features rerolled, cells: terrain shapes broader than this are
+    /// kept, everything finer is replaced. Raise it to reroll a whole peninsula.
+    pub scale: f32,
+    pub octaves: u32,
+    /// Amplitude falloff per octave, (0, 1].
+    pub roughness: f32,
+    /// Cells inside the selection's edge over which the new terrain fades in.
+    pub falloff: u32,
+    pub seed: u32,
+}
+
+impl Default for RenoiseParams {
+    fn default() -> Self {
+        Self {
+            scale: 64.0,
+            octaves: 5,
+            roughness: 0.5,
+            falloff: 16,
+            seed: crate::DEFAULT_SEED,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl RenoiseParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RenoiseParams {
+        Self::default()
+    }
+}
+
+impl RenoiseParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !(4.0..=(WIDTH / 2) as f32).contains(&self.scale) {
+            return Err(JsValue::from_str(&format!(
+                "scale must be within [4, {}]",
+                WIDTH / 2
+            )));
+        }
+        if !(1..=MAX_RENOISE_OCTAVES).contains(&self.octaves) {
+            return Err(JsValue::from_str(&format!(
+                "octaves must be within [1, {MAX_RENOISE_OCTAVES}]"
+            )));
+        }
+        if !(self.roughness > 0.0 && self.roughness <= 1.0) {
+            return Err(JsValue::from_str("roughness must be within (0, 1]"));
+        }
+        if self.falloff as usize > WIDTH / 4 {
+            return Err(JsValue::from_str(
+                "falloff must be at most a quarter of the grid width",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Mean and root-mean-square deviation of `value` over the cells in `cells`, weighted by
+/// `blend`.
+fn weighted_moments(cells: &[usize], blend: &[f32], value: impl Fn(usize) -> f32) -> (f64, f64) {
+    let total: f64 = cells.iter().map(|&i| blend[i] as f64).sum();
+    let mean = cells
+        .iter()
+        .map(|&i| blend[i] as f64 * value(i) as f64)
+        .sum::<f64>()
+        / total;
+    let variance = cells
+        .iter()
+        .map(|&i| blend[i] as f64 * (value(i) as f64 - mean).powi(2))
+        .sum::<f64>()
+        / total;
+    (mean, variance.sqrt())
+}
+
+/// `flat` with its detail finer than `params.scale` replaced by fresh fbm, mixed in by
+/// `blend` per cell (0 leaves a cell alone). The broad shape comes from a smoothed copy of
+/// the terrain, and the new detail is scaled to the old detail's strength over the
+/// blended cells, so a rerolled area stays as rugged as before.
+pub(crate) fn renoise(flat: &[f32], blend: &[f32], params: &RenoiseParams) -> Vec<f32> {
+    let radius = (params.scale / 4.0).round().max(1.0) as usize;
+    let base = box_blur(&box_blur(flat, radius), radius);
+    let cells: Vec<usize> = (0..flat.len()).filter(|&i| blend[i] > 0.0).collect();
+    let mut out = flat.to_vec();
+    if cells.is_empty() {
+        return out;
+    }
+    let period = (WIDTH as f32 / params.scale).round().max(1.0);
+    let lattice = period / WIDTH as f32;
+    let mut noise = vec![0.0_f32; flat.len()];
+    for &idx in &cells {
+        let (x, y) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+        noise[idx] = tiled_fbm(
+            x * lattice,
+            y * lattice,
+            period as i32,
+            params.roughness,
+            params.octaves,
+            params.seed,
+        );
+    }
+    let (_, old_rms) = weighted_moments(&cells, blend, |i| flat[i] - base[i]);
+    let (noise_mean, noise_rms) = weighted_moments(&cells, blend, |i| noise[i]);
+    let gain = if noise_rms > 0.0 {
+        old_rms / noise_rms
+    } else {
+        0.0
+    };
+    for &idx in &cells {
+        let detail = ((noise[idx] as f64 - noise_mean) * gain) as f32;
+        let h = flat[idx];
+        out[idx] = (h + (base[idx] + detail - h) * blend[idx]).clamp(0.0, 1.0);
+    }
+    out
+}
@@ -0,0 +1,235 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+
+/// Largest side `resample` produces, Unity's and Unreal's largest terrains.
+const MAX_RESAMPLE_SIDE: u32 = 8193;
+
+/// Reconstruction filter for resampling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Filter {
+    /// The source sample under each output centre; never blends, so class maps stay valid.
+    Nearest,
+    /// Tent filter: bilinear interpolation when enlarging.
+    Bilinear,
+    /// Catmull–Rom cubic (Keys, a = −0.5): sharper than bilinear, mild overshoot.
+    Bicubic,
+    /// Windowed sinc over three lobes: the sharpest, with some ringing at cliffs.
+    Lanczos,
+}
+
+impl Filter {
+    pub(crate) fn parse(method: &str) -> Result<Filter, String> {
+        match method {
+            "nearest" => Ok(Filter::Nearest),
+            "bilinear" => Ok(Filter::Bilinear),
+            "bicubic" => Ok(Filter::Bicubic),
+            "lanczos" => Ok(Filter::Lanczos),
+            _ => Err("method must be nearest, bilinear, bicubic or lanczos".to_string()),
+        }
+    }
+
+    fn radius(self) -> f32 {
+        match self {
+            Filter::Nearest => 0.5,
+            Filter::Bilinear => 1.0,
+            Filter::Bicubic => 2.0,
+            Filter::Lanczos => 3.0,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Filter::Nearest => 1.0,
+            Filter::Bilinear => (1.0 - x).max(0.0),
+            Filter::Bicubic => {
+                const A: f32 = -0.5;
+                if x < 1.0 {
+                    ((A + 2.0) * x - (A + 3.0)) * x * x + 1.0
+                } else if x < 2.0 {
+                    ((A * x - 5.0 * A) * x + 8.0 * A) * x - 4.0 * A
+                } else {
+                    0.0
+                }
+            }
+            Filter::Lanczos => {
+                let sinc = |t: f32| {
+                    if t < 1e-6 {
+                        1.0
+                    } else {
+                        let t = t * std::f32::consts::PI;
+                        t.sin() / t
+                    }
+                };
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Source samples and weights of each output sample along one axis.
+type AxisWeights = Vec<Vec<(usize, f32)>>;
+
+/// Weights for resampling `in_len` samples to `out_len` with sample centres aligned.
+/// When shrinking, the filter is stretched over the source samples each output covers,
+/// so detail is averaged in rather than aliased. Samples past the ends wrap or clamp.
+fn axis_weights(in_len: usize, out_len: usize, filter: Filter, wrap: bool) -> AxisWeights {
+    let step = in_len as f64 / out_len as f64;
+    let source = |i: i64| {
+        if wrap {
+            i.rem_euclid(in_len as i64) as usize
+        } else {
+            i.clamp(0, in_len as i64 - 1) as usize
+        }
+    };
+    if filter == Filter::Nearest {
+        return (0..out_len)
+            .map(|o| vec![(source(((o as f64 + 0.5) * step) as i64), 1.0)])
+            .collect();
+    }
+    let scale = step.max(1.0);
+    let support = filter.radius() as f64 * scale;
+    (0..out_len)
+        .map(|o| {
+            let centre = (o as f64 + 0.5) * step - 0.5;
+            let taps: Vec<(usize, f32)> = ((centre - support).ceil() as i64
+                ..=(centre + support).floor() as i64)
+                .map(|i| {
+                    (
+                        source(i),
+                        filter.weight(((i as f64 - centre) / scale) as f32),
+                    )
+                })
+                .filter(|&(_, w)| w != 0.0)
+                .collect();
+            let total: f32 = taps.iter().map(|(_, w)| w).sum();
+            taps.into_iter().map(|(i, w)| (i, w / total)).collect()
+        })
+        .collect()
+}
+
+/// Applies `columns` along each row, then `rows` down each column.
+fn apply_separable(
+    field: &[f32],
+    in_width: usize,
+    in_height: usize,
+    columns: &AxisWeights,
+    rows: &AxisWeights,
+) -> Vec<f32> {
+    let out_width = columns.len();
+    let narrowed: Vec<f32> = (0..in_height)
+        .flat_map(|y| {
+            let row = &field[y * in_width..(y + 1) * in_width];
+            columns
+                .iter()
+                .map(move |taps| taps.iter().map(|&(i, w)| row[i] * w).sum::<f32>())
+        })
+        .collect();
+    (0..out_width * rows.len())
+        .map(|i| {
+            let (x, y) = (i % out_width, i / out_width);
+            rows[y]
+                .iter()
+                .map(|&(row, w)| narrowed[row * out_width + x] * w)
+                .sum()
+        })
+        .collect()
+}
+
+/// `field` (`in_width` × `in_height`, row-major) resampled to `out_width` × `out_height`
+/// with `filter`. `wrap` joins the east and west edges, as on the grid; otherwise edges
+/// clamp, as an arbitrary image need not tile. North and south always clamp.
+pub(crate) fn resample_field(
+    field: &[f32],
+    (in_width, in_height): (usize, usize),
+    (out_width, out_height): (usize, usize),
+    filter: Filter,
+    wrap: bool,
+) -> Vec<f32> {
+    apply_separable(
+        field,
+        in_width,
+        in_height,
+        &axis_weights(in_width, out_width, filter, wrap),
+        &axis_weights(in_height, out_height, filter, false),
+    )
+}
+
+/// A grid-sized field resampled to `width` × `height`, wrapping east–west.
+pub(crate) fn resample_grid(
+    field: &[f32],
+    width: usize,
+    height: usize,
+    filter: Filter,
+) -> Vec<f32> {
+    resample_field(field, (WIDTH, HEIGHT), (width, height), filter, true)
+}
+
+/// Grid resampling to `width` × `height` evaluated a window at a time, so outputs far
+/// larger than memory allows can be produced piecewise. Windows match `resample_grid`.
+pub(crate) struct GridResampler {
+    columns: AxisWeights,
+    rows: AxisWeights,
+}
+
+impl GridResampler {
+    pub(crate) fn new(width: usize, height: usize, filter: Filter) -> GridResampler {
+        GridResampler {
+            columns: axis_weights(WIDTH, width, filter, true),
+            rows: axis_weights(HEIGHT, height, filter, false),
+        }
+    }
+
+    /// Output samples `x0..x0 + width` × `y0..y0 + height` of `field`, row-major.
+    pub(crate) fn window(
+        &self,
+        field: &[f32],
+        (x0, y0): (usize, usize),
+        (width, height): (usize, usize),
+    ) -> Vec<f32> {
+        let columns = &self.columns[x0..x0 + width];
+        self.rows[y0..y0 + height]
+            .iter()
+            .flat_map(|row_taps| {
+                columns.iter().map(move |column_taps| {
+                    row_taps
+                        .iter()
+                        .map(|&(row, wy)| {
+                            let source = &field[row * WIDTH..(row + 1) * WIDTH];
+                            wy * column_taps
+                                .iter()
+                                .map(|&(i, wx)| source[i] * wx)
+                                .sum::<f32>()
+                        })
+                        .sum()
+                })
+            })
+            .collect()
+    }
+}
+
+/// Heightmap resampled to `width` × `height` with `method`: `"nearest"`, `"bilinear"`,
+/// `"bicubic"` or `"lanczos"`. Sample centres stay aligned, so the grid size returns the
+/// heightmap unchanged, and shrinking filters over every cell each sample covers. The
+/// cubic and Lanczos filters can overshoot slightly beside cliffs and coasts.
+#[wasm_bindgen]
+pub fn resample(
+    flat: &[f32],
+    width: u32,
+    height: u32,
+    method: &str,
+) -> Result<Box<[f32]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(1..=MAX_RESAMPLE_SIDE).contains(&width) || !(1..=MAX_RESAMPLE_SIDE).contains(&height) {
+        return Err(JsValue::from_str(&format!(
+            "width and height must be within [1, {MAX_RESAMPLE_SIDE}]"
+        )));
+    }
+    let filter = Filter::parse(method).map_err(|e| JsValue::from_str(&e))?;
+    Ok(resample_grid(flat, width as usize, height as usize, filter).into_boxed_slice())
+}
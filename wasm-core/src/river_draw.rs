@@ -0,0 +1,232 @@
+use wasm_bindgen::prelude::*;
+
+use crate::flow::{d8_receivers, fill_depressions, flow_accumulation};
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, clamp_y, wrap_x};
+use crate::vector::cell_area_km2;
+
+/// Widest river bank, cells either side of the channel.
+const MAX_RIVER_WIDTH: u32 = 64;
+
+/// Channel shape and joining rule for `TerrainEditor::draw_river`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RiverParams {
+    /// How far the channel is cut below the ground, metres.
+    pub depth_metres: f32,
+    /// Cells either side of the channel over which the banks rise back to the ground.
+    pub width: u32,
+    /// Drainage area from which a cell counts as part of the river network, km²; the
+    /// vector exports draw rivers from 50 000 km² by default.
+    pub join_area_km2: f32,
+}
+
+impl Default for RiverParams {
+    fn default() -> Self {
+        Self {
+            depth_metres: 40.0,
+            width: 3,
+            join_area_km2: 50_000.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl RiverParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RiverParams {
+        Self::default()
+    }
+}
+
+impl RiverParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !(self.depth_metres.is_finite() && self.depth_metres >= 0.0) {
+            return Err(JsValue::from_str("depth_metres must be finite and >= 0"));
+        }
+        if self.width > MAX_RIVER_WIDTH {
+            return Err(JsValue::from_str(&format!(
+                "width must be at most {MAX_RIVER_WIDTH}"
+            )));
+        }
+        if !(self.join_area_km2.is_finite() && self.join_area_km2 > 0.0) {
+            return Err(JsValue::from_str("join_area_km2 must be finite and > 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Where a drawn river ends up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RiverMouth {
+    Sea,
+    River,
+    Lake,
+    /// A closed basin with nowhere lower to go (only on maps without sea).
+    Basin,
+}
+
+impl RiverMouth {
+    fn name(self) -> &'static str {
+        match self {
+            RiverMouth::Sea => "sea",
+            RiverMouth::River => "river",
+            RiverMouth::Lake => "lake",
+            RiverMouth::Basin => "basin",
+        }
+    }
+}
+
+/// A river carved by `TerrainEditor::draw_river`.
+#[wasm_bindgen]
+pub struct DrawnRiver {
+    cells: Vec<u32>,
+    mouth: RiverMouth,
+}
+
+#[wasm_bindgen]
+impl DrawnRiver {
+    /// Cell indices from source to mouth: the stroke, then the existing drainage it was
+    /// led down, ending on the water or river cell it joins.
+    pub fn cells(&self) -> Box<[u32]> {
+        self.cells.clone().into_boxed_slice()
+    }
+
+    /// What the river flows into: `"sea"`, `"river"`, `"lake"` or `"basin"`.
+    #[wasm_bindgen(getter)]
+    pub fn mouth(&self) -> String {
+        self.mouth.name().to_string()
+    }
+}
+
+impl DrawnRiver {
+    pub(crate) fn new(cells: Vec<usize>, mouth: RiverMouth) -> DrawnRiver {
+        DrawnRiver {
+            cells: cells.into_iter().map(|c| c as u32).collect(),
+            mouth,
+        }
+    }
+}
+
+/// Cells along a polyline of `[x0, y0, x1, y1, ...]`, 8-connected and without repeats:
+/// where the stroke crosses itself the loop is cut out. Points may run past the east or
+/// west edge.
+pub(crate) fn rasterize_stroke(points: &[f32]) -> Vec<usize> {
+    let mut path: Vec<usize> = Vec::new();
+    let mut visit = |x: f32, y: f32| {
+        let idx = clamp_y(y.round() as i64) * WIDTH + wrap_x(x.round() as i64);
+        if let Some(i) = path.iter().rposition(|&c| c == idx) {
+            path.truncate(i + 1);
+        } else {
+            path.push(idx);
+        }
+    };
+    let vertices: Vec<(f32, f32)> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    visit(vertices[0].0, vertices[0].1);
+    for pair in vertices.windows(2) {
+        let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+        // Half-cell steps never skip a cell.
+        let steps = ((bx - ax).abs().max((by - ay).abs()) * 2.0).ceil().max(1.0) as usize;
+        for s in 1..=steps {
+            let t = s as f32 / steps as f32;
+            visit(ax + (bx - ax) * t, ay + (by - ay) * t);
+        }
+    }
+    path
+}
+
+/// Turns a rasterised stroke into a river course from source to mouth: runs it downhill
+/// (reversing a stroke drawn from the low end) from where it first comes ashore, cuts it
+/// off where it first reaches water or an existing river, and otherwise leads it on down
+/// the existing drainage until it does. `lake` tells whether a cell is under a hand-filled
+/// lake.
+pub(crate) fn route_river(
+    flat: &[f32],
+    sea_level: f32,
+    lake: impl Fn(usize) -> bool,
+    mut path: Vec<usize>,
+    join_area_km2: f32,
+) -> (Vec<usize>, RiverMouth) {
+    if flat[path[0]] < flat[path[path.len() - 1]] {
+        path.reverse();
+    }
+    let water: Vec<bool> = flat.iter().map(|&h| h < sea_level).collect();
+    // A source drawn out at sea starts where the stroke comes ashore.
+    let ashore = path.iter().position(|&idx| !water[idx]).unwrap_or(0);
+    path.drain(..ashore);
+    let filled = fill_depressions(flat, |i| water[i]);
+    let receivers = d8_receivers(&filled);
+    let area = flow_accumulation(&receivers, |i| cell_area_km2(i / WIDTH) as f32);
+    let mouth_at = |idx: usize| {
+        if water[idx] {
+            Some(RiverMouth::Sea)
+        } else if lake(idx) {
+            Some(RiverMouth::Lake)
+        } else if area[idx] >= join_area_km2 {
+            Some(RiverMouth::River)
+        } else {
+            None
+        }
+    };
+    // The source may sit on the network already; only joining it further down counts.
+    if let Some((i, mouth)) = path
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find_map(|(i, &idx)| Some((i, mouth_at(idx)?)))
+    {
+        path.truncate(i + 1);
+        return (path, mouth);
+    }
+    let mut idx = path[path.len() - 1];
+    for _ in 0..CELL_COUNT {
+        let next = receivers[idx] as usize;
+        if next == idx {
+            break;
+        }
+        // Drainage running back across the stroke: drop the loop and follow it on.
+        match path.iter().position(|&c| c == next) {
+            Some(i) => path.truncate(i + 1),
+            None => path.push(next),
+        }
+        if let Some(mouth) = mouth_at(next) {
+            return (path, mouth);
+        }
+        idx = next;
+    }
+    (path, RiverMouth::Basin)
+}
+
+/// Channel heights for `course` (source to mouth, the mouth left as it is): `depth` below
+/// the ground, strictly falling all the way, and always above the mouth so the water
+/// reaches it; dips along the way are filled to keep it flowing.
+pub(crate) fn channel_levels(flat: &[f32], course: &[usize], depth: f32) -> Vec<f32> {
+    let (channel, mouth) = course.split_at(course.len() - 1);
+    let mut levels: Vec<f32> = Vec::with_capacity(channel.len());
+    for &idx in channel {
+        let cut = flat[idx] - depth;
+        levels.push(match levels.last() {
+            Some(&previous) => cut.min(previous.next_down()),
+            None => cut,
+        });
+    }
+    let mut floor = flat[mouth[0]];
+    for level in levels.iter_mut().rev() {
+        floor = floor.next_up();
+        *level = level.max(floor).clamp(0.0, 1.0);
+    }
+    levels
+}
+
+/// Cells within `width` of `idx`, with their distance in cells.
+pub(crate) fn bank_cells(idx: usize, width: u32) -> impl Iterator<Item = (usize, f32)> {
+    let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+    let w = width as i64;
+    (-w..=w).flat_map(move |dy| {
+        (-w..=w).filter_map(move |dx| {
+            let ny = y + dy;
+            let distance = (dx as f32).hypot(dy as f32);
+            ((0..HEIGHT as i64).contains(&ny) && distance <= width as f32)
+                .then(|| (ny as usize * WIDTH + wrap_x(x + dx), distance))
+        })
+    })
+}
@@ -0,0 +1,483 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{
+    BIOME_COLD_DESERT, BIOME_DESERT, BIOME_ICE, BIOME_TAIGA, BIOME_TEMPERATE_FOREST,
+    BIOME_TEMPERATE_RAINFOREST, BIOME_TROPICAL_RAINFOREST, BIOME_TUNDRA, BIOME_WOODLAND,
+};
+use crate::climate::RELIEF_METRES;
+use crate::flow::{Flooded, ring};
+use crate::geojson::{feature_collection, line_feature};
+use crate::grid::{CELL_COUNT, SEA_LEVEL, WIDTH, check_grid_len, latitude_deg};
+use crate::hydrology::Hydrology;
+use crate::json::{self, ObjectWriter};
+use crate::landmass::label_landmasses;
+use crate::vector::{block_size_km, cell_path_polylines, polylines_json};
+
+/// Lowest settlement tier (villages) `build_roads` accepts.
+const MAX_TIER: u8 = 3;
+
+/// Importance of a road, from the lesser of the two settlements it joins.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum RoadClass {
+    Highway,
+    Road,
+    Track,
+}
+
+impl RoadClass {
+    fn for_tier(tier: u8) -> RoadClass {
+        match tier {
+            0 | 1 => RoadClass::Highway,
+            2 => RoadClass::Road,
+            _ => RoadClass::Track,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            RoadClass::Highway => "highway",
+            RoadClass::Road => "road",
+            RoadClass::Track => "track",
+        }
+    }
+
+    /// Value in `RoadNetwork::road_mask`.
+    fn mask_value(self) -> u8 {
+        match self {
+            RoadClass::Highway => 3,
+            RoadClass::Road => 2,
+            RoadClass::Track => 1,
+        }
+    }
+}
+
+/// Cost field and output settings for `build_roads`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RoadParams {
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km², as in the map exports.
+    pub river_min_area_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes, which
+    /// roads go round.
+    pub lake_min_depth_metres: f32,
+    /// Gradient (rise over run) at which a step costs twice as much as on the level; cost
+    /// grows with its square, so roads climb by the gentlest pass they can find.
+    pub grade_scale: f32,
+    /// Extra cost of crossing a river at the size where it starts, in cells of level
+    /// road; it grows with the square root of the drainage area, so roads ford rivers
+    /// upstream rather than bridge them near the mouth.
+    pub ford_cost: f32,
+    /// Cost multiplier on cells an earlier road already runs over, in (0, 1]; lower
+    /// values merge roads into trunks.
+    pub reuse_discount: f32,
+    /// Douglas–Peucker tolerance, cells.
+    pub tolerance: f32,
+}
+
+impl Default for RoadParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 20_000.0,
+            lake_min_depth_metres: 20.0,
+            grade_scale: 0.02,
+            ford_cost: 4.0,
+            reuse_discount: 0.5,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl RoadParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RoadParams {
+        Self::default()
+    }
+}
+
+impl RoadParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        let limits = [
+            self.river_min_area_km2,
+            self.lake_min_depth_metres,
+            self.ford_cost,
+            self.tolerance,
+        ];
+        if limits.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "river area, lake depth, ford_cost and tolerance must be >= 0",
+            ));
+        }
+        if !self.grade_scale.is_finite() || self.grade_scale <= 0.0 {
+            return Err(JsValue::from_str("grade_scale must be > 0"));
+        }
+        if !(self.reuse_discount > 0.0 && self.reuse_discount <= 1.0) {
+            return Err(JsValue::from_str("reuse_discount must be within (0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+/// How hard a biome's ground is to build a road across, per cell, at least 1.
+fn biome_cost(biome: u8) -> f32 {
+    match biome {
+        BIOME_ICE => 4.0,
+        BIOME_TROPICAL_RAINFOREST => 2.5,
+        BIOME_TEMPERATE_RAINFOREST => 2.0,
+        BIOME_TAIGA | BIOME_TUNDRA | BIOME_TEMPERATE_FOREST => 1.5,
+        BIOME_DESERT | BIOME_COLD_DESERT => 1.6,
+        BIOME_WOODLAND => 1.2,
+        _ => 1.0,
+    }
+}
+
+/// Length of a step from `idx` by (`dx`, `dy`) cells, km.
+pub(crate) fn step_km(idx: usize, dx: usize, dy: usize) -> f32 {
+    let (ew, ns) = block_size_km(latitude_deg(idx / WIDTH) as f64, 1, 1);
+    ((ew * dx as f64).hypot(ns * dy as f64)) as f32
+}
+
+/// Straight-line distance between two cells in cells, the short way across the seam.
+pub(crate) fn cell_distance(a: usize, b: usize) -> f32 {
+    let dx = ((a % WIDTH) as f32 - (b % WIDTH) as f32).abs();
+    let dy = (a / WIDTH) as f32 - (b / WIDTH) as f32;
+    dx.min(WIDTH as f32 - dx).hypot(dy)
+}
+
+/// A* search state over the grid, kept between searches so each one only resets the cells
+/// it reached.
+pub(crate) struct PathSearch {
+    best: Vec<f32>,
+    came_from: Vec<u32>,
+    touched: Vec<usize>,
+}
+
+impl PathSearch {
+    pub(crate) fn new() -> PathSearch {
+        PathSearch {
+            best: vec![f32::INFINITY; CELL_COUNT],
+            came_from: vec![0; CELL_COUNT],
+            touched: Vec::new(),
+        }
+    }
+
+    /// Cheapest 8-connected path from `start` to `goal` (both ends included). `step` is the
+    /// cost of moving from one cell to a neighbour, told whether the move is diagonal, or
+    /// `None` where the neighbour cannot be entered; `heuristic` must never overestimate
+    /// the cost left to the goal. `None` when the goal cannot be reached.
+    pub(crate) fn find(
+        &mut self,
+        start: usize,
+        goal: usize,
+        step: impl Fn(usize, usize, bool) -> Option<f32>,
+        heuristic: impl Fn(usize) -> f32,
+    ) -> Option<Vec<usize>> {
+        for idx in self.touched.drain(..) {
+            self.best[idx] = f32::INFINITY;
+        }
+        let mut queue = BinaryHeap::new();
+        self.best[start] = 0.0;
+        self.touched.push(start);
+        queue.push(Reverse(Flooded(heuristic(start), start)));
+        while let Some(Reverse(Flooded(estimate, idx))) = queue.pop() {
+            if idx == goal {
+                let mut path = vec![goal];
+                let mut at = goal;
+                while at != start {
+                    at = self.came_from[at] as usize;
+                    path.push(at);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let cost = self.best[idx];
+            if estimate > cost + heuristic(idx) {
+                continue;
+            }
+            for (i, n) in ring(idx).into_iter().enumerate() {
+                let Some(n) = n else {
+                    continue;
+                };
+                let Some(step_cost) = step(idx, n, i % 2 == 1) else {
+                    continue;
+                };
+                let next = cost + step_cost;
+                if next < self.best[n] {
+                    if self.best[n].is_infinite() {
+                        self.touched.push(n);
+                    }
+                    self.best[n] = next;
+                    self.came_from[n] = idx as u32;
+                    queue.push(Reverse(Flooded(next + heuristic(n), n)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Travel costs for roads over the map.
+struct CostField {
+    flat: Vec<f32>,
+    /// Per-cell cost multiplier; infinite over water.
+    ground: Vec<f32>,
+    /// Extra cost of stepping onto each river cell; 0 off rivers.
+    ford: Vec<f32>,
+    on_road: Vec<bool>,
+    metres_per_unit: f32,
+    /// North–south extent of a cell, metres; used as the run of every step, so grades
+    /// near the poles come out a little gentle.
+    cell_metres: f32,
+    grade_scale: f32,
+    reuse_discount: f32,
+}
+
+impl CostField {
+    /// Cost of a step onto `to`, `None` over water.
+    fn step_cost(&self, from: usize, to: usize, diagonal: bool) -> Option<f32> {
+        if !self.ground[to].is_finite() {
+            return None;
+        }
+        let distance = if diagonal {
+            std::f32::consts::SQRT_2
+        } else {
+            1.0
+        };
+        let grade = (self.flat[to] - self.flat[from]).abs() * self.metres_per_unit
+            / (distance * self.cell_metres);
+        let mut cost = distance * self.ground[to] * (1.0 + (grade / self.grade_scale).powi(2));
+        if self.on_road[to] {
+            cost *= self.reuse_discount;
+        }
+        if self.ford[from] == 0.0 {
+            cost += self.ford[to];
+        }
+        Some(cost)
+    }
+}
+
+pub(crate) struct Road {
+    class: RoadClass,
+    /// Indices of the settlements joined, into the lists given to `build_roads`.
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    /// Cells from `from` to `to`.
+    pub(crate) cells: Vec<usize>,
+    pub(crate) length_km: f32,
+}
+
+/// Roads between settlements from `build_roads`, most important first.
+#[wasm_bindgen]
+pub struct RoadNetwork {
+    pub(crate) roads: Vec<Road>,
+    tolerance: f32,
+}
+
+#[wasm_bindgen]
+impl RoadNetwork {
+    /// `[{"class","from","to","length_km","lines":[[[x,y],...],...]},...]`: `class` is
+    /// `"highway"`, `"road"` or `"track"`, `from` and `to` index the settlements given to
+    /// `build_roads`, and `lines` are polylines in cell-centre grid coordinates, split
+    /// where a road crosses the east–west seam.
+    pub fn roads_json(&self) -> String {
+        json::array(self.roads.iter().map(|road| {
+            ObjectWriter::new()
+                .raw("class", &json::quote(road.class.name()))
+                .integer("from", road.from as u64)
+                .integer("to", road.to as u64)
+                .number("length_km", road.length_km as f64, 1)
+                .raw(
+                    "lines",
+                    &polylines_json(&cell_path_polylines(&road.cells, self.tolerance)),
+                )
+                .finish()
+        }))
+    }
+
+    /// The roads as a GeoJSON FeatureCollection of LineStrings in lon/lat, with `class`,
+    /// `from`, `to` and `length_km`.
+    pub fn geojson(&self) -> String {
+        let features: Vec<String> = self
+            .roads
+            .iter()
+            .flat_map(|road| {
+                let properties = format!(
+                    "{{\"class\":\"{}\",\"from\":{},\"to\":{},\"length_km\":{:.1}}}",
+                    road.class.name(),
+                    road.from,
+                    road.to,
+                    road.length_km
+                );
+                cell_path_polylines(&road.cells, self.tolerance)
+                    .into_iter()
+                    .map(move |line| line_feature(&properties, &line))
+            })
+            .collect();
+        feature_collection(&features)
+    }
+
+    /// The most important road on each cell: 3 highway, 2 road, 1 track, 0 none.
+    pub fn road_mask(&self) -> Box<[u8]> {
+        let mut mask = vec![0_u8; CELL_COUNT];
+        for road in &self.roads {
+            for &idx in &road.cells {
+                mask[idx] = mask[idx].max(road.class.mask_value());
+            }
+        }
+        mask.into_boxed_slice()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.roads.len() as u32
+    }
+}
+
+/// Joins settlements (`cells` and `tiers` as from `SettlementHierarchy`, 0 capital to 3
+/// village) with roads: each settlement to its nearest one of a higher tier, and all but
+/// villages to their nearest of the same tier as well, on the same landmass. Each road is
+/// the cheapest A* path over a cost field of slope, river crossings and biome, so roads
+/// wind through passes and cross rivers at fords; highways are laid first and the rest
+/// prefer to follow them. A road's class comes from the lesser of its two settlements:
+/// highways between capitals and cities, roads to towns and tracks to villages.
+#[wasm_bindgen]
+pub fn build_roads(
+    flat: &[f32],
+    biomes: &[u8],
+    cells: &[u32],
+    tiers: &[u8],
+    params: &RoadParams,
+) -> Result<RoadNetwork, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_grid_len(biomes, "biome map")?;
+    params.validate()?;
+    if cells.len() != tiers.len() {
+        return Err(JsValue::from_str(
+            "cells and tiers must have the same length",
+        ));
+    }
+    if cells.iter().any(|&c| c as usize >= CELL_COUNT) {
+        return Err(JsValue::from_str("settlement cell out of range"));
+    }
+    if tiers.iter().any(|&t| t > MAX_TIER) {
+        return Err(JsValue::from_str(&format!(
+            "tiers must be within [0, {MAX_TIER}]"
+        )));
+    }
+
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+    let water = |i: usize| flat[i] < params.sea_level || hydrology.lakes[i];
+    let (landmass, _) = label_landmasses(flat, params.sea_level);
+
+    // Each settlement to its nearest of a higher tier, and to its nearest peer unless a
+    // village, kept once per pair.
+    let nearest = |a: usize, admit: &dyn Fn(u8) -> bool| {
+        let home = cells[a] as usize;
+        (0..cells.len())
+            .filter(|&b| {
+                b != a
+                    && landmass[home] != 0
+                    && landmass[cells[b] as usize] == landmass[home]
+                    && admit(tiers[b])
+            })
+            .min_by(|&p, &q| {
+                cell_distance(home, cells[p] as usize)
+                    .total_cmp(&cell_distance(home, cells[q] as usize))
+            })
+    };
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    for (a, &own) in tiers.iter().enumerate() {
+        let higher = nearest(a, &|tier| tier < own);
+        let peer = if own < MAX_TIER {
+            nearest(a, &|tier| tier == own)
+        } else {
+            None
+        };
+        for b in [higher, peer].into_iter().flatten() {
+            let pair = (a.min(b), a.max(b));
+            if !links.contains(&pair) {
+                links.push(pair);
+            }
+        }
+    }
+    let class = |(a, b): (usize, usize)| RoadClass::for_tier(tiers[a].max(tiers[b]));
+    let span = |(a, b): (usize, usize)| cell_distance(cells[a] as usize, cells[b] as usize);
+    links.sort_by(|&p, &q| {
+        class(p)
+            .partial_cmp(&class(q))
+            .expect("road classes are ordered")
+            .then(span(p).total_cmp(&span(q)))
+    });
+
+    let min_area = params.river_min_area_km2.max(f32::MIN_POSITIVE);
+    let mut field = CostField {
+        flat: flat.to_vec(),
+        ground: (0..CELL_COUNT)
+            .map(|i| {
+                if water(i) {
+                    f32::INFINITY
+                } else {
+                    biome_cost(biomes[i])
+                }
+            })
+            .collect(),
+        ford: (0..CELL_COUNT)
+            .map(|i| {
+                let area = hydrology.area_km2[i];
+                if water(i) || area < min_area {
+                    0.0
+                } else {
+                    params.ford_cost * (area / min_area).sqrt()
+                }
+            })
+            .collect(),
+        on_road: vec![false; CELL_COUNT],
+        metres_per_unit: RELIEF_METRES / (1.0 - params.sea_level),
+        cell_metres: (block_size_km(0.0, 1, 1).1 * 1000.0) as f32,
+        grade_scale: params.grade_scale,
+        reuse_discount: params.reuse_discount,
+    };
+    let mut search = PathSearch::new();
+    let mut roads = Vec::new();
+    for (a, b) in links {
+        let goal = cells[b] as usize;
+        // No step costs less than a cell of discounted road, so this never overestimates.
+        let Some(path) = search.find(
+            cells[a] as usize,
+            goal,
+            |from, to, diagonal| field.step_cost(from, to, diagonal),
+            |idx| cell_distance(idx, goal) * params.reuse_discount,
+        ) else {
+            continue;
+        };
+        let mut length_km = 0.0;
+        for pair in path.windows(2) {
+            field.on_road[pair[0]] = true;
+            let dx = usize::from(pair[0] % WIDTH != pair[1] % WIDTH);
+            let dy = usize::from(pair[0] / WIDTH != pair[1] / WIDTH);
+            length_km += step_km(pair[0], dx, dy);
+        }
+        if let Some(&last) = path.last() {
+            field.on_road[last] = true;
+        }
+        roads.push(Road {
+            class: class((a, b)),
+            from: a,
+            to: b,
+            cells: path,
+            length_km,
+        });
+    }
+    Ok(RoadNetwork {
+        roads,
+        tolerance: params.tolerance,
+    })
+}
@@ -0,0 +1,96 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, check_grid_len};
+use crate::vector::cell_area_km2;
+
+/// Land, water and coastline at one sea level, from `apply_sea_level`.
+#[wasm_bindgen]
+pub struct SeaLevelUpdate {
+    land_percent: f64,
+    land: Vec<u8>,
+    coastline: Vec<u32>,
+    changed: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl SeaLevelUpdate {
+    /// Share of the globe's surface above sea level, weighting cells by true area.
+    #[wasm_bindgen(getter)]
+    pub fn land_percent(&self) -> f64 {
+        self.land_percent
+    }
+
+    /// 1 for land, 0 for sea.
+    pub fn land_mask(&self) -> Box<[u8]> {
+        self.land.clone().into_boxed_slice()
+    }
+
+    /// Indices of coastline cells: land with sea to the north, south, east or west.
+    pub fn coastline(&self) -> Box<[u32]> {
+        self.coastline.clone().into_boxed_slice()
+    }
+
+    /// Indices of cells whose land or coastline status differs from the previous level,
+    /// ascending: what a map overlay needs to redraw. Empty without a previous level.
+    pub fn changed_cells(&self) -> Box<[u32]> {
+        self.changed.clone().into_boxed_slice()
+    }
+}
+
+/// Whether land cell `idx` has sea among its 4 neighbours (wrapping east–west).
+fn on_coast(is_land: impl Fn(usize) -> bool, idx: usize) -> bool {
+    let (x, y) = (idx % WIDTH, idx / WIDTH);
+    let row = y * WIDTH;
+    let neighbours = [
+        (y > 0).then(|| idx - WIDTH),
+        (y + 1 < HEIGHT).then(|| idx + WIDTH),
+        Some(row + (x + 1) % WIDTH),
+        Some(row + (x + WIDTH - 1) % WIDTH),
+    ];
+    is_land(idx) && neighbours.into_iter().flatten().any(|n| !is_land(n))
+}
+
+/// Land mask, land share and coastline of the heightmap with the sea at `level`, in one
+/// pass and without regenerating anything, for live feedback from a sea-level slider.
+/// Give the slider's previous value as `previous_level` to also list the cells that changed
+/// (pass NaN to skip).
+#[wasm_bindgen]
+pub fn apply_sea_level(
+    heightmap: &[f32],
+    level: f32,
+    previous_level: f32,
+) -> Result<SeaLevelUpdate, JsValue> {
+    check_grid_len(heightmap, "heightmap")?;
+    if !level.is_finite() || previous_level.is_infinite() {
+        return Err(JsValue::from_str(
+            "level must be finite and previous_level finite or NaN",
+        ));
+    }
+    let land = |idx: usize| heightmap[idx] >= level;
+    let was_land = |idx: usize| heightmap[idx] >= previous_level;
+    let row_areas: Vec<f64> = (0..HEIGHT).map(cell_area_km2).collect();
+    let total_area: f64 = row_areas.iter().sum::<f64>() * WIDTH as f64;
+    let mut land_area = 0.0;
+    let mut coastline = Vec::new();
+    let mut changed = Vec::new();
+    for idx in 0..CELL_COUNT {
+        if land(idx) {
+            land_area += row_areas[idx / WIDTH];
+        }
+        let coast = on_coast(land, idx);
+        if coast {
+            coastline.push(idx as u32);
+        }
+        if !previous_level.is_nan()
+            && (land(idx) != was_land(idx) || coast != on_coast(was_land, idx))
+        {
+            changed.push(idx as u32);
+        }
+    }
+    Ok(SeaLevelUpdate {
+        land_percent: land_area / total_area * 100.0,
+        land: (0..CELL_COUNT).map(|idx| u8::from(land(idx))).collect(),
+        coastline,
+        changed,
+    })
+}
@@ -0,0 +1,218 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, box_blur, check_grid_len, wrap_x};
+use crate::landmass::label_landmasses;
+
+/// A soft selection over the grid: a weight per cell from 0 (untouched) to 1 (fully
+/// selected). Build one with the `select_*` functions, combine them, and hand the result
+/// to `TerrainEditor::set_selection` to confine edits to it.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Selection {
+    weights: Vec<f32>,
+}
+
+impl Selection {
+    fn from_fn(weight: impl Fn(usize) -> f32) -> Selection {
+        Selection {
+            weights: (0..CELL_COUNT).map(weight).collect(),
+        }
+    }
+
+    /// Fully selects the cells where `selected` holds.
+    fn from_mask(selected: impl Fn(usize) -> bool) -> Selection {
+        Selection::from_fn(|idx| if selected(idx) { 1.0 } else { 0.0 })
+    }
+
+    pub(crate) fn weights_slice(&self) -> &[f32] {
+        &self.weights
+    }
+}
+
+#[wasm_bindgen]
+impl Selection {
+    /// An empty selection.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Selection {
+        Selection::from_fn(|_| 0.0)
+    }
+
+    /// Cells with any weight.
+    #[wasm_bindgen(getter)]
+    pub fn cell_count(&self) -> u32 {
+        self.weights.iter().filter(|&&w| w > 0.0).count() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.iter().all(|&w| w == 0.0)
+    }
+
+    /// Weight per cell, 0 to 1.
+    pub fn weights(&self) -> Box<[f32]> {
+        self.weights.clone().into_boxed_slice()
+    }
+
+    /// Weights as bytes, 0 to 255, for drawing the selection as an overlay.
+    pub fn mask(&self) -> Box<[u8]> {
+        self.weights
+            .iter()
+            .map(|&w| (w * 255.0).round() as u8)
+            .collect()
+    }
+
+    /// Combines `other` into this selection: `"union"` keeps the larger weight,
+    /// `"intersect"` the smaller, `"subtract"` removes `other`, and `"xor"` keeps what
+    /// lies in just one of them.
+    pub fn combine(&mut self, other: &Selection, op: &str) -> Result<(), JsValue> {
+        let op: fn(f32, f32) -> f32 = match op {
+            "union" => f32::max,
+            "intersect" => f32::min,
+            "subtract" => |a, b| a.min(1.0 - b),
+            "xor" => |a, b| a.max(b) - a.min(b),
+            _ => {
+                return Err(JsValue::from_str(
+                    "op must be union, intersect, subtract or xor",
+                ));
+            }
+        };
+        for (a, &b) in self.weights.iter_mut().zip(&other.weights) {
+            *a = op(*a, b);
+        }
+        Ok(())
+    }
+
+    pub fn invert(&mut self) {
+        for w in &mut self.weights {
+            *w = 1.0 - *w;
+        }
+    }
+
+    /// Softens the edge over about `radius` cells either side (two box blurs), so edits
+    /// fade out across it instead of stopping at a step.
+    pub fn feather(&mut self, radius: u32) -> Result<(), JsValue> {
+        if radius as usize > WIDTH / 4 {
+            return Err(JsValue::from_str(
+                "radius must be at most a quarter of the grid width",
+            ));
+        }
+        if radius > 0 {
+            let half = radius as usize / 2;
+            let pass = box_blur(&self.weights, half.max(1));
+            self.weights = box_blur(&pass, (radius as usize - half).max(1));
+            // Running sums leave rounding residue far from the edge; snap it back.
+            for w in &mut self.weights {
+                *w = if *w < 1e-4 {
+                    0.0
+                } else if *w > 1.0 - 1e-4 {
+                    1.0
+                } else {
+                    *w
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The whole grid.
+#[wasm_bindgen]
+pub fn select_all() -> Selection {
+    Selection::from_fn(|_| 1.0)
+}
+
+/// Cells `x..x + width` × `y..y + height`; columns wrap east–west, rows are clipped.
+#[wasm_bindgen]
+pub fn select_rectangle(x: u32, y: u32, width: u32, height: u32) -> Result<Selection, JsValue> {
+    if x as usize >= WIDTH || y as usize >= HEIGHT || width as usize > WIDTH {
+        return Err(JsValue::from_str(
+            "rectangle must start within the grid and be at most the grid width",
+        ));
+    }
+    let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+    Ok(Selection::from_mask(|idx| {
+        let (cx, cy) = (idx % WIDTH, idx / WIDTH);
+        (cx + WIDTH - x) % WIDTH < width && cy >= y && cy - y < height
+    }))
+}
+
+/// Cells inside a closed polygon of `[x0, y0, x1, y1, ...]` cell coordinates, by the
+/// even–odd rule. Points may run past the east or west edge to wrap a polygon across the
+/// seam.
+#[wasm_bindgen]
+pub fn select_lasso(points: &[f32]) -> Result<Selection, JsValue> {
+    if points.len() < 6 || !points.len().is_multiple_of(2) || points.iter().any(|v| !v.is_finite())
+    {
+        return Err(JsValue::from_str(
+            "points must hold at least three finite x, y pairs",
+        ));
+    }
+    let vertices: Vec<(f32, f32)> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    let mut selection = Selection::new();
+    let (top, bottom) = vertices
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(t, b), &(_, y)| {
+            (t.min(y), b.max(y))
+        });
+    let rows = (top.ceil().max(0.0) as usize)..=(bottom.floor().min(HEIGHT as f32 - 1.0) as usize);
+    let mut crossings = Vec::new();
+    for y in rows {
+        let fy = y as f32;
+        crossings.clear();
+        for (i, &(ax, ay)) in vertices.iter().enumerate() {
+            let (bx, by) = vertices[(i + 1) % vertices.len()];
+            // Half-open in y, so a vertex on the scan line counts once.
+            if (ay <= fy) != (by <= fy) {
+                crossings.push(ax + (fy - ay) / (by - ay) * (bx - ax));
+            }
+        }
+        crossings.sort_by(f32::total_cmp);
+        for span in crossings.chunks_exact(2) {
+            let (start, end) = (span[0].ceil() as i64, span[1].floor() as i64);
+            let end = end.min(start + WIDTH as i64 - 1);
+            for x in start..=end {
+                selection.weights[y * WIDTH + wrap_x(x)] = 1.0;
+            }
+        }
+    }
+    Ok(selection)
+}
+
+/// Cells with heights in `[min, max]`, heightmap units.
+#[wasm_bindgen]
+pub fn select_elevation_range(flat: &[f32], min: f32, max: f32) -> Result<Selection, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if min.is_nan() || max.is_nan() || min > max {
+        return Err(JsValue::from_str("min must be <= max"));
+    }
+    Ok(Selection::from_mask(|idx| (min..=max).contains(&flat[idx])))
+}
+
+/// Cells of biome id `biome` in a biome map such as `whittaker_biomes` produces.
+#[wasm_bindgen]
+pub fn select_biome(biome_map: &[u8], biome: u8) -> Result<Selection, JsValue> {
+    check_grid_len(biome_map, "biome map")?;
+    Ok(Selection::from_mask(|idx| biome_map[idx] == biome))
+}
+
+/// Cells of landmass `id`, numbered as `landmasses` numbers them (1 = largest).
+#[wasm_bindgen]
+pub fn select_landmass(flat: &[f32], sea_level: f32, id: u32) -> Result<Selection, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !sea_level.is_finite() {
+        return Err(JsValue::from_str("sea_level must be finite"));
+    }
+    let (labels, islands) = label_landmasses(flat, sea_level);
+    if id == 0 || id as usize > islands.len() {
+        return Err(JsValue::from_str(&format!(
+            "id must be within [1, {}]",
+            islands.len()
+        )));
+    }
+    Ok(Selection::from_mask(|idx| labels[idx] == id))
+}
@@ -0,0 +1,380 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::BIOME_ICE;
+use crate::climate::Climate;
+use crate::grid::{
+    CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, box_blur, check_grid_len, smoothstep,
+    wrapped_distance_field,
+};
+use crate::growing_season::months_above;
+use crate::hydrology::Hydrology;
+use crate::json::{self, ObjectWriter};
+use crate::terrain::{gradient, ruggedness};
+use crate::vector::grid_to_lon_lat;
+use crate::vegetation::biome_canopy;
+
+/// Gradient (elevation units per cell) up to which ground counts as level for building,
+/// and from which it is too steep to settle comfortably.
+const FLAT_SLOPE: f32 = 0.002;
+const STEEP_SLOPE: f32 = 0.02;
+/// Crop base temperature for the growing season, °C, as in `growing_season_months`.
+const CROP_BASE_C: f32 = 5.0;
+/// Cells around a coast cell whose share of land tells a sheltered bay from open shore.
+const HARBOUR_RADIUS: usize = 5;
+/// Cells around a site searched for stone, ore and timber.
+const RESOURCE_RADIUS: usize = 6;
+/// Ruggedness (mean elevation difference to the neighbours) where hills start to yield
+/// stone and ore, and where they yield all they can.
+const QUARRY_RUGGEDNESS: f32 = 0.003;
+const MINE_RUGGEDNESS: f32 = 0.02;
+/// Most sites `score_settlement_sites` returns.
+const MAX_SITES: u32 = 4096;
+
+/// Weights and reach of the suitability factors for `score_settlement_sites`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SettlementParams {
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km², as in the map exports.
+    pub river_min_area_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes.
+    pub lake_min_depth_metres: f32,
+    /// Distance from a river or lake over which fresh water stops counting, cells.
+    pub water_reach: f32,
+    /// Distance from the sea over which coast access stops counting, cells.
+    pub coast_reach: f32,
+    pub water_weight: f32,
+    pub flatness_weight: f32,
+    pub coast_weight: f32,
+    pub fertility_weight: f32,
+    pub resources_weight: f32,
+    /// How many of the best sites to pick.
+    pub site_count: u32,
+    /// Closest two picked sites may be, cells.
+    pub min_spacing: f32,
+}
+
+impl Default for SettlementParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 20_000.0,
+            lake_min_depth_metres: 20.0,
+            water_reach: 6.0,
+            coast_reach: 4.0,
+            water_weight: 0.3,
+            flatness_weight: 0.2,
+            coast_weight: 0.15,
+            fertility_weight: 0.25,
+            resources_weight: 0.1,
+            site_count: 32,
+            min_spacing: 24.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SettlementParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SettlementParams {
+        Self::default()
+    }
+}
+
+impl SettlementParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        let limits = [
+            self.river_min_area_km2,
+            self.lake_min_depth_metres,
+            self.min_spacing,
+        ];
+        if limits.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "river area, lake depth and spacing must be >= 0",
+            ));
+        }
+        if [self.water_reach, self.coast_reach]
+            .iter()
+            .any(|v| !v.is_finite() || *v <= 0.0)
+        {
+            return Err(JsValue::from_str("water_reach and coast_reach must be > 0"));
+        }
+        let weights = self.weights();
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(JsValue::from_str("weights must be >= 0"));
+        }
+        if weights.iter().sum::<f32>() <= 0.0 {
+            return Err(JsValue::from_str("at least one weight must be > 0"));
+        }
+        if self.site_count > MAX_SITES {
+            return Err(JsValue::from_str(&format!(
+                "site_count must be at most {MAX_SITES}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Factor weights in `Factors` order.
+    fn weights(&self) -> [f32; 5] {
+        [
+            self.water_weight,
+            self.flatness_weight,
+            self.coast_weight,
+            self.fertility_weight,
+            self.resources_weight,
+        ]
+    }
+}
+
+/// A cell's suitability factors, each in [0, 1].
+#[derive(Clone, Copy, Debug, Default)]
+struct Factors {
+    /// Nearness to a river or lake.
+    water: f32,
+    /// Level ground to build and farm on.
+    flatness: f32,
+    /// Nearness to the sea, more for a sheltered bay that makes a harbour.
+    coast: f32,
+    /// Growing season and water for crops.
+    fertility: f32,
+    /// Stone, ore and timber close by.
+    resources: f32,
+}
+
+impl Factors {
+    fn values(&self) -> [f32; 5] {
+        [
+            self.water,
+            self.flatness,
+            self.coast,
+            self.fertility,
+            self.resources,
+        ]
+    }
+}
+
+/// Keeps picked sites at least `spacing` cells apart (across the east–west seam too),
+/// checking only the buckets around each candidate.
+pub(crate) struct SpacedSites {
+    spacing: f32,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl SpacedSites {
+    pub(crate) fn new(spacing: f32) -> SpacedSites {
+        // Buckets at least `spacing` wide, so anything closer is in a neighbouring one.
+        let columns = ((WIDTH as f32 / spacing.max(1.0)) as usize).clamp(1, WIDTH);
+        let rows = ((HEIGHT as f32 / spacing.max(1.0)) as usize).clamp(1, HEIGHT);
+        SpacedSites {
+            spacing,
+            columns,
+            rows,
+            buckets: vec![Vec::new(); columns * rows],
+        }
+    }
+
+    fn bucket(&self, idx: usize) -> (usize, usize) {
+        (
+            idx % WIDTH * self.columns / WIDTH,
+            idx / WIDTH * self.rows / HEIGHT,
+        )
+    }
+
+    /// Whether `idx` is at least the spacing away from every site added so far.
+    pub(crate) fn fits(&self, idx: usize) -> bool {
+        let (bx, by) = self.bucket(idx);
+        let mut columns = [
+            (bx + self.columns - 1) % self.columns,
+            bx,
+            (bx + 1) % self.columns,
+        ];
+        columns.sort_unstable();
+        let (x, y) = ((idx % WIDTH) as f32, (idx / WIDTH) as f32);
+        let mut last = usize::MAX;
+        for column in columns {
+            if column == last {
+                continue;
+            }
+            last = column;
+            for row in by.saturating_sub(1)..(by + 2).min(self.rows) {
+                let crowded = self.buckets[row * self.columns + column]
+                    .iter()
+                    .any(|&other| {
+                        let dx = ((other % WIDTH) as f32 - x).abs();
+                        let dy = (other / WIDTH) as f32 - y;
+                        dx.min(WIDTH as f32 - dx).hypot(dy) < self.spacing
+                    });
+                if crowded {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    pub(crate) fn insert(&mut self, idx: usize) {
+        let (bx, by) = self.bucket(idx);
+        self.buckets[by * self.columns + bx].push(idx);
+    }
+}
+
+/// Settlement suitability of every cell, with the best sites picked from it.
+#[wasm_bindgen]
+pub struct SettlementSites {
+    scores: Vec<f32>,
+    sites: Vec<(usize, Factors)>,
+}
+
+#[wasm_bindgen]
+impl SettlementSites {
+    /// Suitability per cell in [0, 1]: the weighted mean of the factors. Water, lakes and
+    /// ice are 0.
+    pub fn scores(&self) -> Box<[f32]> {
+        self.scores.clone().into_boxed_slice()
+    }
+
+    /// Cell indices of the picked sites, best first.
+    pub fn site_cells(&self) -> Box<[u32]> {
+        self.sites.iter().map(|&(idx, _)| idx as u32).collect()
+    }
+
+    /// The picked sites, best first: `[{"x","y","lon","lat","score","water","flatness",
+    /// "coast","fertility","resources"},...]` with `x`, `y` in cell-centre grid
+    /// coordinates and every factor in [0, 1].
+    pub fn sites_json(&self) -> String {
+        json::array(self.sites.iter().map(|&(idx, factors)| {
+            let (x, y) = ((idx % WIDTH) as f32 + 0.5, (idx / WIDTH) as f32 + 0.5);
+            let (lon, lat) = grid_to_lon_lat(x, y);
+            ObjectWriter::new()
+                .number("x", x as f64, 1)
+                .number("y", y as f64, 1)
+                .number("lon", lon as f64, 3)
+                .number("lat", lat as f64, 3)
+                .number("score", self.scores[idx] as f64, 4)
+                .number("water", factors.water as f64, 4)
+                .number("flatness", factors.flatness as f64, 4)
+                .number("coast", factors.coast as f64, 4)
+                .number("fertility", factors.fertility as f64, 4)
+                .number("resources", factors.resources as f64, 4)
+                .finish()
+        }))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn site_count(&self) -> u32 {
+        self.sites.len() as u32
+    }
+}
+
+/// Rates every land cell for founding a settlement on five factors: fresh water (distance
+/// to a river or lake), flatness (local slope), coast (distance to the sea, raised for a
+/// sheltered bay), fertility (growing-season length times water availability, AET / PET)
+/// and resources (hills for stone and ore, forest for timber, within a few cells). Pass
+/// the biome map with any overrides applied, and an annual `climate` run (the growing
+/// season comes from its seasonal amplitude). Then picks up to `site_count` of the best
+/// cells, best first, no two closer than `min_spacing`.
+#[wasm_bindgen]
+pub fn score_settlement_sites(
+    flat: &[f32],
+    climate: &Climate,
+    biomes: &[u8],
+    params: &SettlementParams,
+) -> Result<SettlementSites, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_grid_len(biomes, "biome map")?;
+    climate.require_annual("score_settlement_sites")?;
+    params.validate()?;
+
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+    let land: Vec<bool> = (0..CELL_COUNT)
+        .map(|i| flat[i] >= params.sea_level && !hydrology.lakes[i])
+        .collect();
+    let sea: Vec<bool> = (0..CELL_COUNT)
+        .map(|i| flat[i] < params.sea_level && !hydrology.lakes[i])
+        .collect();
+    let mut fresh = hydrology.lakes.clone();
+    for river in hydrology.rivers(params.river_min_area_km2) {
+        for idx in river.cells {
+            fresh[idx] |= land[idx];
+        }
+    }
+    let to_fresh = wrapped_distance_field(&fresh);
+    let to_sea = wrapped_distance_field(&sea);
+    let land_share = box_blur(
+        &land
+            .iter()
+            .map(|&l| f32::from(u8::from(l)))
+            .collect::<Vec<_>>(),
+        HARBOUR_RADIUS,
+    );
+    let hills = box_blur(
+        &(0..CELL_COUNT)
+            .map(|i| smoothstep(QUARRY_RUGGEDNESS, MINE_RUGGEDNESS, ruggedness(flat, i)))
+            .collect::<Vec<_>>(),
+        RESOURCE_RADIUS,
+    );
+    let forest = box_blur(
+        &biomes.iter().map(|&b| biome_canopy(b)).collect::<Vec<_>>(),
+        RESOURCE_RADIUS,
+    );
+
+    let weights = params.weights();
+    let total_weight: f32 = weights.iter().sum();
+    let mut scores = vec![0.0_f32; CELL_COUNT];
+    let mut factors = vec![Factors::default(); CELL_COUNT];
+    for idx in 0..CELL_COUNT {
+        if !land[idx] || biomes[idx] == BIOME_ICE {
+            continue;
+        }
+        let (gx, gy) = gradient(flat, idx);
+        let pet = climate.pet[idx];
+        let wetness = if pet > 0.0 {
+            (climate.evapotranspiration[idx] / pet).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let season = months_above(
+            climate.temperature[idx],
+            climate.amplitude_at(idx),
+            CROP_BASE_C,
+        ) / 12.0;
+        // A bay has land on most sides; open shore about half.
+        let shelter = smoothstep(0.5, 0.8, land_share[idx]);
+        let cell = Factors {
+            water: 1.0 - smoothstep(0.0, params.water_reach, to_fresh[idx]),
+            flatness: 1.0 - smoothstep(FLAT_SLOPE, STEEP_SLOPE, gx.hypot(gy)),
+            coast: (1.0 - smoothstep(1.0, 1.0 + params.coast_reach, to_sea[idx]))
+                * (0.5 + 0.5 * shelter),
+            fertility: season * wetness,
+            resources: 0.5 * (hills[idx] + forest[idx]),
+        };
+        scores[idx] = cell
+            .values()
+            .iter()
+            .zip(weights)
+            .map(|(v, w)| v * w)
+            .sum::<f32>()
+            / total_weight;
+        factors[idx] = cell;
+    }
+
+    let mut order: Vec<usize> = (0..CELL_COUNT).filter(|&i| scores[i] > 0.0).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    let mut spaced = SpacedSites::new(params.min_spacing);
+    let mut sites = Vec::new();
+    for idx in order {
+        if sites.len() >= params.site_count as usize {
+            break;
+        }
+        if spaced.fits(idx) {
+            spaced.insert(idx);
+            sites.push((idx, factors[idx]));
+        }
+    }
+    Ok(SettlementSites { scores, sites })
+}
@@ -0,0 +1,248 @@
+use std::cmp::Reverse;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, WIDTH, box_blur, check_grid_len};
+use crate::json::{self, ObjectWriter};
+use crate::settlement::SpacedSites;
+use crate::vector::grid_to_lon_lat;
+
+/// Most settlements `place_settlements` places across all tiers.
+const MAX_SETTLEMENTS: u32 = 4096;
+
+/// Rank of a settlement, largest first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tier {
+    Capital,
+    City,
+    Town,
+    Village,
+}
+
+impl Tier {
+    const ALL: [Tier; 4] = [Tier::Capital, Tier::City, Tier::Town, Tier::Village];
+
+    fn name(self) -> &'static str {
+        match self {
+            Tier::Capital => "capital",
+            Tier::City => "city",
+            Tier::Town => "town",
+            Tier::Village => "village",
+        }
+    }
+
+    /// Population of a settlement of this tier on average land (suitability 0.5 around
+    /// it).
+    fn typical_population(self) -> f32 {
+        match self {
+            Tier::Capital => 400_000.0,
+            Tier::City => 80_000.0,
+            Tier::Town => 8_000.0,
+            Tier::Village => 600.0,
+        }
+    }
+}
+
+/// How many settlements of each tier to place and how far apart, for `place_settlements`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SettlementHierarchyParams {
+    pub capitals: u32,
+    pub cities: u32,
+    pub towns: u32,
+    pub villages: u32,
+    /// Closest a settlement of each tier may be to any settlement already placed, cells.
+    pub capital_spacing: f32,
+    pub city_spacing: f32,
+    pub town_spacing: f32,
+    pub village_spacing: f32,
+    /// Multiplies every population estimate.
+    pub population_scale: f32,
+}
+
+impl Default for SettlementHierarchyParams {
+    fn default() -> Self {
+        Self {
+            capitals: 1,
+            cities: 6,
+            towns: 24,
+            villages: 96,
+            capital_spacing: 256.0,
+            city_spacing: 96.0,
+            town_spacing: 40.0,
+            village_spacing: 16.0,
+            population_scale: 1.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SettlementHierarchyParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SettlementHierarchyParams {
+        Self::default()
+    }
+}
+
+impl SettlementHierarchyParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        let total: u64 = Tier::ALL.iter().map(|&t| self.count(t) as u64).sum();
+        if total > MAX_SETTLEMENTS as u64 {
+            return Err(JsValue::from_str(&format!(
+                "at most {MAX_SETTLEMENTS} settlements in all"
+            )));
+        }
+        if Tier::ALL
+            .iter()
+            .any(|&t| !(0.0..=(WIDTH / 2) as f32).contains(&self.spacing(t)))
+        {
+            return Err(JsValue::from_str(&format!(
+                "spacings must be within [0, {}]",
+                WIDTH / 2
+            )));
+        }
+        if !self.population_scale.is_finite() || self.population_scale < 0.0 {
+            return Err(JsValue::from_str("population_scale must be >= 0"));
+        }
+        Ok(())
+    }
+
+    fn count(&self, tier: Tier) -> u32 {
+        match tier {
+            Tier::Capital => self.capitals,
+            Tier::City => self.cities,
+            Tier::Town => self.towns,
+            Tier::Village => self.villages,
+        }
+    }
+
+    fn spacing(&self, tier: Tier) -> f32 {
+        match tier {
+            Tier::Capital => self.capital_spacing,
+            Tier::City => self.city_spacing,
+            Tier::Town => self.town_spacing,
+            Tier::Village => self.village_spacing,
+        }
+    }
+}
+
+struct Settlement {
+    idx: usize,
+    tier: Tier,
+    score: f32,
+    population: u32,
+}
+
+impl Settlement {
+    fn to_json(&self) -> String {
+        let (x, y) = (
+            (self.idx % WIDTH) as f32 + 0.5,
+            (self.idx / WIDTH) as f32 + 0.5,
+        );
+        let (lon, lat) = grid_to_lon_lat(x, y);
+        ObjectWriter::new()
+            .raw("tier", &json::quote(self.tier.name()))
+            .number("x", x as f64, 1)
+            .number("y", y as f64, 1)
+            .number("lon", lon as f64, 3)
+            .number("lat", lat as f64, 3)
+            .number("score", self.score as f64, 4)
+            .integer("population", self.population as u64)
+            .finish()
+    }
+}
+
+/// Settlements placed by `place_settlements`, capitals first, then cities, towns and
+/// villages, each tier by falling population.
+#[wasm_bindgen]
+pub struct SettlementHierarchy {
+    settlements: Vec<Settlement>,
+}
+
+#[wasm_bindgen]
+impl SettlementHierarchy {
+    /// `[{"tier","x","y","lon","lat","score","population"},...]` with `tier` one of
+    /// `"capital"`, `"city"`, `"town"` or `"village"` and `x`, `y` in cell-centre grid
+    /// coordinates.
+    pub fn settlements_json(&self) -> String {
+        json::array(self.settlements.iter().map(Settlement::to_json))
+    }
+
+    /// Cell index of each settlement.
+    pub fn cells(&self) -> Box<[u32]> {
+        self.settlements.iter().map(|s| s.idx as u32).collect()
+    }
+
+    /// Tier of each settlement: 0 capital, 1 city, 2 town, 3 village.
+    pub fn tiers(&self) -> Box<[u8]> {
+        self.settlements.iter().map(|s| s.tier as u8).collect()
+    }
+
+    pub fn populations(&self) -> Box<[u32]> {
+        self.settlements.iter().map(|s| s.population).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.settlements.len() as u32
+    }
+}
+
+/// Places settlements on the suitability layer from `SettlementSites::scores`, from the
+/// top tier down: each tier takes the best remaining cells at least its spacing from every
+/// settlement placed so far, so capitals get the finest sites and villages fill in the
+/// gaps. A settlement's population is its tier's typical size (400 000, 80 000, 8 000 and
+/// 600) scaled by how good the land around it is: the mean suitability within half the
+/// tier's spacing, over land only so a port is not penalised for the sea, relative to 0.5.
+/// Tiers run short where the land runs out.
+#[wasm_bindgen]
+pub fn place_settlements(
+    scores: &[f32],
+    params: &SettlementHierarchyParams,
+) -> Result<SettlementHierarchy, JsValue> {
+    check_grid_len(scores, "suitability scores")?;
+    params.validate()?;
+
+    let mut order: Vec<usize> = (0..CELL_COUNT).filter(|&i| scores[i] > 0.0).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    let land: Vec<f32> = scores
+        .iter()
+        .map(|&s| f32::from(u8::from(s > 0.0)))
+        .collect();
+    let mut taken = vec![false; CELL_COUNT];
+    let mut settlements: Vec<Settlement> = Vec::new();
+    for tier in Tier::ALL {
+        let count = params.count(tier) as usize;
+        if count == 0 {
+            continue;
+        }
+        let spacing = params.spacing(tier);
+        let mut spaced = SpacedSites::new(spacing);
+        for s in &settlements {
+            spaced.insert(s.idx);
+        }
+        let radius = (spacing / 2.0).round().max(1.0) as usize;
+        let (hinterland, land_share) = (box_blur(scores, radius), box_blur(&land, radius));
+        let first = settlements.len();
+        for &idx in &order {
+            if settlements.len() - first >= count {
+                break;
+            }
+            if taken[idx] || !spaced.fits(idx) {
+                continue;
+            }
+            spaced.insert(idx);
+            taken[idx] = true;
+            let richness = hinterland[idx].max(0.0) / land_share[idx].max(f32::EPSILON) / 0.5;
+            let population = tier.typical_population() * richness * params.population_scale;
+            settlements.push(Settlement {
+                idx,
+                tier,
+                score: scores[idx],
+                population: population.round() as u32,
+            });
+        }
+        settlements[first..].sort_by_key(|s| Reverse(s.population));
+    }
+    Ok(SettlementHierarchy { settlements })
+}
@@ -0,0 +1,73 @@
+use crate::deflate::{zlib_compress, zlib_decompress};
+use crate::grid::CELL_COUNT;
+use crate::json::{self, Json, ObjectWriter};
+use crate::png::encode_png;
+use crate::raw::{delta_planes, undelta_planes};
+use crate::render::ColorRamp;
+use crate::thumbnail::thumbnail_rgba;
+
+/// Size of snapshot thumbnails, pixels.
+pub(crate) const SNAPSHOT_THUMBNAIL_WIDTH: usize = 256;
+pub(crate) const SNAPSHOT_THUMBNAIL_HEIGHT: usize = 128;
+
+/// Heightmap as a zlib stream of its delta-coded byte planes, as `pack_layer` stores f32
+/// layers; lossless, and several times smaller than the raw floats.
+pub(crate) fn pack_heights(flat: &[f32]) -> Vec<u8> {
+    let raw: Vec<u8> = flat.iter().flat_map(|h| h.to_le_bytes()).collect();
+    zlib_compress(&delta_planes(&raw, 4))
+}
+
+/// Inverse of `pack_heights`.
+pub(crate) fn unpack_heights(packed: &[u8]) -> Result<Vec<f32>, String> {
+    let planes = zlib_decompress(packed, CELL_COUNT * 4)?;
+    if planes.len() != CELL_COUNT * 4 {
+        return Err("heightmap length mismatch".to_string());
+    }
+    Ok(undelta_planes(&planes, 4)
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// A named copy of the editor's state to come back to, e.g. "before erosion".
+#[derive(Clone)]
+pub(crate) struct Snapshot {
+    pub(crate) name: String,
+    pub(crate) sea_level: f32,
+    pub(crate) params: Json,
+    /// Heights as `pack_heights` stores them.
+    pub(crate) heights: Vec<u8>,
+    /// Shaded-relief preview as a PNG file.
+    pub(crate) thumbnail: Vec<u8>,
+}
+
+impl Snapshot {
+    pub(crate) fn capture(
+        name: &str,
+        flat: &[f32],
+        sea_level: f32,
+        params: &Json,
+        ramp: &ColorRamp,
+    ) -> Snapshot {
+        let (width, height) = (SNAPSHOT_THUMBNAIL_WIDTH, SNAPSHOT_THUMBNAIL_HEIGHT);
+        let rgba = thumbnail_rgba(flat, width, height, ramp, sea_level);
+        Snapshot {
+            name: name.to_owned(),
+            sea_level,
+            params: params.clone(),
+            heights: pack_heights(flat),
+            thumbnail: encode_png(width, height, 6, 8, &rgba),
+        }
+    }
+
+    /// `{"name","sea_level","bytes","params"}`; `bytes` counts the packed heights and
+    /// thumbnail.
+    pub(crate) fn to_json(&self) -> String {
+        ObjectWriter::new()
+            .raw("name", &json::quote(&self.name))
+            .raw("sea_level", &self.sea_level.to_string())
+            .integer("bytes", (self.heights.len() + self.thumbnail.len()) as u64)
+            .raw("params", &self.params.to_string())
+            .finish()
+    }
+}
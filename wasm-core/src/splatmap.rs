@@ -0,0 +1,134 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{BIOME_COLD_DESERT, BIOME_DESERT, BIOME_ICE, BIOME_TUNDRA};
+use crate::climate::RELIEF_METRES;
+use crate::grid::{CELL_COUNT, SEA_LEVEL, check_grid_len, smoothstep};
+use crate::render::RELIEF_SCALE;
+use crate::terrain::slope_aspect;
+
+/// Material thresholds for `splatmap_rgba`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SplatParams {
+    pub sea_level: f32,
+    /// Mesh units per heightmap unit, as `MeshParams::vertical_scale`; slopes are measured
+    /// on a terrain exported with the same value.
+    pub vertical_scale: f32,
+    /// Slopes where rock starts to show and where it covers the ground, degrees.
+    pub rock_slope_start: f32,
+    pub rock_slope_full: f32,
+    /// Height of the snow line above sea level, and the height over which snow fades in
+    /// around it, metres.
+    pub snow_line_metres: f32,
+    pub snow_blend_metres: f32,
+    /// Beaches give way to grass this far above sea level, metres.
+    pub beach_metres: f32,
+}
+
+impl Default for SplatParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            vertical_scale: RELIEF_SCALE,
+            rock_slope_start: 25.0,
+            rock_slope_full: 40.0,
+            snow_line_metres: 3500.0,
+            snow_blend_metres: 600.0,
+            beach_metres: 25.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SplatParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SplatParams {
+        Self::default()
+    }
+}
+
+impl SplatParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !(self.vertical_scale.is_finite() && self.vertical_scale > 0.0) {
+            return Err(JsValue::from_str("vertical_scale must be > 0"));
+        }
+        if !(0.0 <= self.rock_slope_start
+            && self.rock_slope_start < self.rock_slope_full
+            && self.rock_slope_full <= 90.0)
+        {
+            return Err(JsValue::from_str(
+                "rock slopes must satisfy 0 <= rock_slope_start < rock_slope_full <= 90",
+            ));
+        }
+        let positive = |v: f32| v.is_finite() && v > 0.0;
+        if !(self.snow_line_metres.is_finite()
+            && positive(self.snow_blend_metres)
+            && positive(self.beach_metres))
+        {
+            return Err(JsValue::from_str(
+                "snow_line_metres must be finite; snow_blend_metres and beach_metres > 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Terrain material splatmap, four bytes a cell: red rock, green grass, blue sand, alpha
+/// snow, always summing to 255 as terrain shaders expect. Steep ground is rock whatever
+/// else applies; what is left turns to snow above the snow line, then to sand on beaches,
+/// the sea floor and deserts, and to grass elsewhere. With a `biome_map`, ice is snow,
+/// tundra at least half snow and deserts sand; pass it empty to go by height and slope
+/// only.
+#[wasm_bindgen]
+pub fn splatmap_rgba(
+    flat: &[f32],
+    biome_map: &[u8],
+    params: &SplatParams,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !biome_map.is_empty() {
+        check_grid_len(biome_map, "biome map")?;
+    }
+    params.validate()?;
+    let metres_per_unit = RELIEF_METRES / (1.0 - params.sea_level);
+    let mut rgba = vec![0_u8; CELL_COUNT * 4];
+    for (idx, texel) in rgba.chunks_exact_mut(4).enumerate() {
+        let metres = (flat[idx] - params.sea_level) * metres_per_unit;
+        let biome = biome_map.get(idx).copied();
+        let slope = slope_aspect(flat, idx, 1.0 / params.vertical_scale).0;
+        let rock = smoothstep(params.rock_slope_start, params.rock_slope_full, slope);
+        let mut snow = smoothstep(
+            params.snow_line_metres - params.snow_blend_metres,
+            params.snow_line_metres + params.snow_blend_metres,
+            metres,
+        );
+        let mut sand = if metres < 0.0 {
+            1.0
+        } else {
+            1.0 - smoothstep(0.0, params.beach_metres, metres)
+        };
+        match biome {
+            Some(BIOME_ICE) => snow = 1.0,
+            Some(BIOME_TUNDRA) => snow = snow.max(0.5),
+            Some(BIOME_DESERT | BIOME_COLD_DESERT) => sand = 1.0,
+            _ => {}
+        }
+        if metres < 0.0 {
+            snow = 0.0;
+        }
+        let snow = (1.0 - rock) * snow;
+        let sand = (1.0 - rock - snow) * sand;
+        // Rounding running totals keeps the bytes summing to exactly 255.
+        let totals = [rock, rock + snow, rock + snow + sand].map(|t| (t * 255.0).round() as u8);
+        texel.copy_from_slice(&[
+            totals[0],
+            255 - totals[2],
+            totals[2] - totals[1],
+            totals[1] - totals[0],
+        ]);
+    }
+    Ok(rgba.into_boxed_slice())
+}
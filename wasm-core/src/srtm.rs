@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH};
+use crate::resample::{Filter, resample_field};
+
+/// SRTM's marker for voids (radar shadow, water glint).
+const NODATA: i16 = -32768;
+/// Longest side the mosaic is assembled at before resampling to the grid; finer tiles are
+/// box-averaged down to it.
+const MAX_MOSAIC_SIDE: usize = 2 * WIDTH + 1;
+
+/// Height conversion for `SrtmMosaic::heightmap`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SrtmParams {
+    pub sea_level: f32,
+    /// SRTM has no bathymetry: samples at or below 0 m, and tiles missing from the mosaic,
+    /// become sea this deep.
+    pub ocean_depth_metres: f32,
+    /// Stretches the highest point to 1 instead of keeping the generator's 8000 m relief,
+    /// so lowland regions use the full height range.
+    pub fit_relief: bool,
+}
+
+impl Default for SrtmParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            ocean_depth_metres: 200.0,
+            fit_relief: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SrtmParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SrtmParams {
+        Self::default()
+    }
+}
+
+impl SrtmParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(self.sea_level >= 0.0 && self.sea_level < 1.0) {
+            return Err(JsValue::from_str("sea_level must be within [0, 1)"));
+        }
+        if !(self.ocean_depth_metres.is_finite() && self.ocean_depth_metres >= 0.0) {
+            return Err(JsValue::from_str("ocean_depth_metres must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Latitude and longitude of a tile's south-west corner from its SRTM name, such as
+/// `N45W122.hgt`; any directory or extension is ignored.
+fn parse_tile_name(name: &str) -> Option<(i32, i32)> {
+    let stem = name.rsplit(['/', '\\']).next()?;
+    let stem = stem.get(..7)?.to_ascii_uppercase();
+    let (lat, lon) = stem.split_at(3);
+    let lat: i32 = lat[1..].parse().ok()?;
+    let lon: i32 = lon[1..].parse().ok()?;
+    let lat = match &stem[..1] {
+        "N" => lat,
+        "S" => -lat,
+        _ => return None,
+    };
+    let lon = match &stem[3..4] {
+        "E" => lon,
+        "W" => -lon,
+        _ => return None,
+    };
+    (lat < 90 && (-180..180).contains(&lon)).then_some((lat, lon))
+}
+
+/// Fills NaN voids by push–pull: the field is halved with voids left out of each 2 × 2
+/// average until none remain, and every void takes the bilinear value of the level above,
+/// so voids close smoothly from the surrounding terrain at every size.
+fn fill_voids(field: &mut [f32], width: usize, height: usize) {
+    if !field.iter().any(|v| v.is_nan()) || width * height == 1 {
+        return;
+    }
+    let (cw, ch) = (width.div_ceil(2), height.div_ceil(2));
+    let mut coarse: Vec<f32> = (0..cw * ch)
+        .map(|i| {
+            let (x, y) = (2 * (i % cw), 2 * (i / cw));
+            let (mut sum, mut count) = (0.0, 0);
+            for yy in y..(y + 2).min(height) {
+                for xx in x..(x + 2).min(width) {
+                    let v = field[yy * width + xx];
+                    if !v.is_nan() {
+                        sum += v;
+                        count += 1;
+                    }
+                }
+            }
+            if count == 0 {
+                f32::NAN
+            } else {
+                sum / count as f32
+            }
+        })
+        .collect();
+    fill_voids(&mut coarse, cw, ch);
+    let at = |x: f32, y: f32| {
+        let x = x.clamp(0.0, (cw - 1) as f32);
+        let y = y.clamp(0.0, (ch - 1) as f32);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(cw - 1), (y0 + 1).min(ch - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let top = coarse[y0 * cw + x0] * (1.0 - tx) + coarse[y0 * cw + x1] * tx;
+        let bottom = coarse[y1 * cw + x0] * (1.0 - tx) + coarse[y1 * cw + x1] * tx;
+        top * (1.0 - ty) + bottom * ty
+    };
+    for (i, v) in field.iter_mut().enumerate() {
+        if v.is_nan() {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+            *v = at((x + 0.5) / 2.0 - 0.5, (y + 0.5) / 2.0 - 0.5);
+        }
+    }
+}
+
+/// SRTM `.hgt` tiles assembled into one heightmap, so the hydrology and biome subsystems
+/// can run over real terrain. Add 1″ (3601 × 3601) or 3″ (1201 × 1201) tiles with their
+/// file names, which give their positions, then call `heightmap`.
+#[wasm_bindgen]
+pub struct SrtmMosaic {
+    side: usize,
+    /// Samples of each tile keyed by its south-west corner, latitude then longitude.
+    tiles: BTreeMap<(i32, i32), Vec<i16>>,
+}
+
+impl Default for SrtmMosaic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl SrtmMosaic {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SrtmMosaic {
+        Self {
+            side: 0,
+            tiles: BTreeMap::new(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tile_count(&self) -> u32 {
+        self.tiles.len() as u32
+    }
+
+    /// Adds the tile in `bytes` (big-endian i16 metres, north row first) at the position
+    /// encoded in `name`, e.g. `N45W122.hgt`.
+    pub fn add_tile(&mut self, name: &str, bytes: &[u8]) -> Result<(), JsValue> {
+        let (lat, lon) = parse_tile_name(name)
+            .ok_or_else(|| JsValue::from_str("tile name must start like N45W122 or S03E017"))?;
+        self.add(lat, lon, bytes)
+    }
+
+    /// Grid-sized heightmap of the mosaic's bounding box, stretched to the grid. Voids are
+    /// filled from the surrounding terrain; see `SrtmParams` for the height scale.
+    pub fn heightmap(&self, params: &SrtmParams) -> Result<Box<[f32]>, JsValue> {
+        params.validate()?;
+        if self.tiles.is_empty() {
+            return Err(JsValue::from_str("no tiles were added"));
+        }
+        let (width, height, mut metres) = self.assemble();
+        let max = metres
+            .iter()
+            .copied()
+            .filter(|m| !m.is_nan())
+            .fold(f32::NAN, f32::max);
+        if max.is_nan() {
+            return Err(JsValue::from_str("the tiles hold no valid samples"));
+        }
+        fill_voids(&mut metres, width, height);
+        let relief = if params.fit_relief && max > 0.0 {
+            max
+        } else {
+            RELIEF_METRES
+        };
+        let sea = params.sea_level;
+        let heights: Vec<f32> = metres
+            .iter()
+            .map(|&m| {
+                let m = if m > 0.0 {
+                    m
+                } else {
+                    -params.ocean_depth_metres
+                };
+                (sea + m / relief * (1.0 - sea)).clamp(0.0, 1.0)
+            })
+            .collect();
+        Ok(resample_field(
+            &heights,
+            (width, height),
+            (WIDTH, HEIGHT),
+            Filter::Bilinear,
+            false,
+        )
+        .into_boxed_slice())
+    }
+}
+
+impl SrtmMosaic {
+    fn add(&mut self, lat: i32, lon: i32, bytes: &[u8]) -> Result<(), JsValue> {
+        let side = (bytes.len() as f64 / 2.0).sqrt().round() as usize;
+        if side < 2 || 2 * side * side != bytes.len() {
+            return Err(JsValue::from_str(
+                "an .hgt tile must hold a square of i16 samples",
+            ));
+        }
+        if self.side != 0 && side != self.side {
+            return Err(JsValue::from_str("all tiles must have the same resolution"));
+        }
+        if self.tiles.contains_key(&(lat, lon)) {
+            return Err(JsValue::from_str("that tile was already added"));
+        }
+        let samples = bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_be_bytes([b[0], b[1]]))
+            .collect();
+        self.side = side;
+        self.tiles.insert((lat, lon), samples);
+        Ok(())
+    }
+
+    /// The bounding box of the tiles in metres, north row first, box-averaged by the
+    /// smallest step that divides a tile's span and fits `MAX_MOSAIC_SIDE`. Voids are NaN;
+    /// missing tiles are 0 m, the sea.
+    fn assemble(&self) -> (usize, usize, Vec<f32>) {
+        let lats = self.tiles.keys().map(|k| k.0);
+        let lons = self.tiles.keys().map(|k| k.1);
+        let (lat_min, lat_max) = (lats.clone().min().unwrap_or(0), lats.max().unwrap_or(0));
+        let (lon_min, lon_max) = (lons.clone().min().unwrap_or(0), lons.max().unwrap_or(0));
+        let (cols, rows) = (
+            (lon_max - lon_min + 1) as usize,
+            (lat_max - lat_min + 1) as usize,
+        );
+        // Adjacent tiles share their edge rows and columns.
+        let span = self.side - 1;
+        let step = (1..=span)
+            .find(|k| span.is_multiple_of(*k) && cols.max(rows) * span / k < MAX_MOSAIC_SIDE)
+            .unwrap_or(span);
+        let per_tile = span / step;
+        let (width, height) = (cols * per_tile + 1, rows * per_tile + 1);
+        let half = step / 2;
+        let mut out = vec![0.0_f32; width * height];
+        for (my, row) in out.chunks_exact_mut(width).enumerate() {
+            for (mx, value) in row.iter_mut().enumerate() {
+                // The tile holding this sample, preferring the north-west one on shared edges.
+                let (ty, tx) = ((my / per_tile).min(rows - 1), (mx / per_tile).min(cols - 1));
+                let key = (lat_max - ty as i32, lon_min + tx as i32);
+                let Some(tile) = self.tiles.get(&key) else {
+                    continue;
+                };
+                let (cy, cx) = ((my - ty * per_tile) * step, (mx - tx * per_tile) * step);
+                let (mut sum, mut count) = (0.0, 0);
+                for y in cy.saturating_sub(half)..(cy + half + 1).min(self.side) {
+                    for x in cx.saturating_sub(half)..(cx + half + 1).min(self.side) {
+                        let sample = tile[y * self.side + x];
+                        if sample != NODATA {
+                            sum += sample as f32;
+                            count += 1;
+                        }
+                    }
+                }
+                *value = if count == 0 {
+                    f32::NAN
+                } else {
+                    sum / count as f32
+                };
+            }
+        }
+        (width, height, out)
+    }
+}
+
+/// Heightmap from a single SRTM `.hgt` tile; shorthand for a one-tile `SrtmMosaic`.
+#[wasm_bindgen]
+pub fn import_hgt(bytes: &[u8], params: &SrtmParams) -> Result<Box<[f32]>, JsValue> {
+    let mut mosaic = SrtmMosaic::new();
+    mosaic.add(0, 0, bytes)?;
+    mosaic.heightmap(params)
+}
@@ -0,0 +1,185 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::smoothstep;
+use crate::noise::perlin;
+
+/// Largest stamp reach (radius times elongation), cells, as for brushes.
+pub(crate) const MAX_STAMP_REACH: f32 = 512.0;
+
+/// Size, orientation and blending of a `TerrainEditor::stamp`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct StampParams {
+    /// Radius across the stamp's short axis, cells.
+    pub radius: f32,
+    /// Length over width, 1 to 8; the long axis points along `rotation`.
+    pub elongation: f32,
+    /// Degrees anticlockwise from east, as seen on the map.
+    pub rotation: f32,
+    /// Relief at the highest point of the shape, heightmap units; a crater's floor sits
+    /// this far below its surroundings.
+    pub height: f32,
+    /// Fraction of the radius, inward from the rim, over which the stamp fades into the
+    /// terrain.
+    pub feather: f32,
+    /// How far Perlin noise roughens the outline, 0 for a clean shape.
+    pub roughness: f32,
+    pub seed: u32,
+}
+
+impl Default for StampParams {
+    fn default() -> Self {
+        Self {
+            radius: 48.0,
+            elongation: 1.0,
+            rotation: 0.0,
+            height: 0.15,
+            feather: 0.25,
+            roughness: 0.15,
+            seed: crate::DEFAULT_SEED,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl StampParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> StampParams {
+        Self::default()
+    }
+}
+
+impl StampParams {
+    pub(crate) fn validate(&self) -> Result<(), JsValue> {
+        if !(self.radius >= 1.0 && (1.0..=8.0).contains(&self.elongation)) {
+            return Err(JsValue::from_str(
+                "radius must be >= 1 and elongation within [1, 8]",
+            ));
+        }
+        if self.radius * self.elongation > MAX_STAMP_REACH {
+            return Err(JsValue::from_str(&format!(
+                "radius times elongation must be <= {MAX_STAMP_REACH}"
+            )));
+        }
+        if !self.rotation.is_finite() || !(0.0..=1.0).contains(&self.height) {
+            return Err(JsValue::from_str(
+                "rotation must be finite and height within [0, 1]",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.feather) || !(0.0..=1.0).contains(&self.roughness) {
+            return Err(JsValue::from_str(
+                "feather and roughness must be within [0, 1]",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Stamp relief and blend weight at offset `(dx, dy)` cells from the centre (y down),
+    /// or `None` outside the shape.
+    pub(crate) fn sample(&self, shape: StampShape, (dx, dy): (f32, f32)) -> Option<(f32, f32)> {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        // Map y points down, so anticlockwise on screen turns the other way on the grid.
+        let along = dx * cos - dy * sin;
+        let across = dx * sin + dy * cos;
+        let r = (along / self.elongation).hypot(across) / self.radius;
+        let wobble = if self.roughness > 0.0 {
+            let scale = 3.0 / self.radius;
+            perlin(dx * scale, dy * scale, self.seed, 0) * self.roughness
+        } else {
+            0.0
+        };
+        let r = r / (1.0 + wobble);
+        (r < 1.0).then(|| {
+            let weight = 1.0 - smoothstep(1.0 - self.feather, 1.0, r);
+            (shape.profile(r) * self.height, weight)
+        })
+    }
+}
+
+/// Parametric landforms for `TerrainEditor::stamp`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum StampShape {
+    /// Steep concave cone with a summit crater.
+    Volcano,
+    /// Broad convex dome with a small caldera.
+    ShieldMountain,
+    /// Bowl sunk below the surroundings inside a raised rim and ejecta apron.
+    Crater,
+    /// Dome with gentle flanks and rounded shoulders, meant to rise out of the sea.
+    Island,
+    /// Flat top ending in a steep escarpment.
+    Plateau,
+}
+
+impl StampShape {
+    pub(crate) fn parse(shape: &str) -> Result<StampShape, String> {
+        match shape {
+            "volcano" => Ok(StampShape::Volcano),
+            "shield" => Ok(StampShape::ShieldMountain),
+            "crater" => Ok(StampShape::Crater),
+            "island" => Ok(StampShape::Island),
+            "plateau" => Ok(StampShape::Plateau),
+            _ => Err("shape must be volcano, shield, crater, island or plateau".to_string()),
+        }
+    }
+
+    /// Relief at normalised radius `r` (0 at the centre, 1 at the rim), peaking at 1.
+    fn profile(self, r: f32) -> f32 {
+        // Summit depressions: a parabolic dip of `depth` inside radius `rim`.
+        let dip = |rim: f32, depth: f32| {
+            if r < rim {
+                depth * (1.0 - (r / rim).powi(2))
+            } else {
+                0.0
+            }
+        };
+        match self {
+            StampShape::Volcano => (1.0 - r.max(0.1)).powi(2) / 0.81 - dip(0.1, 0.3),
+            StampShape::ShieldMountain => (1.0 - r * r).powf(0.75) - dip(0.08, 0.1),
+            StampShape::Crater => {
+                const RIM: f32 = 0.7;
+                const RIM_HEIGHT: f32 = 0.3;
+                if r < RIM {
+                    RIM_HEIGHT - (1.0 + RIM_HEIGHT) * (1.0 - (r / RIM).powi(2))
+                } else {
+                    RIM_HEIGHT * ((1.0 - r) / (1.0 - RIM)).powi(2)
+                }
+            }
+            StampShape::Island => 1.0 - smoothstep(0.0, 1.0, r),
+            StampShape::Plateau => 1.0 - 0.05 * r - 0.95 * smoothstep(0.65, 0.85, r),
+        }
+    }
+}
+
+/// How a stamp combines with the terrain beneath it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum StampBlend {
+    /// Stacks the stamp's relief on the existing ground.
+    Add,
+    /// Raises ground to the stamp, set on the height under its centre, and leaves higher
+    /// ground alone.
+    Max,
+    /// Replaces the ground with the stamp set on the height under its centre.
+    Replace,
+}
+
+impl StampBlend {
+    pub(crate) fn parse(mode: &str) -> Result<StampBlend, String> {
+        match mode {
+            "add" => Ok(StampBlend::Add),
+            "max" => Ok(StampBlend::Max),
+            "replace" => Ok(StampBlend::Replace),
+            _ => Err("mode must be add, max or replace".to_string()),
+        }
+    }
+
+    /// New height at a cell of height `h`, given the stamp `relief` and `weight` there and
+    /// the height `base` under the stamp's centre.
+    pub(crate) fn apply(self, h: f32, base: f32, relief: f32, weight: f32) -> f32 {
+        match self {
+            StampBlend::Add => h + relief * weight,
+            StampBlend::Max => h.max(h + (base + relief - h) * weight),
+            StampBlend::Replace => h + (base + relief - h) * weight,
+        }
+    }
+}
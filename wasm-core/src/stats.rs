@@ -0,0 +1,44 @@
+/// Area-weighted quantiles of `(value, weight)` samples; `quantiles` are fractions in [0, 1].
+/// Sorts `samples` in place. Returns zeros when the total weight is zero.
+pub(crate) fn weighted_quantiles(samples: &mut [(f32, f64)], quantiles: &[f64]) -> Vec<f32> {
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total: f64 = samples.iter().map(|s| s.1).sum();
+    if total <= 0.0 {
+        return vec![0.0; quantiles.len()];
+    }
+    quantiles
+        .iter()
+        .map(|&q| {
+            let target = q.clamp(0.0, 1.0) * total;
+            let mut acc = 0.0;
+            for &(value, weight) in samples.iter() {
+                acc += weight;
+                if acc >= target {
+                    return value;
+                }
+            }
+            samples[samples.len() - 1].0
+        })
+        .collect()
+}
+
+/// Area-weighted mean of `(value, weight)` samples (0 when empty).
+pub(crate) fn weighted_mean(samples: &[(f32, f64)]) -> f64 {
+    let total: f64 = samples.iter().map(|s| s.1).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    samples.iter().map(|&(v, w)| v as f64 * w).sum::<f64>() / total
+}
+
+/// Linearly interpolated percentile (`p` in [0, 100]) of ascending `sorted` values.
+pub(crate) fn percentile_sorted(sorted: &[f32], p: f64) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = (lo + 1).min(sorted.len() - 1);
+    let t = (rank - lo as f64) as f32;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * t
+}
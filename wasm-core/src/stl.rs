@@ -0,0 +1,158 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{SEA_LEVEL, check_grid_len};
+use crate::mesh::{GridMesh, MeshParams};
+
+const EQUATOR_METRES: f32 = 40_075_000.0;
+
+/// Physical size of a printed terrain from `export_terrain_stl`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct StlParams {
+    /// East–west size of the print, mm; north–south is half of it.
+    pub width_mm: f32,
+    /// Solid base under the lowest point of the surface, mm.
+    pub base_mm: f32,
+    /// Multiplier on true relief at the print's horizontal scale (at 200 mm wide, 80×
+    /// turns 8 km of relief into 3.2 mm).
+    pub vertical_exaggeration: f32,
+    /// Upper bound on surface vertices, as `MeshParams::max_vertices`.
+    pub max_vertices: u32,
+    /// Prints water as a flat surface at sea level instead of the sea floor.
+    pub flatten_ocean: bool,
+}
+
+impl Default for StlParams {
+    fn default() -> Self {
+        Self {
+            width_mm: 200.0,
+            base_mm: 3.0,
+            vertical_exaggeration: 80.0,
+            max_vertices: 512 * 256,
+            flatten_ocean: true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl StlParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> StlParams {
+        Self::default()
+    }
+}
+
+impl StlParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        let positive = |v: f32| v.is_finite() && v > 0.0;
+        if !positive(self.width_mm) || !positive(self.base_mm) {
+            return Err(JsValue::from_str("width_mm and base_mm must be > 0"));
+        }
+        if !self.vertical_exaggeration.is_finite() || self.vertical_exaggeration < 0.0 {
+            return Err(JsValue::from_str("vertical_exaggeration must be >= 0"));
+        }
+        if self.max_vertices < 4 {
+            return Err(JsValue::from_str("max_vertices must be >= 4"));
+        }
+        Ok(())
+    }
+}
+
+fn facet(out: &mut Vec<u8>, [a, b, c]: [[f32; 3]; 3]) {
+    let (u, v) = (
+        [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+        [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+    );
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2])
+        .sqrt()
+        .max(f32::MIN_POSITIVE);
+    for value in n
+        .map(|c| c / length)
+        .iter()
+        .chain(a.iter())
+        .chain(&b)
+        .chain(&c)
+    {
+        out.extend(value.to_le_bytes());
+    }
+    out.extend(0_u16.to_le_bytes());
+}
+
+/// Binary STL of the terrain as a closed solid for 3D printing, in millimetres with z up
+/// and north toward +y: the surface on a flat-bottomed base, closed by walls on all four
+/// map edges. Relief is measured from the lowest printed point.
+#[wasm_bindgen]
+pub fn export_terrain_stl(flat: &[f32], params: &StlParams) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let mesh = GridMesh::build(
+        flat,
+        &MeshParams {
+            max_vertices: params.max_vertices,
+            vertical_scale: 1.0,
+            sea_level: 0.0,
+            max_error_metres: 0.0,
+        },
+    );
+    let (cols, rows) = (mesh.cols, mesh.rows);
+    let mm_per_cell = params.width_mm / (cols - 1) as f32;
+    let row_mm = params.width_mm / 2.0 / (rows - 1) as f32;
+    let mm_per_metre = params.width_mm / EQUATOR_METRES * params.vertical_exaggeration;
+    let metres: Vec<f32> = mesh
+        .positions
+        .iter()
+        .map(|p| {
+            let m = (p[1] - SEA_LEVEL) / (1.0 - SEA_LEVEL) * RELIEF_METRES;
+            if params.flatten_ocean { m.max(0.0) } else { m }
+        })
+        .collect();
+    let lowest = metres.iter().copied().fold(f32::INFINITY, f32::min);
+    let xy = |i: usize, j: usize| (i as f32 * mm_per_cell, (rows - 1 - j) as f32 * row_mm);
+    let top: Vec<[f32; 3]> = (0..cols * rows)
+        .map(|v| {
+            let (x, y) = xy(v % cols, v / cols);
+            [x, y, params.base_mm + (metres[v] - lowest) * mm_per_metre]
+        })
+        .collect();
+    let bottom = |v: usize| {
+        let (x, y) = xy(v % cols, v / cols);
+        [x, y, 0.0]
+    };
+
+    // Boundary vertices clockwise seen from above: north edge west to east, then down the
+    // east edge, back along the south edge, and up the west edge.
+    let mut ring: Vec<usize> = (0..cols).collect();
+    ring.extend((1..rows).map(|j| j * cols + cols - 1));
+    ring.extend((0..cols - 1).rev().map(|i| (rows - 1) * cols + i));
+    ring.extend((1..rows - 1).rev().map(|j| j * cols));
+
+    let surface = mesh.triangles();
+    let triangle_count = surface.len() + 3 * ring.len();
+    let mut out = vec![0_u8; 80];
+    let title = b"Continent-Generator terrain";
+    out[..title.len()].copy_from_slice(title);
+    out.extend((triangle_count as u32).to_le_bytes());
+    out.reserve(50 * triangle_count);
+    // The mesh's triangles face up (they map to +z here without changing handedness).
+    for [a, b, c] in surface {
+        facet(
+            &mut out,
+            [top[a as usize], top[b as usize], top[c as usize]],
+        );
+    }
+    let centre = [params.width_mm / 2.0, params.width_mm / 4.0, 0.0];
+    for (k, &v) in ring.iter().enumerate() {
+        let next = ring[(k + 1) % ring.len()];
+        // Wall quad facing outward, then a bottom fan triangle facing down.
+        facet(&mut out, [top[v], top[next], bottom(next)]);
+        facet(&mut out, [top[v], bottom(next), bottom(v)]);
+        facet(&mut out, [bottom(v), bottom(next), centre]);
+    }
+    Ok(out.into_boxed_slice())
+}
@@ -0,0 +1,123 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, box_blur, latitude_deg, sample_wrapped, wrap_x};
+use crate::zones::climate_zone_polygons_json;
+
+/// Sea surface temperature (warmest month, °C) needed for tropical cyclone genesis.
+const GENESIS_SST_C: f32 = 26.5;
+/// Genesis band; Coriolis is too weak to spin up storms closer to the equator.
+const GENESIS_MIN_LAT: f32 = 5.0;
+const GENESIS_MAX_LAT: f32 = 25.0;
+/// Spacing of seeded storms over the genesis region, cells.
+const GENESIS_SPACING: usize = 6;
+/// Track step length (cells) and maximum number of steps.
+const STEP_CELLS: f32 = 1.5;
+const MAX_STEPS: usize = 400;
+/// Poleward "beta drift" added to the steering wind, which makes tracks recurve.
+const BETA_DRIFT: f32 = 0.5;
+/// Per-step intensity change over warm water, cooler water, and land.
+const WARM_WATER_GAIN: f32 = 0.02;
+const COOL_WATER_DECAY: f32 = 0.015;
+const LANDFALL_DECAY: f32 = 0.2;
+const DISSIPATED: f32 = 0.05;
+/// Smoothing of the track density, cells.
+const RISK_BLUR_CELLS: usize = 8;
+/// Risk levels (share of peak track density) splitting the polygon classes.
+const ELEVATED_RISK: f32 = 0.15;
+const HIGH_RISK: f32 = 0.45;
+
+pub(crate) const STORM_RISK_NONE: u8 = 0;
+pub(crate) const STORM_RISK_ELEVATED: u8 = 1;
+pub(crate) const STORM_RISK_HIGH: u8 = 2;
+
+/// Intensity-weighted track density from storms seeded over warm tropical water, steered
+/// by the climate winds plus a poleward drift, strengthening over warm water and dying
+/// after landfall.
+fn track_density(climate: &Climate) -> Vec<f32> {
+    let mut density = vec![0.0_f32; CELL_COUNT];
+    let ocean = |idx: usize| climate.water_distance[idx] <= 0.0;
+    let winds_u: Vec<f32> = climate.winds.iter().map(|w| w.0).collect();
+    let winds_v: Vec<f32> = climate.winds.iter().map(|w| w.1).collect();
+    for y in (GENESIS_SPACING / 2..HEIGHT).step_by(GENESIS_SPACING) {
+        let lat = latitude_deg(y).abs();
+        if !(GENESIS_MIN_LAT..=GENESIS_MAX_LAT).contains(&lat) {
+            continue;
+        }
+        for x in (GENESIS_SPACING / 2..WIDTH).step_by(GENESIS_SPACING) {
+            let idx = y * WIDTH + x;
+            if !ocean(idx) || climate.warmest_month_at(idx) < GENESIS_SST_C {
+                continue;
+            }
+            let (mut fx, mut fy) = (x as f32, y as f32);
+            let mut intensity = 0.5;
+            for _ in 0..MAX_STEPS {
+                let (cx, cy) = (fx.round() as i64, fy.round() as i64);
+                if cy < 0 || cy >= HEIGHT as i64 {
+                    break;
+                }
+                let cell = cy as usize * WIDTH + wrap_x(cx);
+                density[cell] += intensity;
+                intensity = if !ocean(cell) {
+                    intensity - LANDFALL_DECAY
+                } else if climate.warmest_month_at(cell) >= GENESIS_SST_C {
+                    (intensity + WARM_WATER_GAIN).min(1.0)
+                } else {
+                    intensity - COOL_WATER_DECAY
+                };
+                if intensity < DISSIPATED {
+                    break;
+                }
+                let poleward = -latitude_deg(cy as usize).signum();
+                let u = sample_wrapped(&winds_u, cx, cy);
+                let v = sample_wrapped(&winds_v, cx, cy) + BETA_DRIFT * poleward;
+                let speed = u.hypot(v).max(1e-3);
+                fx += u / speed * STEP_CELLS;
+                fy += v / speed * STEP_CELLS;
+            }
+        }
+    }
+    density
+}
+
+fn risk_field(climate: &Climate) -> Vec<f32> {
+    let density = box_blur(&track_density(climate), RISK_BLUR_CELLS);
+    let peak = density.iter().copied().fold(0.0_f32, f32::max);
+    if peak <= 0.0 {
+        return density;
+    }
+    density.into_iter().map(|d| d / peak).collect()
+}
+
+/// Tropical cyclone risk per cell in [0, 1], relative to the busiest corridor on the map.
+/// Covers both ocean and the coasts where storms make landfall. `climate` must be an annual
+/// run, since genesis reads the warmest-month sea surface.
+#[wasm_bindgen]
+pub fn storm_risk(climate: &Climate) -> Result<Box<[f32]>, JsValue> {
+    climate.require_annual("storm_risk")?;
+    Ok(risk_field(climate).into_boxed_slice())
+}
+
+/// Storm corridors as GeoJSON polygons (see `climate_zone_polygons_json`), with `class`
+/// 1 = elevated and 2 = high risk. `climate` must be an annual run.
+#[wasm_bindgen]
+pub fn storm_track_polygons_json(
+    climate: &Climate,
+    min_cells: u32,
+    tolerance: f32,
+) -> Result<String, JsValue> {
+    climate.require_annual("storm_track_polygons_json")?;
+    let classes: Vec<u8> = risk_field(climate)
+        .into_iter()
+        .map(|r| {
+            if r >= HIGH_RISK {
+                STORM_RISK_HIGH
+            } else if r >= ELEVATED_RISK {
+                STORM_RISK_ELEVATED
+            } else {
+                STORM_RISK_NONE
+            }
+        })
+        .collect();
+    climate_zone_polygons_json(&classes, min_cells, tolerance)
+}
@@ -0,0 +1,208 @@
+use wasm_bindgen::prelude::*;
+
+use crate::fingerprint::quantize;
+use crate::grid::check_grid_len;
+use crate::json::{self, ObjectWriter};
+use crate::resample::{Filter, GridResampler};
+
+/// Largest side `stream_heightmap_raw` produces: 16 times the grid width, plus one.
+const MAX_STREAM_SIDE: u32 = 32769;
+const STREAM_VERSION: u64 = 1;
+
+/// Output size and chunking for `stream_heightmap_raw`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct StreamParams {
+    /// Output size in samples, 1 to 32769 a side.
+    pub width: u32,
+    pub height: u32,
+    /// 16 for u16 over [0, 1] (`.r16`), 32 for f32 heightmap values (`.r32`).
+    pub bit_depth: u32,
+    /// Samples per chunk across; 0 makes full-width strips, which concatenate into a
+    /// headerless raw file.
+    pub chunk_width: u32,
+    /// Sample rows per chunk.
+    pub chunk_height: u32,
+}
+
+impl Default for StreamParams {
+    fn default() -> Self {
+        Self {
+            width: 8193,
+            height: 8193,
+            bit_depth: 16,
+            chunk_width: 0,
+            chunk_height: 256,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl StreamParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> StreamParams {
+        Self::default()
+    }
+}
+
+impl StreamParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(1..=MAX_STREAM_SIDE).contains(&self.width)
+            || !(1..=MAX_STREAM_SIDE).contains(&self.height)
+        {
+            return Err(JsValue::from_str(&format!(
+                "width and height must be within [1, {MAX_STREAM_SIDE}]"
+            )));
+        }
+        if self.bit_depth != 16 && self.bit_depth != 32 {
+            return Err(JsValue::from_str("bit_depth must be 16 or 32"));
+        }
+        if self.chunk_height == 0 {
+            return Err(JsValue::from_str("chunk_height must be >= 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Chunked heightmap export from `stream_heightmap_raw`. Chunks are cut row-major from the
+/// north-west and each is a headerless little-endian raw block of its own width and height;
+/// only one is in memory at a time. Take them in order with `next_chunk`, or any of them
+/// with `chunk`, and see `manifest_json` for where each belongs.
+#[wasm_bindgen]
+pub struct HeightmapStream {
+    flat: Vec<f32>,
+    resampler: GridResampler,
+    params: StreamParams,
+    chunk_width: usize,
+    columns: usize,
+    rows: usize,
+    next: usize,
+}
+
+#[wasm_bindgen]
+impl HeightmapStream {
+    #[wasm_bindgen(getter)]
+    pub fn chunk_count(&self) -> u32 {
+        (self.columns * self.rows) as u32
+    }
+
+    /// The next chunk in order, or `undefined` after the last.
+    pub fn next_chunk(&mut self) -> Option<Box<[u8]>> {
+        let index = self.next;
+        (index < self.columns * self.rows).then(|| {
+            self.next += 1;
+            self.encode(index)
+        })
+    }
+
+    /// Chunk `index` (0 to `chunk_count` − 1), independent of `next_chunk`; lets an
+    /// interrupted download resume or chunks be built in parallel workers.
+    pub fn chunk(&self, index: u32) -> Result<Box<[u8]>, JsValue> {
+        if index as usize >= self.columns * self.rows {
+            return Err(JsValue::from_str("chunk index out of range"));
+        }
+        Ok(self.encode(index as usize))
+    }
+
+    /// How the chunks reassemble: `{"format":"continent-raw-chunks","version":1,"width",
+    /// "height","bit_depth","columns","rows","chunks":[{"index","name","x","y","width",
+    /// "height","offset","bytes"},...]}`. Sample (i, j) of a chunk is sample (x + i, y + j)
+    /// of the whole map, and `offset` is where the chunk's first row starts in the full raw
+    /// file. Full-width strips simply concatenate in index order.
+    pub fn manifest_json(&self) -> String {
+        let sample_bytes = self.params.bit_depth as usize / 8;
+        let extension = if self.params.bit_depth == 16 {
+            "r16"
+        } else {
+            "r32"
+        };
+        let chunks = (0..self.columns * self.rows).map(|index| {
+            let ((x, y), (width, height)) = self.bounds(index);
+            let (column, row) = (index % self.columns, index / self.columns);
+            ObjectWriter::new()
+                .integer("index", index as u64)
+                .raw(
+                    "name",
+                    &json::quote(&format!("chunk_{row:03}_{column:03}.{extension}")),
+                )
+                .integer("x", x as u64)
+                .integer("y", y as u64)
+                .integer("width", width as u64)
+                .integer("height", height as u64)
+                .integer(
+                    "offset",
+                    ((y * self.params.width as usize + x) * sample_bytes) as u64,
+                )
+                .integer("bytes", (width * height * sample_bytes) as u64)
+                .finish()
+        });
+        ObjectWriter::new()
+            .raw("format", &json::quote("continent-raw-chunks"))
+            .integer("version", STREAM_VERSION)
+            .integer("width", self.params.width as u64)
+            .integer("height", self.params.height as u64)
+            .integer("bit_depth", self.params.bit_depth as u64)
+            .integer("columns", self.columns as u64)
+            .integer("rows", self.rows as u64)
+            .raw("chunks", &json::array(chunks))
+            .finish()
+    }
+}
+
+impl HeightmapStream {
+    /// Origin and size of chunk `index`, in output samples.
+    fn bounds(&self, index: usize) -> ((usize, usize), (usize, usize)) {
+        let (width, height) = (self.params.width as usize, self.params.height as usize);
+        let chunk_height = self.params.chunk_height as usize;
+        let x = index % self.columns * self.chunk_width;
+        let y = index / self.columns * chunk_height;
+        (
+            (x, y),
+            (
+                self.chunk_width.min(width - x),
+                chunk_height.min(height - y),
+            ),
+        )
+    }
+
+    fn encode(&self, index: usize) -> Box<[u8]> {
+        let (origin, size) = self.bounds(index);
+        let samples = self.resampler.window(&self.flat, origin, size);
+        let bytes: Vec<u8> = if self.params.bit_depth == 16 {
+            samples
+                .iter()
+                .flat_map(|&h| quantize(h, 16).to_le_bytes())
+                .collect()
+        } else {
+            samples.iter().flat_map(|h| h.to_le_bytes()).collect()
+        };
+        bytes.into_boxed_slice()
+    }
+}
+
+/// `export_heightmap_raw` for sizes too large for one buffer (a 32769² `.r32` is 4 GiB):
+/// the heightmap resampled bilinearly to `width` × `height`, handed out in chunks with a
+/// manifest. Concatenated full-width strips are byte-identical to `export_heightmap_raw`
+/// at sizes it accepts.
+#[wasm_bindgen]
+pub fn stream_heightmap_raw(
+    flat: &[f32],
+    params: &StreamParams,
+) -> Result<HeightmapStream, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let (width, height) = (params.width as usize, params.height as usize);
+    let chunk_width = match params.chunk_width {
+        0 => width,
+        w => (w as usize).min(width),
+    };
+    Ok(HeightmapStream {
+        flat: flat.to_vec(),
+        resampler: GridResampler::new(width, height, Filter::Bilinear),
+        params: *params,
+        chunk_width,
+        columns: width.div_ceil(chunk_width),
+        rows: height.div_ceil(params.chunk_height as usize),
+        next: 0,
+    })
+}
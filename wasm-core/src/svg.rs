@@ -0,0 +1,337 @@
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::contours::{ContourParams, contour_lines, simplified_isolines};
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::hydrology::Hydrology;
+use crate::json::{self, Json};
+use crate::render::parse_hex_color;
+use crate::vector::{cell_path_polylines, label_regions};
+
+const DEFAULT_STYLE: &str = r##"{"background":"#ffffff",
+"coastline":{"stroke":"#1d3a5f","width":1.2},
+"contour":{"stroke":"#b08a5e","width":0.4},
+"index_contour":{"stroke":"#8a6438","width":0.9},
+"river":{"stroke":"#3b7fc4","width":0.6},
+"lake":{"fill":"#c6dbef","stroke":"#3b7fc4","width":0.5}}"##;
+
+/// Layers and linework for `export_map_svg`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SvgParams {
+    /// Height of the coastline; also replaces the contour parameters' `sea_level`.
+    pub sea_level: f32,
+    /// Output pixels per grid cell; line widths in the style are in output pixels.
+    pub scale: f32,
+    pub coastlines: bool,
+    pub contours: bool,
+    pub rivers: bool,
+    pub lakes: bool,
+    /// Drainage area at which a river is drawn, km²; rivers thicken with the square root
+    /// of the area they drain, up to 4× the style width.
+    pub river_min_area_km2: f32,
+    /// Basins on land deeper than this when filled to their spill point are drawn as lakes,
+    /// as are inland seas below sea level.
+    pub lake_min_depth_metres: f32,
+    /// Lakes smaller than this many cells are dropped.
+    pub lake_min_cells: u32,
+    /// Douglas–Peucker tolerance for coastlines, rivers and lakes, cells.
+    pub tolerance: f32,
+}
+
+impl Default for SvgParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            scale: 1.0,
+            coastlines: true,
+            contours: true,
+            rivers: true,
+            lakes: true,
+            river_min_area_km2: 50_000.0,
+            lake_min_depth_metres: 20.0,
+            lake_min_cells: 4,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SvgParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SvgParams {
+        Self::default()
+    }
+}
+
+impl SvgParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !(self.scale > 0.0 && self.scale <= 16.0) {
+            return Err(JsValue::from_str("scale must be within (0, 16]"));
+        }
+        let non_negative = [
+            self.river_min_area_km2,
+            self.lake_min_depth_metres,
+            self.tolerance,
+        ];
+        if non_negative.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(JsValue::from_str(
+                "river_min_area_km2, lake_min_depth_metres and tolerance must be >= 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Paint for one layer; `None` colours are written as `none`.
+#[derive(Clone, Copy)]
+struct LayerStyle {
+    stroke: Option<[u8; 3]>,
+    fill: Option<[u8; 3]>,
+    width: f32,
+}
+
+struct MapStyle {
+    background: Option<[u8; 3]>,
+    coastline: LayerStyle,
+    contour: LayerStyle,
+    index_contour: LayerStyle,
+    river: LayerStyle,
+    lake: LayerStyle,
+}
+
+fn parse_paint(value: &Json, what: &str) -> Result<Option<[u8; 3]>, String> {
+    match value.as_str() {
+        Some("none") => Ok(None),
+        Some(s) => parse_hex_color(s)
+            .map(Some)
+            .ok_or_else(|| format!("{what} must be a #rgb or #rrggbb colour or \"none\"")),
+        None => Err(format!("{what} must be a string")),
+    }
+}
+
+impl MapStyle {
+    /// Overrides the defaults with `style`:
+    /// `{"background", "coastline"|"contour"|"index_contour"|"river"|"lake": {"stroke",
+    /// "fill", "width"}}`, any of them left out.
+    fn parse(style: &str) -> Result<MapStyle, String> {
+        let unset = LayerStyle {
+            stroke: None,
+            fill: None,
+            width: 1.0,
+        };
+        let mut out = MapStyle {
+            background: None,
+            coastline: unset,
+            contour: unset,
+            index_contour: unset,
+            river: unset,
+            lake: unset,
+        };
+        for source in [DEFAULT_STYLE, style] {
+            if source.trim().is_empty() {
+                continue;
+            }
+            let root = json::parse(source)?;
+            let Json::Object(fields) = &root else {
+                return Err("style must be a JSON object".to_string());
+            };
+            for (key, value) in fields {
+                let layer = match key.as_str() {
+                    "background" => {
+                        out.background = parse_paint(value, "background")?;
+                        continue;
+                    }
+                    "coastline" => &mut out.coastline,
+                    "contour" => &mut out.contour,
+                    "index_contour" => &mut out.index_contour,
+                    "river" => &mut out.river,
+                    "lake" => &mut out.lake,
+                    other => return Err(format!("unknown style key {other:?}")),
+                };
+                if let Some(stroke) = value.get("stroke") {
+                    layer.stroke = parse_paint(stroke, &format!("{key}.stroke"))?;
+                }
+                if let Some(fill) = value.get("fill") {
+                    layer.fill = parse_paint(fill, &format!("{key}.fill"))?;
+                }
+                if let Some(width) = value.get("width") {
+                    layer.width = width
+                        .as_f64()
+                        .filter(|w| w.is_finite() && *w >= 0.0)
+                        .ok_or_else(|| format!("{key}.width must be a number >= 0"))?
+                        as f32;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn paint(color: Option<[u8; 3]>) -> String {
+    match color {
+        Some([r, g, b]) => format!("#{r:02x}{g:02x}{b:02x}"),
+        None => "none".to_string(),
+    }
+}
+
+/// SVG path data for lines in grid coordinates, closed ones ending in `Z`.
+fn path_data<'a>(lines: impl IntoIterator<Item = (bool, &'a [(f32, f32)])>) -> String {
+    let mut d = String::new();
+    for (closed, points) in lines {
+        for (i, (x, y)) in points.iter().enumerate() {
+            let _ = write!(d, "{}{x:.2} {y:.2}", if i == 0 { "M" } else { "L" });
+        }
+        if closed {
+            d.push('Z');
+        }
+    }
+    d
+}
+
+/// `<path>` painted with `style`, widths converted from output pixels to grid units.
+fn path_element(d: &str, style: &LayerStyle, scale: f32, extra: &str) -> String {
+    format!(
+        "<path d=\"{d}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{:.3}\"{extra}/>\n",
+        paint(style.fill),
+        paint(style.stroke),
+        style.width / scale,
+    )
+}
+
+/// Opens an Inkscape layer; Illustrator takes top-level groups as layers by their id.
+fn open_layer(svg: &mut String, id: &str, label: &str) {
+    let _ = writeln!(
+        svg,
+        "<g id=\"{id}\" inkscape:groupmode=\"layer\" inkscape:label=\"{label}\" \
+         stroke-linecap=\"round\" stroke-linejoin=\"round\">"
+    );
+}
+
+/// Composes coastlines, contours, rivers and lake outlines into a layered SVG document for
+/// finishing in Illustrator or Inkscape, one group per layer. Coordinates are grid cells
+/// with north up, scaled by `params.scale`. `contours` sets the contour layer's interval,
+/// index contours and bathymetry; `style` is JSON overriding `svg_style_json`, or empty
+/// for the defaults.
+#[wasm_bindgen]
+pub fn export_map_svg(
+    flat: &[f32],
+    params: &SvgParams,
+    contours: &ContourParams,
+    style: &str,
+) -> Result<String, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let contour_params = ContourParams {
+        sea_level: params.sea_level,
+        ..*contours
+    };
+    contour_params.validate()?;
+    let style = MapStyle::parse(style).map_err(|e| JsValue::from_str(&e))?;
+
+    let (w, h) = (WIDTH as f32 * params.scale, HEIGHT as f32 * params.scale);
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" \
+         xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\" \
+         width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {WIDTH} {HEIGHT}\">\n\
+         <title>Continent-Generator map</title>\n"
+    );
+    if style.background.is_some() {
+        let _ = writeln!(
+            svg,
+            "<rect id=\"background\" width=\"{WIDTH}\" height=\"{HEIGHT}\" fill=\"{}\"/>",
+            paint(style.background)
+        );
+    }
+
+    let hydrology = (params.rivers || params.lakes || params.coastlines)
+        .then(|| Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres));
+
+    if let Some(hydrology) = hydrology.as_ref().filter(|_| params.lakes) {
+        let (labels, count) = label_regions(&hydrology.lakes, |l| l);
+        let mut cells = vec![0_u32; count as usize];
+        for &label in labels.iter().filter(|&&l| l != 0) {
+            cells[label as usize - 1] += 1;
+        }
+        // Outlines run midway between lake and shore cell centres, like the other layers.
+        let field: Vec<f32> = labels
+            .iter()
+            .map(|&l| {
+                let kept = l != 0 && cells[l as usize - 1] >= params.lake_min_cells.max(1);
+                if kept { 1.0 } else { 0.0 }
+            })
+            .collect();
+        let rings = simplified_isolines(&field, 0.5, params.tolerance);
+        open_layer(&mut svg, "lakes", "Lakes");
+        let d = path_data(rings.iter().map(|(closed, r)| (*closed, r.as_slice())));
+        svg.push_str(&path_element(
+            &d,
+            &style.lake,
+            params.scale,
+            " fill-rule=\"evenodd\"",
+        ));
+        svg.push_str("</g>\n");
+    }
+
+    if params.contours {
+        let lines = contour_lines(flat, &contour_params);
+        open_layer(&mut svg, "contours", "Contours");
+        for (index, layer_style) in [(false, &style.contour), (true, &style.index_contour)] {
+            let d = path_data(
+                lines
+                    .iter()
+                    .filter(|c| c.index == index)
+                    .map(|c| (c.closed, c.points.as_slice())),
+            );
+            let class = if index {
+                " class=\"index-contour\""
+            } else {
+                " class=\"contour\""
+            };
+            svg.push_str(&path_element(&d, layer_style, params.scale, class));
+        }
+        svg.push_str("</g>\n");
+    }
+
+    if let Some(hydrology) = hydrology.as_ref().filter(|_| params.rivers) {
+        open_layer(&mut svg, "rivers", "Rivers");
+        for river in hydrology.rivers(params.river_min_area_km2) {
+            let widen = (river.area_km2 / params.river_min_area_km2.max(1.0))
+                .sqrt()
+                .clamp(1.0, 4.0);
+            let river_style = LayerStyle {
+                width: style.river.width * widen,
+                ..style.river
+            };
+            let lines = cell_path_polylines(&river.cells, params.tolerance);
+            let d = path_data(lines.iter().map(|l| (false, l.as_slice())));
+            svg.push_str(&path_element(&d, &river_style, params.scale, ""));
+        }
+        svg.push_str("</g>\n");
+    }
+
+    if let Some(hydrology) = hydrology.as_ref().filter(|_| params.coastlines) {
+        let field = hydrology.coastline_field(flat);
+        let lines = simplified_isolines(&field, params.sea_level, params.tolerance);
+        open_layer(&mut svg, "coastline", "Coastline");
+        let d = path_data(lines.iter().map(|(closed, l)| (*closed, l.as_slice())));
+        svg.push_str(&path_element(&d, &style.coastline, params.scale, ""));
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// The default `export_map_svg` style, as a starting point for custom styles.
+#[wasm_bindgen]
+pub fn svg_style_json() -> String {
+    json::parse(DEFAULT_STYLE)
+        .map(|style| style.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,131 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::resample::{Filter, resample_grid};
+
+const EQUATOR_KM: f32 = 40_075.0;
+/// Largest side accepted, as for `export_heightmap_raw`.
+const MAX_TER_SIDE: u32 = 8193;
+
+/// Size and scale of a Terragen terrain from `export_heightmap_ter`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TerParams {
+    /// Points east–west and north–south; Terragen Classic wants 2ⁿ + 1 squares such as
+    /// 1025 × 1025 or 2049 × 2049, newer versions take any size.
+    pub width: u32,
+    pub height: u32,
+    /// East–west extent of the terrain, km; the whole planet by default. Smaller values
+    /// turn the map into a smaller landscape with the same heights.
+    pub world_width_km: f32,
+    pub vertical_exaggeration: f32,
+    pub sea_level: f32,
+}
+
+impl Default for TerParams {
+    fn default() -> Self {
+        Self {
+            width: WIDTH as u32,
+            height: HEIGHT as u32,
+            world_width_km: EQUATOR_KM,
+            vertical_exaggeration: 1.0,
+            sea_level: SEA_LEVEL,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TerParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TerParams {
+        Self::default()
+    }
+}
+
+impl TerParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(2..=MAX_TER_SIDE).contains(&self.width) || !(2..=MAX_TER_SIDE).contains(&self.height) {
+            return Err(JsValue::from_str(&format!(
+                "width and height must be within [2, {MAX_TER_SIDE}]"
+            )));
+        }
+        if !(self.world_width_km.is_finite() && self.world_width_km > 0.0) {
+            return Err(JsValue::from_str("world_width_km must be > 0"));
+        }
+        if !(self.vertical_exaggeration.is_finite() && self.vertical_exaggeration >= 0.0) {
+            return Err(JsValue::from_str("vertical_exaggeration must be >= 0"));
+        }
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        Ok(())
+    }
+}
+
+/// A chunk holding one u16 padded to four bytes, as Terragen writes its size fields.
+fn size_chunk(out: &mut Vec<u8>, marker: &[u8; 4], value: u32) {
+    out.extend(marker);
+    out.extend((value as u16).to_le_bytes());
+    out.extend([0, 0]);
+}
+
+/// Terragen `.ter` terrain, the format Terragen Classic and much fantasy-map tooling read:
+/// elevations relative to sea level (the sea floor below it), resampled to
+/// `params.width` × `params.height` points. Points are `world_width_km` / (width − 1)
+/// apart in both directions, so the terrain keeps the map's proportions only at a 2:1
+/// size. Heights are spread over the full 16-bit range of the file.
+#[wasm_bindgen]
+pub fn export_heightmap_ter(flat: &[f32], params: &TerParams) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let (width, height) = (params.width as usize, params.height as usize);
+    let spacing = params.world_width_km * 1000.0 / (width - 1) as f32;
+    // Heights in terrain units: metres over the point spacing, as the file scales all
+    // three axes alike.
+    let units: Vec<f32> = resample_grid(flat, width, height, Filter::Bilinear)
+        .iter()
+        .map(|&h| {
+            let metres = (h - params.sea_level) / (1.0 - params.sea_level) * RELIEF_METRES;
+            metres * params.vertical_exaggeration / spacing
+        })
+        .collect();
+    let (min, max) = units
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &u| {
+            (lo.min(u), hi.max(u))
+        });
+    // Each stored value is base + sample · scale / 65536 terrain units.
+    let base = ((min + max) / 2.0).round();
+    let reach = (max - base).max(base - min);
+    let scale = (reach * 65536.0 / 32767.0).ceil().max(1.0);
+    if base.abs() > 32767.0 || scale > 32767.0 {
+        return Err(JsValue::from_str(
+            "relief is too large for the file at this world_width_km",
+        ));
+    }
+
+    let mut out = Vec::with_capacity(64 + width * height * 2);
+    out.extend(b"TERRAGENTERRAIN ");
+    size_chunk(&mut out, b"SIZE", width.min(height) as u32 - 1);
+    size_chunk(&mut out, b"XPTS", width as u32);
+    size_chunk(&mut out, b"YPTS", height as u32);
+    out.extend(b"SCAL");
+    for _ in 0..3 {
+        out.extend(spacing.to_le_bytes());
+    }
+    out.extend(b"ALTW");
+    out.extend((scale as i16).to_le_bytes());
+    out.extend((base as i16).to_le_bytes());
+    // Terragen's y axis points north, so rows run from the south.
+    for row in units.chunks_exact(width).rev() {
+        for &u in row {
+            let sample = ((u - base) * 65536.0 / scale)
+                .round()
+                .clamp(-32768.0, 32767.0);
+            out.extend((sample as i16).to_le_bytes());
+        }
+    }
+    out.extend(b"EOF ");
+    Ok(out.into_boxed_slice())
+}
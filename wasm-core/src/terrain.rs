@@ -0,0 +1,166 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{CELL_COUNT, SEA_LEVEL, WIDTH, check_grid_len, clamp_y, sample_wrapped, wrap_x};
+
+/// Central-difference gradient (elevation units per cell) at cell `idx`: +x east, +y south.
+pub(crate) fn gradient(flat: &[f32], idx: usize) -> (f32, f32) {
+    let x = (idx % WIDTH) as i64;
+    let y = (idx / WIDTH) as i64;
+    let dx = (sample_wrapped(flat, x + 1, y) - sample_wrapped(flat, x - 1, y)) * 0.5;
+    let dy = (sample_wrapped(flat, x, y + 1) - sample_wrapped(flat, x, y - 1)) * 0.5;
+    (dx, dy)
+}
+
+/// Terrain Ruggedness Index at `idx`: mean absolute elevation difference to the 8 neighbours.
+pub(crate) fn ruggedness(flat: &[f32], idx: usize) -> f32 {
+    let (x, y) = (idx % WIDTH, (idx / WIDTH) as i64);
+    let row = |y: i64| &flat[clamp_y(y) * WIDTH..][..WIDTH];
+    ruggedness_in_rows([row(y - 1), row(y), row(y + 1)], x)
+}
+
+/// `ruggedness` at column `x` of the middle of three consecutive rows (north to south; pass
+/// a row twice to clamp at a pole).
+pub(crate) fn ruggedness_in_rows(rows: [&[f32]; 3], x: usize) -> f32 {
+    let centre = rows[1][x];
+    let mut sum = 0.0;
+    for (dy, row) in rows.iter().enumerate() {
+        for dx in -1..=1 {
+            if dx != 0 || dy != 1 {
+                sum += (row[wrap_x(x as i64 + dx)] - centre).abs();
+            }
+        }
+    }
+    sum / 8.0
+}
+
+pub(crate) fn ruggedness_field(flat: &[f32]) -> Vec<f32> {
+    (0..CELL_COUNT).map(|idx| ruggedness(flat, idx)).collect()
+}
+
+/// Terrain Ruggedness Index per cell, in elevation units (mean absolute difference to the
+/// 8 neighbours; columns wrap, rows clamp at the poles).
+#[wasm_bindgen]
+pub fn terrain_ruggedness(flat: &[f32]) -> Result<Box<[f32]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    Ok(ruggedness_field(flat).into_boxed_slice())
+}
+
+/// Slope (degrees) and aspect (compass degrees the slope faces, 0 = north, clockwise; −1 on
+/// flat ground) at `idx`, with `cell_size` the horizontal cell spacing in elevation units.
+pub(crate) fn slope_aspect(flat: &[f32], idx: usize, cell_size: f32) -> (f32, f32) {
+    let (dx, dy) = gradient(flat, idx);
+    let slope = (dx.hypot(dy) / cell_size).atan().to_degrees();
+    if dx == 0.0 && dy == 0.0 {
+        return (slope, -1.0);
+    }
+    // Downslope is (−dx, −dy) in grid space; grid +y is south, so its northward part is +dy.
+    let aspect = (-dx).atan2(dy).to_degrees().rem_euclid(360.0);
+    (slope, aspect)
+}
+
+/// Horizontal size of a cell at the equator, metres.
+const CELL_METRES: f32 = 40_075_000.0 / WIDTH as f32;
+
+/// Slope in degrees at `idx` with heights scaled to metres (sea level to 1.0 spans
+/// `RELIEF_METRES`) and cells at their equatorial width.
+pub(crate) fn slope_degrees(flat: &[f32], idx: usize) -> f32 {
+    let metres_per_unit = RELIEF_METRES / (1.0 - SEA_LEVEL);
+    slope_aspect(flat, idx, CELL_METRES / metres_per_unit).0
+}
+
+/// Slope and aspect layers from `compute_slope_aspect`.
+#[wasm_bindgen]
+pub struct SlopeAspect {
+    slope: Vec<f32>,
+    aspect: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl SlopeAspect {
+    /// Slope per cell, degrees from horizontal.
+    pub fn slope(&self) -> Box<[f32]> {
+        self.slope.clone().into_boxed_slice()
+    }
+
+    /// Downslope compass direction per cell in [0, 360) (0 = north, 90 = east); −1 where
+    /// the ground is perfectly flat, as in GDAL.
+    pub fn aspect(&self) -> Box<[f32]> {
+        self.aspect.clone().into_boxed_slice()
+    }
+}
+
+/// Slope and aspect per cell. `cell_size` is the horizontal spacing between cells in the
+/// heightmap's own units (e.g. metres per cell ÷ metres per elevation unit).
+#[wasm_bindgen]
+pub fn compute_slope_aspect(heightmap: &[f32], cell_size: f32) -> Result<SlopeAspect, JsValue> {
+    check_grid_len(heightmap, "flat heightmap")?;
+    if !cell_size.is_finite() || cell_size <= 0.0 {
+        return Err(JsValue::from_str("cell_size must be > 0"));
+    }
+    let (slope, aspect) = (0..CELL_COUNT)
+        .map(|idx| slope_aspect(heightmap, idx, cell_size))
+        .unzip();
+    Ok(SlopeAspect { slope, aspect })
+}
+
+/// Profile and plan curvature at `idx` (Zevenbergen–Thorne 3×3 fit) with `cell_size` the
+/// horizontal spacing in elevation units. Both are directional second derivatives, in
+/// 1 / elevation units: profile along the slope direction, plan across it. Positive is
+/// concave (footslopes, hollows, channels), negative convex (shoulders, spurs, ridges).
+/// Flat cells report 0 for both.
+pub(crate) fn curvature(flat: &[f32], idx: usize, cell_size: f32) -> (f32, f32) {
+    let x = (idx % WIDTH) as i64;
+    let y = (idx / WIDTH) as i64;
+    let z = |dx: i64, dy: i64| sample_wrapped(flat, x + dx, y + dy);
+    let l2 = cell_size * cell_size;
+    let centre = z(0, 0);
+    // Grid +y points south; the fit below uses +y north, as in the original formulation.
+    let d = ((z(-1, 0) + z(1, 0)) * 0.5 - centre) / l2;
+    let e = ((z(0, -1) + z(0, 1)) * 0.5 - centre) / l2;
+    let f = (-z(-1, -1) + z(1, -1) + z(-1, 1) - z(1, 1)) / (4.0 * l2);
+    let g = (z(1, 0) - z(-1, 0)) / (2.0 * cell_size);
+    let h = (z(0, -1) - z(0, 1)) / (2.0 * cell_size);
+    let slope2 = g * g + h * h;
+    if slope2 <= f32::EPSILON * f32::EPSILON {
+        return (0.0, 0.0);
+    }
+    let profile = 2.0 * (d * g * g + f * g * h + e * h * h) / slope2;
+    let plan = 2.0 * (d * h * h - f * g * h + e * g * g) / slope2;
+    (profile, plan)
+}
+
+/// Profile and plan curvature layers from `compute_curvature`.
+#[wasm_bindgen]
+pub struct Curvature {
+    profile: Vec<f32>,
+    plan: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl Curvature {
+    /// Curvature along the slope: positive where flow decelerates (concave footslopes,
+    /// deposition), negative where it accelerates (convex shoulders).
+    pub fn profile(&self) -> Box<[f32]> {
+        self.profile.clone().into_boxed_slice()
+    }
+
+    /// Curvature across the slope: positive where flow converges (channels, hollows),
+    /// negative where it diverges (ridges, spurs).
+    pub fn plan(&self) -> Box<[f32]> {
+        self.plan.clone().into_boxed_slice()
+    }
+}
+
+/// Plan and profile curvature per cell; `cell_size` as in `compute_slope_aspect`.
+#[wasm_bindgen]
+pub fn compute_curvature(heightmap: &[f32], cell_size: f32) -> Result<Curvature, JsValue> {
+    check_grid_len(heightmap, "flat heightmap")?;
+    if !cell_size.is_finite() || cell_size <= 0.0 {
+        return Err(JsValue::from_str("cell_size must be > 0"));
+    }
+    let (profile, plan) = (0..CELL_COUNT)
+        .map(|idx| curvature(heightmap, idx, cell_size))
+        .unzip();
+    Ok(Curvature { profile, plan })
+}
@@ -0,0 +1,127 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{SEA_LEVEL, check_grid_len, distance_field};
+
+pub(crate) const TERRAIN_DEEP_OCEAN: u8 = 0;
+pub(crate) const TERRAIN_SHALLOW_OCEAN: u8 = 1;
+pub(crate) const TERRAIN_COAST: u8 = 2;
+pub(crate) const TERRAIN_PLAIN: u8 = 3;
+pub(crate) const TERRAIN_HILLS: u8 = 4;
+pub(crate) const TERRAIN_MOUNTAINS: u8 = 5;
+pub(crate) const TERRAIN_HIGH_PEAKS: u8 = 6;
+
+/// (name, legend colour) indexed by terrain class id.
+pub(crate) const TERRAIN_CLASSES: [(&str, &str); 7] = [
+    ("Deep ocean", "#1f3f66"),
+    ("Shallow ocean", "#3f7fbf"),
+    ("Coast", "#e6d8a8"),
+    ("Plain", "#8fbf6a"),
+    ("Hills", "#b8a862"),
+    ("Mountains", "#8a6a4a"),
+    ("High peaks", "#f2f4f7"),
+];
+
+/// Elevation thresholds for `terrain_class_map`, in heightmap units.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainClassParams {
+    pub sea_level: f32,
+    /// Water at or above this is shallow ocean (continental shelf).
+    pub shallow_ocean: f32,
+    /// Land within this many cells of water is coast (0 disables the class).
+    pub coast_width: u32,
+    /// Land at or above this is hills (~470 m with the default relief).
+    pub hills: f32,
+    /// Land at or above this is mountains (~1500 m).
+    pub mountains: f32,
+    /// Land at or above this is high peaks (~3500 m).
+    pub high_peaks: f32,
+}
+
+impl Default for TerrainClassParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            shallow_ocean: 0.10,
+            coast_width: 1,
+            hills: 0.20,
+            mountains: 0.31,
+            high_peaks: 0.52,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TerrainClassParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TerrainClassParams {
+        Self::default()
+    }
+}
+
+impl TerrainClassParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        let thresholds = [
+            self.shallow_ocean,
+            self.sea_level,
+            self.hills,
+            self.mountains,
+            self.high_peaks,
+        ];
+        if thresholds.iter().any(|t| !t.is_finite()) {
+            return Err(JsValue::from_str("terrain class thresholds must be finite"));
+        }
+        if !thresholds.windows(2).all(|w| w[0] <= w[1]) {
+            return Err(JsValue::from_str(
+                "thresholds must satisfy shallow_ocean <= sea_level <= hills <= mountains <= high_peaks",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Terrain class per cell: 0 = deep ocean, 1 = shallow ocean, 2 = coast, 3 = plain,
+/// 4 = hills, 5 = mountains, 6 = high peaks. Coast takes precedence over elevation bands.
+/// See `terrain_class_legend_json`.
+#[wasm_bindgen]
+pub fn terrain_class_map(flat: &[f32], params: &TerrainClassParams) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    let water: Vec<bool> = flat.iter().map(|&h| h < params.sea_level).collect();
+    let to_water = distance_field(&water);
+    let classes: Vec<u8> = flat
+        .iter()
+        .zip(&to_water)
+        .map(|(&h, &d)| {
+            if h < params.shallow_ocean {
+                TERRAIN_DEEP_OCEAN
+            } else if h < params.sea_level {
+                TERRAIN_SHALLOW_OCEAN
+            } else if d <= params.coast_width as f32 {
+                TERRAIN_COAST
+            } else if h < params.hills {
+                TERRAIN_PLAIN
+            } else if h < params.mountains {
+                TERRAIN_HILLS
+            } else if h < params.high_peaks {
+                TERRAIN_MOUNTAINS
+            } else {
+                TERRAIN_HIGH_PEAKS
+            }
+        })
+        .collect();
+    Ok(classes.into_boxed_slice())
+}
+
+/// Legend for `terrain_class_map`: `[{"id","name","color"}, ...]`.
+#[wasm_bindgen]
+pub fn terrain_class_legend_json() -> String {
+    let entries: Vec<String> = TERRAIN_CLASSES
+        .iter()
+        .enumerate()
+        .map(|(id, (name, color))| {
+            format!("{{\"id\":{id},\"name\":\"{name}\",\"color\":\"{color}\"}}")
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
@@ -0,0 +1,70 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::RELIEF_METRES;
+use crate::grid::{HEIGHT, WIDTH, check_grid_len};
+use crate::hillshade::{HillshadeParams, shade};
+use crate::render::ColorRamp;
+use crate::resample::{Filter, resample_grid};
+
+/// How strongly the hillshade modulates the tint: 0 keeps the flat tint, 1 multiplies by
+/// the full shade relative to flat ground.
+const SHADE_STRENGTH: f32 = 0.6;
+
+/// Shaded-relief thumbnail as RGBA8, `width` × `height` (e.g. 256 × 128), for galleries
+/// and history views without the GPU pipeline: the heightmap is filtered down, tinted
+/// through `ramp_json` as in `render_hypsometric_rgba` (empty for the atlas ramp), and lit
+/// by the preview's multidirectional hillshade, with the relief scaled so slopes read as
+/// they do at full size. The sea stays flat.
+#[wasm_bindgen]
+pub fn render_thumbnail_rgba(
+    flat: &[f32],
+    width: u32,
+    height: u32,
+    ramp_json: &str,
+    sea_level: f32,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !(1..=WIDTH as u32).contains(&width) || !(1..=HEIGHT as u32).contains(&height) {
+        return Err(JsValue::from_str(
+            "width and height must be within the grid size",
+        ));
+    }
+    if !(sea_level.is_finite() && sea_level < 1.0) {
+        return Err(JsValue::from_str("sea_level must be below 1"));
+    }
+    let ramp = ColorRamp::parse(ramp_json).map_err(|e| JsValue::from_str(&e))?;
+    Ok(thumbnail_rgba(flat, width as usize, height as usize, &ramp, sea_level).into_boxed_slice())
+}
+
+/// `render_thumbnail_rgba` with its arguments already checked.
+pub(crate) fn thumbnail_rgba(
+    flat: &[f32],
+    width: usize,
+    height: usize,
+    ramp: &ColorRamp,
+    sea_level: f32,
+) -> Vec<u8> {
+    let small = resample_grid(flat, width, height, Filter::Bilinear);
+
+    let lighting = HillshadeParams::default();
+    let lights = lighting.lights();
+    // Each thumbnail pixel spans this many cells, so its height steps are that much larger.
+    let cells_per_pixel = WIDTH as f32 / width as f32;
+    let relief = lighting.elevation_scale * lighting.vertical_exaggeration / cells_per_pixel;
+    let lit = shade(&small, width, height, relief, &lights);
+    let flat_lit: f32 = lights.iter().map(|(l, weight)| l[2] * weight).sum();
+
+    let mut rgba = vec![255_u8; width * height * 4];
+    for ((px, &h), &light) in rgba.chunks_exact_mut(4).zip(&small).zip(&lit) {
+        let metres = (h - sea_level) / (1.0 - sea_level) * RELIEF_METRES;
+        let factor = if h < sea_level {
+            1.0
+        } else {
+            1.0 + SHADE_STRENGTH * (light / flat_lit - 1.0)
+        };
+        for (out, c) in px.iter_mut().zip(ramp.color_at(metres)) {
+            *out = (c * factor).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    rgba
+}
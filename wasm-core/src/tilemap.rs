@@ -0,0 +1,568 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len};
+use crate::hydrology::{Hydrology, River};
+use crate::json::{self, ObjectWriter};
+use crate::terrain_classes::{
+    TERRAIN_CLASSES, TERRAIN_HIGH_PEAKS, TERRAIN_HILLS, TERRAIN_MOUNTAINS,
+    terrain_class_legend_json,
+};
+
+/// `flags` bit: most of the tile is sea or lake.
+const FLAG_WATER: u8 = 1;
+/// `flags` bit: most of the tile's water is lake.
+const FLAG_LAKE: u8 = 2;
+/// `flags` bit: land next to a water tile, or water next to a land tile.
+const FLAG_COAST: u8 = 4;
+/// `flags` bit: a river runs through the tile.
+const FLAG_RIVER: u8 = 8;
+
+/// Per-tile aggregates shared by the tile exports.
+struct TileStats {
+    mean_height: Vec<f32>,
+    /// `FLAG_WATER`, `FLAG_LAKE` and `FLAG_RIVER`; coasts depend on the tiling.
+    flags: Vec<u8>,
+    /// Commonest `classes` value among the cells of the tile's majority kind (land or water).
+    dominant: Vec<u8>,
+}
+
+/// Cells that rivers run through, leaving out the sea or lake cell each ends in.
+fn river_cells(rivers: &[River]) -> Vec<bool> {
+    let mut cells = vec![false; CELL_COUNT];
+    for river in rivers {
+        for &idx in &river.cells[..river.cells.len() - 1] {
+            cells[idx] = true;
+        }
+    }
+    cells
+}
+
+/// Aggregates the cells of each tile, `tile_of` giving every cell's tile. An empty
+/// `classes` leaves `dominant` at 0.
+fn tile_stats(
+    flat: &[f32],
+    classes: &[u8],
+    hydrology: &Hydrology,
+    river_cells: &[bool],
+    sea_level: f32,
+    tile_of: &[u32],
+    count: usize,
+) -> TileStats {
+    // Cells grouped by tile (a counting sort), so each tile is visited once.
+    let mut starts = vec![0_usize; count + 1];
+    for &t in tile_of {
+        starts[t as usize + 1] += 1;
+    }
+    for t in 0..count {
+        starts[t + 1] += starts[t];
+    }
+    let mut next = starts.clone();
+    let mut members = vec![0_u32; CELL_COUNT];
+    for (idx, &t) in tile_of.iter().enumerate() {
+        members[next[t as usize]] = idx as u32;
+        next[t as usize] += 1;
+    }
+
+    let mut stats = TileStats {
+        mean_height: vec![0.0; count],
+        flags: vec![0; count],
+        dominant: vec![0; count],
+    };
+    let mut histogram = [0_u32; 256];
+    for t in 0..count {
+        let cells = &members[starts[t]..starts[t + 1]];
+        if cells.is_empty() {
+            continue;
+        }
+        let water = |idx: usize| flat[idx] < sea_level || hydrology.lakes[idx];
+        let water_cells = cells.iter().filter(|&&i| water(i as usize)).count();
+        let lake_cells = cells
+            .iter()
+            .filter(|&&i| hydrology.lakes[i as usize])
+            .count();
+        let is_water = 2 * water_cells > cells.len();
+        let mut flags = 0;
+        if is_water {
+            flags |= FLAG_WATER;
+            if 2 * lake_cells > water_cells {
+                flags |= FLAG_LAKE;
+            }
+        }
+        if cells.iter().any(|&i| river_cells[i as usize]) {
+            flags |= FLAG_RIVER;
+        }
+        stats.flags[t] = flags;
+        stats.mean_height[t] =
+            cells.iter().map(|&i| flat[i as usize] as f64).sum::<f64>() as f32 / cells.len() as f32;
+        if !classes.is_empty() {
+            histogram.fill(0);
+            for &i in cells.iter().filter(|&&i| water(i as usize) == is_water) {
+                histogram[classes[i as usize] as usize] += 1;
+            }
+            stats.dominant[t] = (0..256)
+                .max_by_key(|&b| (histogram[b], 255 - b))
+                .unwrap_or(0) as u8;
+        }
+    }
+    stats
+}
+
+/// Hex overlay for `export_hex_tiles`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct HexParams {
+    /// Hexes around the map east–west, 4 to 1024; rows follow from the hex size.
+    pub columns: u32,
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km², as in the map exports.
+    pub river_min_area_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes.
+    pub lake_min_depth_metres: f32,
+}
+
+impl Default for HexParams {
+    fn default() -> Self {
+        Self {
+            columns: 96,
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 50_000.0,
+            lake_min_depth_metres: 20.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl HexParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HexParams {
+        Self::default()
+    }
+}
+
+impl HexParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(4..=1024).contains(&self.columns) {
+            return Err(JsValue::from_str("columns must be within [4, 1024]"));
+        }
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if [self.river_min_area_km2, self.lake_min_depth_metres]
+            .iter()
+            .any(|v| !v.is_finite() || *v < 0.0)
+        {
+            return Err(JsValue::from_str(
+                "river_min_area_km2 and lake_min_depth_metres must be >= 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Pointy-top hex layout with odd rows shifted half a hex east ("odd-r"), wrapping
+/// east–west like the grid.
+struct HexLayout {
+    columns: usize,
+    rows: usize,
+    /// Hex width, cells.
+    width: f64,
+    /// Centre-to-corner radius, cells.
+    size: f64,
+}
+
+impl HexLayout {
+    fn new(columns: usize) -> HexLayout {
+        let width = WIDTH as f64 / columns as f64;
+        let size = width / 3_f64.sqrt();
+        let rows = (HEIGHT as f64 / (1.5 * size)).round().max(1.0) as usize;
+        HexLayout {
+            columns,
+            rows,
+            width,
+            size,
+        }
+    }
+
+    /// Hex containing the point (`x`, `y`) in grid coordinates; rows past the top and
+    /// bottom clamp to the outermost ones.
+    fn hex_at(&self, x: f64, y: f64) -> usize {
+        let (px, py) = (x - self.width / 2.0, y - self.size);
+        // Axial coordinates, rounded through cube coordinates.
+        let q = (3_f64.sqrt() / 3.0 * px - py / 3.0) / self.size;
+        let r = 2.0 / 3.0 * py / self.size;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), (-q - r).round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs + q + r).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        let (q, r) = (rq as i64, rr as i64);
+        let (col, row) = if (0..self.rows as i64).contains(&r) {
+            (q + (r - r.rem_euclid(2)) / 2, r)
+        } else {
+            let row = r.clamp(0, self.rows as i64 - 1);
+            let shift = 0.5 * row.rem_euclid(2) as f64;
+            ((x / self.width - shift).floor() as i64, row)
+        };
+        row as usize * self.columns + col.rem_euclid(self.columns as i64) as usize
+    }
+
+    /// Neighbour of hex `hex` in direction `k`: 0 east, then counter-clockwise (north-east,
+    /// north-west, west, south-west, south-east); none past the top or bottom row.
+    fn neighbour(&self, hex: usize, k: usize) -> Option<usize> {
+        const EVEN: [(i64, i64); 6] = [(1, 0), (0, -1), (-1, -1), (-1, 0), (-1, 1), (0, 1)];
+        const ODD: [(i64, i64); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (0, 1), (1, 1)];
+        let (col, row) = ((hex % self.columns) as i64, (hex / self.columns) as i64);
+        let (dc, dr) = if row % 2 == 0 { EVEN[k] } else { ODD[k] };
+        let row = row + dr;
+        (0..self.rows as i64).contains(&row).then(|| {
+            row as usize * self.columns + (col + dc).rem_euclid(self.columns as i64) as usize
+        })
+    }
+}
+
+/// Per-hex layers from `export_hex_tiles`, row-major (`columns` per row, north row first).
+/// Hexes are pointy-topped, odd rows shifted half a hex east: hex (c, r) is centred at
+/// x = (c + 0.5 + 0.5 · (r mod 2)) · `hex_width`, y = `hex_size` · (1 + 1.5 r) in grid
+/// cells, and the columns wrap around east–west.
+#[wasm_bindgen]
+pub struct HexTiles {
+    columns: u32,
+    rows: u32,
+    hex_width: f32,
+    hex_size: f32,
+    elevation: Vec<f32>,
+    biome: Vec<u8>,
+    flags: Vec<u8>,
+    river_edges: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl HexTiles {
+    #[wasm_bindgen(getter)]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Flat-to-flat width of a hex, grid cells.
+    #[wasm_bindgen(getter)]
+    pub fn hex_width(&self) -> f32 {
+        self.hex_width
+    }
+
+    /// Centre-to-corner radius of a hex, grid cells.
+    #[wasm_bindgen(getter)]
+    pub fn hex_size(&self) -> f32 {
+        self.hex_size
+    }
+
+    /// Mean heightmap value of each hex.
+    pub fn elevation(&self) -> Box<[f32]> {
+        self.elevation.clone().into_boxed_slice()
+    }
+
+    /// Commonest biome id of each hex among its land cells, or its water cells for a
+    /// water hex; 0 without a biome map.
+    pub fn biome(&self) -> Box<[u8]> {
+        self.biome.clone().into_boxed_slice()
+    }
+
+    /// Bit flags of each hex: 1 water (mostly sea or lake), 2 lake (its water is mostly
+    /// lake), 4 coast (borders a hex of the other kind), 8 river (a river runs through).
+    pub fn flags(&self) -> Box<[u8]> {
+        self.flags.clone().into_boxed_slice()
+    }
+
+    /// Edges of each hex that a river crosses, bit k for direction k: 0 east, 1 north-east,
+    /// 2 north-west, 3 west, 4 south-west, 5 south-east. Both hexes sharing the edge have
+    /// it set.
+    pub fn river_edges(&self) -> Box<[u8]> {
+        self.river_edges.clone().into_boxed_slice()
+    }
+}
+
+/// Hex-grid summary of the map for hex-based strategy games: a hex overlay `columns` hexes
+/// around, with each hex's mean elevation, dominant biome, water, coast and river flags,
+/// and the hex edges rivers cross. Rivers and lakes come from the same drainage as
+/// `export_map_svg`. Pass an empty `biome_map` to skip biomes.
+#[wasm_bindgen]
+pub fn export_hex_tiles(
+    flat: &[f32],
+    biome_map: &[u8],
+    params: &HexParams,
+) -> Result<HexTiles, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if !biome_map.is_empty() {
+        check_grid_len(biome_map, "biome map")?;
+    }
+    params.validate()?;
+    let layout = HexLayout::new(params.columns as usize);
+    let count = layout.columns * layout.rows;
+    let tile_of: Vec<u32> = (0..CELL_COUNT)
+        .map(|idx| {
+            let (x, y) = ((idx % WIDTH) as f64 + 0.5, (idx / WIDTH) as f64 + 0.5);
+            layout.hex_at(x, y) as u32
+        })
+        .collect();
+
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+    let rivers = hydrology.rivers(params.river_min_area_km2);
+    let mut river_edges = vec![0_u8; count];
+    for river in &rivers {
+        for pair in river.cells.windows(2) {
+            let (a, b) = (tile_of[pair[0]] as usize, tile_of[pair[1]] as usize);
+            if let Some(k) = (0..6).find(|&k| layout.neighbour(a, k) == Some(b)) {
+                river_edges[a] |= 1 << k;
+                river_edges[b] |= 1 << ((k + 3) % 6);
+            }
+        }
+    }
+
+    let mut stats = tile_stats(
+        flat,
+        biome_map,
+        &hydrology,
+        &river_cells(&rivers),
+        params.sea_level,
+        &tile_of,
+        count,
+    );
+    let water: Vec<bool> = stats.flags.iter().map(|f| f & FLAG_WATER != 0).collect();
+    for (hex, flags) in stats.flags.iter_mut().enumerate() {
+        let coast = (0..6)
+            .filter_map(|k| layout.neighbour(hex, k))
+            .any(|n| water[n] != water[hex]);
+        if coast {
+            *flags |= FLAG_COAST;
+        }
+    }
+    Ok(HexTiles {
+        columns: layout.columns as u32,
+        rows: layout.rows as u32,
+        hex_width: layout.width as f32,
+        hex_size: layout.size as f32,
+        elevation: stats.mean_height,
+        biome: stats.dominant,
+        flags: stats.flags,
+        river_edges,
+    })
+}
+
+/// Tile size and water rules for `export_square_tiles`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SquareTileParams {
+    /// Grid cells per tile side, 1 to 256; edge tiles are cut short when it does not
+    /// divide the grid.
+    pub tile_size: u32,
+    pub sea_level: f32,
+    /// Drainage area at which a river starts, km², as in the map exports.
+    pub river_min_area_km2: f32,
+    /// Land basins deeper than this when filled to their spill point become lakes.
+    pub lake_min_depth_metres: f32,
+}
+
+impl Default for SquareTileParams {
+    fn default() -> Self {
+        Self {
+            tile_size: 16,
+            sea_level: SEA_LEVEL,
+            river_min_area_km2: 50_000.0,
+            lake_min_depth_metres: 20.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SquareTileParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SquareTileParams {
+        Self::default()
+    }
+}
+
+impl SquareTileParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !(1..=256).contains(&self.tile_size) {
+            return Err(JsValue::from_str("tile_size must be within [1, 256]"));
+        }
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if [self.river_min_area_km2, self.lake_min_depth_metres]
+            .iter()
+            .any(|v| !v.is_finite() || *v < 0.0)
+        {
+            return Err(JsValue::from_str(
+                "river_min_area_km2 and lake_min_depth_metres must be >= 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Movement cost of impassable tiles (water, lakes and high peaks for land units).
+const MOVEMENT_IMPASSABLE: u8 = 255;
+const SQUARE_TILES_VERSION: u64 = 1;
+
+/// Movement points to enter a tile: 1 on coast and plains, 2 in hills, 3 in mountains, one
+/// more where a river runs through.
+fn movement_cost(terrain: u8, flags: u8) -> u8 {
+    if flags & FLAG_WATER != 0 || terrain == TERRAIN_HIGH_PEAKS {
+        return MOVEMENT_IMPASSABLE;
+    }
+    let base = match terrain {
+        TERRAIN_HILLS => 2,
+        TERRAIN_MOUNTAINS => 3,
+        _ => 1,
+    };
+    base + u8::from(flags & FLAG_RIVER != 0)
+}
+
+/// Per-tile layers from `export_square_tiles`, row-major with the north row first; tile
+/// (c, r) covers grid cells from (c · `tile_size`, r · `tile_size`).
+#[wasm_bindgen]
+pub struct SquareTiles {
+    columns: u32,
+    rows: u32,
+    tile_size: u32,
+    terrain: Vec<u8>,
+    flags: Vec<u8>,
+    movement_cost: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl SquareTiles {
+    #[wasm_bindgen(getter)]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// Terrain class of each tile (ids as `terrain_class_map`): the commonest among its
+    /// land cells, or its water cells for a water tile.
+    pub fn terrain(&self) -> Box<[u8]> {
+        self.terrain.clone().into_boxed_slice()
+    }
+
+    /// Bit flags of each tile: 1 water (mostly sea or lake), 2 lake, 4 coast (touches a
+    /// tile of the other kind, diagonals included), 8 river.
+    pub fn flags(&self) -> Box<[u8]> {
+        self.flags.clone().into_boxed_slice()
+    }
+
+    /// Movement points to enter each tile: 1 on coast and plains, 2 in hills, 3 in
+    /// mountains, plus 1 with a river; 255 for water and high peaks.
+    pub fn movement_cost(&self) -> Box<[u8]> {
+        self.movement_cost.clone().into_boxed_slice()
+    }
+
+    /// One u16 per tile: terrain class in bits 0–3, flags in bits 4–7 and movement cost
+    /// in the high byte.
+    pub fn packed(&self) -> Box<[u16]> {
+        (0..self.terrain.len())
+            .map(|t| {
+                self.terrain[t] as u16
+                    | (self.flags[t] as u16) << 4
+                    | (self.movement_cost[t] as u16) << 8
+            })
+            .collect()
+    }
+
+    /// The tilemap as JSON: `{"format":"continent-tilemap","version":1,"columns","rows",
+    /// "tile_size","terrain_legend":[...],"terrain":[...],"flags":[...],
+    /// "movement_cost":[...]}`, with the legend as `terrain_class_legend_json`.
+    pub fn to_json(&self) -> String {
+        ObjectWriter::new()
+            .raw("format", &json::quote("continent-tilemap"))
+            .integer("version", SQUARE_TILES_VERSION)
+            .integer("columns", self.columns as u64)
+            .integer("rows", self.rows as u64)
+            .integer("tile_size", self.tile_size as u64)
+            .raw("terrain_legend", &terrain_class_legend_json())
+            .raw("terrain", &json::array(&self.terrain))
+            .raw("flags", &json::array(&self.flags))
+            .raw("movement_cost", &json::array(&self.movement_cost))
+            .finish()
+    }
+}
+
+/// Square-tile summary of the map for Civ-like prototypes: each `tile_size` × `tile_size`
+/// block of cells becomes one tile with a terrain type, water, coast and river flags and a
+/// movement cost. `terrain_classes` is a `terrain_class_map` of the same heightmap, ideally
+/// with the same sea level; rivers and lakes come from the same drainage as
+/// `export_map_svg`. Export it as JSON or as a compact grid (`packed`, or the u8 layers).
+#[wasm_bindgen]
+pub fn export_square_tiles(
+    flat: &[f32],
+    terrain_classes: &[u8],
+    params: &SquareTileParams,
+) -> Result<SquareTiles, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_grid_len(terrain_classes, "terrain class map")?;
+    params.validate()?;
+    if terrain_classes
+        .iter()
+        .any(|&c| c as usize >= TERRAIN_CLASSES.len())
+    {
+        return Err(JsValue::from_str("terrain class map has unknown class ids"));
+    }
+    let size = params.tile_size as usize;
+    let (columns, rows) = (WIDTH.div_ceil(size), HEIGHT.div_ceil(size));
+    let tile_of: Vec<u32> = (0..CELL_COUNT)
+        .map(|idx| ((idx / WIDTH / size) * columns + idx % WIDTH / size) as u32)
+        .collect();
+
+    let hydrology = Hydrology::build(flat, params.sea_level, params.lake_min_depth_metres);
+    let rivers = hydrology.rivers(params.river_min_area_km2);
+    let mut stats = tile_stats(
+        flat,
+        terrain_classes,
+        &hydrology,
+        &river_cells(&rivers),
+        params.sea_level,
+        &tile_of,
+        columns * rows,
+    );
+    let water: Vec<bool> = stats.flags.iter().map(|f| f & FLAG_WATER != 0).collect();
+    for (tile, flags) in stats.flags.iter_mut().enumerate() {
+        let (c, r) = (tile % columns, tile / columns);
+        let coast = (r.saturating_sub(1)..(r + 2).min(rows)).any(|nr| {
+            [columns - 1, 0, 1]
+                .iter()
+                .any(|dc| water[nr * columns + (c + dc) % columns] != water[tile])
+        });
+        if coast {
+            *flags |= FLAG_COAST;
+        }
+    }
+    let movement_cost = stats
+        .dominant
+        .iter()
+        .zip(&stats.flags)
+        .map(|(&terrain, &flags)| movement_cost(terrain, flags))
+        .collect();
+    Ok(SquareTiles {
+        columns: columns as u32,
+        rows: rows as u32,
+        tile_size: params.tile_size,
+        terrain: stats.dominant,
+        flags: stats.flags,
+        movement_cost,
+    })
+}
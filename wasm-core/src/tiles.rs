@@ -0,0 +1,258 @@
+use wasm_bindgen::prelude::*;
+
+use crate::fingerprint::quantize;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH, check_grid_len};
+use crate::png::{COLOR_GRAY, COLOR_RGBA, encode_png};
+use crate::projection::{WEB_MERCATOR_MAX_LATITUDE, mercator_resample};
+use crate::resample::{Filter, resample_grid};
+
+const TILE_SIZE: usize = 256;
+/// Zoom at which the map is as wide as the grid: 8 × 4 equirectangular tiles, or 8 × 8
+/// Web Mercator ones.
+const fn native_zoom(web_mercator: bool) -> u32 {
+    if web_mercator { 3 } else { 2 }
+}
+const MAX_ZOOM: u32 = 4;
+
+/// Zoom range for `export_map_tiles` and `export_heightmap_tiles`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TileParams {
+    pub min_zoom: u32,
+    /// Up to 4; zooms finer than the grid's own resolution (2, or 3 in Web Mercator) are
+    /// upsampled.
+    pub max_zoom: u32,
+    /// Cuts the standard Web Mercator (EPSG:3857) pyramid that Leaflet and MapLibre use by
+    /// default, 2^z × 2^z tiles per zoom up to ±85.05° latitude, instead of the
+    /// equirectangular one.
+    pub web_mercator: bool,
+}
+
+impl Default for TileParams {
+    fn default() -> Self {
+        Self {
+            min_zoom: 0,
+            max_zoom: native_zoom(false),
+            web_mercator: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TileParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TileParams {
+        Self::default()
+    }
+}
+
+impl TileParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if self.min_zoom > self.max_zoom || self.max_zoom > MAX_ZOOM {
+            return Err(JsValue::from_str(&format!(
+                "zooms must satisfy min_zoom <= max_zoom <= {MAX_ZOOM}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// PNG tiles from `export_map_tiles` or `export_heightmap_tiles`, named `z/x/y.png`.
+#[wasm_bindgen]
+pub struct TileSet {
+    names: Vec<String>,
+    tiles: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl TileSet {
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> u32 {
+        self.tiles.len() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Tile paths, coarsest zoom first, in the order of `tile`.
+    pub fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    pub fn tile(&self, index: u32) -> Result<Box<[u8]>, JsValue> {
+        self.tiles
+            .get(index as usize)
+            .map(|t| t.clone().into_boxed_slice())
+            .ok_or_else(|| JsValue::from_str("tile index out of range"))
+    }
+}
+
+/// One zoom level as channel planes, each `width * height` samples.
+struct Level {
+    width: usize,
+    height: usize,
+    planes: Vec<Vec<f32>>,
+}
+
+impl Level {
+    /// 2 × 2 box average, halving each side.
+    fn halved(&self) -> Level {
+        let (width, height) = (self.width / 2, self.height / 2);
+        let planes = self
+            .planes
+            .iter()
+            .map(|plane| {
+                (0..width * height)
+                    .map(|i| {
+                        let (x, y) = (2 * (i % width), 2 * (i / width));
+                        let at = |dx: usize, dy: usize| plane[(y + dy) * self.width + x + dx];
+                        (at(0, 0) + at(1, 0) + at(0, 1) + at(1, 1)) / 4.0
+                    })
+                    .collect()
+            })
+            .collect();
+        Level {
+            width,
+            height,
+            planes,
+        }
+    }
+}
+
+/// Cuts `level` into tiles named for zoom `z`.
+fn cut_tiles(
+    z: u32,
+    level: &Level,
+    encode: &impl Fn(&[Vec<f32>], usize, usize) -> Vec<u8>,
+    out: &mut Vec<(u32, String, Vec<u8>)>,
+) {
+    let (cols, rows) = (level.width / TILE_SIZE, level.height / TILE_SIZE);
+    for ty in 0..rows {
+        for tx in 0..cols {
+            let origin = ty * TILE_SIZE * level.width + tx * TILE_SIZE;
+            let tile = encode(&level.planes, level.width, origin);
+            out.push((z, format!("{z}/{tx}/{ty}.png"), tile));
+        }
+    }
+}
+
+/// Zoom `z` at or above the native zoom, resampled from the grid-sized `planes`.
+fn projected_level(planes: &[Vec<f32>], z: u32, web_mercator: bool) -> Level {
+    let scale = 1 << (z - native_zoom(web_mercator));
+    let (width, height) = if web_mercator {
+        (WIDTH * scale, WIDTH * scale)
+    } else {
+        (WIDTH * scale, HEIGHT * scale)
+    };
+    let planes = planes
+        .iter()
+        .map(|p| {
+            if web_mercator {
+                mercator_resample(p, width, height, WEB_MERCATOR_MAX_LATITUDE)
+            } else if scale == 1 {
+                p.clone()
+            } else {
+                resample_grid(p, width, height, Filter::Bilinear)
+            }
+        })
+        .collect();
+    Level {
+        width,
+        height,
+        planes,
+    }
+}
+
+/// Every tile of the pyramid over `planes` (grid-sized channels). `encode` makes a PNG
+/// from the tile whose top-left sample is `origin` in a level `width` samples wide.
+fn build_pyramid(
+    planes: Vec<Vec<f32>>,
+    params: &TileParams,
+    encode: impl Fn(&[Vec<f32>], usize, usize) -> Vec<u8>,
+) -> TileSet {
+    let native = native_zoom(params.web_mercator);
+    let mut out = Vec::new();
+    // Finer zooms are interpolated from the grid, coarser ones box-averaged from the
+    // zoom above.
+    for z in (native + 1).max(params.min_zoom)..=params.max_zoom {
+        let level = projected_level(&planes, z, params.web_mercator);
+        cut_tiles(z, &level, &encode, &mut out);
+    }
+    if params.min_zoom <= native {
+        let mut level = projected_level(&planes, native, params.web_mercator);
+        drop(planes);
+        for z in (params.min_zoom..=native).rev() {
+            if z < native {
+                level = level.halved();
+            }
+            if z <= params.max_zoom {
+                cut_tiles(z, &level, &encode, &mut out);
+            }
+        }
+    }
+    out.sort_by_key(|(z, _, _)| *z);
+    let (names, tiles) = out.into_iter().map(|(_, name, tile)| (name, tile)).unzip();
+    TileSet { names, tiles }
+}
+
+/// Tile samples of one plane, row by row.
+fn tile_samples<'a>(
+    plane: &'a [f32],
+    width: usize,
+    origin: usize,
+) -> impl Iterator<Item = f32> + 'a {
+    (0..TILE_SIZE).flat_map(move |row| {
+        let start = origin + row * width;
+        plane[start..start + TILE_SIZE].iter().copied()
+    })
+}
+
+/// Slippy-map tiles of a rendered map (RGBA, as from `render_biome_rgba` or
+/// `render_hypsometric_rgba`): 256-px PNGs counted from the north-west. By default they
+/// follow the equirectangular EPSG:4326 tiling (Leaflet's `CRS.EPSG4326`, WorldCRS84Quad),
+/// where zoom z has 2^(z+1) × 2^z tiles and zoom 2 is the grid's own resolution; see
+/// `TileParams::web_mercator` for the usual web-map tiling.
+#[wasm_bindgen]
+pub fn export_map_tiles(rgba: &[u8], params: &TileParams) -> Result<TileSet, JsValue> {
+    if rgba.len() != CELL_COUNT * 4 {
+        return Err(JsValue::from_str("rgba must be 2048x1024x4 bytes"));
+    }
+    params.validate()?;
+    let planes = (0..4)
+        .map(|c| rgba.iter().skip(c).step_by(4).map(|&v| v as f32).collect())
+        .collect();
+    Ok(build_pyramid(planes, params, |planes, width, origin| {
+        let channels: Vec<Vec<f32>> = planes
+            .iter()
+            .map(|p| tile_samples(p, width, origin).collect())
+            .collect();
+        let pixels: Vec<u8> = (0..TILE_SIZE * TILE_SIZE)
+            .flat_map(|i| {
+                channels
+                    .iter()
+                    .map(move |c| c[i].round().clamp(0.0, 255.0) as u8)
+            })
+            .collect();
+        encode_png(TILE_SIZE, TILE_SIZE, COLOR_RGBA, 8, &pixels)
+    }))
+}
+
+/// Heightmap tiles in the tiling of `export_map_tiles`, as 16-bit grayscale PNGs scaled
+/// like `export_heightmap_png`, for client-side hillshading or terrain meshes.
+#[wasm_bindgen]
+pub fn export_heightmap_tiles(flat: &[f32], params: &TileParams) -> Result<TileSet, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    Ok(build_pyramid(
+        vec![flat.to_vec()],
+        params,
+        |planes, width, origin| {
+            let pixels: Vec<u8> = tile_samples(&planes[0], width, origin)
+                .flat_map(|h| quantize(h, 16).to_be_bytes())
+                .collect();
+            encode_png(TILE_SIZE, TILE_SIZE, COLOR_GRAY, 16, &pixels)
+        },
+    ))
+}
@@ -0,0 +1,185 @@
+use wasm_bindgen::prelude::*;
+
+use crate::json::{self, Json, ObjectWriter};
+use crate::morph::{Easing, interpolate_value};
+
+/// Parameter values pinned at one time.
+#[derive(Clone, Debug)]
+struct Keyframe {
+    time: f64,
+    params: Vec<(String, Json)>,
+    /// Timing of the move from this keyframe to the next that sets each parameter.
+    easing: Easing,
+}
+
+impl Keyframe {
+    fn get(&self, key: &str) -> Option<&Json> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// Keyframed generation parameters over time, for flythrough and evolution videos rendered
+/// frame by frame: set keyframes with `set_keyframe`, then ask `sample_at` for the
+/// parameter set of each frame. A keyframe need not set every parameter; each one moves
+/// between the keyframes that do set it.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct ParamTimeline {
+    /// In time order, at most one per time.
+    keyframes: Vec<Keyframe>,
+}
+
+impl ParamTimeline {
+    fn insert(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .binary_search_by(|k| k.time.total_cmp(&keyframe.time))
+        {
+            Ok(i) => self.keyframes[i] = keyframe,
+            Err(i) => self.keyframes.insert(i, keyframe),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ParamTimeline {
+    /// An empty timeline.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ParamTimeline {
+        Self::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn keyframe_count(&self) -> u32 {
+        self.keyframes.len() as u32
+    }
+
+    /// Pins the parameters in `params_json` (a JSON object, e.g. `{"sea_level":0.2}`) at
+    /// `time`, replacing any keyframe already there. `easing` (`"linear"`, `"ease_in"`,
+    /// `"ease_out"` or `"ease_in_out"`) times the move from here to each parameter's next
+    /// keyframe.
+    pub fn set_keyframe(
+        &mut self,
+        time: f64,
+        params_json: &str,
+        easing: &str,
+    ) -> Result<(), JsValue> {
+        if !time.is_finite() {
+            return Err(JsValue::from_str("time must be finite"));
+        }
+        let params = match json::parse(params_json).map_err(|e| JsValue::from_str(&e))? {
+            Json::Object(fields) => fields,
+            _ => return Err(JsValue::from_str("params must be a JSON object")),
+        };
+        let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e))?;
+        self.insert(Keyframe {
+            time,
+            params,
+            easing,
+        });
+        Ok(())
+    }
+
+    /// Removes the keyframe at `time`; false if there is none.
+    pub fn remove_keyframe(&mut self, time: f64) -> bool {
+        let count = self.keyframes.len();
+        self.keyframes.retain(|k| k.time != time);
+        self.keyframes.len() < count
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    /// Keyframes in time order, `[{"time","easing","params"},...]`, to save with a project
+    /// and load again with `param_timeline_from_json`.
+    pub fn keyframes_json(&self) -> String {
+        json::array(self.keyframes.iter().map(|k| {
+            ObjectWriter::new()
+                .raw("time", &Json::Number(k.time).to_string())
+                .raw("easing", &json::quote(k.easing.name()))
+                .raw("params", &Json::Object(k.params.clone()).to_string())
+                .finish()
+        }))
+    }
+
+    /// The parameter set at `time`, as a JSON object with every parameter any keyframe
+    /// sets. Between two keyframes that set it, a number moves with the earlier one's
+    /// easing and any other value switches half way; `"seed"` always switches, since
+    /// seeds in between are unrelated worlds. Before its first keyframe or after its last
+    /// a parameter holds that keyframe's value. `{}` with no keyframes.
+    pub fn sample_at(&self, time: f64) -> Result<String, JsValue> {
+        if !time.is_finite() {
+            return Err(JsValue::from_str("time must be finite"));
+        }
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in self.keyframes.iter().flat_map(|k| &k.params) {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        let fields = keys
+            .into_iter()
+            .map(|key| {
+                let before = self
+                    .keyframes
+                    .iter()
+                    .rev()
+                    .filter(|k| k.time <= time)
+                    .find_map(|k| Some((k, k.get(key)?)));
+                let after = self
+                    .keyframes
+                    .iter()
+                    .filter(|k| k.time > time)
+                    .find_map(|k| Some((k, k.get(key)?)));
+                let value = match (before, after) {
+                    (Some((from, a)), Some((to, b))) => {
+                        let u = from
+                            .easing
+                            .apply((time - from.time) / (to.time - from.time));
+                        match key {
+                            "seed" if u >= 0.5 => b.clone(),
+                            "seed" => a.clone(),
+                            _ => interpolate_value(a, b, u),
+                        }
+                    }
+                    (Some((_, value)), None) | (None, Some((_, value))) => value.clone(),
+                    (None, None) => unreachable!("every key comes from a keyframe"),
+                };
+                (key.to_owned(), value)
+            })
+            .collect();
+        Ok(Json::Object(fields).to_string())
+    }
+}
+
+/// A timeline from the JSON `ParamTimeline::keyframes_json` writes.
+#[wasm_bindgen]
+pub fn param_timeline_from_json(keyframes_json: &str) -> Result<ParamTimeline, JsValue> {
+    let keyframes = json::parse(keyframes_json).map_err(|e| JsValue::from_str(&e))?;
+    let mut timeline = ParamTimeline::new();
+    for keyframe in keyframes
+        .as_array()
+        .ok_or_else(|| JsValue::from_str("keyframes must be a JSON array"))?
+    {
+        let time = keyframe
+            .get("time")
+            .and_then(Json::as_f64)
+            .ok_or_else(|| JsValue::from_str("keyframe time must be a number"))?;
+        let params = match keyframe.get("params") {
+            Some(Json::Object(fields)) => fields.clone(),
+            _ => return Err(JsValue::from_str("keyframe params must be a JSON object")),
+        };
+        let easing = keyframe
+            .get("easing")
+            .and_then(Json::as_str)
+            .unwrap_or("linear");
+        let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e))?;
+        timeline.insert(Keyframe {
+            time,
+            params,
+            easing,
+        });
+    }
+    Ok(timeline)
+}
@@ -0,0 +1,438 @@
+use crate::climate::RELIEF_METRES;
+use crate::grid::{HEIGHT, WIDTH, clamp_y, sample_bilinear, wrap_x};
+use crate::mesh::{MeshParams, TerrainMesh};
+
+/// Marks a missing half-edge twin or queue slot.
+const NONE: usize = usize::MAX;
+
+/// Twice the signed area of `abc`; zero when the points are collinear.
+fn orient(a: [i64; 2], b: [i64; 2], c: [i64; 2]) -> i64 {
+    (b[0] - c[0]) * (a[1] - c[1]) - (b[1] - c[1]) * (a[0] - c[0])
+}
+
+/// Whether `p` lies inside the circumcircle of `abc`, exactly.
+fn in_circle(a: [i64; 2], b: [i64; 2], c: [i64; 2], p: [i64; 2]) -> bool {
+    let (dx, dy) = (a[0] - p[0], a[1] - p[1]);
+    let (ex, ey) = (b[0] - p[0], b[1] - p[1]);
+    let (fx, fy) = (c[0] - p[0], c[1] - p[1]);
+    let (ap, bp, cp) = (dx * dx + dy * dy, ex * ex + ey * ey, fx * fx + fy * fy);
+    dx * (ey * cp - bp * fy) - dy * (ex * cp - bp * fx) + ap * (ex * fy - ey * fx) < 0
+}
+
+/// Greedy-insertion Delaunay triangulation of a height field (Garland and Heckbert, "Fast
+/// Polygonal Approximation of Terrains and Height Fields"): starting from two triangles
+/// over the whole field, the sample farthest from the surface is inserted each step, and
+/// edges are flipped to keep the triangulation Delaunay. Triangles live in half-edge
+/// form, three half-edges per triangle; only triangles touched by an insertion are
+/// rescanned for their worst sample.
+struct Triangulation<'a> {
+    field: &'a [f32],
+    width: usize,
+    coords: Vec<[i64; 2]>,
+    /// Vertex of each half-edge's start.
+    triangles: Vec<usize>,
+    /// Opposite half-edge in the neighbouring triangle, or `NONE` on the border.
+    halfedges: Vec<usize>,
+    /// Worst-approximated sample of each triangle.
+    candidates: Vec<[i64; 2]>,
+    /// Max-heap of triangles by the error at their candidate.
+    queue: Vec<usize>,
+    errors: Vec<f32>,
+    queue_indices: Vec<usize>,
+    /// Triangles created since the last `flush`, not yet scanned or queued.
+    pending: Vec<usize>,
+}
+
+impl<'a> Triangulation<'a> {
+    fn new(field: &'a [f32], width: usize, height: usize) -> Self {
+        let mut tin = Self {
+            field,
+            width,
+            coords: Vec::new(),
+            triangles: Vec::new(),
+            halfedges: Vec::new(),
+            candidates: Vec::new(),
+            queue: Vec::new(),
+            errors: Vec::new(),
+            queue_indices: Vec::new(),
+            pending: Vec::new(),
+        };
+        let (x1, y1) = (width as i64 - 1, height as i64 - 1);
+        let p0 = tin.add_point([0, 0]);
+        let p1 = tin.add_point([x1, 0]);
+        let p2 = tin.add_point([0, y1]);
+        let p3 = tin.add_point([x1, y1]);
+        let t0 = tin.add_triangle([p3, p0, p2], [NONE; 3], NONE);
+        tin.add_triangle([p0, p3, p1], [t0, NONE, NONE], NONE);
+        tin.flush();
+        tin
+    }
+
+    fn height_at(&self, [x, y]: [i64; 2]) -> f32 {
+        self.field[y as usize * self.width + x as usize]
+    }
+
+    /// Largest remaining error, 0 once the surface matches every sample.
+    fn max_error(&self) -> f32 {
+        self.errors.first().copied().unwrap_or(0.0)
+    }
+
+    fn add_point(&mut self, p: [i64; 2]) -> usize {
+        self.coords.push(p);
+        self.coords.len() - 1
+    }
+
+    /// Writes a triangle at half-edge `e` (appending when `NONE`) with the given twins,
+    /// and marks it pending. Returns its first half-edge.
+    fn add_triangle(&mut self, vertices: [usize; 3], twins: [usize; 3], e: usize) -> usize {
+        let e = if e == NONE {
+            self.triangles.extend([0; 3]);
+            self.halfedges.extend([NONE; 3]);
+            self.candidates.push([0, 0]);
+            self.queue_indices.push(NONE);
+            self.triangles.len() - 3
+        } else {
+            e
+        };
+        for k in 0..3 {
+            self.triangles[e + k] = vertices[k];
+            self.halfedges[e + k] = twins[k];
+            if twins[k] != NONE {
+                self.halfedges[twins[k]] = e + k;
+            }
+        }
+        let t = e / 3;
+        self.candidates[t] = [0, 0];
+        self.queue_indices[t] = NONE;
+        self.pending.push(t);
+        e
+    }
+
+    fn flush(&mut self) {
+        for t in std::mem::take(&mut self.pending) {
+            self.find_candidate(t);
+        }
+    }
+
+    /// Rasterizes triangle `t` to find the sample farthest from its plane, and queues it.
+    fn find_candidate(&mut self, t: usize) {
+        let [p0, p1, p2] = [0, 1, 2].map(|k| self.coords[self.triangles[3 * t + k]]);
+        let min_x = p0[0].min(p1[0]).min(p2[0]);
+        let min_y = p0[1].min(p1[1]).min(p2[1]);
+        let max_x = p0[0].max(p1[0]).max(p2[0]);
+        let max_y = p0[1].max(p1[1]).max(p2[1]);
+        // Barycentric weights of the bounding box corner, stepped along rows and columns.
+        let (mut w00, mut w01, mut w02) = (
+            orient(p1, p2, [min_x, min_y]),
+            orient(p2, p0, [min_x, min_y]),
+            orient(p0, p1, [min_x, min_y]),
+        );
+        let (a01, b01) = (p1[1] - p0[1], p0[0] - p1[0]);
+        let (a12, b12) = (p2[1] - p1[1], p1[0] - p2[0]);
+        let (a20, b20) = (p0[1] - p2[1], p2[0] - p0[0]);
+        let area = orient(p0, p1, p2) as f64;
+        let [z0, z1, z2] = [p0, p1, p2].map(|p| self.height_at(p) as f64 / area);
+
+        let (mut max_error, mut best) = (0.0_f32, p0);
+        for y in min_y..=max_y {
+            // Skip to the first column inside the triangle.
+            let mut dx = 0;
+            if w00 < 0 && a12 != 0 {
+                dx = dx.max((-w00).div_euclid(a12));
+            }
+            if w01 < 0 && a20 != 0 {
+                dx = dx.max((-w01).div_euclid(a20));
+            }
+            if w02 < 0 && a01 != 0 {
+                dx = dx.max((-w02).div_euclid(a01));
+            }
+            let (mut w0, mut w1, mut w2) = (w00 + a12 * dx, w01 + a20 * dx, w02 + a01 * dx);
+            let mut was_inside = false;
+            for x in min_x + dx..=max_x {
+                if w0 >= 0 && w1 >= 0 && w2 >= 0 {
+                    was_inside = true;
+                    let z = z0 * w0 as f64 + z1 * w1 as f64 + z2 * w2 as f64;
+                    let error = (z as f32 - self.height_at([x, y])).abs();
+                    if error > max_error {
+                        max_error = error;
+                        best = [x, y];
+                    }
+                } else if was_inside {
+                    break;
+                }
+                w0 += a12;
+                w1 += a20;
+                w2 += a01;
+            }
+            w00 += b12;
+            w01 += b20;
+            w02 += b01;
+        }
+        if [p0, p1, p2].contains(&best) {
+            max_error = 0.0;
+        }
+        self.candidates[t] = best;
+        self.queue_push(t, max_error);
+    }
+
+    /// Inserts the worst sample of the worst triangle.
+    fn step(&mut self) {
+        let t = self.queue_pop();
+        let [e0, e1, e2] = [3 * t, 3 * t + 1, 3 * t + 2];
+        let [p0, p1, p2] = [e0, e1, e2].map(|e| self.triangles[e]);
+        let [a, b, c] = [p0, p1, p2].map(|p| self.coords[p]);
+        let p = self.candidates[t];
+        let pn = self.add_point(p);
+        if orient(a, b, p) == 0 {
+            self.handle_collinear(pn, e0);
+        } else if orient(b, c, p) == 0 {
+            self.handle_collinear(pn, e1);
+        } else if orient(c, a, p) == 0 {
+            self.handle_collinear(pn, e2);
+        } else {
+            let [h0, h1, h2] = [e0, e1, e2].map(|e| self.halfedges[e]);
+            let t0 = self.add_triangle([p0, p1, pn], [h0, NONE, NONE], e0);
+            let t1 = self.add_triangle([p1, p2, pn], [h1, NONE, t0 + 1], NONE);
+            let t2 = self.add_triangle([p2, p0, pn], [h2, t0 + 2, t1 + 1], NONE);
+            self.legalize(t0);
+            self.legalize(t1);
+            self.legalize(t2);
+        }
+    }
+
+    /// Flips half-edge `a` if the triangle across it violates the Delaunay condition, and
+    /// recurses into the new outer edges.
+    fn legalize(&mut self, a: usize) {
+        let b = self.halfedges[a];
+        if b == NONE {
+            return;
+        }
+        let (a0, b0) = (a - a % 3, b - b % 3);
+        let (al, ar) = (a0 + (a + 1) % 3, a0 + (a + 2) % 3);
+        let (bl, br) = (b0 + (b + 2) % 3, b0 + (b + 1) % 3);
+        let (p0, pr, pl, p1) = (
+            self.triangles[ar],
+            self.triangles[a],
+            self.triangles[al],
+            self.triangles[bl],
+        );
+        let coords = &self.coords;
+        if !in_circle(coords[p0], coords[pr], coords[pl], coords[p1]) {
+            return;
+        }
+        let [hal, har, hbl, hbr] = [al, ar, bl, br].map(|e| self.halfedges[e]);
+        self.queue_remove(a0 / 3);
+        self.queue_remove(b0 / 3);
+        let t0 = self.add_triangle([p0, p1, pl], [NONE, hbl, hal], a0);
+        let t1 = self.add_triangle([p1, p0, pr], [t0, har, hbr], b0);
+        self.legalize(t0 + 1);
+        self.legalize(t1 + 2);
+    }
+
+    /// Inserts `pn`, which lies on half-edge `a`, splitting the triangles on both sides.
+    fn handle_collinear(&mut self, pn: usize, a: usize) {
+        let a0 = a - a % 3;
+        let (al, ar) = (a0 + (a + 1) % 3, a0 + (a + 2) % 3);
+        let (p0, pr, pl) = (self.triangles[ar], self.triangles[a], self.triangles[al]);
+        let (hal, har) = (self.halfedges[al], self.halfedges[ar]);
+        let b = self.halfedges[a];
+        if b == NONE {
+            let t0 = self.add_triangle([pn, p0, pr], [NONE, har, NONE], a0);
+            let t1 = self.add_triangle([p0, pn, pl], [t0, NONE, hal], NONE);
+            self.legalize(t0 + 1);
+            self.legalize(t1 + 2);
+            return;
+        }
+        let b0 = b - b % 3;
+        let (bl, br) = (b0 + (b + 2) % 3, b0 + (b + 1) % 3);
+        let p1 = self.triangles[bl];
+        let (hbl, hbr) = (self.halfedges[bl], self.halfedges[br]);
+        self.queue_remove(b0 / 3);
+        let t0 = self.add_triangle([p0, pr, pn], [har, NONE, NONE], a0);
+        let t1 = self.add_triangle([pr, p1, pn], [hbr, NONE, t0 + 1], b0);
+        let t2 = self.add_triangle([p1, pl, pn], [hbl, NONE, t1 + 1], NONE);
+        let t3 = self.add_triangle([pl, p0, pn], [hal, t0 + 2, t2 + 1], NONE);
+        self.legalize(t0);
+        self.legalize(t1);
+        self.legalize(t2);
+        self.legalize(t3);
+    }
+
+    fn queue_push(&mut self, t: usize, error: f32) {
+        let i = self.queue.len();
+        self.queue_indices[t] = i;
+        self.queue.push(t);
+        self.errors.push(error);
+        self.queue_up(i);
+    }
+
+    fn queue_pop(&mut self) -> usize {
+        let n = self.queue.len() - 1;
+        self.queue_swap(0, n);
+        self.queue_down(0, n);
+        self.queue_pop_back()
+    }
+
+    fn queue_pop_back(&mut self) -> usize {
+        let t = self.queue.pop().expect("queue is not empty");
+        self.errors.pop();
+        self.queue_indices[t] = NONE;
+        t
+    }
+
+    /// Drops triangle `t` from the queue, or from the pending list if not yet queued.
+    fn queue_remove(&mut self, t: usize) {
+        let i = self.queue_indices[t];
+        if i == NONE {
+            let k = self
+                .pending
+                .iter()
+                .position(|&p| p == t)
+                .expect("an unqueued triangle is pending");
+            self.pending.swap_remove(k);
+            return;
+        }
+        let n = self.queue.len() - 1;
+        if n != i {
+            self.queue_swap(i, n);
+            if !self.queue_down(i, n) {
+                self.queue_up(i);
+            }
+        }
+        self.queue_pop_back();
+    }
+
+    fn queue_swap(&mut self, i: usize, j: usize) {
+        self.queue.swap(i, j);
+        self.errors.swap(i, j);
+        self.queue_indices[self.queue[i]] = i;
+        self.queue_indices[self.queue[j]] = j;
+    }
+
+    fn queue_up(&mut self, mut j: usize) {
+        while j > 0 {
+            let i = (j - 1) / 2;
+            if self.errors[j] <= self.errors[i] {
+                break;
+            }
+            self.queue_swap(i, j);
+            j = i;
+        }
+    }
+
+    /// Sifts entry `i0` down within the first `n`; returns whether it moved.
+    fn queue_down(&mut self, i0: usize, n: usize) -> bool {
+        let mut i = i0;
+        loop {
+            let j1 = 2 * i + 1;
+            if j1 >= n {
+                break;
+            }
+            let j2 = j1 + 1;
+            let j = if j2 < n && self.errors[j2] > self.errors[j1] {
+                j2
+            } else {
+                j1
+            };
+            if self.errors[j] <= self.errors[i] {
+                break;
+            }
+            self.queue_swap(i, j);
+            i = j;
+        }
+        i > i0
+    }
+}
+
+/// Adaptive terrain mesh over the same extent and layout as the regular-grid one: vertices
+/// sit on cell corners (heights averaged from the four cells around each), and samples
+/// are inserted greedily until the surface is within `params.max_error_metres` of every
+/// corner or the vertex budget is spent. Flat sea floors and plains take a few large
+/// triangles, so the mesh is far smaller than a grid of the same accuracy. The east and
+/// west edges are refined independently and need not share vertices.
+pub(crate) fn build_tin(flat: &[f32], params: &MeshParams) -> TerrainMesh {
+    let (width, height) = (WIDTH + 1, HEIGHT + 1);
+    let corners: Vec<f32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+            sample_bilinear(flat, x - 0.5, y - 0.5)
+        })
+        .collect();
+    let max_error = params.max_error_metres / RELIEF_METRES * (1.0 - params.sea_level);
+    let budget = (params.max_vertices as usize).max(4);
+    let mut tin = Triangulation::new(&corners, width, height);
+    while tin.max_error() > max_error && tin.coords.len() < budget {
+        tin.step();
+        tin.flush();
+    }
+
+    let positions: Vec<[f32; 3]> = tin
+        .coords
+        .iter()
+        .map(|&[x, y]| {
+            let h = tin.height_at([x, y]);
+            [
+                x as f32 - WIDTH as f32 / 2.0,
+                (h - params.sea_level) * params.vertical_scale,
+                y as f32 - HEIGHT as f32 / 2.0,
+            ]
+        })
+        .collect();
+    // Counter-clockwise seen from above, as on the grid mesh.
+    let triangles: Vec<[u32; 3]> = tin
+        .triangles
+        .chunks_exact(3)
+        .map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]];
+            if orient(tin.coords[a], tin.coords[b], tin.coords[c]) < 0 {
+                [a as u32, c as u32, b as u32]
+            } else {
+                [a as u32, b as u32, c as u32]
+            }
+        })
+        .collect();
+    // Area-weighted face normals, summed per vertex.
+    let mut normals = vec![[0.0_f32; 3]; positions.len()];
+    for &[a, b, c] in &triangles {
+        let [pa, pb, pc] = [a, b, c].map(|v| positions[v as usize]);
+        let (u, v) = (
+            [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]],
+            [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]],
+        );
+        let n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        for vertex in [a, b, c] {
+            for k in 0..3 {
+                normals[vertex as usize][k] += n[k];
+            }
+        }
+    }
+    for n in &mut normals {
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        *n = if length > 0.0 {
+            n.map(|c| c / length)
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+    }
+    let uvs = tin
+        .coords
+        .iter()
+        .map(|&[x, y]| [x as f32 / WIDTH as f32, y as f32 / HEIGHT as f32])
+        .collect();
+    let cells = tin
+        .coords
+        .iter()
+        .map(|&[x, y]| clamp_y(y) * WIDTH + wrap_x(x))
+        .collect();
+    TerrainMesh {
+        positions,
+        normals,
+        uvs,
+        cells,
+        triangles,
+    }
+}
@@ -0,0 +1,417 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use wasm_bindgen::prelude::*;
+
+use crate::flow::{Flooded, ring};
+use crate::geojson::{feature_collection, line_feature};
+use crate::grid::{CELL_COUNT, SEA_LEVEL, WIDTH, check_grid_len, clamp_y, wrap_x};
+use crate::json::{self, ObjectWriter};
+use crate::landmass::label_land;
+use crate::roads::{PathSearch, RoadNetwork, step_km};
+use crate::vector::{cell_path_polylines, grid_to_lon_lat, polylines_json};
+
+const EARTH_RADIUS_KM: f32 = 6371.0;
+/// Most trading partners kept per settlement.
+const MAX_PARTNERS: u32 = 16;
+/// Cost multiplier on sea cells next to land, keeping lanes off the shoals.
+const SHORE_COST: f32 = 1.5;
+/// Steps along a lane are measured on the grid, which can come out a little shorter than
+/// the great circle between their ends; the A* heuristic is scaled down by this much so
+/// it never overestimates.
+const GREAT_CIRCLE_SLACK: f32 = 0.95;
+
+/// How trade is carried, for `build_trade_routes`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TradeMode {
+    Land,
+    Sea,
+}
+
+impl TradeMode {
+    fn name(self) -> &'static str {
+        match self {
+            TradeMode::Land => "land",
+            TradeMode::Sea => "sea",
+        }
+    }
+}
+
+/// Partner choice and sea travel settings for `build_trade_routes`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TradeParams {
+    pub sea_level: f32,
+    /// Farthest a settlement may lie from the sea to ship from it, cells.
+    pub port_reach: f32,
+    /// Trading partners kept per settlement, by trade volume.
+    pub partners: u32,
+    /// Cost of a kilometre by sea relative to a kilometre by road; below 1 ships are the
+    /// cheaper carrier, as they were before railways.
+    pub sea_cost: f32,
+    /// Douglas–Peucker tolerance, cells.
+    pub tolerance: f32,
+}
+
+impl Default for TradeParams {
+    fn default() -> Self {
+        Self {
+            sea_level: SEA_LEVEL,
+            port_reach: 2.0,
+            partners: 3,
+            sea_cost: 0.5,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TradeParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TradeParams {
+        Self::default()
+    }
+}
+
+impl TradeParams {
+    fn validate(&self) -> Result<(), JsValue> {
+        if !self.sea_level.is_finite() || self.sea_level >= 1.0 {
+            return Err(JsValue::from_str("sea_level must be finite and < 1"));
+        }
+        if !(0.0..=(WIDTH / 8) as f32).contains(&self.port_reach) {
+            return Err(JsValue::from_str(&format!(
+                "port_reach must be within [0, {}]",
+                WIDTH / 8
+            )));
+        }
+        if !(1..=MAX_PARTNERS).contains(&self.partners) {
+            return Err(JsValue::from_str(&format!(
+                "partners must be within [1, {MAX_PARTNERS}]"
+            )));
+        }
+        if !self.sea_cost.is_finite() || self.sea_cost <= 0.0 {
+            return Err(JsValue::from_str("sea_cost must be > 0"));
+        }
+        if !self.tolerance.is_finite() || self.tolerance < 0.0 {
+            return Err(JsValue::from_str("tolerance must be >= 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Great-circle distance between two cell centres, km.
+fn great_circle_km(a: usize, b: usize) -> f32 {
+    let lon_lat = |idx: usize| {
+        let (lon, lat) = grid_to_lon_lat((idx % WIDTH) as f32 + 0.5, (idx / WIDTH) as f32 + 0.5);
+        (lon.to_radians(), lat.to_radians())
+    };
+    let ((lon_a, lat_a), (lon_b, lat_b)) = (lon_lat(a), lon_lat(b));
+    let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+}
+
+/// Length of the step between two neighbouring cells, km.
+fn neighbour_km(from: usize, to: usize) -> f32 {
+    let dx = usize::from(from % WIDTH != to % WIDTH);
+    let dy = usize::from(from / WIDTH != to / WIDTH);
+    step_km(from, dx, dy)
+}
+
+/// 8-connected cells from `a` to `b` (both included) in a straight line, the short way
+/// across the seam.
+fn walk_between(a: usize, b: usize) -> Vec<usize> {
+    let (ax, ay) = ((a % WIDTH) as i64, (a / WIDTH) as i64);
+    let mut dx = (b % WIDTH) as i64 - ax;
+    if dx.abs() > WIDTH as i64 / 2 {
+        dx -= dx.signum() * WIDTH as i64;
+    }
+    let dy = (b / WIDTH) as i64 - ay;
+    let steps = dx.abs().max(dy.abs()).max(1);
+    (0..=steps)
+        .map(|s| {
+            let x = ax + (dx as f64 * s as f64 / steps as f64).round() as i64;
+            let y = ay + (dy as f64 * s as f64 / steps as f64).round() as i64;
+            clamp_y(y) * WIDTH + wrap_x(x)
+        })
+        .collect()
+}
+
+/// Nearest sea cell within `reach` cells of `idx`, if any.
+fn harbour(sea: &[bool], idx: usize, reach: f32) -> Option<usize> {
+    let (x, y) = ((idx % WIDTH) as i64, (idx / WIDTH) as i64);
+    let r = reach.floor() as i64;
+    let mut best: Option<(f32, usize)> = None;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let distance = (dx as f32).hypot(dy as f32);
+            let ny = y + dy;
+            if distance > reach || ny != clamp_y(ny) as i64 {
+                continue;
+            }
+            let n = ny as usize * WIDTH + wrap_x(x + dx);
+            if sea[n] && best.is_none_or(|(d, _)| distance < d) {
+                best = Some((distance, n));
+            }
+        }
+    }
+    best.map(|(_, n)| n)
+}
+
+/// Shortest road distances from settlement `from` over the road network, km, with the
+/// road taken into each settlement.
+fn road_distances(
+    network: &RoadNetwork,
+    edges: &[Vec<(usize, usize)>],
+    from: usize,
+) -> (Vec<f32>, Vec<Option<usize>>) {
+    let mut distance = vec![f32::INFINITY; edges.len()];
+    let mut via = vec![None; edges.len()];
+    let mut queue = BinaryHeap::new();
+    distance[from] = 0.0;
+    queue.push(Reverse(Flooded(0.0, from)));
+    while let Some(Reverse(Flooded(d, node))) = queue.pop() {
+        if d > distance[node] {
+            continue;
+        }
+        for &(next, road) in &edges[node] {
+            let nd = d + network.roads[road].length_km;
+            if nd < distance[next] {
+                distance[next] = nd;
+                via[next] = Some(road);
+                queue.push(Reverse(Flooded(nd, next)));
+            }
+        }
+    }
+    (distance, via)
+}
+
+struct TradeRoute {
+    mode: TradeMode,
+    from: usize,
+    to: usize,
+    /// Gravity-model trade between the two ends, relative to the busiest route.
+    volume: f32,
+    length_km: f32,
+    cells: Vec<usize>,
+}
+
+/// Trade routes from `build_trade_routes`, busiest first.
+#[wasm_bindgen]
+pub struct TradeNetwork {
+    routes: Vec<TradeRoute>,
+    tolerance: f32,
+}
+
+#[wasm_bindgen]
+impl TradeNetwork {
+    /// `[{"mode","from","to","volume","length_km","lines":[[[x,y],...],...]},...]`:
+    /// `mode` is `"land"` or `"sea"`, `from` and `to` index the settlements given to
+    /// `build_trade_routes`, `volume` is in [0, 1] relative to the busiest route, and
+    /// `lines` are polylines in cell-centre grid coordinates, split where a route crosses
+    /// the east–west seam.
+    pub fn routes_json(&self) -> String {
+        json::array(self.routes.iter().map(|route| {
+            ObjectWriter::new()
+                .raw("mode", &json::quote(route.mode.name()))
+                .integer("from", route.from as u64)
+                .integer("to", route.to as u64)
+                .number("volume", route.volume as f64, 4)
+                .number("length_km", route.length_km as f64, 1)
+                .raw(
+                    "lines",
+                    &polylines_json(&cell_path_polylines(&route.cells, self.tolerance)),
+                )
+                .finish()
+        }))
+    }
+
+    /// The routes as a GeoJSON FeatureCollection of LineStrings in lon/lat, with `mode`,
+    /// `from`, `to`, `volume` and `length_km`.
+    pub fn geojson(&self) -> String {
+        let features: Vec<String> = self
+            .routes
+            .iter()
+            .flat_map(|route| {
+                let properties = format!(
+                    "{{\"mode\":\"{}\",\"from\":{},\"to\":{},\"volume\":{:.4},\"length_km\":{:.1}}}",
+                    route.mode.name(),
+                    route.from,
+                    route.to,
+                    route.volume,
+                    route.length_km
+                );
+                cell_path_polylines(&route.cells, self.tolerance)
+                    .into_iter()
+                    .map(move |line| line_feature(&properties, &line))
+            })
+            .collect();
+        feature_collection(&features)
+    }
+
+    /// Total volume of the routes through each cell, for drawing lanes by how busy they
+    /// are.
+    pub fn traffic(&self) -> Box<[f32]> {
+        let mut traffic = vec![0.0_f32; CELL_COUNT];
+        for route in &self.routes {
+            for &idx in &route.cells {
+                traffic[idx] += route.volume;
+            }
+        }
+        traffic.into_boxed_slice()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.routes.len() as u32
+    }
+}
+
+/// Trade between settlements (`cells` and `populations` as from `SettlementHierarchy`,
+/// with `roads` built for the same list). Each settlement trades with the `partners` it
+/// would trade most with under a gravity model, population times population over
+/// great-circle distance squared, among those it can reach. Overland routes follow the
+/// road network; settlements within `port_reach` of the same sea can also ship by sea
+/// lanes, the shortest paths over open water measured in kilometres, so they bend toward
+/// the poles as great circles do on this map. Each pair uses whichever way is cheaper,
+/// with a sea kilometre costing `sea_cost` road kilometres.
+#[wasm_bindgen]
+pub fn build_trade_routes(
+    flat: &[f32],
+    cells: &[u32],
+    populations: &[u32],
+    roads: &RoadNetwork,
+    params: &TradeParams,
+) -> Result<TradeNetwork, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    params.validate()?;
+    if cells.len() != populations.len() {
+        return Err(JsValue::from_str(
+            "cells and populations must have the same length",
+        ));
+    }
+    if cells.iter().any(|&c| c as usize >= CELL_COUNT) {
+        return Err(JsValue::from_str("settlement cell out of range"));
+    }
+    if roads.roads.iter().any(|r| r.from.max(r.to) >= cells.len()) {
+        return Err(JsValue::from_str(
+            "roads must be built for the same settlements",
+        ));
+    }
+
+    let sea: Vec<bool> = flat.iter().map(|&h| h < params.sea_level).collect();
+    let (body, _) = label_land(&sea);
+    let shore: Vec<bool> = (0..CELL_COUNT)
+        .map(|i| sea[i] && ring(i).into_iter().flatten().any(|n| !sea[n]))
+        .collect();
+    let harbours: Vec<Option<usize>> = cells
+        .iter()
+        .map(|&c| harbour(&sea, c as usize, params.port_reach))
+        .collect();
+    let mut edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); cells.len()];
+    for (r, road) in roads.roads.iter().enumerate() {
+        edges[road.from].push((road.to, r));
+        edges[road.to].push((road.from, r));
+    }
+
+    let mut search = PathSearch::new();
+    let mut routes: Vec<TradeRoute> = Vec::new();
+    let mut paired: HashSet<(usize, usize)> = HashSet::new();
+    for a in 0..cells.len() {
+        let (land_km, via) = road_distances(roads, &edges, a);
+        let same_sea = |b: usize| match (harbours[a], harbours[b]) {
+            (Some(ha), Some(hb)) => body[ha] == body[hb],
+            _ => false,
+        };
+        let gravity = |b: usize| {
+            let km = great_circle_km(cells[a] as usize, cells[b] as usize).max(1.0);
+            populations[a] as f32 * populations[b] as f32 / (km * km)
+        };
+        let mut partners: Vec<usize> = (0..cells.len())
+            .filter(|&b| b != a && (land_km[b].is_finite() || same_sea(b)))
+            .collect();
+        partners.sort_by(|&p, &q| gravity(q).total_cmp(&gravity(p)).then(p.cmp(&q)));
+        partners.truncate(params.partners as usize);
+        for b in partners {
+            let pair = (a.min(b), a.max(b));
+            if !paired.insert(pair) {
+                continue;
+            }
+            let (start, goal) = (cells[a] as usize, cells[b] as usize);
+            // Only look for a lane that could beat the road.
+            let lane = (same_sea(b)
+                && great_circle_km(start, goal) * GREAT_CIRCLE_SLACK * params.sea_cost
+                    < land_km[b])
+                .then(|| {
+                    let (ha, hb) = (harbours[a]?, harbours[b]?);
+                    search.find(
+                        ha,
+                        hb,
+                        |from, to, _| {
+                            sea[to].then(|| {
+                                neighbour_km(from, to) * if shore[to] { SHORE_COST } else { 1.0 }
+                            })
+                        },
+                        |idx| great_circle_km(idx, hb) * GREAT_CIRCLE_SLACK,
+                    )
+                })
+                .flatten()
+                .map(|water| {
+                    let mut path = walk_between(start, water[0]);
+                    path.extend(&water[1..]);
+                    path.pop();
+                    path.extend(walk_between(water[water.len() - 1], goal));
+                    let km: f32 = path.windows(2).map(|p| neighbour_km(p[0], p[1])).sum();
+                    (path, km)
+                })
+                .filter(|(_, km)| km * params.sea_cost < land_km[b]);
+            let (mode, path, length_km) = match lane {
+                Some((path, km)) => (TradeMode::Sea, path, km),
+                None if land_km[b].is_finite() => {
+                    // Back along the roads from `b`, then turned to run from `a`.
+                    let mut path: Vec<usize> = Vec::new();
+                    let mut at = b;
+                    while let Some(r) = via[at] {
+                        let road = &roads.roads[r];
+                        // Each stretch runs from `at` back toward `a`.
+                        let mut stretch = road.cells.clone();
+                        if road.from == at {
+                            at = road.to;
+                        } else {
+                            stretch.reverse();
+                            at = road.from;
+                        }
+                        if !path.is_empty() {
+                            stretch.remove(0);
+                        }
+                        path.extend(stretch);
+                    }
+                    path.reverse();
+                    (TradeMode::Land, path, land_km[b])
+                }
+                None => continue,
+            };
+            routes.push(TradeRoute {
+                mode,
+                from: a,
+                to: b,
+                volume: gravity(b),
+                length_km,
+                cells: path,
+            });
+        }
+    }
+    let busiest = routes.iter().map(|r| r.volume).fold(0.0, f32::max);
+    for route in &mut routes {
+        route.volume = if busiest > 0.0 {
+            route.volume / busiest
+        } else {
+            0.0
+        };
+    }
+    routes.sort_by(|p, q| q.volume.total_cmp(&p.volume));
+    Ok(TradeNetwork {
+        routes,
+        tolerance: params.tolerance,
+    })
+}
@@ -0,0 +1,438 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::flow::ring;
+use crate::grid::{CELL_COUNT, HEIGHT, WIDTH};
+
+/// A ring of cell-corner vertices in grid coordinates (x right, y down); closed implicitly.
+pub(crate) type Ring = Vec<(f32, f32)>;
+
+/// Labels 4-connected regions of equal value among cells accepted by `include`.
+/// Returns per-cell labels (0 = excluded, regions numbered from 1) and the region count.
+/// Regions do not wrap across the east–west seam.
+pub(crate) fn label_regions<T: Copy + PartialEq>(
+    values: &[T],
+    include: impl Fn(T) -> bool,
+) -> (Vec<u32>, u32) {
+    let mut labels = vec![0_u32; CELL_COUNT];
+    let mut count = 0;
+    let mut stack = Vec::new();
+    for start in 0..CELL_COUNT {
+        if labels[start] != 0 || !include(values[start]) {
+            continue;
+        }
+        count += 1;
+        let value = values[start];
+        labels[start] = count;
+        stack.push(start);
+        while let Some(idx) = stack.pop() {
+            let (x, y) = (idx % WIDTH, idx / WIDTH);
+            let mut visit = |n: usize| {
+                if labels[n] == 0 && values[n] == value {
+                    labels[n] = count;
+                    stack.push(n);
+                }
+            };
+            if x > 0 {
+                visit(idx - 1);
+            }
+            if x + 1 < WIDTH {
+                visit(idx + 1);
+            }
+            if y > 0 {
+                visit(idx - WIDTH);
+            }
+            if y + 1 < HEIGHT {
+                visit(idx + WIDTH);
+            }
+        }
+    }
+    (labels, count)
+}
+
+type Vertex = (i32, i32);
+
+/// Boundary rings of every labelled region, indexed by `label - 1`. Edges run with the
+/// region on their left (screen space), so outer rings and holes wind in opposite senses.
+pub(crate) fn trace_region_rings(labels: &[u32], count: u32) -> Vec<Vec<Ring>> {
+    let mut edges: Vec<Vec<(Vertex, Vertex)>> = vec![Vec::new(); count as usize];
+    let label_at = |x: i64, y: i64| -> u32 {
+        if x < 0 || y < 0 || x >= WIDTH as i64 || y >= HEIGHT as i64 {
+            0
+        } else {
+            labels[y as usize * WIDTH + x as usize]
+        }
+    };
+    for y in 0..HEIGHT as i64 {
+        for x in 0..WIDTH as i64 {
+            let label = label_at(x, y);
+            if label == 0 {
+                continue;
+            }
+            let list = &mut edges[label as usize - 1];
+            let (xi, yi) = (x as i32, y as i32);
+            if label_at(x, y - 1) != label {
+                list.push(((xi + 1, yi), (xi, yi)));
+            }
+            if label_at(x - 1, y) != label {
+                list.push(((xi, yi), (xi, yi + 1)));
+            }
+            if label_at(x, y + 1) != label {
+                list.push(((xi, yi + 1), (xi + 1, yi + 1)));
+            }
+            if label_at(x + 1, y) != label {
+                list.push(((xi + 1, yi + 1), (xi + 1, yi)));
+            }
+        }
+    }
+    edges.into_iter().map(|e| chain_edges(&e)).collect()
+}
+
+fn chain_edges(edges: &[(Vertex, Vertex)]) -> Vec<Ring> {
+    let mut outgoing: HashMap<Vertex, Vec<usize>> = HashMap::new();
+    for (i, &(start, _)) in edges.iter().enumerate() {
+        outgoing.entry(start).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let origin = edges[first].0;
+        let mut ring: Vec<Vertex> = vec![origin];
+        let mut current = first;
+        loop {
+            let (from, to) = edges[current];
+            if to == origin {
+                break;
+            }
+            ring.push(to);
+            let dir = (to.0 - from.0, to.1 - from.1);
+            // Prefer the tightest left turn so diagonal touches stay separate (4-connectivity).
+            let turns = [(dir.1, -dir.0), dir, (-dir.1, dir.0)];
+            let candidates = &outgoing[&to];
+            let next = turns.iter().find_map(|&turn| {
+                candidates.iter().copied().find(|&c| {
+                    !used[c] && {
+                        let (s, e) = edges[c];
+                        (e.0 - s.0, e.1 - s.1) == turn
+                    }
+                })
+            });
+            match next {
+                Some(n) => {
+                    used[n] = true;
+                    current = n;
+                }
+                None => break,
+            }
+        }
+        rings.push(drop_collinear(&ring));
+    }
+    rings
+}
+
+fn drop_collinear(ring: &[Vertex]) -> Ring {
+    let n = ring.len();
+    let mut out = Vec::with_capacity(n);
+    for i in 0..n {
+        let prev = ring[(i + n - 1) % n];
+        let cur = ring[i];
+        let next = ring[(i + 1) % n];
+        let cross = (cur.0 - prev.0) * (next.1 - cur.1) - (cur.1 - prev.1) * (next.0 - cur.0);
+        if cross != 0 {
+            out.push((cur.0 as f32, cur.1 as f32));
+        }
+    }
+    out
+}
+
+/// Shoelace signed area in grid space.
+pub(crate) fn signed_area(ring: &[(f32, f32)]) -> f32 {
+    let n = ring.len();
+    let mut sum = 0.0;
+    for i in 0..n {
+        let (x0, y0) = ring[i];
+        let (x1, y1) = ring[(i + 1) % n];
+        sum += x0 * y1 - x1 * y0;
+    }
+    sum * 0.5
+}
+
+fn perpendicular_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len = dx.hypot(dy);
+    if len == 0.0 {
+        return (p.0 - a.0).hypot(p.1 - a.1);
+    }
+    ((p.0 - a.0) * dy - (p.1 - a.1) * dx).abs() / len
+}
+
+/// Douglas–Peucker simplification of an open polyline.
+pub(crate) fn simplify_polyline(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let mut worst = (0.0, start);
+        for i in start + 1..end {
+            let d = perpendicular_distance(points[i], points[start], points[end]);
+            if d > worst.0 {
+                worst = (d, i);
+            }
+        }
+        if worst.0 > tolerance {
+            keep[worst.1] = true;
+            stack.push((start, worst.1));
+            stack.push((worst.1, end));
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&p, k)| k.then_some(p))
+        .collect()
+}
+
+/// Douglas–Peucker on a closed ring, split at its first and farthest vertices. Rings that
+/// would collapse below a triangle are returned unchanged.
+pub(crate) fn simplify_ring(ring: &[(f32, f32)], tolerance: f32) -> Ring {
+    if ring.len() < 4 || tolerance <= 0.0 {
+        return ring.to_vec();
+    }
+    let origin = ring[0];
+    let far = (1..ring.len())
+        .max_by(|&a, &b| {
+            let da = (ring[a].0 - origin.0).hypot(ring[a].1 - origin.1);
+            let db = (ring[b].0 - origin.0).hypot(ring[b].1 - origin.1);
+            da.total_cmp(&db)
+        })
+        .unwrap_or(1);
+    let mut first: Vec<(f32, f32)> = ring[..=far].to_vec();
+    let mut second: Vec<(f32, f32)> = ring[far..].to_vec();
+    second.push(origin);
+    first = simplify_polyline(&first, tolerance);
+    second = simplify_polyline(&second, tolerance);
+    first.pop();
+    second.pop();
+    first.extend(second);
+    if first.len() < 3 {
+        return ring.to_vec();
+    }
+    first
+}
+
+/// Grid corner coordinates → (longitude, latitude) degrees on the equirectangular grid.
+pub(crate) fn grid_to_lon_lat(x: f32, y: f32) -> (f32, f32) {
+    (
+        x / WIDTH as f32 * 360.0 - 180.0,
+        90.0 - y / HEIGHT as f32 * 180.0,
+    )
+}
+
+/// GeoJSON linear ring (`[[lon,lat],...]`, first point repeated). With `geographic_ccw`,
+/// the winding is set counter-clockwise in lon/lat as RFC 7946 asks for exterior rings.
+pub(crate) fn ring_to_geojson(ring: &[(f32, f32)], geographic_ccw: bool) -> String {
+    // Grid y points down, so a positive grid-space area is clockwise once in lon/lat.
+    let reverse = (signed_area(ring) > 0.0) == geographic_ccw;
+    let mut points: Vec<(f32, f32)> = ring.to_vec();
+    if reverse {
+        points.reverse();
+    }
+    if let Some(&first) = points.first() {
+        points.push(first);
+    }
+    let coords: Vec<String> = points
+        .iter()
+        .map(|&(x, y)| {
+            let (lon, lat) = grid_to_lon_lat(x, y);
+            format!("[{lon:.4},{lat:.4}]")
+        })
+        .collect();
+    format!("[{}]", coords.join(","))
+}
+
+/// GeoJSON LineString coordinates (`[[lon,lat],...]`) for a polyline in grid coordinates.
+pub(crate) fn line_to_geojson(points: &[(f32, f32)]) -> String {
+    let coords: Vec<String> = points
+        .iter()
+        .map(|&(x, y)| {
+            let (lon, lat) = grid_to_lon_lat(x, y);
+            format!("[{lon:.4},{lat:.4}]")
+        })
+        .collect();
+    format!("[{}]", coords.join(","))
+}
+
+/// Splits a region's rings into its exterior (largest |area|) and holes.
+pub(crate) fn exterior_and_holes(mut rings: Vec<Ring>) -> Option<(Ring, Vec<Ring>)> {
+    let outer = (0..rings.len()).max_by(|&a, &b| {
+        signed_area(&rings[a])
+            .abs()
+            .total_cmp(&signed_area(&rings[b]).abs())
+    })?;
+    let exterior = rings.swap_remove(outer);
+    Some((exterior, rings))
+}
+
+/// GeoJSON Polygon geometry from an exterior ring and holes.
+pub(crate) fn polygon_geometry_json(exterior: &[(f32, f32)], holes: &[Ring]) -> String {
+    let mut rings = vec![ring_to_geojson(exterior, true)];
+    rings.extend(holes.iter().map(|h| ring_to_geojson(h, false)));
+    format!(
+        "{{\"type\":\"Polygon\",\"coordinates\":[{}]}}",
+        rings.join(",")
+    )
+}
+
+const EQUATOR_KM: f64 = 40_075.0;
+const MERIDIAN_KM: f64 = 20_004.0;
+
+/// East–west and north–south extent (km) of a block `rows` cells tall and `cols` wide
+/// centred on latitude `lat_deg`, on an Earth-sized equirectangular grid.
+pub(crate) fn block_size_km(lat_deg: f64, cols: usize, rows: usize) -> (f64, f64) {
+    (
+        EQUATOR_KM / WIDTH as f64 * cols as f64 * lat_deg.to_radians().cos(),
+        MERIDIAN_KM / HEIGHT as f64 * rows as f64,
+    )
+}
+
+/// Area of one grid cell in row `y`, km², on an Earth-sized equirectangular grid.
+pub(crate) fn cell_area_km2(y: usize) -> f64 {
+    let (w, h) = block_size_km(crate::grid::latitude_deg(y) as f64, 1, 1);
+    w * h
+}
+
+/// Zhang–Suen thinning of `mask` to 8-connected lines one cell wide (wrapping east–west).
+pub(crate) fn thin_mask(mask: &mut [bool]) {
+    let mut active: Vec<usize> = (0..CELL_COUNT).filter(|&i| mask[i]).collect();
+    loop {
+        let mut changed = false;
+        for pass in 0..2 {
+            let removable: Vec<usize> = active
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    let p = ring(idx).map(|n| n.is_some_and(|n| mask[n]));
+                    let count = p.iter().filter(|&&b| b).count();
+                    let transitions = (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count();
+                    // p[0] = N, p[2] = E, p[4] = S, p[6] = W.
+                    let sides = if pass == 0 {
+                        !(p[2] && p[4] && (p[0] || p[6]))
+                    } else {
+                        !(p[0] && p[6] && (p[2] || p[4]))
+                    };
+                    (2..=6).contains(&count) && transitions == 1 && sides
+                })
+                .collect();
+            changed |= !removable.is_empty();
+            for idx in removable {
+                mask[idx] = false;
+            }
+            active.retain(|&i| mask[i]);
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Splits a thinned mask into paths of cell indices between endpoints and junctions;
+/// closed loops come back as paths that end next to where they began.
+pub(crate) fn skeleton_paths(mask: &[bool]) -> Vec<Vec<usize>> {
+    let neighbours = |idx: usize| -> Vec<usize> {
+        // Edge neighbours first so a path prefers them over cutting a corner.
+        let ring = ring(idx);
+        [0, 2, 4, 6, 1, 3, 5, 7]
+            .into_iter()
+            .filter_map(|i| ring[i])
+            .filter(|&n| mask[n])
+            .collect()
+    };
+    let branches = |idx: usize| {
+        let p = ring(idx).map(|n| n.is_some_and(|n| mask[n]));
+        (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count()
+    };
+    let is_node = |idx: usize| branches(idx) != 2;
+    let mut visited = vec![false; CELL_COUNT];
+    let mut paths = Vec::new();
+    let walk = |start: usize, first: usize, visited: &mut Vec<bool>| {
+        let mut path = vec![start, first];
+        let (mut previous, mut current) = (start, first);
+        while !is_node(current) {
+            visited[current] = true;
+            let next = neighbours(current)
+                .into_iter()
+                .find(|&n| n != previous && (n == start || is_node(n) || !visited[n]));
+            let Some(next) = next else {
+                break;
+            };
+            path.push(next);
+            if next == start {
+                break;
+            }
+            (previous, current) = (current, next);
+        }
+        path
+    };
+    let nodes: Vec<usize> = (0..CELL_COUNT).filter(|&i| mask[i] && is_node(i)).collect();
+    let mut joined: HashSet<(usize, usize)> = HashSet::new();
+    for &node in &nodes {
+        visited[node] = true;
+        for first in neighbours(node) {
+            if visited[first] && !is_node(first) {
+                continue;
+            }
+            if is_node(first) && !joined.insert((node.min(first), node.max(first))) {
+                continue;
+            }
+            paths.push(walk(node, first, &mut visited));
+        }
+    }
+    for start in 0..CELL_COUNT {
+        if mask[start] && !visited[start] {
+            visited[start] = true;
+            if let Some(first) = neighbours(start).into_iter().find(|&n| !visited[n]) {
+                paths.push(walk(start, first, &mut visited));
+            }
+        }
+    }
+    paths
+}
+
+/// Cell-centre polylines through a path of cell indices, split where a step crosses the
+/// east–west seam and simplified with `tolerance` (cells). Single points are dropped.
+pub(crate) fn cell_path_polylines(path: &[usize], tolerance: f32) -> Vec<Vec<(f32, f32)>> {
+    let mut lines = vec![Vec::new()];
+    for &idx in path {
+        let point = ((idx % WIDTH) as f32 + 0.5, (idx / WIDTH) as f32 + 0.5);
+        let line = lines.last_mut().expect("lines starts non-empty");
+        if line
+            .last()
+            .is_some_and(|p: &(f32, f32)| (p.0 - point.0).abs() > 1.5)
+        {
+            lines.push(vec![point]);
+        } else {
+            line.push(point);
+        }
+    }
+    lines
+        .into_iter()
+        .filter(|l| l.len() > 1)
+        .map(|l| simplify_polyline(&l, tolerance))
+        .collect()
+}
+
+/// JSON `[[[x,y],...],...]` for polylines in grid coordinates.
+pub(crate) fn polylines_json(lines: &[Vec<(f32, f32)>]) -> String {
+    crate::json::array(
+        lines
+            .iter()
+            .map(|line| crate::json::array(line.iter().map(|(x, y)| format!("[{x:.1},{y:.1}]")))),
+    )
+}
@@ -0,0 +1,63 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{
+    BIOME_COLD_DESERT, BIOME_DESERT, BIOME_GRASSLAND, BIOME_ICE, BIOME_SAVANNA, BIOME_TAIGA,
+    BIOME_TEMPERATE_FOREST, BIOME_TEMPERATE_RAINFOREST, BIOME_TROPICAL_RAINFOREST, BIOME_TUNDRA,
+    BIOME_WOODLAND,
+};
+use crate::climate::Climate;
+use crate::grid::{CELL_COUNT, check_grid_len, smoothstep};
+use crate::terrain::gradient;
+
+/// Gradient (elevation units per cell) where steep ground starts thinning vegetation, and
+/// where it reaches the maximum reduction.
+const SLOPE_THIN_START: f32 = 0.004;
+const SLOPE_THIN_FULL: f32 = 0.03;
+const MAX_SLOPE_REDUCTION: f32 = 0.7;
+
+pub(crate) fn biome_canopy(biome: u8) -> f32 {
+    match biome {
+        BIOME_ICE => 0.0,
+        BIOME_TUNDRA => 0.15,
+        BIOME_TAIGA => 0.7,
+        BIOME_GRASSLAND => 0.35,
+        BIOME_WOODLAND => 0.5,
+        BIOME_TEMPERATE_FOREST => 0.85,
+        BIOME_TEMPERATE_RAINFOREST => 0.95,
+        BIOME_DESERT => 0.05,
+        BIOME_COLD_DESERT => 0.05,
+        BIOME_SAVANNA => 0.4,
+        BIOME_TROPICAL_RAINFOREST => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Vegetation density in [0, 1]: the biome's canopy, scaled by water availability
+/// (AET / PET) and thinned on steep slopes. Water cells are 0.
+#[wasm_bindgen]
+pub fn vegetation_density(
+    flat: &[f32],
+    climate: &Climate,
+    biomes: &[u8],
+) -> Result<Box<[f32]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    check_grid_len(biomes, "biome map")?;
+
+    let mut density = vec![0.0_f32; CELL_COUNT];
+    for (idx, d) in density.iter_mut().enumerate() {
+        let canopy = biome_canopy(biomes[idx]);
+        if canopy == 0.0 {
+            continue;
+        }
+        let pet = climate.pet[idx];
+        let wetness = if pet > 0.0 {
+            (climate.evapotranspiration[idx] / pet).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let (gx, gy) = gradient(flat, idx);
+        let steepness = smoothstep(SLOPE_THIN_START, SLOPE_THIN_FULL, gx.hypot(gy));
+        *d = canopy * (0.5 + 0.5 * wetness) * (1.0 - MAX_SLOPE_REDUCTION * steepness);
+    }
+    Ok(density.into_boxed_slice())
+}
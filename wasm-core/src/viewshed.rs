@@ -0,0 +1,73 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::elevation_metres;
+use crate::grid::{CELL_COUNT, HEIGHT, SEA_LEVEL, WIDTH, check_grid_len, latitude_deg, wrap_x};
+use crate::vector::block_size_km;
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+/// Standard atmospheric refraction coefficient; light bends to follow the curve slightly.
+const REFRACTION: f64 = 0.13;
+
+/// Cells visible from `(x, y)` with the eye `observer_height` metres above the ground (or
+/// sea surface), out to `max_radius` cells: 1 = visible, 0 = hidden or out of range.
+/// Sight lines follow the ground in metres, account for Earth curvature with standard
+/// refraction, and treat water as its flat surface. Rays run to every cell on the edge of
+/// the search square; a cell is visible if any ray through it sees it.
+#[wasm_bindgen]
+pub fn viewshed(
+    flat: &[f32],
+    x: u32,
+    y: u32,
+    observer_height: f32,
+    max_radius: u32,
+) -> Result<Box<[u8]>, JsValue> {
+    check_grid_len(flat, "flat heightmap")?;
+    if x as usize >= WIDTH || y as usize >= HEIGHT {
+        return Err(JsValue::from_str("observer must lie within the grid"));
+    }
+    if !observer_height.is_finite() || observer_height < 0.0 {
+        return Err(JsValue::from_str("observer_height must be >= 0"));
+    }
+    if max_radius == 0 || max_radius as usize > WIDTH / 2 {
+        return Err(JsValue::from_str(
+            "max_radius must be within [1, half the grid width]",
+        ));
+    }
+    let (ox, oy) = (x as i64, y as i64);
+    let radius = max_radius as i64;
+    let height_m = |idx: usize| elevation_metres(flat[idx], SEA_LEVEL) as f64;
+    let eye = height_m(y as usize * WIDTH + x as usize) + observer_height as f64;
+    // Local metres per cell east–west and north–south around the observer.
+    let (cell_w, cell_h) = block_size_km(latitude_deg(y as usize) as f64, 1, 1);
+    let (cell_w, cell_h) = (cell_w * 1000.0, cell_h * 1000.0);
+    let effective_radius = EARTH_RADIUS_M / (1.0 - REFRACTION);
+
+    let mut visible = vec![0_u8; CELL_COUNT];
+    visible[y as usize * WIDTH + x as usize] = 1;
+    let edge =
+        (-radius..=radius).flat_map(|t| [(t, -radius), (t, radius), (-radius, t), (radius, t)]);
+    for (ex, ey) in edge {
+        let mut horizon = f64::NEG_INFINITY;
+        for step in 1..=radius {
+            let dx = (ex * step) as f64 / radius as f64;
+            let dy = (ey * step) as f64 / radius as f64;
+            if dx.hypot(dy) > radius as f64 {
+                break;
+            }
+            let (dx, dy) = (dx.round(), dy.round());
+            let ty = oy + dy as i64;
+            if !(0..HEIGHT as i64).contains(&ty) {
+                break;
+            }
+            let idx = ty as usize * WIDTH + wrap_x(ox + dx as i64);
+            let distance = (dx * cell_w).hypot(dy * cell_h);
+            let drop = distance * distance / (2.0 * effective_radius);
+            let angle = (height_m(idx) - drop - eye) / distance;
+            if angle >= horizon {
+                visible[idx] = 1;
+                horizon = angle;
+            }
+        }
+    }
+    Ok(visible.into_boxed_slice())
+}
@@ -0,0 +1,44 @@
+use wasm_bindgen::prelude::*;
+
+use crate::climate::Climate;
+use crate::grid::{HEIGHT, WIDTH};
+
+/// Downsampled wind field for arrow overlays:
+/// `{"cols","rows","direction_deg":[...],"strength":[...]}`, both arrays row-major.
+/// `direction_deg` is the compass bearing the wind blows toward (0 = north, 90 = east);
+/// `strength` is the magnitude of the block's mean wind vector (trade winds ≈ 1).
+#[wasm_bindgen]
+pub fn wind_grid_json(climate: &Climate, cols: u32, rows: u32) -> Result<String, JsValue> {
+    if cols == 0 || rows == 0 || cols as usize > WIDTH || rows as usize > HEIGHT {
+        return Err(JsValue::from_str(
+            "cols and rows must be within the grid dimensions",
+        ));
+    }
+    let (cols, rows) = (cols as usize, rows as usize);
+    let mut directions = Vec::with_capacity(cols * rows);
+    let mut strengths = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+        let (y0, y1) = (row * HEIGHT / rows, (row + 1) * HEIGHT / rows);
+        for col in 0..cols {
+            let (x0, x1) = (col * WIDTH / cols, (col + 1) * WIDTH / cols);
+            let (mut u, mut v) = (0.0_f64, 0.0_f64);
+            for y in y0..y1 {
+                for &(wu, wv) in &climate.winds[y * WIDTH + x0..y * WIDTH + x1] {
+                    u += wu as f64;
+                    v += wv as f64;
+                }
+            }
+            let n = ((y1 - y0) * (x1 - x0)) as f64;
+            let (u, v) = (u / n, v / n);
+            // Grid +y points south, so north is −v.
+            let bearing = u.atan2(-v).to_degrees().rem_euclid(360.0);
+            directions.push(format!("{bearing:.1}"));
+            strengths.push(format!("{:.3}", u.hypot(v)));
+        }
+    }
+    Ok(format!(
+        "{{\"cols\":{cols},\"rows\":{rows},\"direction_deg\":[{}],\"strength\":[{}]}}",
+        directions.join(","),
+        strengths.join(",")
+    ))
+}
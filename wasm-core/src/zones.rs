@@ -0,0 +1,62 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::{WIDTH, check_grid_len};
+use crate::vector::{
+    cell_area_km2, exterior_and_holes, label_regions, polygon_geometry_json, simplify_ring,
+    trace_region_rings,
+};
+
+/// Vectorizes contiguous regions of a class map (Köppen, Whittaker, or any u8 layer with
+/// 0 = unclassified) into a GeoJSON FeatureCollection of Polygons with holes, in lon/lat.
+/// Each feature carries `class`, `cells`, and `area_km2`. Regions smaller than `min_cells`
+/// are dropped; `tolerance` (cells) applies Douglas–Peucker simplification, 0 keeps the
+/// exact cell outlines.
+#[wasm_bindgen]
+pub fn climate_zone_polygons_json(
+    classes: &[u8],
+    min_cells: u32,
+    tolerance: f32,
+) -> Result<String, JsValue> {
+    check_grid_len(classes, "class map")?;
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(JsValue::from_str("tolerance must be >= 0"));
+    }
+
+    let (labels, count) = label_regions(classes, |c| c != 0);
+    let mut cells = vec![0_u32; count as usize];
+    let mut area = vec![0.0_f64; count as usize];
+    let mut class_of = vec![0_u8; count as usize];
+    for (idx, &label) in labels.iter().enumerate() {
+        if label != 0 {
+            let r = label as usize - 1;
+            cells[r] += 1;
+            area[r] += cell_area_km2(idx / WIDTH);
+            class_of[r] = classes[idx];
+        }
+    }
+
+    let mut features = Vec::new();
+    for (r, rings) in trace_region_rings(&labels, count).into_iter().enumerate() {
+        if cells[r] < min_cells.max(1) {
+            continue;
+        }
+        let rings = rings
+            .iter()
+            .map(|ring| simplify_ring(ring, tolerance))
+            .collect();
+        let Some((exterior, holes)) = exterior_and_holes(rings) else {
+            continue;
+        };
+        features.push(format!(
+            "{{\"type\":\"Feature\",\"properties\":{{\"class\":{class},\"cells\":{cells},\"area_km2\":{area:.1}}},\"geometry\":{geometry}}}",
+            class = class_of[r],
+            cells = cells[r],
+            area = area[r],
+            geometry = polygon_geometry_json(&exterior, &holes),
+        ));
+    }
+    Ok(format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}",
+        features.join(",")
+    ))
+}